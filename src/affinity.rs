@@ -0,0 +1,174 @@
+//! How similar the tastes of two users are, see
+//! [`MALClient::compare_with_user`](crate::MALClient::compare_with_user)
+//!
+//! The correlations only use anime both users scored, unscored entries still count as shared or
+//! unique shows.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let report = client.compare_with_user("[SOME_USERNAME]").await?;
+//! if let Some(affinity) = report.affinity() {
+//!     println!("{affinity:.1}% affinity over {} shared shows", report.shared.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{ids::AnimeId, Anime, ListNode, Score};
+
+///An anime on both lists, with the scores the users gave it. A score of 0 means it wasn't
+///scored.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SharedScore {
+    pub anime: Anime,
+    pub mine: u8,
+    pub theirs: u8,
+}
+
+impl SharedScore {
+    const fn both_scored(&self) -> bool {
+        self.mine > 0 && self.theirs > 0
+    }
+}
+
+///The comparison of two users' anime lists
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AffinityReport {
+    ///In the order of the first list
+    pub shared: Vec<SharedScore>,
+    pub only_mine: Vec<Anime>,
+    pub only_theirs: Vec<Anime>,
+    ///Pearson correlation of the scores of the anime both users scored, from -1 to 1. `None` if
+    ///there are fewer than two of them or one user gave them all the same score.
+    pub pearson: Option<f64>,
+    ///Spearman correlation, like `pearson` but comparing the order the users rank the anime in
+    pub spearman: Option<f64>,
+    ///The mean score of every anime the first user scored
+    pub mean_score_mine: Option<f64>,
+    pub mean_score_theirs: Option<f64>,
+    ///How much higher the first user scored the anime both scored, on average. Negative if the
+    ///other user scored them higher.
+    pub mean_difference: Option<f64>,
+}
+
+impl AffinityReport {
+    ///Compares two lists, which need the `list_status` field for the scores
+    pub fn from_lists(mine: &[ListNode], theirs: &[ListNode]) -> Self {
+        let scores = |list: &[ListNode]| -> HashMap<AnimeId, u8> {
+            list.iter()
+                .map(|entry| (entry.node.id, score_of(entry)))
+                .collect()
+        };
+        let (my_scores, their_scores) = (scores(mine), scores(theirs));
+
+        let mut seen = HashSet::new();
+        let mut shared = Vec::new();
+        let mut only_mine = Vec::new();
+        for entry in mine.iter().filter(|e| seen.insert(e.node.id)) {
+            match their_scores.get(&entry.node.id) {
+                Some(&theirs) => shared.push(SharedScore {
+                    anime: entry.node.clone(),
+                    mine: score_of(entry),
+                    theirs,
+                }),
+                None => only_mine.push(entry.node.clone()),
+            }
+        }
+        let mut seen = HashSet::new();
+        let only_theirs = theirs
+            .iter()
+            .filter(|e| !my_scores.contains_key(&e.node.id) && seen.insert(e.node.id))
+            .map(|e| e.node.clone())
+            .collect();
+
+        let (xs, ys): (Vec<f64>, Vec<f64>) = shared
+            .iter()
+            .filter(|s| s.both_scored())
+            .map(|s| (f64::from(s.mine), f64::from(s.theirs)))
+            .unzip();
+        let mean_difference = (!xs.is_empty()).then(|| mean(&xs) - mean(&ys));
+        let scored = |scores: &HashMap<AnimeId, u8>| -> Vec<f64> {
+            scores
+                .values()
+                .filter(|s| **s > 0)
+                .map(|s| f64::from(*s))
+                .collect()
+        };
+        let (mine_scored, theirs_scored) = (scored(&my_scores), scored(&their_scores));
+
+        Self {
+            pearson: pearson(&xs, &ys),
+            spearman: pearson(&ranks(&xs), &ranks(&ys)),
+            mean_score_mine: (!mine_scored.is_empty()).then(|| mean(&mine_scored)),
+            mean_score_theirs: (!theirs_scored.is_empty()).then(|| mean(&theirs_scored)),
+            mean_difference,
+            shared,
+            only_mine,
+            only_theirs,
+        }
+    }
+
+    ///The Pearson correlation as a percentage, like the affinity MAL shows on profiles
+    pub fn affinity(&self) -> Option<f64> {
+        self.pearson.map(|p| p * 100.0)
+    }
+}
+
+fn score_of(entry: &ListNode) -> u8 {
+    entry
+        .list_status
+        .as_ref()
+        .and_then(|s| s.score)
+        .map_or(0, Score::get)
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    if xs.len() < 2 {
+        return None;
+    }
+    let (mean_x, mean_y) = (mean(xs), mean(ys));
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        let (dx, dy) = (x - mean_x, y - mean_y);
+        cov += dx * dy;
+        var_x += dx * dx;
+        var_y += dy * dy;
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(cov / (var_x * var_y).sqrt())
+}
+
+///Returns the rank of every value, starting from 1. Equal values get the average of their ranks.
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+    let mut res = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        // ranks start..end are tied, so each gets their average
+        let rank = (start + end + 1) as f64 / 2.0;
+        for &i in &order[start..end] {
+            res[i] = rank;
+        }
+        start = end;
+    }
+    res
+}
@@ -0,0 +1,62 @@
+//! Offline archives of a season's anime, see [`MALClient::archive_season`](crate::MALClient::archive_season)
+//!
+//! # Example
+//!
+//! ```no_run
+//! use lib_mal::archive::SeasonArchive;
+//! # use lib_mal::MALError;
+//! # fn test() -> Result<(), MALError> {
+//! let archive = SeasonArchive::load("fall-2020.json.gz")?;
+//! for entry in &archive.data {
+//!     println!("{}: {:?}", entry.node.title, entry.details.mean);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use crate::model::options::Season;
+use crate::model::ListNode;
+use crate::MALError;
+
+///Every anime of a season, stored as gzip compressed JSON
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SeasonArchive {
+    pub season: Season,
+    pub year: usize,
+    ///Unix timestamp of when the season was fetched
+    pub fetched_at: u64,
+    pub data: Vec<ListNode>,
+}
+
+impl SeasonArchive {
+    ///Reads an archive written by [`SeasonArchive::save`]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MALError> {
+        let reader = GzDecoder::new(BufReader::new(File::open(path)?));
+        serde_json::from_reader(reader).map_err(|source| MALError::Parse {
+            source,
+            body: String::new(),
+            context: None,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MALError> {
+        let mut writer =
+            GzEncoder::new(BufWriter::new(File::create(path)?), Compression::default());
+        serde_json::to_writer(&mut writer, self).map_err(|source| MALError::Parse {
+            source,
+            body: String::new(),
+            context: None,
+        })?;
+        writer.finish()?;
+        Ok(())
+    }
+}
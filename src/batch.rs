@@ -0,0 +1,136 @@
+//! Applying many list updates at once, see
+//! [`MALClientTrait::apply_updates`](crate::MALClientTrait::apply_updates)
+//!
+//! Meant for imports from other sites, where hundreds of entries change at once. Updates are sent
+//! a few at a time and still go through the client's rate limit.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::batch::BatchOptions;
+//! use lib_mal::model::ids::AnimeId;
+//! use lib_mal::model::options::{Status, StatusUpdate};
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let updates = vec![
+//!     (AnimeId(30230), StatusUpdate::new().status(Status::Completed).build()?),
+//!     (AnimeId(21), StatusUpdate::new().num_watched_episodes(1000).build()?),
+//! ];
+//! let report = client
+//!     .apply_updates(updates, BatchOptions::new().abort_on_error(), |p| {
+//!         println!("{}/{}", p.done, p.total)
+//!     })
+//!     .await;
+//! for (id, e) in report.failed() {
+//!     eprintln!("{id}: {e}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::model::ids::AnimeId;
+use crate::model::ListStatus;
+use crate::MALError;
+
+///Updates `apply_updates` sends at a time unless `BatchOptions::concurrency` was called
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+
+///How `MALClientTrait::apply_updates` sends the updates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchOptions {
+    pub concurrency: usize,
+    ///Stop sending updates after the first one that failed
+    pub abort_on_error: bool,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatchOptions {
+    ///Sends `DEFAULT_BATCH_CONCURRENCY` updates at a time and keeps going after failures
+    pub const fn new() -> Self {
+        Self {
+            concurrency: DEFAULT_BATCH_CONCURRENCY,
+            abort_on_error: false,
+        }
+    }
+
+    ///Sends at most `concurrency` updates at a time, at least one. With more than one, a later
+    ///update of an anime can overtake an earlier one of the same anime.
+    pub const fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = if concurrency == 0 { 1 } else { concurrency };
+        self
+    }
+
+    ///Stops sending updates once one failed. Updates already in flight are still awaited, the
+    ///remaining ones end up as `BatchOutcome::Skipped`. Updates that were applied aren't undone.
+    pub const fn abort_on_error(mut self) -> Self {
+        self.abort_on_error = true;
+        self
+    }
+}
+
+///Passed to the progress callback of `MALClientTrait::apply_updates` after every update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchProgress {
+    ///Id of the anime whose update was just handled
+    pub id: AnimeId,
+    ///Updates handled so far, including failed and skipped ones
+    pub done: usize,
+    ///Updates that failed so far
+    pub failed: usize,
+    pub total: usize,
+}
+
+///What happened to one update of `MALClientTrait::apply_updates`
+#[derive(Debug)]
+pub enum BatchOutcome {
+    ///The update was applied, with the entry as MAL returned it
+    Applied(ListStatus),
+    Failed(MALError),
+    ///The update wasn't sent because an earlier one failed, see `BatchOptions::abort_on_error`
+    Skipped,
+}
+
+///The outcome of `MALClientTrait::apply_updates`, one per update in the order they were passed
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    pub results: Vec<(AnimeId, BatchOutcome)>,
+}
+
+impl BatchReport {
+    ///Returns the anime whose update was applied
+    pub fn applied(&self) -> impl Iterator<Item = (AnimeId, &ListStatus)> {
+        self.results.iter().filter_map(|(id, outcome)| match outcome {
+            BatchOutcome::Applied(status) => Some((*id, status)),
+            _ => None,
+        })
+    }
+
+    ///Returns the anime whose update failed with the error
+    pub fn failed(&self) -> impl Iterator<Item = (AnimeId, &MALError)> {
+        self.results.iter().filter_map(|(id, outcome)| match outcome {
+            BatchOutcome::Failed(e) => Some((*id, e)),
+            _ => None,
+        })
+    }
+
+    ///Returns the anime whose update wasn't sent
+    pub fn skipped(&self) -> impl Iterator<Item = AnimeId> + '_ {
+        self.results
+            .iter()
+            .filter(|(_, outcome)| matches!(outcome, BatchOutcome::Skipped))
+            .map(|(id, _)| *id)
+    }
+
+    ///Returns true if every update was applied
+    pub fn is_complete(&self) -> bool {
+        self.results
+            .iter()
+            .all(|(_, outcome)| matches!(outcome, BatchOutcome::Applied(_)))
+    }
+}
@@ -0,0 +1,290 @@
+//! A client for apps without an async runtime, like CLI scripts and GUI apps
+//!
+//! [`MALClient`] wraps the async [`crate::MALClient`] and runs every request to completion on an
+//! internal single-threaded tokio runtime. Its methods must not be called from within another
+//! async runtime.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use lib_mal::blocking::MALClient;
+//! # use lib_mal::MALError;
+//! # fn test() -> Result<(), MALError> {
+//! let client = MALClient::with_access_token("[YOUR_SECRET_HERE]")?;
+//! let anime = client.get_anime_details(80, None)?;
+//! println!("{}", anime.title);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::future::Future;
+#[cfg(feature = "callback-server")]
+use std::time::Duration;
+
+use tokio::runtime::{Builder, Runtime};
+
+use crate::limits::Limit;
+use crate::model::fields::{AnimeFields, UserFields};
+use crate::model::options::{
+    ForumTopicQuery, RankingType, SeasonYear, SeasonalSort, StatusParams, SuggestionQuery, UserListQuery,
+};
+use crate::model::{
+    AnimeDetails, AnimeId, TopicId, AnimeList, ForumBoards, ForumTopics, ListStatus,
+    TopicDetails, User,
+};
+#[cfg(feature = "callback-server")]
+use crate::cancel::CancellationToken;
+#[cfg(feature = "oauth")]
+use crate::AuthParts;
+use crate::{AuthState, ClientBuilder, MALClientTrait, MALError, ReauthReason};
+
+fn runtime() -> Result<Runtime, MALError> {
+    Ok(Builder::new_current_thread().enable_all().build()?)
+}
+
+///Generates methods that block on the method of the same name of the async client
+macro_rules! blocking {
+    ($(
+        $(#[$attr:meta])*
+        fn $name:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty;
+    )*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name(&self $(, $arg: $ty)*) -> $ret {
+                self.runtime.block_on(self.client.$name($($arg),*))
+            }
+        )*
+    };
+}
+
+///The blocking version of [`crate::MALClient`], see the module docs
+pub struct MALClient {
+    client: crate::MALClient,
+    runtime: Runtime,
+}
+
+impl MALClient {
+    ///Wraps an async client, e.g. one configured with `ClientBuilder::build_no_refresh`
+    pub fn new(client: crate::MALClient) -> Result<Self, MALError> {
+        Ok(Self {
+            client,
+            runtime: runtime()?,
+        })
+    }
+
+    ///See `ClientBuilder::build_blocking`
+    pub(crate) fn build(builder: ClientBuilder) -> Result<Self, MALError> {
+        let runtime = runtime()?;
+        let client = runtime.block_on(builder.build_with_refresh())?;
+        Ok(Self { client, runtime })
+    }
+
+    ///Creates a client that uses `token` as the access token, see
+    ///`MALClientTrait::with_access_token`
+    pub fn with_access_token(token: &str) -> Result<Self, MALError> {
+        Self::new(crate::MALClient::with_access_token(token))
+    }
+
+    ///Returns the async client, e.g. for changing its settings
+    pub const fn inner(&self) -> &crate::MALClient {
+        &self.client
+    }
+
+    pub fn into_inner(self) -> crate::MALClient {
+        self.client
+    }
+
+    ///Runs `future` on the internal runtime, for the async client's methods that don't have a
+    ///blocking version
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// use lib_mal::blocking::MALClient;
+    /// # use lib_mal::MALError;
+    /// # fn test() -> Result<(), MALError> {
+    /// let client = MALClient::with_access_token("[YOUR_SECRET_HERE]")?;
+    /// let report = client.block_on(client.inner().diagnose());
+    /// # Ok(())
+    /// # }
+    ///```
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    ///See `MALClientTrait::get_auth_parts`
+    #[cfg(feature = "oauth")]
+    pub fn get_auth_parts(&self) -> AuthParts {
+        self.client.get_auth_parts()
+    }
+
+    ///See `MALClientTrait::get_auth_parts_with`
+    #[cfg(feature = "oauth")]
+    pub fn get_auth_parts_with(
+        &self,
+        redirect_uri: Option<&str>,
+        extra_params: &[(&str, &str)],
+    ) -> AuthParts {
+        self.client.get_auth_parts_with(redirect_uri, extra_params)
+    }
+
+    ///See `MALClientTrait::auth`
+    #[cfg(feature = "callback-server")]
+    pub fn auth(
+        &mut self,
+        callback_url: &str,
+        challenge: &str,
+        state: &str,
+    ) -> Result<(), MALError> {
+        self.runtime
+            .block_on(self.client.auth(callback_url, challenge, state))
+    }
+
+    ///See `MALClientTrait::auth_with_timeout`
+    #[cfg(feature = "callback-server")]
+    pub fn auth_with_timeout(
+        &mut self,
+        callback_url: &str,
+        challenge: &str,
+        state: &str,
+        timeout: Option<Duration>,
+    ) -> Result<(), MALError> {
+        self.runtime.block_on(self.client.auth_with_timeout(
+            callback_url,
+            challenge,
+            state,
+            timeout,
+        ))
+    }
+
+    ///See `MALClientTrait::auth_with_cancellation`
+    #[cfg(feature = "callback-server")]
+    pub fn auth_with_cancellation(
+        &mut self,
+        callback_url: &str,
+        challenge: &str,
+        state: &str,
+        cancel: CancellationToken,
+    ) -> Result<(), MALError> {
+        self.runtime.block_on(self.client.auth_with_cancellation(
+            callback_url,
+            challenge,
+            state,
+            cancel,
+        ))
+    }
+
+    ///See `MALClientTrait::auth_with_code`
+    #[cfg(feature = "oauth")]
+    pub fn auth_with_code(&mut self, code: &str, code_verifier: &str) -> Result<(), MALError> {
+        self.runtime
+            .block_on(self.client.auth_with_code(code, code_verifier))
+    }
+
+    pub fn get_access_token(&self) -> String {
+        self.client.get_access_token()
+    }
+
+    pub fn auth_state(&self) -> AuthState {
+        self.client.auth_state()
+    }
+
+    ///Returns true if the user has to authorize the client before it can make requests
+    pub fn need_auth(&self) -> bool {
+        self.client.need_auth()
+    }
+
+    pub fn reauth_reason(&self) -> Option<&ReauthReason> {
+        self.client.reauth_reason()
+    }
+
+    blocking! {
+        ///See `MALClientTrait::get_anime_list`
+        fn get_anime_list(
+            &self,
+            query: &str,
+            limit: impl Into<Limit> + Send,
+            fields: impl Into<Option<AnimeFields>> + Send
+        ) -> Result<AnimeList, MALError>;
+        ///See `MALClientTrait::get_anime_details`
+        fn get_anime_details(
+            &self,
+            id: impl Into<AnimeId> + Send,
+            fields: impl Into<Option<AnimeFields>> + Send
+        ) -> Result<AnimeDetails, MALError>;
+        ///See `MALClientTrait::get_anime_ranking`
+        fn get_anime_ranking(
+            &self,
+            ranking_type: RankingType,
+            limit: impl Into<Limit> + Send,
+            fields: impl Into<Option<AnimeFields>> + Send
+        ) -> Result<AnimeList, MALError>;
+        ///See `MALClientTrait::get_seasonal_anime`
+        fn get_seasonal_anime(
+            &self,
+            season: impl Into<SeasonYear> + Send,
+            limit: impl Into<Limit> + Send,
+            fields: impl Into<Option<AnimeFields>> + Send,
+            sort: impl Into<Option<SeasonalSort>> + Send
+        ) -> Result<AnimeList, MALError>;
+        ///See `MALClientTrait::get_suggested_anime`
+        fn get_suggested_anime(
+            &self,
+            limit: impl Into<Limit> + Send,
+            fields: impl Into<Option<AnimeFields>> + Send
+        ) -> Result<AnimeList, MALError>;
+        ///See `MALClientTrait::get_suggestions`
+        fn get_suggestions(&self, query: SuggestionQuery) -> Result<AnimeList, MALError>;
+        ///See `MALClientTrait::update_user_anime_status`
+        fn update_user_anime_status(&self, id: impl Into<AnimeId> + Send, update: StatusParams) -> Result<ListStatus, MALError>;
+        ///See `MALClientTrait::get_user_anime_list`
+        fn get_user_anime_list(
+            &self,
+            query: impl Into<Option<UserListQuery>> + Send
+        ) -> Result<AnimeList, MALError>;
+        ///See `MALClientTrait::get_user_anime_list_for`
+        fn get_user_anime_list_for(
+            &self,
+            username: &str,
+            query: impl Into<Option<UserListQuery>> + Send
+        ) -> Result<AnimeList, MALError>;
+        ///See `MALClientTrait::increment_watched_episodes`
+        fn increment_watched_episodes(&self, id: impl Into<AnimeId> + Send, by: usize) -> Result<ListStatus, MALError>;
+        ///See `MALClientTrait::mark_completed`
+        fn mark_completed(
+            &self,
+            id: impl Into<AnimeId> + Send,
+            score: impl Into<Option<u8>> + Send
+        ) -> Result<ListStatus, MALError>;
+        ///See `MALClientTrait::delete_anime_list_item`
+        fn delete_anime_list_item(&self, id: impl Into<AnimeId> + Send) -> Result<(), MALError>;
+        ///See `MALClientTrait::get_forum_boards`
+        fn get_forum_boards(&self) -> Result<ForumBoards, MALError>;
+        ///See `MALClientTrait::get_forum_topic_detail`
+        fn get_forum_topic_detail(
+            &self,
+            topic_id: impl Into<TopicId> + Send,
+            limit: impl Into<Limit> + Send
+        ) -> Result<TopicDetails, MALError>;
+        ///See `MALClientTrait::get_forum_topic_posts`
+        fn get_forum_topic_posts(
+            &self,
+            topic_id: impl Into<TopicId> + Send,
+            limit: impl Into<Limit> + Send,
+            offset: usize
+        ) -> Result<TopicDetails, MALError>;
+        ///See `MALClientTrait::get_forum_topics`
+        fn get_forum_topics(
+            &self,
+            query: impl Into<Option<ForumTopicQuery>> + Send
+        ) -> Result<ForumTopics, MALError>;
+        ///See `MALClientTrait::get_my_user_info`
+        fn get_my_user_info(&self, fields: impl Into<Option<UserFields>> + Send) -> Result<User, MALError>;
+        ///See `MALClientTrait::get_user_info`
+        fn get_user_info(
+            &self,
+            username: &str,
+            fields: impl Into<Option<UserFields>> + Send
+        ) -> Result<User, MALError>;
+    }
+}
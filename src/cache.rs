@@ -0,0 +1,358 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::clock::{unix_secs, Clock, SystemClock};
+
+///Settings of the response cache, see `ClientBuilder::response_cache`
+///
+///Covers `get_anime_details`, `get_seasonal_anime`, `get_anime_ranking`, and `get_anime_list`.
+///Responses are cached by URL, so requests with different fields or limits are cached
+///separately. Expired responses with an `ETag` or `Last-Modified` header are revalidated with a
+///conditional request instead of being downloaded again.
+///
+///# Example
+///
+///```
+/// use lib_mal::{CacheCodec, CacheConfig, ClientBuilder, MALClient};
+/// use std::path::PathBuf;
+/// use std::time::Duration;
+///
+/// let config = CacheConfig::new(Duration::from_secs(60 * 60))
+///     .capacity(500)
+///     .disk(PathBuf::from("[SOME_CACHE_DIR]"))
+///     .codec(CacheCodec::Json);
+/// let client: MALClient = ClientBuilder::new().response_cache(config).build_no_refresh();
+///```
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    ttl: Duration,
+    capacity: usize,
+    dir: Option<PathBuf>,
+    codec: CacheCodec,
+}
+
+impl CacheConfig {
+    ///Caches up to 256 responses in memory for `ttl`
+    pub const fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            capacity: 256,
+            dir: None,
+            codec: CacheCodec::Json,
+        }
+    }
+
+    ///Sets how many responses are kept in memory, the least recently used ones are dropped first
+    pub const fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    ///Also stores the responses in `dir` so they survive restarts
+    pub fn disk(mut self, dir: PathBuf) -> Self {
+        self.dir = Some(dir);
+        self
+    }
+
+    ///Sets the format of the files written to the cache directory, JSON by default
+    pub const fn codec(mut self, codec: CacheCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+}
+
+///Format of the files written by the response cache, see `CacheConfig::codec`
+///
+///The binary formats are smaller and faster to read, which helps with big cached lists on slow
+///devices. Each codec uses its own file extension, so switching codecs starts with an empty
+///cache instead of failing to read the old files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CacheCodec {
+    ///Human readable, handy for debugging
+    #[default]
+    Json,
+    ///Requires the `bincode` feature
+    #[cfg(feature = "bincode")]
+    Bincode,
+    ///Requires the `postcard` feature
+    #[cfg(feature = "postcard")]
+    Postcard,
+}
+
+impl CacheCodec {
+    const fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            #[cfg(feature = "bincode")]
+            Self::Bincode => "bincode",
+            #[cfg(feature = "postcard")]
+            Self::Postcard => "postcard",
+        }
+    }
+
+    fn encode<T: Serialize>(self, value: &T) -> Option<Vec<u8>> {
+        match self {
+            Self::Json => serde_json::to_vec(value).ok(),
+            #[cfg(feature = "bincode")]
+            Self::Bincode => bincode::serialize(value).ok(),
+            #[cfg(feature = "postcard")]
+            Self::Postcard => postcard::to_stdvec(value).ok(),
+        }
+    }
+
+    fn decode<T: DeserializeOwned>(self, raw: &[u8]) -> Option<T> {
+        match self {
+            Self::Json => serde_json::from_slice(raw).ok(),
+            #[cfg(feature = "bincode")]
+            Self::Bincode => bincode::deserialize(raw).ok(),
+            #[cfg(feature = "postcard")]
+            Self::Postcard => postcard::from_bytes(raw).ok(),
+        }
+    }
+}
+
+///How often the response cache was used, see `MALClient::cache_stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    ///Responses served from the cache without sending a request
+    pub hits: u64,
+    ///Expired responses the API confirmed are unchanged with 304 Not Modified
+    pub revalidated: u64,
+    ///Responses that had to be downloaded
+    pub misses: u64,
+}
+
+///The `ETag` and `Last-Modified` headers of a cached response, used to ask the API if it changed
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Entry {
+    url: String,
+    ///Unix timestamp of when the response was received
+    stored_at: u64,
+    body: String,
+    #[serde(default)]
+    validators: Validators,
+}
+
+///The result of looking up a URL in the cache
+pub enum Lookup {
+    Fresh(String),
+    ///The cached response expired but can be revalidated with these
+    Stale(Validators),
+    Missing,
+}
+
+///An in-memory LRU cache of response bodies, optionally backed by a directory
+pub struct ResponseCache {
+    config: CacheConfig,
+    ///Decides when responses expire, see `ClientBuilder::clock`
+    pub(crate) clock: Arc<dyn Clock>,
+    entries: Mutex<Lru>,
+    ///When responses with a URL starting with the key were last invalidated, so older copies
+    ///on disk aren't loaded again
+    invalidated: Mutex<HashMap<String, u64>>,
+    hits: AtomicU64,
+    revalidated: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<String, Entry>,
+    ///Least recently used first
+    order: VecDeque<String>,
+}
+
+impl Lru {
+    fn touch(&mut self, url: &str) {
+        if let Some(i) = self.order.iter().position(|u| u == url) {
+            if let Some(url) = self.order.remove(i) {
+                self.order.push_back(url);
+            }
+        }
+    }
+
+    fn insert(&mut self, entry: Entry, capacity: usize) {
+        if self.entries.contains_key(&entry.url) {
+            self.touch(&entry.url);
+        } else {
+            self.order.push_back(entry.url.clone());
+        }
+        self.entries.insert(entry.url.clone(), entry);
+        while self.order.len() > capacity {
+            if let Some(url) = self.order.pop_front() {
+                self.entries.remove(&url);
+            }
+        }
+    }
+}
+
+impl ResponseCache {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            clock: Arc::new(SystemClock),
+            entries: Mutex::new(Lru::default()),
+            invalidated: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            revalidated: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    ///Returns the cached body for `url` if it hasn't expired, or the validators of the expired
+    ///response so it can be revalidated
+    pub fn lookup(&self, url: &str) -> Lookup {
+        let mut lru = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if !lru.entries.contains_key(url) {
+            if let Some(entry) = self.read_disk(url) {
+                lru.insert(entry, self.config.capacity);
+            }
+        }
+        let Some(entry) = lru.entries.get(url) else {
+            return Lookup::Missing;
+        };
+        if self.is_fresh(entry) {
+            let body = entry.body.clone();
+            lru.touch(url);
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            Lookup::Fresh(body)
+        } else if entry.validators.is_empty() {
+            Lookup::Missing
+        } else {
+            Lookup::Stale(entry.validators.clone())
+        }
+    }
+
+    ///Marks the expired response for `url` as fresh again after the API responded with 304 Not
+    ///Modified and returns its body
+    pub fn revalidate(&self, url: &str) -> Option<String> {
+        let mut lru = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let entry = lru.entries.get_mut(url)?;
+        entry.stored_at = self.now();
+        let entry = entry.clone();
+        lru.touch(url);
+        drop(lru);
+        self.write_disk(&entry);
+        self.revalidated.fetch_add(1, Ordering::Relaxed);
+        Some(entry.body)
+    }
+
+    pub fn insert(&self, url: &str, body: String, validators: Validators) {
+        let entry = Entry {
+            url: url.to_owned(),
+            stored_at: self.now(),
+            body,
+            validators,
+        };
+        self.write_disk(&entry);
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(entry, self.config.capacity);
+    }
+
+    ///Drops the responses with a URL starting with `prefix`, e.g. after a write changed what
+    ///they'd return. The copies on disk are ignored from now on and replaced by the next response.
+    pub(crate) fn invalidate(&self, prefix: &str) {
+        self.invalidated
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(prefix.to_owned(), self.now());
+        let mut lru = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        lru.entries.retain(|url, _| !url.starts_with(prefix));
+        lru.order.retain(|url| !url.starts_with(prefix));
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            revalidated: self.revalidated.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    ///Returns the directory the responses are stored in, if any
+    pub fn dir(&self) -> Option<&Path> {
+        self.config.dir.as_deref()
+    }
+
+    fn is_fresh(&self, entry: &Entry) -> bool {
+        self.now().saturating_sub(entry.stored_at) < self.config.ttl.as_secs()
+    }
+
+    fn now(&self) -> u64 {
+        unix_secs(self.clock.now())
+    }
+
+    fn path(&self, url: &str) -> Option<PathBuf> {
+        self.config
+            .dir
+            .as_ref()
+            .map(|dir| {
+                dir.join(format!(
+                    "{:016x}.{}",
+                    fnv1a(url),
+                    self.config.codec.extension()
+                ))
+            })
+    }
+
+    fn read_disk(&self, url: &str) -> Option<Entry> {
+        let raw = fs::read(self.path(url)?).ok()?;
+        self.config
+            .codec
+            .decode::<Entry>(&raw)
+            // guards against hash collisions
+            .filter(|e| e.url == url)
+            .filter(|e| !self.is_invalidated(e))
+    }
+
+    fn is_invalidated(&self, entry: &Entry) -> bool {
+        self.invalidated
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .any(|(prefix, at)| entry.url.starts_with(prefix) && entry.stored_at <= *at)
+    }
+
+    ///Writes the entry to the cache directory, if any. The cache is best effort so failing to
+    ///write is ignored.
+    fn write_disk(&self, entry: &Entry) {
+        if let Some(path) = self.path(&entry.url) {
+            if let Some(raw) = self.config.codec.encode(entry) {
+                if let Some(parent) = path.parent() {
+                    let _ = fs::create_dir_all(parent);
+                }
+                let _ = fs::write(path, raw);
+            }
+        }
+    }
+}
+
+///A hash that stays the same across Rust versions, unlike `DefaultHasher`, so file names on disk
+///remain valid
+pub(crate) fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
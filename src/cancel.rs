@@ -0,0 +1,55 @@
+//! Cancelling requests and long operations like fetching a whole list, crawling a franchise, or
+//! fetching the details of many anime
+//!
+//! Every future returned by the client is cancel-safe: dropping it stops the operation at the
+//! next `.await` without leaving the client in a broken state. Requests that were already sent
+//! may still be applied by MAL, so a cancelled `update_user_anime_status` may or may not have
+//! changed the list. Token refreshes are only saved once they're complete, and the response
+//! cache only stores whole responses.
+//!
+//! [`with_cancellation`] drops an operation once a [`CancellationToken`] is cancelled, so it can
+//! be stopped from elsewhere, e.g. a "Cancel" button or a shutdown handler. The callback listener
+//! of `MALClientTrait::auth_with_cancellation` and `ListWatcher::stop` use the same tokens.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::cancel::{with_cancellation, CancellationToken};
+//! use std::time::Duration;
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let token = CancellationToken::new();
+//! let cancel = token.clone();
+//! tokio::spawn(async move {
+//!     tokio::time::sleep(Duration::from_secs(10)).await;
+//!     cancel.cancel();
+//! });
+//! match with_cancellation(&token, client.get_franchise(1535, 5)).await {
+//!     Err(MALError::Cancelled) => println!("took too long"),
+//!     res => println!("{} anime", res?.anime.len()),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::future::Future;
+
+use futures_util::future::{self, Either};
+pub use tokio_util::sync::CancellationToken;
+
+use crate::MALError;
+
+///Runs `operation` until it's done or `token` is cancelled, whichever comes first. Fails with
+///`MALError::Cancelled` if the token was cancelled, `operation` is dropped then.
+pub async fn with_cancellation<T>(
+    token: &CancellationToken,
+    operation: impl Future<Output = Result<T, MALError>>,
+) -> Result<T, MALError> {
+    let cancelled = std::pin::pin!(token.cancelled());
+    let operation = std::pin::pin!(operation);
+    match future::select(cancelled, operation).await {
+        Either::Left(_) => Err(MALError::Cancelled),
+        Either::Right((res, _)) => res,
+    }
+}
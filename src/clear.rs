@@ -0,0 +1,74 @@
+//! Deleting every entry of the user's anime list, see
+//! [`MALClient::clear_list`](crate::MALClient::clear_list)
+//!
+//! Clearing a list can't be undone, so [`ListClearOptions`] has to be built with
+//! [`CLEAR_LIST_CONFIRMATION`]. Consider exporting the list first, see `model::export`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::clear::{ListClearOptions, CLEAR_LIST_CONFIRMATION};
+//! use lib_mal::model::options::Status;
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let options = ListClearOptions::new(CLEAR_LIST_CONFIRMATION).status(Status::PlanToWatch);
+//! let report = client
+//!     .clear_list(options, |p| println!("{}/{}", p.done, p.total))
+//!     .await?;
+//! println!("deleted {}, {} failed", report.deleted.len(), report.failed.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::model::ids::AnimeId;
+use crate::model::options::Status;
+use crate::MALError;
+
+///The confirmation `ListClearOptions::new` has to be called with
+pub const CLEAR_LIST_CONFIRMATION: &str = "DELETE MY ANIME LIST";
+
+///What `MALClient::clear_list` deletes
+#[derive(Debug, Clone)]
+pub struct ListClearOptions {
+    pub(crate) confirmation: String,
+    pub status: Option<Status>,
+}
+
+impl ListClearOptions {
+    ///`confirmation` has to be [`CLEAR_LIST_CONFIRMATION`], otherwise `clear_list` fails
+    ///without deleting anything
+    pub fn new(confirmation: &str) -> Self {
+        Self {
+            confirmation: confirmation.to_owned(),
+            status: None,
+        }
+    }
+
+    ///Only deletes the entries with `status`, every entry is deleted by default
+    pub fn status(mut self, status: impl Into<Option<Status>>) -> Self {
+        self.status = status.into();
+        self
+    }
+
+    pub(crate) fn is_confirmed(&self) -> bool {
+        self.confirmation == CLEAR_LIST_CONFIRMATION
+    }
+}
+
+///Passed to the progress callback of `MALClient::clear_list` after every entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClearProgress {
+    ///Id of the anime that was just handled
+    pub id: AnimeId,
+    ///Entries handled so far, including failed ones
+    pub done: usize,
+    pub total: usize,
+}
+
+///The outcome of `MALClient::clear_list`, by anime id
+#[derive(Debug, Default)]
+pub struct ClearReport {
+    pub deleted: Vec<AnimeId>,
+    pub failed: Vec<(AnimeId, MALError)>,
+}
@@ -1,22 +1,86 @@
 use crate::{
+    affinity::AffinityReport,
+    archive::SeasonArchive,
+    diagnostics::{Check, CheckKind, CheckStatus, Diagnostics},
+    list_cache,
+    limits::{
+        Limit, DEFAULT_MAX_RETRIES, MAX_FORUM_TOPICS_LIMIT, MAX_RANKING_LIMIT,
+        MAX_SEARCH_LIMIT, MAX_SEASONAL_LIMIT, MAX_SUGGESTIONS_LIMIT, MAX_TOPIC_POSTS_LIMIT,
+        MAX_USER_LIST_LIMIT, RECOMMENDED_REQUESTS_PER_SECOND,
+    },
+    rate_limit::{self, RateLimiter},
+    titles, trace,
     model::{
-        fields::AnimeFields,
-        options::{Params, RankingType, Season, StatusUpdate},
-        AnimeDetails, AnimeList, EpisodesList, ForumBoards, ForumTopics, ListStatus, TopicDetails,
-        User,
+        export::{ConflictPolicy, ExportEntry, ImportReport},
+        fields::{AnimeFields, UserFields},
+        ids::{AnimeId, TopicId},
+        options::{
+            ForumTopicQuery, ListSort, Params, SuggestionQuery, RankingType, RelationQuery, RelationType, Season, SeasonYear, SeasonalSort, Status, StatusParams, StatusUpdate, date_of, parse_timestamp,
+            UserListQuery,
+        },
+        AiringEvent, AiringProgress, AnimeDetails, Franchise, AnimeList, AnimeMatch, EnrichProgress, EnrichedList, PageCursor, EnrichedNode, ForumBoards, ForumTopics, ListNode, ListStatus, NowAiring, now_airing, SharedEntry, StaleEntry, TopicDetails,
+        User, WithRaw,
     },
-    prelude::EpisodeNode,
+};
+#[cfg(not(feature = "minimal-models"))]
+use crate::model::{rank_recommendations, ListRecommendation};
+use futures_util::future::BoxFuture;
+use futures_util::{future, stream, Stream, StreamExt};
+#[cfg(feature = "oauth")]
+use aes_gcm::aead::{rand_core::RngCore, OsRng};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE, DATE, ETAG, IF_MODIFIED_SINCE,
+    IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER,
 };
 use reqwest::Client;
-use reqwest::{Method, StatusCode};
-use serde::{Deserialize, Serialize};
-use std::{fs::File, io::Write, path::PathBuf, str, time::SystemTime};
-use tiny_http::{Response, Server};
+use reqwest::{Method, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, HashSet},
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    str,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError, RwLock, RwLockReadGuard,
+    },
+    time::{Duration, SystemTime},
+};
+#[cfg(feature = "callback-server")]
+use tiny_http::{Header, Response, Server};
+use tokio::sync::watch;
 
-use crate::MALError;
+#[cfg(feature = "oauth")]
+use crate::FileTokenStore;
+#[cfg(not(feature = "oauth"))]
+use crate::MemoryTokenStore;
+use crate::cache::{Lookup, ResponseCache, Validators};
+use crate::debug::{DebugSink, DebugWriter, Dumping};
+use crate::middleware::Hooked;
+use crate::rt::{self, Instant};
+use crate::batch::{BatchOptions, BatchOutcome, BatchProgress, BatchReport};
+#[cfg(feature = "callback-server")]
+use crate::cancel::{with_cancellation, CancellationToken};
+use crate::clear::{ClearProgress, ClearReport, ListClearOptions};
+use crate::clock::{unix_secs, Clock, SystemClock};
+use crate::endpoint::Endpoint;
+use crate::idmap::{self, IdMap, Service};
+use crate::journal::{self, Journal, JournalEntry, JOURNAL_FILE};
+use crate::pictures::{self, Picture, PictureSize};
+use crate::offline::{self, FlushReport, OfflineQueue, PendingAction, PendingChange, QUEUE_FILE};
+use crate::prioritize::{self, PriorityWeights, RankedEntry};
+use crate::ranking::{self, RankingDigest, RankingSnapshot};
+use crate::schedule::{self, AiringSchedule, UtcOffset};
+use crate::scrobble::{self, ScrobbleReport};
+use crate::sync::{self, ListSnapshot, SyncDirection, SyncReport};
+use crate::watch::{reached_threshold, WatchReport, WatchSession, DEFAULT_WATCH_THRESHOLD};
+#[cfg(feature = "jikan")]
+use crate::jikan::{self, JikanClient, JIKAN_API_URL};
+use crate::{AnimeHandle, CacheConfig, ListSession, CacheStats, HttpRequest, HttpResponse, HttpTransport, MALError, MetricsSink, ReqwestTransport, RequestInterceptor, RequestMetrics, TokenStore};
 
-use aes_gcm::{aead::Aead, KeyInit};
-use aes_gcm::{Aes256Gcm, Key, Nonce};
 
 ///Exposes all of the API functions for the [MyAnimeList API](https://myanimelist.net/apiconfig/references/api/v2)
 ///
@@ -40,802 +104,4399 @@ use aes_gcm::{Aes256Gcm, Key, Nonce};
 /// # Ok(())
 /// # }
 ///```
+///
+///# Thread safety
+///
+///`MALClient` is `Send + Sync + Clone`, so one client can serve every request of a web server
+///without wrapping it in a `Mutex`. Cloning the client is cheap: the clones share the HTTP
+///connection pool, the tokens, the rate limiter, and the response cache, so a token refresh or a
+///rate limit on one applies to all of them. Settings changed on a clone, like `debug_mode`, only
+///apply to that clone.
+///
+///```no_run
+/// # use lib_mal::{MALClient, MALClientTrait, MALError};
+/// # async fn test() -> Result<(), MALError> {
+/// let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+/// let tasks: Vec<_> = [1, 5, 30230]
+///     .into_iter()
+///     .map(|id| {
+///         let client = client.clone();
+///         tokio::spawn(async move { client.get_anime_details(id, None).await })
+///     })
+///     .collect();
+/// for task in tasks {
+///     println!("{}", task.await.unwrap()?.show.title);
+/// }
+/// # Ok(())
+/// # }
+///```
+#[derive(Clone)]
 #[allow(clippy::module_name_repetitions)]
 pub struct MALClient {
-    client_secret: String,
+    client_id: String,
+    ///Only set for confidential clients, see `ClientBuilder::client_secret`
+    client_secret: Option<String>,
     dirs: PathBuf,
-    access_token: String,
-    client: reqwest::Client,
+    tokens: Arc<RwLock<Tokens>>,
+    store: Arc<dyn TokenStore>,
+    transport: Arc<dyn HttpTransport>,
     caching: bool,
-    pub need_auth: bool,
+    #[cfg_attr(not(feature = "callback-server"), allow(dead_code))]
+    callback_page: CallbackPage,
+    auth_state: Arc<watch::Sender<AuthState>>,
+    reauth_reason: Option<ReauthReason>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    max_retries: u32,
+    middleware: Vec<Arc<dyn RequestInterceptor>>,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    ///Receives the dumps of `debug_mode`
+    debug_sink: Option<Arc<dyn DebugSink>>,
+    debug: bool,
+    response_cache: Option<Arc<ResponseCache>>,
+    include_nsfw: bool,
+    allow_scraping: bool,
+    ///The `Cookie` header of a logged in myanimelist.net session, see
+    ///`ClientBuilder::site_session`
+    #[cfg_attr(not(feature = "scraping"), allow(dead_code))]
+    site_session: Option<String>,
+    public_client: bool,
+    default_anime_fields: AnimeFields,
+    journal: Option<Arc<Journal>>,
+    offline_queue: Option<Arc<OfflineQueue>>,
+    watch_session: Arc<Mutex<Option<WatchSession>>>,
+    ///The ids of the anime on the user's list, loaded by `get_suggestions` when it's needed
+    list_ids: Arc<Mutex<Option<HashSet<AnimeId>>>>,
+    watch_threshold: f32,
+    ///See `ClientBuilder::auto_paginate`
+    auto_paginate: Option<usize>,
+    clock: Arc<dyn Clock>,
+    ///Replaces `https://api.myanimelist.net/` in API requests
+    api_base_url: String,
+    ///Replaces `https://myanimelist.net/` in the OAuth URLs
+    auth_base_url: String,
+    #[cfg(feature = "jikan")]
+    jikan_rate_limiter: Arc<RateLimiter>,
 }
 
+///The API of a MAL client, implemented by `MALClient` and `MockMALClient`
+///
+///Both clients implement the same trait with the same signatures, so code that's generic over it
+///can run against the mock in tests and the real client in the app. The futures are `Send`, so
+///they can be spawned on a multi-threaded runtime, and cancel-safe, see the `cancel` module.
+///
+///# Example
+///
+///```
+/// use lib_mal::{MALClient, MALClientTrait, MALError, MockMALClient};
+///
+/// async fn title_of<C: MALClientTrait + Sync>(client: &C, id: usize) -> Result<String, MALError> {
+///     Ok(client.get_anime_details(id, None).await?.show.title)
+/// }
+///
+/// # async fn test() -> Result<(), MALError> {
+/// let mock = MockMALClient::with_access_token("token");
+/// assert_eq!(title_of(&mock, 30230).await?, "Diamond no Ace: Second Season");
+///
+/// let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+/// println!("{}", title_of(&client, 30230).await?);
+/// # Ok(())
+/// # }
+///```
 pub trait MALClientTrait {
     fn new(
         client_secret: String,
         dirs: PathBuf,
-        access_token: String,
+        tokens: Tokens,
         client: Client,
         caching: bool,
-        need_auth: bool,
+        auth_state: AuthState,
     ) -> Self;
     fn with_access_token(token: &str) -> Self;
     fn set_cache_dir(&mut self, dir: PathBuf);
     fn set_caching(&mut self, caching: bool);
-    fn get_auth_parts(&self) -> (String, String, String);
+    ///Returns the auth URL, code verifier, and state which will be needed to authorize the user.
+    ///See `get_auth_parts_with`.
+    #[cfg(feature = "oauth")]
+    fn get_auth_parts(&self) -> AuthParts {
+        self.get_auth_parts_with(None, &[])
+    }
+    #[cfg(feature = "oauth")]
+    fn get_auth_parts_with(
+        &self,
+        redirect_uri: Option<&str>,
+        extra_params: &[(&str, &str)],
+    ) -> AuthParts;
+    #[cfg(feature = "callback-server")]
     fn auth(
         &mut self,
         callback_url: &str,
         challenge: &str,
         state: &str,
     ) -> impl std::future::Future<Output = Result<(), MALError>> + Send;
-    fn get_access_token(&self) -> &str;
+    #[cfg(feature = "callback-server")]
+    fn auth_with_timeout(
+        &mut self,
+        callback_url: &str,
+        challenge: &str,
+        state: &str,
+        timeout: Option<Duration>,
+    ) -> impl std::future::Future<Output = Result<(), MALError>> + Send;
+    #[cfg(feature = "callback-server")]
+    fn auth_with_cancellation(
+        &mut self,
+        callback_url: &str,
+        challenge: &str,
+        state: &str,
+        cancel: CancellationToken,
+    ) -> impl std::future::Future<Output = Result<(), MALError>> + Send;
+    #[cfg(feature = "oauth")]
+    fn auth_with_code(
+        &mut self,
+        code: &str,
+        code_verifier: &str,
+    ) -> impl std::future::Future<Output = Result<(), MALError>> + Send;
+    fn get_access_token(&self) -> String;
     fn get_anime_list(
         &self,
         query: &str,
-        limit: impl Into<Option<u8>> + Send,
+        limit: impl Into<Limit> + Send,
+        fields: impl Into<Option<AnimeFields>> + Send,
     ) -> impl std::future::Future<Output = Result<AnimeList, MALError>> + Send;
     fn get_anime_details(
         &self,
-        id: usize,
+        id: impl Into<AnimeId> + Send,
         fields: impl Into<Option<AnimeFields>> + Send,
     ) -> impl std::future::Future<Output = Result<AnimeDetails, MALError>> + Send;
     fn get_anime_ranking(
         &self,
         ranking_type: RankingType,
-        limit: impl Into<Option<u8>> + Send,
+        limit: impl Into<Limit> + Send,
+        fields: impl Into<Option<AnimeFields>> + Send,
     ) -> impl std::future::Future<Output = Result<AnimeList, MALError>> + Send;
     fn get_seasonal_anime(
         &self,
-        season: Season,
-        year: usize,
-        limit: impl Into<Option<u8>> + Send,
+        season: impl Into<SeasonYear> + Send,
+        limit: impl Into<Limit> + Send,
+        fields: impl Into<Option<AnimeFields>> + Send,
+        sort: impl Into<Option<SeasonalSort>> + Send,
     ) -> impl std::future::Future<Output = Result<AnimeList, MALError>> + Send;
     fn get_suggested_anime(
         &self,
-        limit: impl Into<Option<u8>> + Send,
+        limit: impl Into<Limit> + Send,
+        fields: impl Into<Option<AnimeFields>> + Send,
+    ) -> impl std::future::Future<Output = Result<AnimeList, MALError>> + Send;
+    fn get_suggestions(
+        &self,
+        query: SuggestionQuery,
     ) -> impl std::future::Future<Output = Result<AnimeList, MALError>> + Send;
     fn update_user_anime_status(
         &self,
-        id: usize,
-        update: StatusUpdate,
+        id: impl Into<AnimeId> + Send,
+        update: StatusParams,
+    ) -> impl std::future::Future<Output = Result<ListStatus, MALError>> + Send;
+    fn get_user_anime_list(
+        &self,
+        query: impl Into<Option<UserListQuery>> + Send,
+    ) -> impl std::future::Future<Output = Result<AnimeList, MALError>> + Send;
+    fn get_user_anime_list_for(
+        &self,
+        username: &str,
+        query: impl Into<Option<UserListQuery>> + Send,
+    ) -> impl std::future::Future<Output = Result<AnimeList, MALError>> + Send;
+    fn increment_watched_episodes(
+        &self,
+        id: impl Into<AnimeId> + Send,
+        by: usize,
     ) -> impl std::future::Future<Output = Result<ListStatus, MALError>> + Send;
-    fn get_user_anime_list(&self) -> impl std::future::Future<Output = Result<AnimeList, MALError>> + Send;
-    fn delete_anime_list_item(&self, id: usize) -> impl std::future::Future<Output = Result<(), MALError>> + Send;
+    fn mark_completed(
+        &self,
+        id: impl Into<AnimeId> + Send,
+        score: impl Into<Option<u8>> + Send,
+    ) -> impl std::future::Future<Output = Result<ListStatus, MALError>> + Send;
+    fn delete_anime_list_item(&self, id: impl Into<AnimeId> + Send) -> impl std::future::Future<Output = Result<(), MALError>> + Send;
     fn get_forum_boards(&self) -> impl std::future::Future<Output = Result<ForumBoards, MALError>> + Send;
     fn get_forum_topic_detail(
         &self,
-        topic_id: usize,
-        limit: impl Into<Option<u8>> + Send,
+        topic_id: impl Into<TopicId> + Send,
+        limit: impl Into<Limit> + Send,
+    ) -> impl std::future::Future<Output = Result<TopicDetails, MALError>> + Send;
+    fn get_forum_topic_posts(
+        &self,
+        topic_id: impl Into<TopicId> + Send,
+        limit: impl Into<Limit> + Send,
+        offset: usize,
     ) -> impl std::future::Future<Output = Result<TopicDetails, MALError>> + Send;
     fn get_forum_topics(
         &self,
-        board_id: impl Into<Option<usize>> + Send,
-        subboard_id: impl Into<Option<usize>> + Send,
-        query: impl Into<Option<String>> + Send,
-        topic_user_name: impl Into<Option<String>> + Send,
-        user_name: impl Into<Option<String>> + Send,
-        limit: impl Into<Option<usize>> + Send,
+        query: impl Into<Option<ForumTopicQuery>> + Send,
     ) -> impl std::future::Future<Output = Result<ForumTopics, MALError>> + Send;
-    fn get_my_user_info(&self) -> impl std::future::Future<Output = Result<User, MALError>> + Send;
-    fn get_anime_episodes(
+    fn get_my_user_info(
         &self,
-        id: usize,
-        precise_score: bool,
-    ) -> impl std::future::Future<Output = Result<EpisodesList, MALError>> + Send;
-    fn need_auth(&self) -> bool;
-}
-
-impl MALClientTrait for MALClient {
-    fn new(
-        client_secret: String,
-        dirs: PathBuf,
-        access_token: String,
-        client: Client,
-        caching: bool,
-        need_auth: bool,
-    ) -> Self {
-        Self {
-            client_secret,
-            dirs,
-            access_token,
-            client,
-            caching,
-            need_auth,
-        }
-    }
-    ///Creates a client using provided token. Caching is disable by default.
-    ///
-    ///A client created this way can't authenticate the user if needed because it lacks a
-    ///`client_secret`
-    fn with_access_token(token: &str) -> Self {
-        Self {
-            client_secret: String::new(),
-            need_auth: false,
-            dirs: PathBuf::new(),
-            access_token: token.to_owned(),
-            client: reqwest::Client::new(),
-            caching: false,
-        }
-    }
-
-    ///Sets the directory the client will use for the token cache
-    fn set_cache_dir(&mut self, dir: PathBuf) {
-        self.dirs = dir;
-    }
-
-    ///Sets wether the client will cache or not
-    fn set_caching(&mut self, caching: bool) {
-        self.caching = caching;
+        fields: impl Into<Option<UserFields>> + Send,
+    ) -> impl std::future::Future<Output = Result<User, MALError>> + Send;
+    fn get_user_info(
+        &self,
+        username: &str,
+        fields: impl Into<Option<UserFields>> + Send,
+    ) -> impl std::future::Future<Output = Result<User, MALError>> + Send;
+    fn auth_state(&self) -> AuthState;
+    ///Returns true if the user has to authorize the client before it can make requests
+    fn need_auth(&self) -> bool {
+        self.auth_state() == AuthState::NoToken
     }
+    ///Returns why the user has to authorize the client, `None` while `need_auth` is false
+    fn reauth_reason(&self) -> Option<&ReauthReason>;
 
-    ///Returns the auth URL and code challenge which will be needed to authorize the user.
+    ///Authorizes the client again after its token expired or couldn't be loaded, see
+    ///`reauth_reason` for why. Runs the whole flow of `auth_interactive` with a new PKCE
+    ///challenge and state, failing with `MALError::AuthTimeout` if the user doesn't log in
+    ///within `timeout`. `progress` is called as the authorization moves along, e.g. to show the
+    ///URL if no browser could be opened. The new tokens replace the old ones in the token cache.
     ///
     ///# Example
-    ///#[`async_trait`]
+    ///
     ///```no_run
-    ///     use lib_mal::ClientBuilder;
-    ///     # use  lib_mal::MALError;
-    ///     # async fn test() -> Result<(), MALError> {
-    ///     let redirect_uri = "http://localhost:2525";//<-- example uri
-    ///     let mut client =
-    ///     ClientBuilder::new().secret("[YOUR_SECRET_HERE]".to_string()).build_no_refresh();
-    ///     let (url, challenge, state) = client.get_auth_parts();
-    ///     println!("Go here to log in: {}", url);
-    ///     client.auth(&redirect_uri, &challenge, &state).await?;
+    /// # use std::time::Duration;
+    /// # use lib_mal::{AuthProgress, ClientBuilder, MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     let mut client: MALClient = ClientBuilder::new().secret("[YOUR_SECRET_HERE]".to_string()).caching(true).build_with_refresh().await?;
+    ///     if let Some(reason) = client.reauth_reason() {
+    ///         println!("Logging in again: {reason}");
+    ///         client.reauthorize("http://localhost:2525", Duration::from_secs(300), |p| {
+    ///             if let AuthProgress::BrowserUnavailable { url } = p {
+    ///                 println!("Go here to log in: {url}");
+    ///             }
+    ///         }).await?;
+    ///     }
     ///     # Ok(())
-    ///     # }
+    /// # }
     ///```
-    fn get_auth_parts(&self) -> (String, String, String) {
-        let verifier = pkce::code_verifier(128);
-        let challenge = pkce::code_challenge(&verifier);
-        let random = Box::into_raw(Box::new(727)) as u16;
-        let state = random.to_string();
-        let url = format!("https://myanimelist.net/v1/oauth2/authorize?response_type=code&client_id={}&code_challenge={}&state={}", self.client_secret, challenge, state, );
-        (url, challenge, state)
+    #[cfg(feature = "open-browser")]
+    fn reauthorize(
+        &mut self,
+        redirect_uri: &str,
+        timeout: Duration,
+        progress: impl FnMut(AuthProgress) + Send,
+    ) -> impl Future<Output = Result<(), MALError>> + Send
+    where
+        Self: Send,
+    {
+        self.auth_interactive(redirect_uri, Some(timeout), progress)
     }
 
-    ///Listens for the `OAuth2` callback from MAL on `callback_url`, which is the `redirect_uri`
-    ///registered when obtaining the API token from MAL. Only HTTP URIs are supported right now.
-    ///
-    ///# NOTE
-    ///
-    ///For now only applications with a single registered URI are supported, having more than one
-    ///seems to cause issues with the MAL api itself
+    ///Runs the whole authorization: builds the auth URL for `redirect_uri`, opens it in the
+    ///system browser, and waits for the callback on `redirect_uri` for up to `timeout`.
+    ///`progress` is called as the authorization moves along, e.g. to print the URL if no browser
+    ///could be opened.
     ///
     ///# Example
     ///
     ///```no_run
-    ///     use lib_mal::ClientBuilder;
-    ///     # use lib_mal::MALError;
-    ///     # async fn test() -> Result<(), MALError> {
-    ///     let redirect_uri = "localhost:2525";//<-- example uri,
-    ///     //appears as "http://localhost:2525" in the API settings
-    ///     let mut client = ClientBuilder::new().secret("[YOUR_SECRET_HERE]".to_string()).build_no_refresh();
-    ///     let (url, challenge, state) = client.get_auth_parts();
-    ///     println!("Go here to log in: {}", url);
-    ///     client.auth(&redirect_uri, &challenge, &state).await?;
+    /// # use lib_mal::{AuthProgress, ClientBuilder, MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     let mut client: MALClient = ClientBuilder::new().secret("[YOUR_SECRET_HERE]".to_string()).build_no_refresh();
+    ///     client.auth_interactive("http://localhost:2525", None, |p| {
+    ///         if let AuthProgress::BrowserUnavailable { url } = p {
+    ///             println!("Go here to log in: {url}");
+    ///         }
+    ///     }).await?;
     ///     # Ok(())
-    ///     # }
-    ///
+    /// # }
     ///```
-    async fn auth(
+    #[cfg(feature = "open-browser")]
+    fn auth_interactive(
         &mut self,
-        callback_url: &str,
-        challenge: &str,
-        state: &str,
-    ) -> Result<(), MALError> {
-        let mut code = String::new();
-        let url = if callback_url.contains("http") {
-            //server won't work if the url has the protocol in it
-            callback_url
-                .trim_start_matches("http://")
-                .trim_start_matches("https://")
-        } else {
-            callback_url
-        };
-
-        let server = Server::http(url).unwrap();
-        for i in server.incoming_requests() {
-            if !i.url().contains(&format!("state={state}")) {
-                //if the state doesn't match, discard this response
-                continue;
+        redirect_uri: &str,
+        timeout: Option<Duration>,
+        mut progress: impl FnMut(AuthProgress) + Send,
+    ) -> impl Future<Output = Result<(), MALError>> + Send
+    where
+        Self: Send,
+    {
+        let parts = self.get_auth_parts_with(Some(redirect_uri), &[]);
+        let redirect_uri = redirect_uri.to_owned();
+        async move {
+            progress(AuthProgress::OpeningBrowser {
+                url: parts.url.clone(),
+            });
+            if open::that(&parts.url).is_err() {
+                progress(AuthProgress::BrowserUnavailable {
+                    url: parts.url.clone(),
+                });
             }
-            let res_raw = i.url();
-            code = res_raw
-                .split_once('=')
-                .unwrap()
-                .1
-                .split_once('&')
-                .unwrap()
-                .0
-                .to_owned();
-            let response = Response::from_string("You're logged in! You can now close this window");
-            i.respond(response).unwrap();
-            break;
+            progress(AuthProgress::WaitingForCallback);
+            self.auth_with_timeout(&redirect_uri, &parts.code_verifier, &parts.state, timeout)
+                .await?;
+            progress(AuthProgress::Authenticated);
+            Ok(())
         }
-        self.need_auth = false;
-        self.get_tokens(&code, challenge).await
     }
-    ///Returns the current access token. Intended mostly for debugging.
+
+    ///Returns a handle to the anime with `id` that loads its data when it's first needed, see
+    ///`AnimeHandle`
+    fn anime(&self, id: impl Into<AnimeId>) -> AnimeHandle<'_, Self>
+    where
+        Self: Sized + Sync,
+    {
+        AnimeHandle::new(self, id.into())
+    }
+
+    ///Returns a session for writing to the user's anime list that never sends two writes to the
+    ///same entry at once, see `ListSession`
+    fn list_session(&self) -> ListSession<'_, Self>
+    where
+        Self: Sized + Sync,
+    {
+        ListSession::new(self)
+    }
+
+    ///Crawls the relations of the anime with `id` and returns the details of every related anime
+    ///that matches `query`. The anime itself is not included.
+    ///
+    ///Relations are only followed through anime that match the query, so filtering out specials
+    ///and music videos also keeps the crawl from wandering through them. Each level of the crawl
+    ///is fetched a few requests at a time.
     ///
     ///# Example
     ///
     ///```no_run
-    /// # use lib_mal::ClientBuilder;
-    /// # use lib_mal::MALError;
-    /// # use std::path::PathBuf;
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::model::options::RelationQuery;
     /// # async fn test() -> Result<(), MALError> {
-    ///     let client = ClientBuilder::new().secret("[YOUR_SECRET_HERE]".to_string()).caching(true).cache_dir(Some(PathBuf::new())).build_with_refresh().await?;
-    ///     let token = client.get_access_token();
-    ///     Ok(())
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     // TV series and movies up to two relations away from Mobile Suit Gundam
+    ///     let related = client.get_related_anime(80, RelationQuery::watch_order().max_depth(2)).await?;
+    ///     # Ok(())
     /// # }
     ///```
-    fn get_access_token(&self) -> &str {
-        &self.access_token
+    fn get_related_anime(
+        &self,
+        id: impl Into<AnimeId> + Send,
+        query: impl Into<RelationQuery> + Send,
+    ) -> impl Future<Output = Result<Vec<AnimeDetails>, MALError>> + Send
+    where
+        Self: Sync,
+    {
+        let id: AnimeId = id.into();
+        let query = query.into();
+        async move {
+            let mut related = crawl_relations(self, id, &query, |_| true).await?;
+            related.remove(0);
+            Ok(related)
+        }
     }
 
-    //Begin API functions
-
-    //--Anime functions--//
-    ///Gets a list of anime based on the query string provided
-    ///`limit` defaults to 100 if `None`
+    ///Crawls the prequels, sequels, and side stories of the anime with `id` and returns them with
+    ///the anime itself as a `Franchise`. `query` limits how many relations away the crawl goes,
+    ///a plain number is the depth, and which anime and relations it includes.
+    ///
+    ///Each level of the crawl is fetched a few requests at a time, which still go through the
+    ///client's rate limit and retries. Fails if any of the anime can't be fetched.
     ///
     ///# Example
     ///
     ///```no_run
-    /// # use lib_mal::MALClient;
-    /// # use lib_mal::MALError;
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::model::options::RelationQuery;
     /// # async fn test() -> Result<(), MALError> {
     ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
-    ///     let list = client.get_anime_list("Mobile Suit Gundam", None).await?;
+    ///     let franchise = client.get_franchise(80, 5).await?;
+    ///     for anime in &franchise.anime {
+    ///         println!("{} {}", anime.start_date.as_deref().unwrap_or("TBA"), anime.title);
+    ///     }
+    ///     // without the compilation movies and specials
+    ///     let series = client.get_franchise(80, RelationQuery::watch_order().max_depth(5)).await?;
     ///     # Ok(())
     /// # }
     ///```
-    async fn get_anime_list(
+    fn get_franchise(
         &self,
-        query: &str,
-        limit: impl Into<Option<u8>> + Send,
-    ) -> Result<AnimeList, MALError> {
-        let url = format!(
-            "https://api.myanimelist.net/v2/anime?q={}&limit={}",
-            query,
-            limit.into().unwrap_or(100)
-        );
-        let res = self.do_request(url).await?;
-        Self::parse_response(&res)
+        id: impl Into<AnimeId> + Send,
+        query: impl Into<RelationQuery> + Send,
+    ) -> impl Future<Output = Result<Franchise, MALError>> + Send
+    where
+        Self: Sync,
+    {
+        let id: AnimeId = id.into();
+        let query = query.into();
+        async move {
+            let anime = crawl_relations(self, id, &query, RelationType::is_franchise).await?;
+            Ok(Franchise::new(id, anime))
+        }
     }
 
-    ///Gets the details for an anime by the show's ID.
-    ///Only returns the fields specified in the `fields` parameter
+    ///Fetches the details of every anime in `ids`, sending up to `max_concurrency` requests at a
+    ///time. The results are in the same order as `ids`, a failed request doesn't stop the others.
+    ///Requests still go through the client's rate limit.
     ///
-    ///Returns all fields when supplied `None`
+    ///Returns the client's default fields when `fields` is `None`, see
+    ///`ClientBuilder::default_anime_fields`.
     ///
     ///# Example
     ///
     ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
     /// use lib_mal::model::fields::AnimeFields;
-    /// # use lib_mal::{MALError, MALClient};
     /// # async fn test() -> Result<(), MALError> {
     ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
-    /// //returns an AnimeDetails struct with just the Rank, Mean, and Studio data for Mobile Suit Gundam
-    /// let res = client.get_anime_details(80, AnimeFields::Rank | AnimeFields::Mean | AnimeFields::Studios).await?;
-    /// # Ok(())
+    ///     let details = client.get_anime_details_batch(&[30230, 21], AnimeFields::Title, 8).await;
+    ///     for anime in details.into_iter().flatten() {
+    ///         println!("{}", anime.title);
+    ///     }
+    ///     # Ok(())
     /// # }
-    ///
     ///```
-    ///
-    async fn get_anime_details(
+    fn get_anime_details_batch(
         &self,
-        id: usize,
+        ids: &[impl Into<AnimeId> + Copy],
         fields: impl Into<Option<AnimeFields>> + Send,
-    ) -> Result<AnimeDetails, MALError> {
-        let url = fields.into().map_or_else(
-            || {
-                format!(
-                    "https://api.myanimelist.net/v2/anime/{}?fields={}",
-                    id,
-                    AnimeFields::ALL
-                )
-            },
-            |f| format!("https://api.myanimelist.net/v2/anime/{id}?fields={f}"),
-        );
-        let res = self.do_request(url).await?;
-        Self::parse_response(&res)
+        max_concurrency: usize,
+    ) -> impl Future<Output = Vec<Result<AnimeDetails, MALError>>> + Send
+    where
+        Self: Sync,
+    {
+        let fields = fields.into();
+        let ids: Vec<AnimeId> = ids.iter().map(|&id| id.into()).collect();
+        stream::iter(ids)
+            .map(move |id| self.get_anime_details(id, fields))
+            .buffered(max_concurrency.max(1))
+            .collect()
     }
 
-    ///Gets a list of anime ranked by `RankingType`
-    ///
-    ///`limit` defaults to the max of 100 when `None`
-    ///
-    ///# Example
+    ///Moves every entry to its suggested status, one request at a time. The results are in the
+    ///same order as `entries`, a failed update doesn't stop the others.
     ///
-    ///```no_run
-    /// # use lib_mal::{MALError, MALClient};
-    /// use lib_mal::model::options::RankingType;
-    /// # async fn test() -> Result<(), MALError> {
-    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
-    /// // Gets a list of the top 5 most popular anime
-    /// let ranking_list = client.get_anime_ranking(RankingType::ByPopularity, 5).await?;
-    /// # Ok(())
-    /// # }
+    ///See `AnimeList::stale_entries` for an example.
+    fn apply_stale_suggestions(
+        &self,
+        entries: &[StaleEntry],
+    ) -> impl Future<Output = Vec<Result<ListStatus, MALError>>> + Send
+    where
+        Self: Sync,
+    {
+        let updates: Vec<(AnimeId, Status)> =
+            entries.iter().map(|e| (e.anime.id, e.suggestion)).collect();
+        stream::iter(updates)
+            .map(move |(id, status)| {
+                let update = StatusUpdate::new().status(status).build_unchecked();
+                self.update_user_anime_status(id, update)
+            })
+            .buffered(1)
+            .collect()
+    }
+
+    ///Applies every update in `updates`, sending up to `options.concurrency` of them at a time
+    ///through the client's rate limit. `progress` is called after every update, in the order
+    ///they were passed. A failed update doesn't stop the others unless
+    ///`BatchOptions::abort_on_error` was set.
     ///
-    ///```
-    async fn get_anime_ranking(
+    ///See the `batch` module for an example.
+    fn apply_updates(
         &self,
-        ranking_type: RankingType,
-        limit: impl Into<Option<u8>> + Send,
-    ) -> Result<AnimeList, MALError> {
-        let url = format!(
-            "https://api.myanimelist.net/v2/anime/ranking?ranking_type={}&limit={}",
-            ranking_type,
-            limit.into().unwrap_or(100)
-        );
-        let res = self.do_request(url).await?;
-        Ok(serde_json::from_str(&res).unwrap())
+        updates: Vec<(AnimeId, StatusParams)>,
+        options: BatchOptions,
+        mut progress: impl FnMut(BatchProgress) + Send,
+    ) -> impl Future<Output = BatchReport> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let total = updates.len();
+            let aborted = AtomicBool::new(false);
+            let aborted = &aborted;
+            let mut outcomes = stream::iter(updates)
+                .map(|(id, update)| async move {
+                    if aborted.load(Ordering::Relaxed) {
+                        return (id, BatchOutcome::Skipped);
+                    }
+                    match self.update_user_anime_status(id, update).await {
+                        Ok(status) => (id, BatchOutcome::Applied(status)),
+                        Err(e) => (id, BatchOutcome::Failed(e)),
+                    }
+                })
+                .buffered(options.concurrency.max(1));
+            let mut report = BatchReport::default();
+            let mut failed = 0;
+            while let Some((id, outcome)) = outcomes.next().await {
+                if let BatchOutcome::Failed(_) = outcome {
+                    failed += 1;
+                    if options.abort_on_error {
+                        aborted.store(true, Ordering::Relaxed);
+                    }
+                }
+                report.results.push((id, outcome));
+                progress(BatchProgress {
+                    id,
+                    done: report.results.len(),
+                    failed,
+                    total,
+                });
+            }
+            report
+        }
     }
 
-    ///Gets the anime for a given season in a given year
+    ///Pairs every entry of `list` with its `AnimeDetails`, fetching up to `concurrency` of them
+    ///at a time. Anime that appear more than once in the list are only fetched once. Entries
+    ///that were deleted or are age restricted are left out and their ids are listed in
+    ///`EnrichedList::skipped`, any other error aborts.
     ///
-    ///`limit` defaults to the max of 100 when `None`
+    ///Returns the client's default fields when `fields` is `None`, see
+    ///`ClientBuilder::default_anime_fields`.
     ///
     ///# Example
     ///
     ///```no_run
-    /// # use lib_mal::{MALClient, MALError};
-    /// use lib_mal::model::options::Season;
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::model::fields::AnimeFields;
     /// # async fn test() -> Result<(), MALError> {
     ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
-    ///     let summer_2019 = client.get_seasonal_anime(Season::Summer, 2019, None).await?;
+    ///     let list = client.get_user_anime_list(None).await?;
+    ///     let enriched = client.enrich_list(&list, AnimeFields::NumEpisodes | AnimeFields::Genres, 4).await?;
     ///     # Ok(())
     /// # }
     ///```
-    async fn get_seasonal_anime(
+    fn enrich_list(
         &self,
-        season: Season,
-        year: usize,
-        limit: impl Into<Option<u8>> + Send,
-    ) -> Result<AnimeList, MALError> {
-        let url = format!(
-            "https://api.myanimelist.net/v2/anime/season/{}/{}?limit={}",
-            year,
-            season,
-            limit.into().unwrap_or(100)
-        );
-        let res = self.do_request(url).await?;
-        Self::parse_response(&res)
+        list: &AnimeList,
+        fields: impl Into<Option<AnimeFields>> + Send,
+        concurrency: usize,
+    ) -> impl Future<Output = Result<EnrichedList, MALError>> + Send
+    where
+        Self: Sync,
+    {
+        self.enrich_list_with_progress(list, fields, concurrency, |_| {})
     }
 
-    ///Returns the suggested anime for the current user. Can return an empty list if the user has
-    ///no suggestions.
+    ///Like `enrich_list`, but calls `progress` after the details of every anime arrived. At most
+    ///`concurrency` requests are in flight, the next one is only sent once a response was
+    ///handled.
     ///
     ///# Example
     ///
     ///```no_run
-    /// # use lib_mal::{MALClient, MALError};
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
     /// # async fn test() -> Result<(), MALError> {
     ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
-    ///     let suggestions = client.get_suggested_anime(10).await?;
+    ///     let list = client.get_user_anime_list(None).await?;
+    ///     let enriched = client
+    ///         .enrich_list_with_progress(&list, None, 4, |p| println!("{}/{}", p.done, p.total))
+    ///         .await?;
+    ///     for (entry, details) in enriched.into_pairs() {
+    ///         println!("{}: {:?}", entry.node.title, details.mean);
+    ///     }
     ///     # Ok(())
     /// # }
     ///```
-    async fn get_suggested_anime(
+    fn enrich_list_with_progress(
         &self,
-        limit: impl Into<Option<u8>> + Send,
-    ) -> Result<AnimeList, MALError> {
-        let url = format!(
-            "https://api.myanimelist.net/v2/anime/suggestions?limit={}",
-            limit.into().unwrap_or(100)
-        );
-        let res = self.do_request(url).await?;
-        Self::parse_response(&res)
+        list: &AnimeList,
+        fields: impl Into<Option<AnimeFields>> + Send,
+        concurrency: usize,
+        mut progress: impl FnMut(EnrichProgress) + Send,
+    ) -> impl Future<Output = Result<EnrichedList, MALError>> + Send
+    where
+        Self: Sync,
+    {
+        let fields = fields.into();
+        async move {
+            let mut seen = HashSet::new();
+            let ids: Vec<AnimeId> = list
+                .data
+                .iter()
+                .map(|entry| entry.node.id)
+                .filter(|id| seen.insert(*id))
+                .collect();
+            let total = ids.len();
+            let mut results = stream::iter(ids)
+                .map(|id| async move { (id, self.get_anime_details(id, fields).await) })
+                .buffered(concurrency.max(1))
+                .enumerate();
+            let mut details = HashMap::new();
+            let mut skipped = Vec::new();
+            while let Some((done, (id, res))) = results.next().await {
+                progress(EnrichProgress {
+                    id,
+                    done: done + 1,
+                    total,
+                });
+                match res {
+                    Ok(anime) => {
+                        details.insert(id, anime);
+                    }
+                    Err(e) if e.is_unavailable() => skipped.push(id),
+                    Err(e) => return Err(e),
+                }
+            }
+            let data = list
+                .data
+                .iter()
+                .filter_map(|entry| {
+                    Some(EnrichedNode {
+                        entry: entry.clone(),
+                        details: details.get(&entry.node.id)?.clone(),
+                    })
+                })
+                .collect();
+            Ok(EnrichedList { data, skipped })
+        }
     }
 
-    //--User anime list functions--//
-
-    ///Adds an anime to the list, or updates the element if it already exists
-    ///
-    ///# Example
-    ///
-    ///```no_run
-    /// # use lib_mal::{MALClient, MALError};
-    /// use lib_mal::model::StatusBuilder;
-    /// use lib_mal::model::options::Status;
-    /// # async fn test() -> Result<(), MALError> {
-    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
-    ///     // add a new anime to the user's list
-    ///     let updated_status = client.update_user_anime_status(80, StatusBuilder::new().status(Status::Watching).build()).await?;
-    ///     // or update an existing one
-    ///     let new_status = StatusBuilder::new().status(Status::Dropped).num_watched_episodes(2).build();
-    ///     let updated_status = client.update_user_anime_status(32981, new_status).await?;
+    ///Searches for `title` and scores the results by how well their main, English, or
+    ///alternative titles match it. The candidates are sorted with the best match first, results
+    ///MAL ranks higher win ties.
     ///
-    ///     # Ok(())
-    ///
-    /// # }
-    ///```
-    async fn update_user_anime_status(
+    ///See `find_anime` for just the best match.
+    fn find_anime_candidates(
         &self,
-        id: usize,
-        update: StatusUpdate,
-    ) -> Result<ListStatus, MALError> {
-        let params = update.get_params();
-        let url = format!("https://api.myanimelist.net/v2/anime/{id}/my_list_status");
-        let res = self.do_request_forms(url, params).await?;
-        Self::parse_response(&res)
+        title: &str,
+    ) -> impl Future<Output = Result<Vec<AnimeMatch>, MALError>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let fields = AnimeFields::ID
+                | AnimeFields::Title
+                | AnimeFields::MainPicture
+                | AnimeFields::AlternativeTitles
+                | AnimeFields::MediaType
+                | AnimeFields::NumEpisodes;
+            let results = self.get_anime_list(title, FIND_ANIME_CANDIDATES, fields).await?;
+            let mut candidates: Vec<AnimeMatch> = results
+                .data
+                .into_iter()
+                .filter_map(|entry| {
+                    let (best, confidence) = entry
+                        .details
+                        .titles()
+                        .into_iter()
+                        .map(|t| (t, titles::similarity(title, t)))
+                        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+                    Some(AnimeMatch {
+                        title: best.to_owned(),
+                        confidence,
+                        anime: entry.details,
+                    })
+                })
+                .collect();
+            candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+            Ok(candidates)
+        }
     }
 
-    ///Returns the user's full anime list as an `AnimeList` struct.
+    ///Resolves `title`, like a name parsed from a file or a media player, to the anime it most
+    ///likely is. Returns `None` if the search found nothing.
+    ///
+    ///Check `AnimeMatch::confidence` before trusting the match, a low score means the title
+    ///only resembles the anime.
     ///
     ///# Example
     ///
     ///```no_run
-    /// # use lib_mal::{MALClient, MALError};
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
     /// # async fn test() -> Result<(), MALError> {
     ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
-    ///     let my_list = client.get_user_anime_list().await?;
+    ///     if let Some(found) = client.find_anime("fullmetal alchemist brotherhood").await? {
+    ///         if found.confidence > 0.8 {
+    ///             println!("{} ({})", found.anime.title, found.anime.id);
+    ///         }
+    ///     }
     ///     # Ok(())
-    ///
     /// # }
     ///```
-    async fn get_user_anime_list(&self) -> Result<AnimeList, MALError> {
-        let url = "https://api.myanimelist.net/v2/users/@me/animelist?fields=list_status&limit=4";
-        let res = self.do_request(url.to_owned()).await?;
-
-        Self::parse_response(&res)
+    fn find_anime(
+        &self,
+        title: &str,
+    ) -> impl Future<Output = Result<Option<AnimeMatch>, MALError>> + Send
+    where
+        Self: Sync,
+    {
+        async move { Ok(self.find_anime_candidates(title).await?.into_iter().next()) }
     }
+}
 
-    ///Deletes the anime with `id` from the user's anime list
+mod configure {
+    use super::{CallbackPage, ReauthReason};
+    use crate::debug::DebugSink;
+    use crate::model::fields::AnimeFields;
+    use crate::{CacheConfig, Clock, HttpTransport, MetricsSink, RequestInterceptor, TokenStore};
+    use std::sync::Arc;
+
+    ///The settings `ClientBuilder` applies to the clients it builds. It can't be named outside
+    ///the crate, so new settings don't break implementors of `MALClientTrait`. Every setter does
+    ///nothing unless the client overrides it.
+    pub trait Configure {
+        fn set_token_store(&mut self, _store: Arc<dyn TokenStore>) {}
+        fn set_callback_page(&mut self, _page: CallbackPage) {}
+        fn set_rate_limit(&mut self, _requests_per_second: u32) {}
+        fn set_max_retries(&mut self, _max_retries: u32) {}
+        fn add_middleware(&mut self, _middleware: Arc<dyn RequestInterceptor>) {}
+        fn set_metrics_sink(&mut self, _sink: Arc<dyn MetricsSink>) {}
+        fn set_debug_sink(&mut self, _sink: Arc<dyn DebugSink>) {}
+        fn set_response_cache(&mut self, _config: CacheConfig) {}
+        fn set_include_nsfw(&mut self, _include_nsfw: bool) {}
+        fn set_allow_scraping(&mut self, _allow_scraping: bool) {}
+        fn set_site_session(&mut self, _cookie: String) {}
+        fn set_public_client(&mut self, _public_client: bool) {}
+        fn set_client_secret(&mut self, _client_secret: String) {}
+        fn set_reauth_reason(&mut self, _reason: ReauthReason) {}
+        fn set_default_anime_fields(&mut self, _fields: AnimeFields) {}
+        fn set_journal(&mut self, _journal: bool) {}
+        fn set_offline_queue(&mut self, _offline_queue: bool) {}
+        fn set_watch_threshold(&mut self, _threshold: f32) {}
+        fn set_auto_paginate(&mut self, _max_items: Option<usize>) {}
+        fn set_transport(&mut self, _transport: Arc<dyn HttpTransport>) {}
+        fn set_clock(&mut self, _clock: Arc<dyn Clock>) {}
+        fn set_api_base_url(&mut self, _url: String) {}
+        fn set_auth_base_url(&mut self, _url: String) {}
+    }
+}
+pub(crate) use configure::Configure;
+
+impl Configure for MALClient {
+    ///Sets where the client will cache the tokens when caching is enabled
+    fn set_token_store(&mut self, store: Arc<dyn TokenStore>) {
+        self.store = store;
+    }
+
+    ///Sets the page shown in the browser once the auth callback arrives
+    fn set_callback_page(&mut self, page: CallbackPage) {
+        self.callback_page = page;
+    }
+
+    ///Limits how many requests the client sends per second, 0 removes the limit
+    fn set_rate_limit(&mut self, requests_per_second: u32) {
+        self.rate_limiter =
+            (requests_per_second > 0).then(|| Arc::new(RateLimiter::new(requests_per_second)));
+    }
+
+    ///Sets how many times requests rejected with 429 Too Many Requests or a server error are
+    ///retried, 0 disables retrying
+    fn set_max_retries(&mut self, max_retries: u32) {
+        self.max_retries = max_retries;
+    }
+
+    ///Adds hooks that are called around every request, in the order they were added
+    fn add_middleware(&mut self, middleware: Arc<dyn RequestInterceptor>) {
+        self.middleware.push(middleware);
+    }
+
+    ///Records the outcome of every API call in `sink`, see `MetricsSink`
+    fn set_metrics_sink(&mut self, sink: Arc<dyn MetricsSink>) {
+        self.metrics = Some(sink);
+    }
+
+    ///Dumps every request and response to `sink` with the secrets redacted, and turns on
+    ///`MALClient::debug_mode`. See the `debug` module.
+    fn set_debug_sink(&mut self, sink: Arc<dyn DebugSink>) {
+        self.debug_sink = Some(sink);
+        self.debug = true;
+    }
+
+    ///Enables caching the responses of read-only endpoints, see `CacheConfig`
+    fn set_response_cache(&mut self, config: CacheConfig) {
+        let mut cache = ResponseCache::new(config);
+        cache.clock = self.clock.clone();
+        self.response_cache = Some(Arc::new(cache));
+    }
+
+    ///Includes NSFW anime in searches, seasons, rankings, and suggestions, which MAL hides by
+    ///default
+    fn set_include_nsfw(&mut self, include_nsfw: bool) {
+        self.include_nsfw = include_nsfw;
+    }
+
+    ///Allows scraping myanimelist.net for data the API doesn't have, like the precise episode
+    ///scores of `JikanClient::episodes`. Always disabled without the `scraping` feature.
+    fn set_allow_scraping(&mut self, allow_scraping: bool) {
+        self.allow_scraping = allow_scraping;
+    }
+
+    fn set_site_session(&mut self, cookie: String) {
+        self.site_session = Some(cookie);
+    }
+
+    ///Identifies the app with the `X-MAL-CLIENT-ID` header on every API request, which lets it
+    ///read public data without a logged in user
+    fn set_public_client(&mut self, public_client: bool) {
+        self.public_client = public_client;
+    }
+
+    ///Sends `client_secret` with the token requests, for apps registered as confidential clients
+    fn set_client_secret(&mut self, client_secret: String) {
+        self.client_secret = Some(client_secret);
+    }
+
+    fn set_reauth_reason(&mut self, reason: ReauthReason) {
+        self.reauth_reason = Some(reason);
+    }
+
+    ///Sets the fields `get_anime_details` requests when it's passed `None`, `AnimeFields::ALL` by
+    ///default
+    fn set_default_anime_fields(&mut self, fields: AnimeFields) {
+        self.default_anime_fields = fields;
+    }
+
+    ///Appends every change sent to the user's anime list and MAL's response to the journal in
+    ///the cache directory, see the `journal` module
+    fn set_journal(&mut self, journal: bool) {
+        self.journal = journal.then(Arc::default);
+    }
+
+    ///Queues list changes that can't be sent in the cache directory, see the `offline` module
+    fn set_offline_queue(&mut self, offline_queue: bool) {
+        self.offline_queue = offline_queue.then(Arc::default);
+    }
+
+    ///Sets the share of an episode that has to be watched before `end_watch_session` counts it,
+    ///between 0 and 1
+    fn set_watch_threshold(&mut self, threshold: f32) {
+        self.watch_threshold = threshold.clamp(0.0, 1.0);
+    }
+
+    ///Makes the functions returning an `AnimeList` fetch up to `max_items` entries, following
+    ///the pages, or only the first page if it's `None`
+    fn set_auto_paginate(&mut self, max_items: Option<usize>) {
+        self.auto_paginate = max_items;
+    }
+
+    ///Replaces the `ReqwestTransport` the client sends its requests with
+    fn set_transport(&mut self, transport: Arc<dyn HttpTransport>) {
+        self.transport = transport;
+    }
+
+    ///Replaces the `SystemClock` the client reads the time from, e.g. with a `MockClock` in tests.
+    ///A response cache that's shared with clones of the client keeps its clock, so set the clock
+    ///before cloning.
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        if let Some(cache) = self.response_cache.as_mut().and_then(Arc::get_mut) {
+            cache.clock = clock.clone();
+        }
+        self.clock = clock;
+    }
+
+    ///Sends the API requests to `url` instead of `https://api.myanimelist.net/`, e.g. a mock
+    ///server or an API gateway. Paths like `v2/anime/30230` are appended to it.
+    fn set_api_base_url(&mut self, url: String) {
+        self.api_base_url = with_trailing_slash(url);
+    }
+
+    ///Sends the OAuth requests to `url` instead of `https://myanimelist.net/`. Paths like
+    ///`v1/oauth2/token` are appended to it.
+    fn set_auth_base_url(&mut self, url: String) {
+        self.auth_base_url = with_trailing_slash(url);
+    }
+}
+
+impl MALClientTrait for MALClient {
+    fn new(
+        client_secret: String,
+        dirs: PathBuf,
+        tokens: Tokens,
+        client: Client,
+        caching: bool,
+        auth_state: AuthState,
+    ) -> Self {
+        Self {
+            client_id: client_secret,
+            client_secret: None,
+            store: default_store(dirs.clone()),
+            dirs,
+            tokens: Arc::new(RwLock::new(tokens)),
+            transport: Arc::new(ReqwestTransport::new(client)),
+            caching,
+            callback_page: CallbackPage::default(),
+            auth_state: Arc::new(watch::Sender::new(auth_state)),
+            reauth_reason: None,
+            rate_limiter: None,
+            max_retries: DEFAULT_MAX_RETRIES,
+            middleware: Vec::new(),
+            metrics: None,
+            debug_sink: None,
+            debug: false,
+            response_cache: None,
+            include_nsfw: false,
+            allow_scraping: true,
+            site_session: None,
+            public_client: false,
+            default_anime_fields: AnimeFields::ALL,
+            journal: None,
+            offline_queue: None,
+            watch_session: Arc::default(),
+            list_ids: Arc::default(),
+            watch_threshold: DEFAULT_WATCH_THRESHOLD,
+            auto_paginate: None,
+            clock: Arc::new(SystemClock),
+            api_base_url: MAL_API_URL.to_owned(),
+            auth_base_url: MAL_AUTH_URL.to_owned(),
+            #[cfg(feature = "jikan")]
+            jikan_rate_limiter: Arc::new(jikan::rate_limiter()),
+        }
+    }
+    ///Creates a client using provided token. Caching is disable by default.
+    ///
+    ///A client created this way can't authenticate the user if needed because it lacks a
+    ///`client_secret`
+    fn with_access_token(token: &str) -> Self {
+        Self {
+            client_id: String::new(),
+            client_secret: None,
+            auth_state: Arc::new(watch::Sender::new(AuthState::TokenCached)),
+            reauth_reason: None,
+            dirs: PathBuf::new(),
+            tokens: Arc::new(RwLock::new(Tokens {
+                access_token: token.to_owned(),
+                ..Tokens::default()
+            })),
+            store: default_store(PathBuf::new()),
+            transport: Arc::new(ReqwestTransport::default()),
+            caching: false,
+            callback_page: CallbackPage::default(),
+            rate_limiter: None,
+            max_retries: DEFAULT_MAX_RETRIES,
+            middleware: Vec::new(),
+            metrics: None,
+            debug_sink: None,
+            debug: false,
+            response_cache: None,
+            include_nsfw: false,
+            allow_scraping: true,
+            site_session: None,
+            public_client: false,
+            default_anime_fields: AnimeFields::ALL,
+            journal: None,
+            offline_queue: None,
+            watch_session: Arc::default(),
+            list_ids: Arc::default(),
+            watch_threshold: DEFAULT_WATCH_THRESHOLD,
+            auto_paginate: None,
+            clock: Arc::new(SystemClock),
+            api_base_url: MAL_API_URL.to_owned(),
+            auth_base_url: MAL_AUTH_URL.to_owned(),
+            #[cfg(feature = "jikan")]
+            jikan_rate_limiter: Arc::new(jikan::rate_limiter()),
+        }
+    }
+
+    ///Sets the directory the client will use for the token cache. This replaces any custom
+    ///`TokenStore` with a `FileTokenStore` in `dir`, or a `MemoryTokenStore` without the `oauth`
+    ///feature.
+    fn set_cache_dir(&mut self, dir: PathBuf) {
+        self.store = default_store(dir.clone());
+        self.dirs = dir;
+    }
+
+    ///Sets wether the client will cache or not
+    fn set_caching(&mut self, caching: bool) {
+        self.caching = caching;
+    }
+
+    ///Returns the auth URL, code verifier, and state which will be needed to authorize the user.
+    ///
+    ///`redirect_uri` is required by MAL if the application has more than one registered URI.
+    ///`extra_params` are appended to the query string of the URL. `get_auth_parts` is a shorthand
+    ///for calling this without either.
+    ///
+    ///# Example
+    ///```no_run
+    ///     use lib_mal::{ClientBuilder, MALClient, MALClientTrait};
+    ///     # use  lib_mal::MALError;
+    ///     # async fn test() -> Result<(), MALError> {
+    ///     let redirect_uri = "http://localhost:2525";//<-- example uri
+    ///     let mut client: MALClient =
+    ///     ClientBuilder::new().secret("[YOUR_SECRET_HERE]".to_string()).build_no_refresh();
+    ///     let parts = client.get_auth_parts_with(Some(redirect_uri), &[]);
+    ///     println!("Go here to log in: {}", parts.url);
+    ///     client.auth(&redirect_uri, &parts.code_verifier, &parts.state).await?;
+    ///     # Ok(())
+    ///     # }
+    ///```
+    #[cfg(feature = "oauth")]
+    fn get_auth_parts_with(
+        &self,
+        redirect_uri: Option<&str>,
+        extra_params: &[(&str, &str)],
+    ) -> AuthParts {
+        AuthParts::new(
+            &format!("{}v1/oauth2/authorize", self.auth_base_url),
+            &self.client_id,
+            redirect_uri,
+            extra_params,
+        )
+    }
+
+    ///Listens for the `OAuth2` callback from MAL on `callback_url`, which is the `redirect_uri`
+    ///registered when obtaining the API token from MAL. Only HTTP URIs are supported right now.
+    ///Needs the `callback-server` feature, which is enabled by default. Apps that can't listen on
+    ///a port, like ones running in a browser, use `auth_with_code` instead.
+    ///
+    ///# NOTE
+    ///
+    ///For now only applications with a single registered URI are supported, having more than one
+    ///seems to cause issues with the MAL api itself
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    ///     use lib_mal::{ClientBuilder, MALClient, MALClientTrait};
+    ///     # use lib_mal::MALError;
+    ///     # async fn test() -> Result<(), MALError> {
+    ///     let redirect_uri = "localhost:2525";//<-- example uri,
+    ///     //appears as "http://localhost:2525" in the API settings
+    ///     let mut client: MALClient = ClientBuilder::new().secret("[YOUR_SECRET_HERE]".to_string()).build_no_refresh();
+    ///     let parts = client.get_auth_parts();
+    ///     println!("Go here to log in: {}", parts.url);
+    ///     client.auth(&redirect_uri, &parts.code_verifier, &parts.state).await?;
+    ///     # Ok(())
+    ///     # }
+    ///
+    ///```
+    #[cfg(feature = "callback-server")]
+    async fn auth(
+        &mut self,
+        callback_url: &str,
+        challenge: &str,
+        state: &str,
+    ) -> Result<(), MALError> {
+        self.auth_with_timeout(callback_url, challenge, state, None)
+            .await
+    }
+
+    ///Same as `auth`, but gives up waiting for the callback after `timeout` and returns an error
+    ///`MALError::AuthTimeout`. The callback listener is shut down either way.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    ///     use lib_mal::{ClientBuilder, MALClient, MALClientTrait};
+    ///     # use lib_mal::MALError;
+    ///     # use std::time::Duration;
+    ///     # async fn test() -> Result<(), MALError> {
+    ///     let mut client: MALClient = ClientBuilder::new().secret("[YOUR_SECRET_HERE]".to_string()).build_no_refresh();
+    ///     let parts = client.get_auth_parts();
+    ///     println!("Go here to log in: {}", parts.url);
+    ///     match client.auth_with_timeout("localhost:2525", &parts.code_verifier, &parts.state, Some(Duration::from_secs(300))).await {
+    ///         Err(MALError::AuthTimeout) => println!("Login took too long"),
+    ///         res => res?,
+    ///     }
+    ///     # Ok(())
+    ///     # }
+    ///```
+    #[cfg(feature = "callback-server")]
+    async fn auth_with_timeout(
+        &mut self,
+        callback_url: &str,
+        challenge: &str,
+        state: &str,
+        timeout: Option<Duration>,
+    ) -> Result<(), MALError> {
+        self.answer_auth_callback(callback_url, challenge, state, timeout, None)
+            .await
+    }
+
+    ///Same as `auth`, but stops listening for the callback once `cancel` is cancelled and fails
+    ///with `MALError::Cancelled`, e.g. when the user closes the login dialog.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    ///     use lib_mal::{ClientBuilder, MALClient, MALClientTrait};
+    ///     use lib_mal::cancel::CancellationToken;
+    ///     # use lib_mal::MALError;
+    ///     # async fn test() -> Result<(), MALError> {
+    ///     let mut client: MALClient = ClientBuilder::new().secret("[YOUR_SECRET_HERE]".to_string()).build_no_refresh();
+    ///     let parts = client.get_auth_parts();
+    ///     let cancel = CancellationToken::new();
+    ///     let on_close = cancel.clone();
+    ///     std::thread::spawn(move || {
+    ///         std::io::stdin().read_line(&mut String::new()).unwrap();
+    ///         on_close.cancel();
+    ///     });
+    ///     println!("Go here to log in, or press enter to cancel: {}", parts.url);
+    ///     match client.auth_with_cancellation("localhost:2525", &parts.code_verifier, &parts.state, cancel).await {
+    ///         Err(MALError::Cancelled) => println!("Login cancelled"),
+    ///         res => res?,
+    ///     }
+    ///     # Ok(())
+    ///     # }
+    ///```
+    #[cfg(feature = "callback-server")]
+    async fn auth_with_cancellation(
+        &mut self,
+        callback_url: &str,
+        challenge: &str,
+        state: &str,
+        cancel: CancellationToken,
+    ) -> Result<(), MALError> {
+        self.answer_auth_callback(callback_url, challenge, state, None, Some(&cancel))
+            .await
+    }
+
+    ///Completes the authorization by exchanging the `code` MAL sends to the `redirect_uri` for
+    ///the tokens, without listening for the callback. Useful when the callback is handled
+    ///elsewhere, e.g. by a web server or GUI framework, or on a headless machine where the user
+    ///pastes the code in.
+    ///
+    ///`code_verifier` has to be the one from the `AuthParts` used to build the auth URL.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    ///     use lib_mal::{ClientBuilder, MALClient, MALClientTrait};
+    ///     # use lib_mal::MALError;
+    ///     # async fn test() -> Result<(), MALError> {
+    ///     let mut client: MALClient = ClientBuilder::new().secret("[YOUR_SECRET_HERE]".to_string()).build_no_refresh();
+    ///     let parts = client.get_auth_parts();
+    ///     println!("Go here to log in: {}", parts.url);
+    ///     let mut code = String::new();
+    ///     std::io::stdin().read_line(&mut code).unwrap();
+    ///     client.auth_with_code(code.trim(), &parts.code_verifier).await?;
+    ///     # Ok(())
+    ///     # }
+    ///```
+    #[cfg(feature = "oauth")]
+    async fn auth_with_code(&mut self, code: &str, code_verifier: &str) -> Result<(), MALError> {
+        let mut params = vec![
+            ("client_id", self.client_id.as_str()),
+            ("grant_type", "authorization_code"),
+            ("code_verifier", code_verifier),
+            ("code", code),
+        ];
+        if let Some(secret) = &self.client_secret {
+            params.push(("client_secret", secret));
+        }
+        let url = format!("{}{TOKEN_PATH}", self.auth_base_url);
+        let request = HttpRequest::new(Method::POST, &url).form(&params);
+        let text = self.http().send(request).await?.body;
+        if let Ok(tokens) = serde_json::from_str::<TokenResponse>(&text) {
+            let tjson = Tokens {
+                today: self.now(),
+                ..Tokens::from(tokens)
+            };
+            if self.caching {
+                self.cache_tokens(&tjson)?;
+            }
+            *self.tokens.write().unwrap_or_else(PoisonError::into_inner) = tjson;
+            self.auth_state.send_replace(AuthState::Authenticated);
+            self.reauth_reason = None;
+            Ok(())
+        } else {
+            Err(MALError::auth("Unable to get tokens", text))
+        }
+    }
+    ///Returns the current access token. Intended mostly for debugging.
+    ///
+    ///The token may change over the lifetime of the client when it gets refreshed.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::ClientBuilder;
+    /// # use lib_mal::MALError;
+    /// # use std::path::PathBuf;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     let client = ClientBuilder::new().secret("[YOUR_SECRET_HERE]".to_string()).caching(true).cache_dir(Some(PathBuf::new())).build_with_refresh().await?;
+    ///     let token = client.get_access_token();
+    ///     Ok(())
+    /// # }
+    ///```
+    fn get_access_token(&self) -> String {
+        self.tokens().access_token.clone()
+    }
+
+    //Begin API functions
+
+    //--Anime functions--//
+    ///Gets a list of anime based on the query string provided
+    ///`limit` defaults to 100 if `None`
+    ///
+    ///`fields` are requested for every anime in the list, only the id, title, and picture are
+    ///returned when `None`
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::MALClient;
+    /// # use lib_mal::MALError;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let list = client.get_anime_list("Mobile Suit Gundam", None, None).await?;
+    ///     # Ok(())
+    /// # }
+    ///```
+    async fn get_anime_list(
+        &self,
+        query: &str,
+        limit: impl Into<Limit> + Send,
+        fields: impl Into<Option<AnimeFields>> + Send,
+    ) -> Result<AnimeList, MALError> {
+        let limit = limit.into().validate("get_anime_list", MAX_SEARCH_LIMIT)?.to_string();
+        let fields = fields.into().map(|f| f.to_string());
+        let mut params = vec![("q", query), ("limit", &limit)];
+        params.extend(fields.as_deref().map(|f| ("fields", f)));
+        params.extend(self.nsfw_pair());
+        let url = self.api_url(&["anime"], &params);
+        let list = self.do_request_cached("get_anime_list", url).await?;
+        self.paginate("get_anime_list", MAX_SEARCH_LIMIT, list).await
+    }
+
+    ///Gets the details for an anime by the show's ID.
+    ///Only returns the fields specified in the `fields` parameter
+    ///
+    ///Returns the client's default fields when supplied `None`, all fields unless they were
+    ///changed with `ClientBuilder::default_anime_fields`
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// use lib_mal::model::fields::AnimeFields;
+    /// # use lib_mal::{MALError, MALClient};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    /// //returns an AnimeDetails struct with just the Rank, Mean, and Studio data for Mobile Suit Gundam
+    /// let res = client.get_anime_details(80, AnimeFields::Rank | AnimeFields::Mean | AnimeFields::Studios).await?;
+    /// # Ok(())
+    /// # }
+    ///
+    ///```
+    ///
+    async fn get_anime_details(
+        &self,
+        id: impl Into<AnimeId> + Send,
+        fields: impl Into<Option<AnimeFields>> + Send,
+    ) -> Result<AnimeDetails, MALError> {
+        let url = self.anime_details_url(id.into(), fields.into());
+        self.do_request_cached("get_anime_details", url)
+            .await
+            .map_err(classify_anime_error)
+    }
+
+    ///Gets a list of anime ranked by `RankingType`
+    ///
+    ///`limit` defaults to 100 when `None` and can be up to `limits::MAX_RANKING_LIMIT`
+    ///
+    ///`fields` are requested for every anime in the list, only the id, title, and picture are
+    ///returned when `None`
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALError, MALClient};
+    /// use lib_mal::model::fields::AnimeFields;
+    /// use lib_mal::model::options::RankingType;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    /// // Gets a list of the top 5 most popular anime with their mean score
+    /// let ranking_list = client.get_anime_ranking(RankingType::ByPopularity, 5, AnimeFields::Mean).await?;
+    /// # Ok(())
+    /// # }
+    ///
+    ///```
+    async fn get_anime_ranking(
+        &self,
+        ranking_type: RankingType,
+        limit: impl Into<Limit> + Send,
+        fields: impl Into<Option<AnimeFields>> + Send,
+    ) -> Result<AnimeList, MALError> {
+        let ranking_type = ranking_type.to_string();
+        let limit = limit.into().validate("get_anime_ranking", MAX_RANKING_LIMIT)?.to_string();
+        let fields = fields.into().map(|f| f.to_string());
+        let mut params = vec![("ranking_type", ranking_type.as_str()), ("limit", &limit)];
+        params.extend(fields.as_deref().map(|f| ("fields", f)));
+        params.extend(self.nsfw_pair());
+        let url = self.api_url(&["anime", "ranking"], &params);
+        let list = self.do_request_cached("get_anime_ranking", url).await?;
+        self.paginate("get_anime_ranking", MAX_RANKING_LIMIT, list).await
+    }
+
+    ///Gets the anime for a given season in a given year, `season` can also be a
+    ///`(Season, year)` tuple
+    ///
+    ///`limit` defaults to 100 when `None` and can be up to `limits::MAX_SEASONAL_LIMIT`
+    ///
+    ///`sort` orders the anime by score or popularity, MAL's default order is used when `None`
+    ///
+    ///`fields` are requested for every anime in the list, only the id, title, and picture are
+    ///returned when `None`
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALError};
+    /// use lib_mal::model::options::{Season, SeasonYear, SeasonalSort};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let summer_2019 = client.get_seasonal_anime((Season::Summer, 2019), None, None, None).await?;
+    ///     let top_10_next_season = client
+    ///         .get_seasonal_anime(SeasonYear::now().next(), 10, None, SeasonalSort::AnimeScore)
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    ///```
+    async fn get_seasonal_anime(
+        &self,
+        season: impl Into<SeasonYear> + Send,
+        limit: impl Into<Limit> + Send,
+        fields: impl Into<Option<AnimeFields>> + Send,
+        sort: impl Into<Option<SeasonalSort>> + Send,
+    ) -> Result<AnimeList, MALError> {
+        let SeasonYear { season, year } = season.into();
+        let limit = limit.into().validate("get_seasonal_anime", MAX_SEASONAL_LIMIT)?.to_string();
+        let fields = fields.into().map(|f| f.to_string());
+        let sort = sort.into().map(|s| s.to_string());
+        let mut params = vec![("limit", limit.as_str())];
+        params.extend(fields.as_deref().map(|f| ("fields", f)));
+        params.extend(sort.as_deref().map(|s| ("sort", s)));
+        params.extend(self.nsfw_pair());
+        let url = self.api_url(&["anime", "season", &year.to_string(), &season.to_string()], &params);
+        let list = self.do_request_cached("get_seasonal_anime", url).await?;
+        self.paginate("get_seasonal_anime", MAX_SEASONAL_LIMIT, list).await
+    }
+
+    ///Returns the suggested anime for the current user. Can return an empty list if the user has
+    ///no suggestions.
+    ///
+    ///`fields` are requested for every anime in the list, only the id, title, and picture are
+    ///returned when `None`
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let suggestions = client.get_suggested_anime(10, None).await?;
+    ///     # Ok(())
+    /// # }
+    ///```
+    async fn get_suggested_anime(
+        &self,
+        limit: impl Into<Limit> + Send,
+        fields: impl Into<Option<AnimeFields>> + Send,
+    ) -> Result<AnimeList, MALError> {
+        let query = SuggestionQuery::new()
+            .limit(limit)
+            .fields(fields.into());
+        self.get_suggestions(query).await
+    }
+
+    ///Returns a page of the suggested anime for the current user, see `SuggestionQuery`
+    ///
+    ///With `exclude_on_list`, the user's whole list is fetched the first time to find the anime
+    ///that are already on it. The client keeps it, and its clones share it, so later pages only
+    ///need a single request. Anime added or removed through the client are tracked, but changes
+    ///made elsewhere aren't seen until a new client is built.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::model::options::SuggestionQuery;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let query = SuggestionQuery::new().limit(20).offset(20).exclude_on_list(true);
+    ///     for entry in client.get_suggestions(query).await?.data {
+    ///         println!("{}", entry.node.title);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    async fn get_suggestions(&self, query: SuggestionQuery) -> Result<AnimeList, MALError> {
+        query.limit.validate("get_suggestions", MAX_SUGGESTIONS_LIMIT)?;
+        let exclude_on_list = query.exclude_on_list;
+        let mut params = query.get_params();
+        if self.include_nsfw {
+            params.push(("nsfw", String::from("true")));
+        }
+        let url = self.api_url(&["anime", "suggestions"], params);
+        let list = self.do_request("get_suggested_anime", url).await?;
+        let mut list = self.paginate("get_suggested_anime", MAX_SUGGESTIONS_LIMIT, list).await?;
+        if exclude_on_list {
+            self.load_list_ids().await?;
+            let ids = self.list_ids.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(ids) = ids.as_ref() {
+                list.data.retain(|entry| !ids.contains(&entry.node.id));
+            }
+        }
+        Ok(list)
+    }
+
+    //--User anime list functions--//
+
+    ///Adds an anime to the list, or updates the element if it already exists
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::model::options::{Status, StatusUpdate};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     // add a new anime to the user's list
+    ///     let updated_status = client.update_user_anime_status(80, StatusUpdate::new().status(Status::Watching).build()?).await?;
+    ///     // or update an existing one
+    ///     let new_status = StatusUpdate::new().status(Status::Dropped).num_watched_episodes(2).build()?;
+    ///     let updated_status = client.update_user_anime_status(32981, new_status).await?;
+    ///
+    ///     # Ok(())
+    ///
+    /// # }
+    ///```
+    async fn update_user_anime_status(
+        &self,
+        id: impl Into<AnimeId> + Send,
+        update: StatusParams,
+    ) -> Result<ListStatus, MALError> {
+        let id: AnimeId = id.into();
+        let params = update.get_params();
+        let queued = self.offline_queue.is_some().then(|| PendingAction::Update {
+            params: params
+                .iter()
+                .map(|(k, v)| ((*k).to_owned(), v.clone()))
+                .collect(),
+        });
+        let url = self.list_status_url(id);
+        match self.do_request_forms("update_user_anime_status", url, params).await {
+            Err(e) if e.is_offline() => Err(self.queue_change(id, queued, e)),
+            res => {
+                if res.is_ok() {
+                    self.track_list_id(id, true);
+                }
+                res
+            }
+        }
+    }
+
+    ///Adds `by` watched episodes to the user's entry for the anime with `id` and returns the
+    ///updated entry. New and planned entries are moved to Watching, and the entry is marked as
+    ///Completed with today's finish date once the last episode is watched.
+    ///
+    ///The entry is read right before it's updated, bypassing the response cache. Use a
+    ///`ListSession` if other writes to the same anime can run at the same time.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let status = client.increment_watched_episodes(80, 1).await?;
+    ///     println!("watched {:?} episodes", status.num_episodes_watched);
+    ///     # Ok(())
+    /// # }
+    ///```
+    async fn increment_watched_episodes(
+        &self,
+        id: impl Into<AnimeId> + Send,
+        by: usize,
+    ) -> Result<ListStatus, MALError> {
+        let id: AnimeId = id.into();
+        let anime = self.list_entry(id).await?;
+        let update = StatusUpdate::watched_episodes(
+            anime.my_list_status.as_ref(),
+            anime.num_episodes,
+            by,
+            &self.today(),
+        );
+        self.update_user_anime_status(id, update).await
+    }
+
+    ///Marks the anime with `id` as Completed with every episode watched and today's finish
+    ///date, and optionally gives it a `score`. Finishing a rewatch counts it instead of changing
+    ///the finish date.
+    ///
+    ///The entry is read right before it's updated, bypassing the response cache. Use a
+    ///`ListSession` if other writes to the same anime can run at the same time.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     client.mark_completed(80, 8).await?;
+    ///     # Ok(())
+    /// # }
+    ///```
+    async fn mark_completed(
+        &self,
+        id: impl Into<AnimeId> + Send,
+        score: impl Into<Option<u8>> + Send,
+    ) -> Result<ListStatus, MALError> {
+        let id: AnimeId = id.into();
+        let score = score.into();
+        let anime = self.list_entry(id).await?;
+        let update = StatusUpdate::completed(
+            anime.my_list_status.as_ref(),
+            anime.num_episodes,
+            score,
+            &self.today(),
+        )?;
+        self.update_user_anime_status(id, update).await
+    }
+
+    ///Returns the user's anime list as an `AnimeList` struct, filtered and sorted by `query`
+    ///
+    ///Uses the defaults of `UserListQuery` when `query` is `None`.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALError};
+    /// use lib_mal::model::options::{ListSort, Status, UserListQuery};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let my_list = client.get_user_anime_list(None).await?;
+    ///     let watching = client
+    ///         .get_user_anime_list(UserListQuery::new().status(Status::Watching).sort(ListSort::ListScore))
+    ///         .await?;
+    ///     # Ok(())
+    ///
+    /// # }
+    ///```
+    async fn get_user_anime_list(
+        &self,
+        query: impl Into<Option<UserListQuery>> + Send,
+    ) -> Result<AnimeList, MALError> {
+        self.get_user_anime_list_for("@me", query).await
+    }
+
+    ///Returns the anime list of the user called `username`, filtered and sorted by `query`. MAL
+    ///responds with 403 Forbidden if the list is private.
+    ///
+    ///Uses the defaults of `UserListQuery` when `query` is `None`.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::model::options::{Status, UserListQuery};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let query = UserListQuery::new().status(Status::Completed);
+    ///     let mine = client.get_user_anime_list(query.clone()).await?;
+    ///     let theirs = client.get_user_anime_list_for("[SOME_USERNAME]", query).await?;
+    ///     # Ok(())
+    /// # }
+    ///```
+    async fn get_user_anime_list_for(
+        &self,
+        username: &str,
+        query: impl Into<Option<UserListQuery>> + Send,
+    ) -> Result<AnimeList, MALError> {
+        let query = query.into().unwrap_or_default();
+        query.limit.validate("get_user_anime_list", MAX_USER_LIST_LIMIT)?;
+        let url = self.api_url(&["users", username, "animelist"], query.get_params());
+        let list = self.do_request("get_user_anime_list", url).await?;
+        self.paginate("get_user_anime_list", MAX_USER_LIST_LIMIT, list).await
+    }
+
+    ///Deletes the anime with `id` from the user's anime list
+    ///
+    ///# Note
+    /// The [API docs from MAL](https://myanimelist.net/apiconfig/references/api/v2#operation/anime_anime_id_my_list_status_delete) say this method should return 404 if the anime isn't in the user's
+    /// list, but in my testing this wasn't true. Without that there's no way to tell if the item
+    /// was actually deleted or not.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     client.delete_anime_list_item(80).await?;
+    ///     # Ok(())
+    /// # }
+    ///```
+    async fn delete_anime_list_item(&self, id: impl Into<AnimeId> + Send) -> Result<(), MALError> {
+        let id: AnimeId = id.into();
+        let url = self.list_status_url(id);
+        match self.fetch("delete_anime_list_item", Method::DELETE, &url, None).await {
+            Ok(_) => {
+                self.track_list_id(id, false);
+                Ok(())
+            }
+            Err(e) if e.is_offline() => Err(self.queue_change(id, Some(PendingAction::Delete), e)),
+            Err(e) => Err(e),
+        }
+    }
+
+    //--Forum functions--//
+
+    ///Returns all the forum boards on MAL, grouped by category
+    async fn get_forum_boards(&self) -> Result<ForumBoards, MALError> {
+        let url = self.api_url(&["forum", "boards"], [] as [(&str, &str); 0]);
+        self.do_request("get_forum_boards", url).await
+    }
+
+    ///Returns details of the specified topic with its first `limit` posts
+    async fn get_forum_topic_detail(
+        &self,
+        topic_id: impl Into<TopicId> + Send,
+        limit: impl Into<Limit> + Send,
+    ) -> Result<TopicDetails, MALError> {
+        self.topic_posts("get_forum_topic_detail", topic_id.into(), limit.into(), 0)
+            .await
+    }
+
+    ///Returns details of the specified topic with `limit` posts, starting after the first
+    ///`offset` posts. See `MALClient::stream_topic_posts` for reading every post.
+    async fn get_forum_topic_posts(
+        &self,
+        topic_id: impl Into<TopicId> + Send,
+        limit: impl Into<Limit> + Send,
+        offset: usize,
+    ) -> Result<TopicDetails, MALError> {
+        self.topic_posts("get_forum_topic_posts", topic_id.into(), limit.into(), offset)
+            .await
+    }
+
+    ///Returns all topics for a given query
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::model::options::{ForumSort, ForumTopicQuery};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let query = ForumTopicQuery::new().board_id(1).sort(ForumSort::Recent).limit(10);
+    ///     let topics = client.get_forum_topics(query).await?;
+    ///     for topic in &topics.data {
+    ///         println!("{} ({} posts)", topic.title, topic.number_of_posts);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    async fn get_forum_topics(
+        &self,
+        query: impl Into<Option<ForumTopicQuery>> + Send,
+    ) -> Result<ForumTopics, MALError> {
+        let query = query.into().unwrap_or_default();
+        query.limit.validate("get_forum_topics", MAX_FORUM_TOPICS_LIMIT)?;
+        let url = self.api_url(&["forum", "topics"], query.get_params());
+        self.do_request("get_forum_topics", url).await
+    }
+
+    ///Gets the details for the current user
+    ///
+    ///Returns all fields when `fields` is `None`
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::model::fields::UserFields;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let me = client.get_my_user_info(UserFields::AnimeStatistics).await?;
+    ///     if let Some(stats) = me.anime_statistics {
+    ///         println!("{} completed, mean score {}", stats.num_completed, stats.mean_score);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    async fn get_my_user_info(
+        &self,
+        fields: impl Into<Option<UserFields>> + Send,
+    ) -> Result<User, MALError> {
+        self.get_user_info("@me", fields).await
+    }
+
+    ///Gets the public profile of the user called `username`, or of the current user for `@me`.
+    ///MAL decides which fields it returns for other users, and responds with an error if the
+    ///profile can't be viewed by the current user.
+    ///
+    ///Returns all fields when `fields` is `None`
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let user = client.get_user_info("[SOME_USERNAME]", None).await?;
+    ///     println!("{} joined at {}", user.name, user.joined_at);
+    ///     # Ok(())
+    /// # }
+    ///```
+    async fn get_user_info(
+        &self,
+        username: &str,
+        fields: impl Into<Option<UserFields>> + Send,
+    ) -> Result<User, MALError> {
+        let fields = fields.into().unwrap_or(UserFields::ALL).to_string();
+        let url = self.api_url(&["users", username], [("fields", fields)]);
+        self.do_request("get_user_info", url).await
+    }
+
+    ///Returns the current authorization state, see `MALClient::watch_auth_state` for getting
+    ///notified when it changes
+    fn auth_state(&self) -> AuthState {
+        *self.auth_state.as_ref().borrow()
+    }
+
+    fn reauth_reason(&self) -> Option<&ReauthReason> {
+        if !self.need_auth() {
+            return None;
+        }
+        if let Some(reason) = &self.reauth_reason {
+            return Some(reason);
+        }
+        // the client had a token, so MAL stopped accepting it
+        Some(if !self.tokens().access_token.is_empty() {
+            &ReauthReason::TokenExpired
+        } else if self.caching {
+            &ReauthReason::NeverAuthorized
+        } else {
+            &ReauthReason::NoCachedToken
+        })
+    }
+}
+
+impl MALClient {
+    ///Returns when the current access token expires, or `None` if it isn't known
+    pub fn token_expires_at(&self) -> Option<SystemTime> {
+        self.tokens()
+            .expires_at()
+            .map(|t| SystemTime::UNIX_EPOCH + Duration::from_secs(t))
+    }
+
+    ///Returns true if the current access token is past its expiry time
+    pub fn is_token_expired(&self) -> bool {
+        self.tokens().has_expired_at(self.clock.now())
+    }
+
+    ///Fetches the anime of `season` and returns the ones that haven't finished airing grouped
+    ///by the weekday they air on in the time zone `offset`, with the time of the next episode.
+    ///
+    ///See the `schedule` module for an example.
+    pub async fn get_airing_schedule(
+        &self,
+        season: impl Into<SeasonYear>,
+        offset: UtcOffset,
+    ) -> Result<AiringSchedule, MALError> {
+        let SeasonYear { season, year } = season.into();
+        let fields = AnimeFields::ID
+            | AnimeFields::Title
+            | AnimeFields::MainPicture
+            | AnimeFields::StartDate
+            | AnimeFields::MediaType
+            | AnimeFields::Status
+            | AnimeFields::NumEpisodes
+            | AnimeFields::Broadcast;
+        let url = self.season_url(year, season, fields);
+        let data = self.fetch_all_pages("get_airing_schedule", url).await?;
+        Ok(AiringSchedule::from_anime(
+            data.into_iter().map(|entry| entry.details),
+            offset,
+            self.clock.now(),
+        ))
+    }
+
+    ///Fetches every anime of a season with all fields and saves them as a gzip compressed JSON
+    ///archive at `path`, which can be loaded later with `SeasonArchive::load`.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # use lib_mal::model::options::Season;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let archive = client.archive_season(Season::Fall, 2020, "fall-2020.json.gz").await?;
+    ///     println!("Archived {} anime", archive.data.len());
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn archive_season(
+        &self,
+        season: Season,
+        year: usize,
+        path: impl AsRef<Path> + Send,
+    ) -> Result<SeasonArchive, MALError> {
+        let url = self.season_url(year, season, AnimeFields::ALL);
+        let data = self.fetch_all_pages("archive_season", url).await?;
+        let archive = SeasonArchive {
+            season,
+            year,
+            fetched_at: self.now(),
+            data,
+        };
+        archive.save(path)?;
+        Ok(archive)
+    }
+
+    ///Fetches the top `limit` anime of `ranking_type` and compares them with the snapshot saved
+    ///by the previous call for the same ranking type, which is replaced by the new one. The
+    ///snapshots are kept as `ranking-<type>.json` in the cache directory. On the first call every
+    ///anime is a new entry.
+    ///
+    ///See the `ranking` module for an example.
+    pub async fn ranking_digest(
+        &self,
+        ranking_type: RankingType,
+        limit: impl Into<Limit>,
+    ) -> Result<RankingDigest, MALError> {
+        let name = ranking_type.to_string();
+        let path = self.dirs.join(format!("ranking-{name}.json"));
+        let list = self.get_anime_ranking(ranking_type, limit.into(), None).await?;
+        let current = RankingSnapshot::from_list(&name, self.now(), &list);
+        let previous = RankingSnapshot::load(&path)?;
+        let digest = ranking::digest(previous.as_ref(), &current);
+        fs::create_dir_all(&self.dirs)?;
+        current.save(&path)?;
+        Ok(digest)
+    }
+
+    ///Checks the connection to MAL and Jikan, the access token, the clock, the cache
+    ///directories, and the rate limit, e.g. for a troubleshooting screen. Never fails, problems
+    ///are reported in the returned `Diagnostics` instead. Sends three requests, the one checking
+    ///the token goes through the rate limit and refreshes the token if it expired.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait};
+    /// # async fn test() {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let report = client.diagnose().await;
+    ///     if !report.is_healthy() {
+    ///         println!("{report:#?}");
+    ///     }
+    /// # }
+    ///```
+    pub async fn diagnose(&self) -> Diagnostics {
+        let mut checks = Vec::new();
+        let (mal, server_time) = self
+            .check_reachable(CheckKind::MalReachable, &format!("{}v2/", self.api_base_url))
+            .await;
+        let token = if mal.status == CheckStatus::Failed {
+            Check::new(CheckKind::Token, CheckStatus::Skipped, "MAL can't be reached")
+        } else {
+            self.check_token().await
+        };
+        checks.push(mal);
+        checks.push(token);
+        checks.push(check_clock(self.clock.now(), server_time));
+        checks.push(self.check_cache_dirs());
+        #[cfg(feature = "jikan")]
+        let (jikan, _) = self
+            .check_reachable(CheckKind::JikanReachable, &format!("{JIKAN_API_URL}/"))
+            .await;
+        #[cfg(not(feature = "jikan"))]
+        let jikan = Check::new(
+            CheckKind::JikanReachable,
+            CheckStatus::Skipped,
+            "the jikan feature is disabled",
+        );
+        checks.push(jikan);
+        checks.push(self.check_rate_limit());
+        Diagnostics { checks }
+    }
+
+    ///Sends an unauthenticated request to `url`, any response counts as reachable. Also returns
+    ///the server's time if it was sent.
+    async fn check_reachable(&self, kind: CheckKind, url: &str) -> (Check, Option<SystemTime>) {
+        let start = Instant::now();
+        match self.http().send(HttpRequest::new(Method::GET, url)).await {
+            Ok(res) => {
+                let server_time = res
+                    .headers
+                    .get(DATE)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| httpdate::parse_http_date(v).ok());
+                let status = if res.status.is_server_error() {
+                    CheckStatus::Warning
+                } else {
+                    CheckStatus::Ok
+                };
+                let detail = format!(
+                    "responded with {} in {} ms",
+                    res.status,
+                    start.elapsed().as_millis()
+                );
+                (Check::new(kind, status, detail), server_time)
+            }
+            Err(e) => (Check::new(kind, CheckStatus::Failed, e.to_string()), None),
+        }
+    }
+
+    async fn check_token(&self) -> Check {
+        if self.tokens().access_token.is_empty() {
+            return Check::new(CheckKind::Token, CheckStatus::Failed, "not logged in");
+        }
+        let url = self.api_url(&["users", "@me"], [] as [(&str, &str); 0]);
+        match self.fetch("diagnose", Method::GET, &url, None).await {
+            Ok(_) => {
+                let detail = self
+                    .token_expires_at()
+                    .and_then(|t| t.duration_since(self.clock.now()).ok())
+                    .map_or_else(
+                        || String::from("token accepted"),
+                        |left| format!("token accepted, expires in {} hours", left.as_secs() / 3600),
+                    );
+                Check::new(CheckKind::Token, CheckStatus::Ok, detail)
+            }
+            Err(MALError::Unauthorized { .. }) => Check::new(
+                CheckKind::Token,
+                CheckStatus::Failed,
+                "token was rejected, log in again",
+            ),
+            Err(e) => Check::new(
+                CheckKind::Token,
+                CheckStatus::Warning,
+                format!("couldn't verify the token: {e}"),
+            ),
+        }
+    }
+
+    ///Writes and removes a file in every cache directory in use
+    fn check_cache_dirs(&self) -> Check {
+        let mut dirs = Vec::new();
+        if self.caching && !self.dirs.as_os_str().is_empty() {
+            dirs.push(self.dirs.as_path());
+        }
+        if let Some(dir) = self.response_cache.as_deref().and_then(ResponseCache::dir) {
+            dirs.push(dir);
+        }
+        if dirs.is_empty() {
+            return Check::new(
+                CheckKind::CacheDir,
+                CheckStatus::Skipped,
+                "no cache directory is used",
+            );
+        }
+        for dir in &dirs {
+            let probe = dir.join(".lib-mal-write-test");
+            if let Err(e) = fs::write(&probe, b"").and_then(|()| fs::remove_file(&probe)) {
+                return Check::new(
+                    CheckKind::CacheDir,
+                    CheckStatus::Failed,
+                    format!("can't write to {}: {e}", dir.display()),
+                );
+            }
+        }
+        let dirs: Vec<String> = dirs.iter().map(|d| d.display().to_string()).collect();
+        Check::new(
+            CheckKind::CacheDir,
+            CheckStatus::Ok,
+            format!("{} writable", dirs.join(", ")),
+        )
+    }
+
+    fn check_rate_limit(&self) -> Check {
+        let Some(limiter) = &self.rate_limiter else {
+            return Check::new(
+                CheckKind::RateLimit,
+                CheckStatus::Warning,
+                "no rate limit set, MAL may throttle bursts of requests",
+            );
+        };
+        let per_second = 1.0 / limiter.interval().as_secs_f64();
+        let status = if per_second > f64::from(RECOMMENDED_REQUESTS_PER_SECOND) {
+            CheckStatus::Warning
+        } else {
+            CheckStatus::Ok
+        };
+        Check::new(
+            CheckKind::RateLimit,
+            status,
+            format!(
+                "{per_second:.1} requests per second, {RECOMMENDED_REQUESTS_PER_SECOND} recommended, {} retries",
+                self.max_retries
+            ),
+        )
+    }
+
+    ///Returns how often the response cache was used, or `None` if it isn't enabled. Useful for
+    ///checking that the cache works as expected.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.response_cache.as_deref().map(ResponseCache::stats)
+    }
+
+    ///Sends a get request to `path`, relative to `v2/` of the API base URL, and parses the
+    ///response into `T` without going through the response cache. Meant for the types of
+    ///the `wire` module, which keep the data exactly as MAL sent it.
+    ///
+    ///See the `wire` module for an example.
+    pub async fn get_wire<T: DeserializeOwned>(&self, path: &str) -> Result<T, MALError> {
+        let url = format!("{}v2/{}", self.api_base_url, path.trim_start_matches('/'));
+        self.do_request("get_wire", url).await
+    }
+
+    ///Sends a get request to `path`, relative to `v2/` of the API base URL, and returns the JSON
+    ///exactly as MAL sent it. Useful for endpoints and fields the crate doesn't cover yet.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let manga = client.get_raw("manga/2?fields=num_volumes").await?;
+    ///     println!("{}", manga["num_volumes"]);
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn get_raw(&self, path: &str) -> Result<Value, MALError> {
+        let url = format!("{}v2/{}", self.api_base_url, path.trim_start_matches('/'));
+        self.do_request("get_raw", url).await
+    }
+
+    ///Sends the request for `endpoint` and parses the response as `T`, see the `endpoint`
+    ///module. Responses without a body, like the one to `Endpoint::DeleteListStatus`, are
+    ///parsed as `null`, so `()` can be used for them.
+    pub async fn execute<T: DeserializeOwned>(&self, endpoint: Endpoint) -> Result<T, MALError> {
+        let name = endpoint.name();
+        let (segments, mut params) = endpoint.path(self.default_anime_fields)?;
+        if endpoint.lists_anime() {
+            params.extend(self.nsfw_pair().map(|(k, v)| (k.to_owned(), v.to_owned())));
+        }
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        let url = self.api_url(&segments, &params);
+        let body = endpoint.body();
+        let form: Option<Vec<(&str, String)>> = body
+            .as_ref()
+            .map(|body| body.iter().map(|(k, v)| (k.as_str(), v.clone())).collect());
+        let res = self.fetch(name, endpoint.method(), &url, form.as_deref()).await;
+        let (status, res) = match endpoint {
+            Endpoint::AnimeDetails { .. } => res.map_err(classify_anime_error)?,
+            Endpoint::UpdateListStatus { id, .. } => {
+                let res = res?;
+                self.track_list_id(id, true);
+                res
+            }
+            Endpoint::DeleteListStatus { id } => {
+                let res = res?;
+                self.track_list_id(id, false);
+                res
+            }
+            _ => res?,
+        };
+        let res = if res.trim().is_empty() { "null" } else { res.as_str() };
+        Self::parse_response(res).map_err(|e| e.with_context(name, &url, Some(status)))
+    }
+
+    ///Same as `get_anime_details`, but returns the JSON exactly as MAL sent it
+    pub async fn get_anime_details_raw(
+        &self,
+        id: impl Into<AnimeId>,
+        fields: impl Into<Option<AnimeFields>>,
+    ) -> Result<Value, MALError> {
+        let url = self.anime_details_url(id.into(), fields.into());
+        self.do_request_cached("get_anime_details", url)
+            .await
+            .map_err(classify_anime_error)
+    }
+
+    ///Same as `get_anime_details`, but also returns the JSON the details were parsed from
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let details = client.get_anime_details_with_raw(80, None).await?;
+    ///     println!("{}: {}", details.show.title, details.raw["nsfw"]);
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn get_anime_details_with_raw(
+        &self,
+        id: impl Into<AnimeId>,
+        fields: impl Into<Option<AnimeFields>>,
+    ) -> Result<WithRaw<AnimeDetails>, MALError> {
+        let url = self.anime_details_url(id.into(), fields.into());
+        self.do_request_cached("get_anime_details", url)
+            .await
+            .map_err(classify_anime_error)
+    }
+
+    ///Returns the URL of the details of `id`, with the default fields when `fields` is `None`
+    fn anime_details_url(&self, id: AnimeId, fields: Option<AnimeFields>) -> String {
+        let fields = fields.unwrap_or(self.default_anime_fields);
+        self.api_url(&["anime", &id.to_string()], [("fields", fields.to_string())])
+    }
+
+    ///Same as `update_user_anime_status`, but only if the user's entry is still the one the
+    ///update was based on, so edits made on the website or another device aren't overwritten.
+    ///`based_on` is the `updated_at` of that entry, or `None` if the anime wasn't on the list.
+    ///
+    ///Fails with `MALError::Conflict` holding the current entry if it changed. The entry is read
+    ///right before the update, so a change in between those two requests still goes unnoticed.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::model::options::StatusUpdate;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let details = client.get_anime_details(80, None).await?;
+    ///     let based_on = details.my_list_status.as_ref().and_then(|s| s.updated_at.as_deref());
+    ///     let update = StatusUpdate::new().score(9).build()?;
+    ///     match client.update_if_unchanged(80, update, based_on).await {
+    ///         Err(MALError::Conflict { current }) => println!("changed elsewhere: {current:?}"),
+    ///         res => println!("{:?}", res?),
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn update_if_unchanged(
+        &self,
+        id: impl Into<AnimeId>,
+        update: StatusParams,
+        based_on: Option<&str>,
+    ) -> Result<ListStatus, MALError> {
+        let id = id.into();
+        let current = self.list_entry(id).await?.my_list_status;
+        let updated_at = current.as_ref().and_then(|s| s.updated_at.as_deref());
+        // compare the times rather than the strings, in case MAL changes how it formats them
+        let unchanged = match (updated_at, based_on) {
+            (Some(a), Some(b)) => {
+                a == b || parse_timestamp(a).is_some_and(|a| parse_timestamp(b) == Some(a))
+            }
+            (None, None) => current.is_none(),
+            _ => false,
+        };
+        if !unchanged {
+            return Err(MALError::Conflict {
+                current: current.map(Box::new),
+            });
+        }
+        self.update_user_anime_status(id, update).await
+    }
+
+    ///Starts timing `episode` of the anime with `id`, e.g. when a player starts playing it.
+    ///Replaces the session that's running and returns it, if there is one.
+    ///
+    ///See the `watch` module for an example.
+    pub fn begin_watch_session(&self, id: impl Into<AnimeId>, episode: usize) -> Option<WatchSession> {
+        self.watch_session
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .replace(WatchSession::new(id.into(), episode))
+    }
+
+    ///Returns the watch session that's running, if any
+    pub fn watch_session(&self) -> Option<WatchSession> {
+        *self
+            .watch_session
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    ///Stops the watch session that's running, returns `None` if there is none. The episode is
+    ///marked as watched if the session lasted for at least the watch threshold of the anime's
+    ///average episode duration, and the user's list shows fewer episodes watched. Completing
+    ///the last episode completes the entry, see `increment_watched_episodes`.
+    ///
+    ///The threshold is 80% by default and can be changed with `ClientBuilder::watch_threshold`.
+    pub async fn end_watch_session(&self) -> Result<Option<WatchReport>, MALError> {
+        let Some(session) = self
+            .watch_session
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        else {
+            return Ok(None);
+        };
+        let elapsed = session.started.elapsed();
+        let anime = self.list_entry(session.id).await?;
+        let status = anime.my_list_status.as_ref();
+        let watched = status
+            .and_then(|s| s.num_episodes_watched)
+            .unwrap_or_default();
+        let duration = anime.average_episode_duration.unwrap_or_default();
+        let counted = if session.episode > watched
+            && reached_threshold(elapsed, duration, self.watch_threshold)
+        {
+            let update = StatusUpdate::watched_episodes(
+                status,
+                anime.num_episodes,
+                session.episode - watched,
+                &self.today(),
+            );
+            Some(self.update_user_anime_status(session.id, update).await?)
+        } else {
+            None
+        };
+        Ok(Some(WatchReport {
+            id: session.id,
+            episode: session.episode,
+            elapsed,
+            counted,
+        }))
+    }
+
+    ///Marks the episode in `name`, a file name or a title followed by an episode, as watched.
+    ///Returns `None` if no title could be parsed from `name`, see `scrobble::parse`.
+    ///
+    ///The title is searched for and the list is only updated if the best match has a
+    ///confidence of at least `scrobble::MIN_CONFIDENCE`, and the user's list shows fewer
+    ///episodes watched. Names without an episode only count for anime with a single episode,
+    ///like movies. Watching the last episode completes the entry, see
+    ///`increment_watched_episodes`.
+    pub async fn scrobble(&self, name: &str) -> Result<Option<ScrobbleReport>, MALError> {
+        let Some(media) = scrobble::parse(name) else {
+            return Ok(None);
+        };
+        let matched = self.find_anime(&media.search_query()).await?;
+        let mut counted = None;
+        if let Some(found) = matched.as_ref().filter(|m| m.confidence >= scrobble::MIN_CONFIDENCE) {
+            let anime = self.list_entry(found.anime.id).await?;
+            let status = anime.my_list_status.as_ref();
+            let watched = status
+                .and_then(|s| s.num_episodes_watched)
+                .unwrap_or_default();
+            let episode = media
+                .episode
+                .or_else(|| (anime.num_episodes == Some(1)).then_some(1));
+            if let Some(episode) = episode.filter(|e| *e > watched) {
+                let update = StatusUpdate::watched_episodes(
+                    status,
+                    anime.num_episodes,
+                    episode - watched,
+                    &self.today(),
+                );
+                counted = Some(self.update_user_anime_status(found.anime.id, update).await?);
+            }
+        }
+        Ok(Some(ScrobbleReport {
+            media,
+            matched,
+            counted,
+        }))
+    }
+
+    ///Fetches the user's entry, episode count, and episode duration of the anime with `id`
+    ///without the response cache, so read-modify-write helpers don't start from an outdated entry
+    async fn list_entry(&self, id: AnimeId) -> Result<AnimeDetails, MALError> {
+        let fields = AnimeFields::MyListStatus
+            | AnimeFields::NumEpisodes
+            | AnimeFields::AverageEpisodeDuration;
+        let url = self.api_url(&["anime", &id.to_string()], [("fields", fields.to_string())]);
+        self.do_request("list_entry", url)
+            .await
+            .map_err(classify_anime_error)
+    }
+
+    ///Fetches `limit` posts of the topic starting after the first `offset`, as `endpoint`
+    async fn topic_posts(
+        &self,
+        endpoint: &str,
+        topic_id: TopicId,
+        limit: Limit,
+        offset: usize,
+    ) -> Result<TopicDetails, MALError> {
+        let limit = limit.validate(endpoint, MAX_TOPIC_POSTS_LIMIT)?;
+        let url = self.api_url(
+            &["forum", "topic", &topic_id.to_string()],
+            [("limit", limit.to_string()), ("offset", offset.to_string())],
+        );
+        self.do_request(endpoint, url).await
+    }
+
+    ///Puts `action` in the offline queue if it's enabled and returns `MALError::Queued`, returns
+    ///`err` otherwise
+    fn queue_change(&self, id: AnimeId, action: Option<PendingAction>, err: MALError) -> MALError {
+        let (Some(queue), Some(action)) = (&self.offline_queue, action) else {
+            return err;
+        };
+        let change = PendingChange {
+            queued_at: self.now(),
+            id,
+            action,
+        };
+        match queue.append(&self.dirs, &change) {
+            Ok(()) => MALError::Queued,
+            Err(e) => e,
+        }
+    }
+
+    ///Returns the changes waiting in the offline queue, oldest first
+    pub fn pending_changes(&self) -> Result<Vec<PendingChange>, MALError> {
+        offline::read(self.dirs.join(QUEUE_FILE))
+    }
+
+    ///Sends the changes in the offline queue, oldest first. A change is dropped instead if the
+    ///entry was updated on MAL after the change was queued, so edits made on another device
+    ///aren't overwritten. Changes MAL rejects as invalid are dropped. Stops at the first change
+    ///that can't be sent right now, because there's no connection, the token was rejected, the
+    ///requests are rate limited, or MAL has a server error; that one and the rest stay queued.
+    ///
+    ///See the `offline` module for an example.
+    pub async fn flush_pending(&self) -> Result<FlushReport, MALError> {
+        let Some(queue) = &self.offline_queue else {
+            return Ok(FlushReport::default());
+        };
+        let changes = self.pending_changes()?;
+        let handled = changes.len();
+        let mut report = FlushReport::default();
+        // changes sent during this flush update the entry too, they aren't conflicts
+        let mut replayed = HashSet::new();
+        let mut changes = changes.into_iter();
+        while let Some(change) = changes.next() {
+            match self.replay(&change, replayed.contains(&change.id)).await {
+                Ok(true) => {
+                    replayed.insert(change.id);
+                    report.applied.push(change);
+                }
+                Ok(false) => report.conflicts.push(change),
+                Err(e) if is_rejected(&e) => report.failed.push((change, e)),
+                Err(_) => {
+                    report.pending.push(change);
+                    report.pending.extend(changes);
+                    break;
+                }
+            }
+        }
+        queue.finish(&self.dirs, handled, &report.pending)?;
+        Ok(report)
+    }
+
+    ///Sends a queued change unless the entry was changed after it was queued, returns whether it
+    ///was sent
+    async fn replay(&self, change: &PendingChange, skip_check: bool) -> Result<bool, MALError> {
+        if !skip_check {
+            let entry = match self.list_entry(change.id).await {
+                Ok(entry) => entry.my_list_status,
+                // the anime is gone, so the entry is too
+                Err(e) if e.is_unavailable() => None,
+                Err(e) => return Err(e),
+            };
+            let updated_at = entry
+                .as_ref()
+                .and_then(|s| parse_timestamp(s.updated_at.as_deref()?))
+                .map(unix_secs);
+            if updated_at.is_some_and(|updated| updated > change.queued_at) {
+                return Ok(false);
+            }
+        }
+        let url = self.list_status_url(change.id);
+        match &change.action {
+            PendingAction::Update { params } => {
+                let params = params.iter().map(|(k, v)| (k.as_str(), v.clone())).collect();
+                self.do_request_forms::<ListStatus>("flush_pending", url, params)
+                    .await?;
+                self.track_list_id(change.id, true);
+            }
+            PendingAction::Delete => {
+                match self.fetch("flush_pending", Method::DELETE, &url, None).await {
+                    // already deleted
+                    Ok(_) | Err(MALError::NotFound { .. }) => {}
+                    Err(e) => return Err(e),
+                }
+                self.track_list_id(change.id, false);
+            }
+        }
+        Ok(true)
+    }
+
+    ///Reads the changes this client, or an earlier one with the same cache directory, sent to
+    ///the user's anime list. Empty unless the journal was enabled with `ClientBuilder::journal`.
+    pub fn read_journal(&self) -> Result<Vec<JournalEntry>, MALError> {
+        journal::read(self.dirs.join(JOURNAL_FILE))
+    }
+
+    ///Returns a client for the Jikan API, which has data the MAL API doesn't, like episodes,
+    ///characters, and staff. It shares this client's HTTP client, rate limit, and response cache.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let episodes = client.jikan().episodes(30230, None, false).await?;
+    ///     println!("{} episodes", episodes.data.len());
+    ///     # Ok(())
+    /// # }
+    ///```
+    #[cfg(feature = "jikan")]
+    pub const fn jikan(&self) -> JikanClient<'_> {
+        JikanClient::new(self)
+    }
+
+    ///Fetches the plan to watch lists of `usernames` and returns the anime that are on all of
+    ///them, for finding something to watch together. The lists have to be public.
+    ///
+    ///Anime more users gave a higher priority come first, see `AnimeList::shared_entries`.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let shared = client.shared_plan_to_watch(&["user_one", "user_two"]).await?;
+    ///     if let Some(pick) = shared.first() {
+    ///         println!("Watch {} together", pick.anime.title);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn shared_plan_to_watch(
+        &self,
+        usernames: &[&str],
+    ) -> Result<Vec<SharedEntry>, MALError> {
+        let mut lists = Vec::with_capacity(usernames.len());
+        for username in usernames {
+            let url = self.api_url(
+                &["users", username, "animelist"],
+                &[
+                    ("status", "plan_to_watch"),
+                    ("limit", &MAX_USER_LIST_LIMIT.to_string()),
+                    ("fields", "list_status{priority},mean"),
+                ],
+            );
+            let data = self.fetch_all_pages("shared_plan_to_watch", url).await?;
+            lists.push(AnimeList {
+                data,
+                paging: HashMap::new(),
+                season: None,
+                extra: serde_json::Map::new(),
+            });
+        }
+        Ok(AnimeList::shared_entries(&lists))
+    }
+
+    ///Fetches the user's plan to watch list with the mean score, popularity, and number of
+    ///episodes of every anime and orders it by what to watch next, see the `prioritize` module
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::prioritize::PriorityWeights;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let ranked = client.plan_to_watch_ranked(&PriorityWeights::default()).await?;
+    ///     if let Some(next) = ranked.first() {
+    ///         println!("Watch {} next", next.entry.node.title);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn plan_to_watch_ranked(
+        &self,
+        weights: &PriorityWeights,
+    ) -> Result<Vec<RankedEntry>, MALError> {
+        let url = self.api_url(
+            &["users", "@me", "animelist"],
+            &[
+                ("status", "plan_to_watch"),
+                ("limit", &MAX_USER_LIST_LIMIT.to_string()),
+                ("fields", prioritize::FIELDS),
+            ],
+        );
+        let data = self.fetch_all_pages("plan_to_watch_ranked", url).await?;
+        Ok(prioritize::rank(data, weights))
+    }
+
+    ///Fetches the user's list and the list of `username` and compares the scores on them, see
+    ///the `affinity` module. The other user's list has to be public.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let report = client.compare_with_user("[SOME_USERNAME]").await?;
+    ///     println!("{} shows in common", report.shared.len());
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn compare_with_user(&self, username: &str) -> Result<AffinityReport, MALError> {
+        let limit = MAX_USER_LIST_LIMIT.to_string();
+        let url = |user: &str| {
+            let mut params = vec![("limit", limit.as_str()), ("fields", "list_status")];
+            params.extend(self.nsfw_pair());
+            self.api_url(&["users", user, "animelist"], &params)
+        };
+        let (mine, theirs) = future::try_join(
+            self.fetch_all_pages("compare_with_user", url("@me")),
+            self.fetch_all_pages("compare_with_user", url(username)),
+        )
+        .await?;
+        Ok(AffinityReport::from_lists(&mine, &theirs))
+    }
+
+    ///Downloads `picture` in `size` to the directory `dest` and returns the path of the file, see
+    ///the `pictures` module. Pictures that are already in `dest` aren't downloaded again.
+    ///
+    ///The pictures are on MAL's CDN, so they're fetched without the access token and don't count
+    ///towards the client's rate limit.
+    pub async fn download_picture(
+        &self,
+        picture: &impl Picture,
+        size: PictureSize,
+        dest: impl AsRef<Path>,
+    ) -> Result<PathBuf, MALError> {
+        let url = picture
+            .url(size)
+            .ok_or(MALError::NotFound { context: None })?;
+        let dest = dest.as_ref();
+        let path = dest.join(pictures::file_name(url));
+        if path.is_file() {
+            return Ok(path);
+        }
+        let (status, bytes) = self
+            .http()
+            .send_bytes(HttpRequest::new(Method::GET, url))
+            .await?;
+        if !status.is_success() {
+            return Err(MALError::Http {
+                status,
+                body: String::new(),
+                api_error: None,
+                context: None,
+            });
+        }
+        if !dest.as_os_str().is_empty() {
+            std::fs::create_dir_all(dest)?;
+        }
+        // written under another name first so a cut off download isn't taken for a whole picture
+        let partial = path.with_extension("part");
+        std::fs::write(&partial, bytes)?;
+        std::fs::rename(partial, &path)?;
+        Ok(path)
+    }
+
+    ///Same as `download_picture` for every picture in `pictures`, a few at a time. The results
+    ///are in the same order as `pictures`, a failed download doesn't stop the others.
+    pub async fn download_pictures<P: Picture + Sync>(
+        &self,
+        pictures: &[P],
+        size: PictureSize,
+        dest: impl AsRef<Path>,
+    ) -> Vec<Result<PathBuf, MALError>> {
+        let dest = dest.as_ref();
+        stream::iter(pictures)
+            .map(|picture| self.download_picture(picture, size, dest))
+            .buffered(PICTURE_CONCURRENCY)
+            .collect()
+            .await
+    }
+
+    ///Loads the map of MAL ids to the ids on AniList, Kitsu, and AniDB, see the `idmap` module.
+    ///The database is read from `dir` if it was saved there less than `max_age` ago, otherwise
+    ///it's downloaded from `idmap::OFFLINE_DATABASE_URL` and saved there.
+    pub async fn load_id_map(&self, dir: impl AsRef<Path>, max_age: Duration) -> Result<IdMap, MALError> {
+        let dir = dir.as_ref();
+        let path = dir.join(idmap::ID_MAP_FILE);
+        let fresh = std::fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| self.clock.now().duration_since(modified).unwrap_or_default() < max_age);
+        if fresh {
+            // a damaged copy is downloaded again
+            let cached = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|json| IdMap::from_offline_database(&json).ok());
+            if let Some(map) = cached {
+                return Ok(map);
+            }
+        }
+        let res = self
+            .http()
+            .send(HttpRequest::new(Method::GET, idmap::OFFLINE_DATABASE_URL))
+            .await?;
+        if !res.status.is_success() {
+            return Err(MALError::Http {
+                status: res.status,
+                body: excerpt(&res.body),
+                api_error: None,
+                context: None,
+            });
+        }
+        let map = IdMap::from_offline_database(&res.body)?;
+        if !dir.as_os_str().is_empty() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let partial = path.with_extension("part");
+        std::fs::write(&partial, &res.body)?;
+        std::fs::rename(partial, &path)?;
+        Ok(map)
+    }
+
+    ///Gets the details of the anime with `id` on `service`, using `map` to find its MAL id.
+    ///Fails with `MALError::NotFound` if the anime isn't in the map.
+    pub async fn get_anime_details_by_external_id(
+        &self,
+        map: &IdMap,
+        service: Service,
+        id: u64,
+        fields: impl Into<Option<AnimeFields>> + Send,
+    ) -> Result<AnimeDetails, MALError> {
+        let mal = map
+            .mal_id(service, id)
+            .ok_or(MALError::NotFound { context: None })?;
+        self.get_anime_details(mal, fields).await
+    }
+
+    ///Same as `get_anime_details_by_external_id` with an AniList id
+    pub async fn get_anime_details_by_anilist_id(
+        &self,
+        map: &IdMap,
+        id: u64,
+        fields: impl Into<Option<AnimeFields>> + Send,
+    ) -> Result<AnimeDetails, MALError> {
+        self.get_anime_details_by_external_id(map, Service::AniList, id, fields)
+            .await
+    }
+
+    ///Puts every entry of an exported list on the user's anime list, one request at a time. The
+    ///user's list is fetched first to find the anime already on it, which are handled according
+    ///to `policy`. A failed update doesn't stop the others.
+    ///
+    ///See the `export` module for an example.
+    pub async fn import_list(
+        &self,
+        entries: &[ExportEntry],
+        policy: ConflictPolicy,
+    ) -> Result<ImportReport, MALError> {
+        let existing: HashSet<AnimeId> = if policy == ConflictPolicy::Overwrite {
+            HashSet::new()
+        } else {
+            let url = self.user_list_url(&[("nsfw", "true")]);
+            self.fetch_all_pages("import_list", url)
+                .await?
+                .iter()
+                .map(|entry| entry.node.id)
+                .collect()
+        };
+        let mut report = ImportReport::default();
+        for entry in entries {
+            let id = entry.series_animedb_id;
+            if existing.contains(&id) && !policy.overwrites(entry) {
+                report.skipped.push(id);
+                continue;
+            }
+            let update = match entry.to_update().build() {
+                Ok(update) => update,
+                Err(e) => {
+                    report.failed.push((id, e.into()));
+                    continue;
+                }
+            };
+            match self.update_user_anime_status(id, update).await {
+                Ok(_) => report.imported.push(id),
+                Err(e) => report.failed.push((id, e)),
+            }
+        }
+        Ok(report)
+    }
+
+    ///Deletes every entry of the user's anime list, or only the ones with the status set in
+    ///`options`, one request at a time so the rate limit is respected. `progress` is called after
+    ///every entry. A failed deletion doesn't stop the others.
+    ///
+    ///Fails with `MALError::NotConfirmed` before sending anything unless `options` was built with
+    ///`clear::CLEAR_LIST_CONFIRMATION`. See the `clear` module for an example.
+    pub async fn clear_list(
+        &self,
+        options: ListClearOptions,
+        mut progress: impl FnMut(ClearProgress) + Send,
+    ) -> Result<ClearReport, MALError> {
+        if !options.is_confirmed() {
+            return Err(MALError::NotConfirmed);
+        }
+        let status = options.status.map(|s| s.to_string());
+        let mut params = vec![("nsfw", "true")];
+        params.extend(status.as_deref().map(|s| ("status", s)));
+        let url = self.user_list_url(&params);
+        let ids: Vec<AnimeId> = self
+            .fetch_all_pages("clear_list", url)
+            .await?
+            .iter()
+            .map(|entry| entry.node.id)
+            .collect();
+        let mut report = ClearReport::default();
+        for (done, &id) in ids.iter().enumerate() {
+            match self.delete_anime_list_item(id).await {
+                Ok(()) => report.deleted.push(id),
+                Err(e) => report.failed.push((id, e)),
+            }
+            progress(ClearProgress {
+                id,
+                done: done + 1,
+                total: ids.len(),
+            });
+        }
+        Ok(report)
+    }
+
+    ///Fetches the user's whole anime list and syncs it with `local` in `direction`. Pushing
+    ///sends one request per changed anime, a failed request doesn't stop the others. Pulling
+    ///updates `local` without sending anything else.
+    ///
+    ///In a dry run neither side is changed, the report holds the changes that would be made.
+    ///
+    ///See the `sync` module for an example.
+    pub async fn sync_list(
+        &self,
+        local: &mut ListSnapshot,
+        direction: SyncDirection,
+        dry_run: bool,
+    ) -> Result<SyncReport, MALError> {
+        let fields = format!("list_status{{{SYNC_FIELDS}}}");
+        let url = self.user_list_url(&[("nsfw", "true"), ("fields", &fields)]);
+        let data = self.fetch_all_pages("sync_list", url).await?;
+        let remote = sync::snapshot(&AnimeList {
+            data,
+            paging: HashMap::new(),
+            season: None,
+            extra: serde_json::Map::new(),
+        });
+        let mut report = SyncReport::default();
+        match direction {
+            SyncDirection::Pull => {
+                report.diff = sync::diff(local, &remote);
+                if !dry_run {
+                    report.diff.apply(local);
+                }
+            }
+            SyncDirection::Push => {
+                report.diff = sync::diff(&remote, local);
+                if dry_run {
+                    return Ok(report);
+                }
+                for change in &report.diff.changes {
+                    let res = match change.kind.to_update() {
+                        Some(update) => match update.build() {
+                            Ok(update) => self
+                                .update_user_anime_status(change.id, update)
+                                .await
+                                .map(|_| ()),
+                            Err(e) => Err(e.into()),
+                        },
+                        None => self.delete_anime_list_item(change.id).await,
+                    };
+                    if let Err(e) = res {
+                        report.failed.push((change.id, e));
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    ///Fetches the user's Watching list and returns a stream that emits an `AiringEvent` `lead`
+    ///before each new episode of an airing anime on it, e.g. for notification daemons.
+    ///
+    ///The list is only fetched once, the air times are computed from the weekly broadcast times
+    ///MAL reports and the client's clock, so MAL isn't polled while waiting. The stream only ends
+    ///if nothing on the list is airing. It doesn't notice list changes or anime that finished
+    ///airing, so create a new one every now and then.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use futures_util::StreamExt;
+    /// use std::time::Duration;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let countdowns = client.countdowns_for_watchlist(Duration::from_secs(15 * 60)).await?;
+    ///     let mut countdowns = std::pin::pin!(countdowns);
+    ///     while let Some(event) = countdowns.next().await {
+    ///         println!("{} airs in 15 minutes", event.anime.title);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn countdowns_for_watchlist(
+        &self,
+        lead: Duration,
+    ) -> Result<impl Stream<Item = AiringEvent> + Send + 'static, MALError> {
+        let fields = AnimeFields::Status | AnimeFields::Broadcast;
+        let url = self.watching_url(&fields.to_string());
+        let list = self
+            .fetch_all_pages("countdowns_for_watchlist", url)
+            .await?;
+        let clock = self.clock.clone();
+        let start = clock.now();
+        let schedule: Vec<(SystemTime, AnimeDetails)> = list
+            .into_iter()
+            .filter_map(|entry| Some((entry.details.next_broadcast(start)?, entry.details)))
+            .collect();
+        Ok(stream::unfold(schedule, move |mut schedule| {
+            let clock = clock.clone();
+            async move {
+                let (next, (airs_at, anime)) = schedule
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, (airs_at, _))| *airs_at)?;
+                let (airs_at, event) = (
+                    *airs_at,
+                    AiringEvent {
+                        anime: anime.show.clone(),
+                        airs_at: *airs_at,
+                    },
+                );
+                let notify_at = airs_at.checked_sub(lead).unwrap_or(airs_at);
+                if let Ok(wait) = notify_at.duration_since(clock.now()) {
+                    rt::sleep(wait).await;
+                }
+                let following = schedule[next].1.next_broadcast(airs_at)?;
+                schedule[next].0 = following;
+                Some((event, schedule))
+            }
+        }))
+    }
+
+    ///Returns the currently airing anime on the user's Watching list with how many episodes have
+    ///aired, so trackers can show how far behind the user is. Only the list is requested, the
+    ///aired episodes are estimated with `AnimeDetails::episodes_aired`. Sorted by how many
+    ///episodes the user is behind, most first.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     for entry in client.get_watching_airing().await? {
+    ///         if entry.behind() > 0 {
+    ///             println!("{}: you're {} episodes behind", entry.anime.title, entry.behind());
+    ///         }
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn get_watching_airing(&self) -> Result<Vec<AiringProgress>, MALError> {
+        let fields = AnimeFields::Status
+            | AnimeFields::StartDate
+            | AnimeFields::Broadcast
+            | AnimeFields::NumEpisodes;
+        // the list status isn't one of the anime's fields
+        let url = self.watching_url(&format!("{fields},list_status"));
+        let list = self.fetch_all_pages("get_watching_airing", url).await?;
+        let now = self.clock.now();
+        let mut res: Vec<AiringProgress> = list
+            .into_iter()
+            .filter(|entry| entry.details.status.as_deref() == Some("currently_airing"))
+            .filter_map(|entry| {
+                Some(AiringProgress {
+                    episodes_aired: entry.details.episodes_aired(now)?,
+                    list_status: entry.list_status?,
+                    anime: entry.details,
+                })
+            })
+            .collect();
+        res.sort_by_key(|entry| std::cmp::Reverse(entry.behind()));
+        Ok(res)
+    }
+
+    ///Returns the anime on the user's Plan to Watch list that started airing this season, with
+    ///when their first episode aired, for "now airing from your plan to watch" notifications.
+    ///Requests the list and the current season's anime. The ones that started most recently
+    ///come first.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     for entry in client.plan_to_watch_now_airing().await? {
+    ///         println!("{} is airing, {:?} episodes out", entry.anime.title, entry.episodes_aired);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn plan_to_watch_now_airing(&self) -> Result<Vec<NowAiring>, MALError> {
+        let fields = AnimeFields::Status
+            | AnimeFields::StartDate
+            | AnimeFields::Broadcast
+            | AnimeFields::NumEpisodes;
+        let url = self.user_list_url(&[("status", "plan_to_watch"), ("nsfw", "true")]);
+        let plan_to_watch = self.fetch_all_pages("plan_to_watch_now_airing", url).await?;
+        if plan_to_watch.is_empty() {
+            return Ok(Vec::new());
+        }
+        let now = self.clock.now();
+        let SeasonYear { season, year } = SeasonYear::at(now);
+        let url = self.season_url(year, season, fields);
+        let airing = self.fetch_all_pages("plan_to_watch_now_airing", url).await?;
+        Ok(now_airing(&plan_to_watch, airing, now))
+    }
+
+    ///Returns an iCalendar file with a weekly event for every currently airing anime on the
+    ///user's Watching list, see `schedule::to_ics`. The file can be imported into calendar apps
+    ///like Google Calendar, or served from a URL they subscribe to.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     std::fs::write("watching.ics", client.watching_calendar().await?)?;
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn watching_calendar(&self) -> Result<String, MALError> {
+        let fields = AnimeFields::Status
+            | AnimeFields::StartDate
+            | AnimeFields::Broadcast
+            | AnimeFields::NumEpisodes
+            | AnimeFields::AverageEpisodeDuration;
+        let url = self.watching_url(&fields.to_string());
+        let list = self.fetch_all_pages("watching_calendar", url).await?;
+        let airing = list
+            .into_iter()
+            .map(|entry| entry.details)
+            .filter(|anime| anime.status.as_deref() == Some("currently_airing"));
+        let schedule = AiringSchedule::from_anime(airing, UtcOffset::UTC, self.clock.now());
+        Ok(schedule::to_ics(schedule.days.values().flatten()))
+    }
+
+    ///Recommends anime based on the `top_n` highest rated shows on the user's Completed list.
+    ///The recommendations MAL users made for each of them are added up, weighted by the user's
+    ///score, and anything already on the user's list is left out. Sorted by weight, highest
+    ///first.
+    ///
+    ///Sends a request for every page of the list and then one for each of the `top_n` shows,
+    ///a few at a time.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     for rec in client.recommend_from_list(20).await?.iter().take(10) {
+    ///         println!("{} (recommended for {} of your favorites)", rec.anime.title, rec.because.len());
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    #[cfg(not(feature = "minimal-models"))]
+    pub async fn recommend_from_list(
+        &self,
+        top_n: usize,
+    ) -> Result<Vec<ListRecommendation>, MALError> {
+        let mut params = vec![("fields", "list_status")];
+        params.extend(self.nsfw_pair());
+        let url = self.user_list_url(&params);
+        let list = self.fetch_all_pages("recommend_from_list", url).await?;
+        let on_list: HashSet<AnimeId> = list.iter().map(|entry| entry.node.id).collect();
+        let mut completed: Vec<(u8, AnimeId)> = list
+            .iter()
+            .filter_map(|entry| {
+                let status = entry.list_status.as_ref()?;
+                (status.status.as_deref() == Some("completed"))
+                    .then(|| (status.score.unwrap_or_default().get(), entry.node.id))
+            })
+            .collect();
+        // highest score first, the order of the list breaks ties
+        completed.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        completed.truncate(top_n);
+
+        let ids: Vec<AnimeId> = completed.iter().map(|(_, id)| *id).collect();
+        let details = self
+            .get_anime_details_batch(
+                &ids,
+                AnimeFields::ID | AnimeFields::Recommendations,
+                RECOMMENDATION_CONCURRENCY,
+            )
+            .await;
+        let mut seeds = Vec::with_capacity(details.len());
+        for ((score, _), res) in completed.into_iter().zip(details) {
+            match res {
+                Ok(anime) => seeds.push((score, anime)),
+                Err(e) if e.is_unavailable() => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(rank_recommendations(&seeds, &on_list))
+    }
+
+    ///Searches for anime like `get_anime_list`, returning the results as a stream of pages with
+    ///`page_size` anime each, up to `limits::MAX_SEARCH_LIMIT`. The stream ends after the last
+    ///page or the first error, an invalid `page_size` is the only item of the stream.
+    ///
+    ///With `prefetch`, the next page is requested in a background task as soon as a page is
+    ///returned, so infinite-scroll UIs don't have to wait for it. The prefetch keeps going while
+    ///the stream isn't polled, and finishes even if the stream is dropped. Pages go through the
+    ///response cache, if it's enabled, so scrolling back doesn't send the requests again. Must be
+    ///polled inside a Tokio runtime when `prefetch` is set.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use futures_util::StreamExt;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let mut pages = std::pin::pin!(client.search_pages("gundam", 20, None, true));
+    ///     while let Some(page) = pages.next().await {
+    ///         for entry in page?.data {
+    ///             println!("{}", entry.node.title);
+    ///         }
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub fn search_pages<'a>(
+        &'a self,
+        query: &'a str,
+        page_size: impl Into<Limit>,
+        fields: impl Into<Option<AnimeFields>>,
+        prefetch: bool,
+    ) -> impl Stream<Item = Result<AnimeList, MALError>> + Send + 'a {
+        let fields = fields.into().map(|f| f.to_string());
+        let (page_size, invalid) = match page_size.into().validate("search_pages", MAX_SEARCH_LIMIT) {
+            Ok(size) => (size, None),
+            Err(e) => (1, Some(e)),
+        };
+        let start = invalid.is_none().then_some(0);
+        let url = move |page: usize| {
+            let (limit, offset) = (page_size.to_string(), (page * page_size).to_string());
+            let mut params = vec![("q", query), ("limit", &limit), ("offset", &offset)];
+            params.extend(fields.as_deref().map(|f| ("fields", f)));
+            params.extend(self.nsfw_pair());
+            self.api_url(&["anime"], &params)
+        };
+        let prefetched: Option<BoxFuture<'static, Result<AnimeList, MALError>>> = None;
+        let pages = stream::unfold(start.map(|page| (page, prefetched)), move |state| {
+            let url = url.clone();
+            async move {
+                let (page, prefetched) = state?;
+                let res = match prefetched {
+                    Some(task) => task.await,
+                    None => self.do_request_cached("search_pages", url(page)).await,
+                };
+                // only continue if MAL says there's another page, so no request is sent past the end
+                if !res.as_ref().is_ok_and(|p| p.next_page().is_some()) {
+                    return Some((res, None));
+                }
+                let prefetched = prefetch.then(|| {
+                    let (client, next) = (self.clone(), url(page + 1));
+                    rt::spawn(async move { client.do_request_cached("search_pages", next).await })
+                });
+                Some((res, Some((page + 1, prefetched))))
+            }
+        });
+        stream::iter(invalid.map(Err)).chain(pages)
+    }
+
+    ///Fetches the page at `cursor` and every page after it, appending the entries to `data`.
+    ///
+    ///`cursor` is moved forward after every page and set to `None` once the last page was
+    ///fetched. If a request fails, `cursor` points at the page that failed, so calling this again
+    ///continues from there. The cursor can also be saved with `to_string` to resume after a
+    ///restart.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let first = client.get_user_anime_list(None).await?;
+    ///     let mut data = first.data.clone();
+    ///     let mut cursor = first.next_page();
+    ///     if let Err(e) = client.fetch_remaining(&mut cursor, &mut data).await {
+    ///         if let Some(cursor) = &cursor {
+    ///             std::fs::write("resume.txt", cursor.to_string())?;
+    ///         }
+    ///         return Err(e);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn fetch_remaining(
+        &self,
+        cursor: &mut Option<PageCursor>,
+        data: &mut Vec<ListNode>,
+    ) -> Result<(), MALError> {
+        if let Some(current) = cursor {
+            if !current.url().starts_with(&self.api_base_url) {
+                return Err(MALError::Invalid {
+                    field: "cursor",
+                    reason: format!("{current} isn't a page of the API at {}", self.api_base_url),
+                });
+            }
+        }
+        self.fetch_pages("fetch_remaining", cursor, data).await
+    }
+
+    ///Listens for the auth callback until it arrives, `timeout` passes, or `cancel` is
+    ///cancelled, then completes the authorization and shows the callback page
+    #[cfg(feature = "callback-server")]
+    async fn answer_auth_callback(
+        &mut self,
+        callback_url: &str,
+        challenge: &str,
+        state: &str,
+        timeout: Option<Duration>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<(), MALError> {
+        let url = if callback_url.contains("http") {
+            //server won't work if the url has the protocol in it
+            callback_url
+                .trim_start_matches("http://")
+                .trim_start_matches("https://")
+        } else {
+            callback_url
+        };
+
+        let server = Server::http(url).map_err(|e| {
+            MALError::auth("Unable to start the callback listener", e.to_string())
+        })?;
+        // the listener blocks, so it waits on the blocking pool instead of the executor and is
+        // stopped once this future is done or dropped
+        let listener = StopListener {
+            server: Arc::new(server),
+            stopped: Arc::new(AtomicBool::new(false)),
+        };
+        let waiting = {
+            let (server, stopped) = (listener.server.clone(), listener.stopped.clone());
+            let (state, deadline) = (state.to_owned(), timeout.map(|t| Instant::now() + t));
+            tokio::task::spawn_blocking(move || wait_for_callback(&server, &state, deadline, &stopped))
+        };
+        let waiting = async {
+            waiting
+                .await
+                .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+        };
+        let request = match cancel {
+            Some(cancel) => with_cancellation(cancel, waiting).await?,
+            None => waiting.await?,
+        };
+        drop(listener);
+        let query: HashMap<String, String> = Url::parse("http://localhost")
+            .and_then(|base| base.join(request.url()))
+            .map(|url| url.query_pairs().into_owned().collect())
+            .unwrap_or_default();
+        let res = match query.get("code") {
+            Some(code) => self.auth_with_code(code, challenge).await,
+            None => Err(MALError::auth(
+                query.get("error").map_or("Authorization was denied", String::as_str),
+                query.get("message").cloned(),
+            )),
+        };
+        let html = self.callback_page.render(res.as_ref().map(|_| ()));
+        let mut response = Response::from_string(html);
+        if let Ok(header) = Header::from_bytes("Content-Type", "text/html; charset=utf-8") {
+            response.add_header(header);
+        }
+        // the browser closing the connection early doesn't affect the auth
+        let _ = request.respond(response);
+        res
+    }
+
+    ///Returns the transport, dumping the requests to the debug sink in debug mode
+    fn http(&self) -> Hooked<'_, Dumping<'_>> {
+        Hooked {
+            inner: Dumping {
+                inner: self.transport.as_ref(),
+                sink: self.debug_sink.as_deref().filter(|_| self.debug),
+            },
+            middleware: &self.middleware,
+        }
+    }
+
+    ///Turns dumping every request and response with the secrets redacted on or off, see the
+    ///`debug` module. Dumps go to the sink set with `ClientBuilder::debug_sink`, or to stderr if
+    ///there's none.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let mut client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     client.debug_mode(true);
+    ///     // prints the request with `Authorization: [REDACTED]` and the response
+    ///     client.get_anime_details(80, None).await?;
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub fn debug_mode(&mut self, enabled: bool) {
+        if enabled && self.debug_sink.is_none() {
+            self.debug_sink = Some(Arc::new(DebugWriter::new(std::io::stderr())));
+        }
+        self.debug = enabled;
+    }
+
+    ///Loads the ids of the anime on the user's list into `list_ids`, unless they're loaded
+    ///already
+    async fn load_list_ids(&self) -> Result<(), MALError> {
+        if self.list_ids.lock().unwrap_or_else(PoisonError::into_inner).is_some() {
+            return Ok(());
+        }
+        let url = self.user_list_url(&[("nsfw", "true")]);
+        let ids = self
+            .fetch_all_pages("get_suggested_anime", url)
+            .await?
+            .iter()
+            .map(|entry| entry.node.id)
+            .collect();
+        *self.list_ids.lock().unwrap_or_else(PoisonError::into_inner) = Some(ids);
+        Ok(())
+    }
+
+    ///Keeps the loaded `list_ids` up to date after the anime with `id` was added to or removed
+    ///from the list, and drops its cached details, which include the old `my_list_status`
+    fn track_list_id(&self, id: AnimeId, on_list: bool) {
+        if let Some(cache) = &self.response_cache {
+            let details = self.api_url(&["anime", &id.to_string()], [] as [(&str, &str); 0]);
+            cache.invalidate(&format!("{details}?"));
+        }
+        if let Some(ids) = self.list_ids.lock().unwrap_or_else(PoisonError::into_inner).as_mut() {
+            if on_list {
+                ids.insert(id);
+            } else {
+                ids.remove(&id);
+            }
+        }
+    }
+
+    ///Fetches the list at `url` and every page after it
+    async fn fetch_all_pages(
+        &self,
+        endpoint: &str,
+        url: String,
+    ) -> Result<Vec<ListNode>, MALError> {
+        let mut data = Vec::new();
+        self.fetch_pages(endpoint, &mut Some(PageCursor::new(url)), &mut data)
+            .await?;
+        Ok(data)
+    }
+
+    ///Fetches the user's list with every list status field, the most recently updated entries
+    ///first. Stops after the first page that reaches entries updated before `since`, a Unix
+    ///timestamp, or fetches the whole list if it's `None`.
+    pub(crate) async fn list_updated_since(
+        &self,
+        since: Option<u64>,
+    ) -> Result<Vec<ListNode>, MALError> {
+        let sort = ListSort::ListUpdatedAt.to_string();
+        let fields = format!("list_status{{{SYNC_FIELDS}}}");
+        let url = self.user_list_url(&[("sort", sort.as_str()), ("nsfw", "true"), ("fields", &fields)]);
+        let mut cursor = Some(PageCursor::new(url));
+        let mut data = Vec::new();
+        while let Some(current) = cursor {
+            let page: AnimeList = self.do_request("list_cache", current.url().to_owned()).await?;
+            cursor = page.next_page();
+            let reached = since.is_some_and(|since| {
+                page.data
+                    .last()
+                    .and_then(list_cache::updated_at)
+                    .is_some_and(|updated| updated < since)
+            });
+            data.extend(page.data);
+            if reached {
+                break;
+            }
+        }
+        Ok(data)
+    }
+
+    async fn fetch_pages(
+        &self,
+        endpoint: &str,
+        cursor: &mut Option<PageCursor>,
+        data: &mut Vec<ListNode>,
+    ) -> Result<(), MALError> {
+        while let Some(current) = cursor {
+            let page: AnimeList = self.do_request(endpoint, current.url().to_owned()).await?;
+            *cursor = page.next_page();
+            data.extend(page.data);
+        }
+        Ok(())
+    }
+
+    ///Follows `paging.next` of `list` until it has `auto_paginate` entries or the last page was
+    ///fetched, returns `list` unchanged if automatic pagination is off. The pages after the
+    ///first are requested with up to `page_limit` entries, the largest the endpoint allows.
+    async fn paginate(
+        &self,
+        endpoint: &str,
+        page_limit: usize,
+        mut list: AnimeList,
+    ) -> Result<AnimeList, MALError> {
+        let Some(max_items) = self.auto_paginate else {
+            return Ok(list);
+        };
+        while list.data.len() < max_items {
+            let Some(next) = list.next_page() else {
+                break;
+            };
+            // the offset in the link doesn't depend on the limit, so the page size can change
+            let url = with_limit(next.url(), page_limit.min(max_items - list.data.len()));
+            let page: AnimeList = self.do_request(endpoint, url).await?;
+            list.data.extend(page.data);
+            list.paging = page.paging;
+        }
+        if list.data.len() > max_items {
+            list.data.truncate(max_items);
+            // the next page would skip the entries that were cut off
+            list.paging.remove("next");
+        }
+        Ok(list)
+    }
+
+    ///Returns a receiver that gets notified every time the authorization state changes, e.g. for
+    ///binding it to the UI
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{AuthState, MALClient, MALClientTrait};
+    /// # async fn test() {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let mut state = client.watch_auth_state();
+    ///     while state.changed().await.is_ok() {
+    ///         if *state.borrow() == AuthState::NoToken {
+    ///             println!("Please log in again");
+    ///         }
+    ///     }
+    /// # }
+    ///```
+    pub fn watch_auth_state(&self) -> watch::Receiver<AuthState> {
+        self.auth_state.subscribe()
+    }
+
+    ///Returns the query parameter that includes NSFW anime for `api_url`, if enabled
+    const fn nsfw_pair(&self) -> Option<(&'static str, &'static str)> {
+        if self.include_nsfw {
+            Some(("nsfw", "true"))
+        } else {
+            None
+        }
+    }
+
+    ///Builds the URL of an API endpoint on the configured base URL, percent-encoding the path
+    ///segments and the query, so user input like search terms and usernames can't break the URL
+    pub(crate) fn api_url<I, K, V>(&self, segments: &[&str], params: I) -> String
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        // encoded on a placeholder, an invalid base URL fails when the request is sent instead
+        let mut url = Url::parse("http://localhost/").expect("placeholder URL should be valid");
+        url.path_segments_mut()
+            .expect("placeholder URL should have a path")
+            .pop_if_empty()
+            .push("v2")
+            .extend(segments);
+        let mut params = params.into_iter().peekable();
+        if params.peek().is_some() {
+            url.query_pairs_mut().extend_pairs(params);
+        }
+        let path = url.path().trim_start_matches('/');
+        match url.query() {
+            Some(query) => format!("{}{path}?{query}", self.api_base_url),
+            None => format!("{}{path}", self.api_base_url),
+        }
+    }
+
+    ///Returns the URL of the user's entry for the anime with `id`
+    fn list_status_url(&self, id: AnimeId) -> String {
+        self.api_url(&["anime", &id.to_string(), "my_list_status"], [] as [(&str, &str); 0])
+    }
+
+    ///Returns the URL of the first page of the user's list with the largest page size and
+    ///`params`
+    fn user_list_url(&self, params: &[(&str, &str)]) -> String {
+        let limit = MAX_USER_LIST_LIMIT.to_string();
+        let mut all = vec![("limit", limit.as_str())];
+        all.extend_from_slice(params);
+        self.api_url(&["users", "@me", "animelist"], &all)
+    }
+
+    ///Returns the URL of the first page of a season's anime with `fields`, with the largest page
+    ///size
+    fn season_url(&self, year: usize, season: Season, fields: AnimeFields) -> String {
+        let limit = MAX_SEASONAL_LIMIT.to_string();
+        let fields = fields.to_string();
+        let mut params = vec![("limit", limit.as_str()), ("fields", &fields)];
+        params.extend(self.nsfw_pair());
+        self.api_url(&["anime", "season", &year.to_string(), &season.to_string()], &params)
+    }
+
+    ///Returns the URL of the first page of the anime the user is watching with `fields`
+    fn watching_url(&self, fields: &str) -> String {
+        let mut params = vec![("status", "watching"), ("fields", fields)];
+        params.extend(self.nsfw_pair());
+        self.user_list_url(&params)
+    }
+
+    fn tokens(&self) -> RwLockReadGuard<'_, Tokens> {
+        self.tokens.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    ///Returns the current Unix timestamp according to the client's clock
+    pub(crate) fn now(&self) -> u64 {
+        unix_secs(self.clock.now())
+    }
+
+    ///Returns today's date according to the client's clock, for list updates
+    fn today(&self) -> String {
+        date_of(self.clock.now())
+    }
+
+    ///Writes the tokens to the token store
+    fn cache_tokens(&self, tokens: &Tokens) -> Result<(), MALError> {
+        self.store.save(tokens)
+    }
+
+    ///Forces a refresh of the access token using the stored refresh token, updating the cache if
+    ///caching is enabled.
     ///
-    ///# Note
-    /// The [API docs from MAL](https://myanimelist.net/apiconfig/references/api/v2#operation/anime_anime_id_my_list_status_delete) say this method should return 404 if the anime isn't in the user's
-    /// list, but in my testing this wasn't true. Without that there's no way to tell if the item
-    /// was actually deleted or not.
+    ///Useful for refreshing proactively before starting a long batch of requests. Returns an
+    ///error if the client has no refresh token, e.g. when created with `with_access_token`.
     ///
     ///# Example
     ///
     ///```no_run
-    /// # use lib_mal::{MALClient, MALError};
+    /// # use lib_mal::{ClientBuilder, MALClient, MALError};
+    /// # use std::path::PathBuf;
     /// # async fn test() -> Result<(), MALError> {
-    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
-    ///     client.delete_anime_list_item(80).await?;
+    ///     let client: MALClient = ClientBuilder::new().secret("[YOUR_SECRET_HERE]".to_string()).caching(true).cache_dir(PathBuf::new()).build_with_refresh().await?;
+    ///     if client.is_token_expired() {
+    ///         client.refresh_token().await?;
+    ///     }
     ///     # Ok(())
     /// # }
     ///```
-    async fn delete_anime_list_item(&self, id: usize) -> Result<(), MALError> {
-        let url = format!("https://api.myanimelist.net/v2/anime/{id}/my_list_status");
-        let res = self
-            .client
-            .delete(url)
-            .bearer_auth(&self.access_token)
-            .send()
-            .await;
-        match res {
-            Ok(r) => {
-                if r.status() == StatusCode::NOT_FOUND {
-                    Err(MALError::new(
-                        &format!("Anime {id} not found"),
-                        r.status().as_str(),
-                        None,
-                    ))
-                } else {
-                    Ok(())
-                }
-            }
-            Err(e) => Err(MALError::new(
-                "Unable to send request",
-                &format!("{e}"),
-                None,
-            )),
+    pub async fn refresh_token(&self) -> Result<(), MALError> {
+        if !self.tokens().can_refresh() {
+            return Err(MALError::auth("No refresh token available", None));
         }
+        let mut previous = None;
+        self.auth_state.send_if_modified(|state| {
+            if *state == AuthState::Refreshing {
+                return false;
+            }
+            previous = Some(std::mem::replace(state, AuthState::Refreshing));
+            true
+        });
+        let Some(previous) = previous else {
+            // another refresh is already running, wait for it instead of refreshing twice
+            let mut state = self.auth_state.subscribe();
+            let state = *state
+                .wait_for(|s| *s != AuthState::Refreshing)
+                .await
+                .map_err(|e| MALError::auth("Unable to refresh token", e.to_string()))?;
+            return if state == AuthState::Authenticated {
+                Ok(())
+            } else {
+                Err(MALError::auth("Concurrent refresh failed", None))
+            };
+        };
+        let refresh_token = self.tokens().refresh_token.clone();
+        let res = refresh_tokens(
+            &self.http(),
+            &self.auth_base_url,
+            &self.client_id,
+            self.client_secret.as_deref(),
+            &refresh_token,
+        )
+            .await
+            .and_then(|tokens| {
+                let tokens = Tokens {
+                    today: self.now(),
+                    ..tokens
+                };
+                if self.caching {
+                    self.cache_tokens(&tokens)?;
+                }
+                *self.tokens.write().unwrap_or_else(PoisonError::into_inner) = tokens;
+                Ok(())
+            });
+        self.auth_state.send_replace(match &res {
+            Ok(()) => AuthState::Authenticated,
+            Err(e) if e.is_refresh_rejected() => AuthState::NoToken,
+            Err(_) => previous,
+        });
+        res
     }
 
-    //--Forum functions--//
-
-    ///Returns a vector of `HashMap`s that represent all the forum boards on MAL
-    async fn get_forum_boards(&self) -> Result<ForumBoards, MALError> {
-        let res = self
-            .do_request("https://api.myanimelist.net/v2/forum/boards".to_owned())
+    ///Sends a request to the specified URL with the appropriate auth header.
+    ///
+    ///The access token is refreshed before sending if it has expired, and the request is retried
+    ///once with a fresh token if the API responds with 401 Unauthorized.
+    async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        form: Option<&[(&str, String)]>,
+        headers: Option<&HeaderMap>,
+    ) -> Result<HttpResponse, MALError> {
+        if !url.starts_with(&self.api_base_url) {
+            // other sites don't need the access token, so don't refresh it for them either
+            return self.send_retrying(method, url, form, headers).await;
+        }
+        if self.public_client && self.client_id.is_empty() {
+            return Err(MALError::auth(
+                "missing_client_id",
+                String::from("public client mode needs a client ID, see ClientBuilder::client_id"),
+            ));
+        }
+        if self.tokens().is_expired_at(self.clock.now()) {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("access token expired, refreshing it");
+            self.refresh_token().await?;
+        }
+        let mut res = self
+            .send_retrying(method.clone(), url, form, headers)
             .await?;
-        Self::parse_response(&res)
+        if res.status == StatusCode::UNAUTHORIZED && self.tokens().can_refresh() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("access token rejected, refreshing it");
+            self.refresh_token().await?;
+            res = self.send_retrying(method, url, form, headers).await?;
+        }
+        if res.status == StatusCode::UNAUTHORIZED {
+            self.auth_state.send_replace(AuthState::NoToken);
+        } else {
+            // the token has been accepted by the API
+            self.auth_state.send_if_modified(|state| {
+                let cached = *state == AuthState::TokenCached;
+                if cached {
+                    *state = AuthState::Authenticated;
+                }
+                cached
+            });
+        }
+        Ok(res)
     }
 
-    ///Returns details of the specified topic
-    async fn get_forum_topic_detail(
+    ///Sends a request, waiting for the rate limiter first, and retries it with exponential
+    ///backoff while the API responds with 429 Too Many Requests or a server error
+    async fn send_retrying(
         &self,
-        topic_id: usize,
-        limit: impl Into<Option<u8>> + Send,
-    ) -> Result<TopicDetails, MALError> {
-        let url = format!(
-            "https://api.myanimelist.net/v2/forum/topic/{}?limit={}",
-            topic_id,
-            limit.into().unwrap_or(100)
-        );
-        let res = self.do_request(url).await?;
-        Self::parse_response(&res)
+        method: Method,
+        url: &str,
+        form: Option<&[(&str, String)]>,
+        headers: Option<&HeaderMap>,
+    ) -> Result<HttpResponse, MALError> {
+        let mut attempt = 0;
+        loop {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire().await;
+            }
+            #[cfg(feature = "jikan")]
+            if url.starts_with(JIKAN_API_URL) {
+                self.jikan_rate_limiter.acquire().await;
+            }
+            let res = self.send_once(method.clone(), url, form, headers).await?;
+            let status = res.status;
+            let retryable = status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+            if !retryable || attempt >= self.max_retries {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(status = status.as_u16(), retries = attempt, "request finished");
+                return Ok(res);
+            }
+            let delay = retry_after(&res.headers).unwrap_or_else(|| rate_limit::backoff(attempt));
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                status = status.as_u16(),
+                attempt = attempt + 1,
+                delay_ms = delay.as_millis() as u64,
+                "retrying request"
+            );
+            rt::sleep(delay).await;
+            attempt += 1;
+        }
     }
 
-    ///Returns all topics for a given query
-    async fn get_forum_topics(
+    async fn send_once(
         &self,
-        board_id: impl Into<Option<usize>> + Send,
-        subboard_id: impl Into<Option<usize>> + Send,
-        query: impl Into<Option<String>> + Send,
-        topic_user_name: impl Into<Option<String>> + Send,
-        user_name: impl Into<Option<String>> + Send,
-        limit: impl Into<Option<usize>> + Send,
-    ) -> Result<ForumTopics, MALError> {
-        let params = {
-            let mut tmp = vec![];
-            if let Some(bid) = board_id.into() {
-                tmp.push(format!("board_id={bid}"));
-            }
-            if let Some(bid) = subboard_id.into() {
-                tmp.push(format!("subboard_id={bid}"));
-            }
-            if let Some(bid) = query.into() {
-                tmp.push(format!("q={bid}"));
+        method: Method,
+        url: &str,
+        form: Option<&[(&str, String)]>,
+        headers: Option<&HeaderMap>,
+    ) -> Result<HttpResponse, MALError> {
+        let access_token = self.tokens().access_token.clone();
+        let mut req = HttpRequest::new(method, url);
+        if url.starts_with(&self.api_base_url) {
+            if !(self.public_client && access_token.is_empty()) {
+                if let Ok(mut value) = HeaderValue::from_str(&format!("Bearer {access_token}")) {
+                    value.set_sensitive(true);
+                    req.headers.insert(AUTHORIZATION, value);
+                }
             }
-            if let Some(bid) = topic_user_name.into() {
-                tmp.push(format!("topic_user_name={bid}"));
+            if self.public_client {
+                if let Ok(value) = HeaderValue::from_str(&self.client_id) {
+                    req.headers.insert(CLIENT_ID_HEADER, value);
+                }
             }
-            if let Some(bid) = user_name.into() {
-                tmp.push(format!("user_name={bid}"));
+        }
+        if let Some(params) = form {
+            req = req.form(params);
+        }
+        if let Some(headers) = headers {
+            req.headers.extend(headers.clone());
+        }
+        #[cfg(feature = "tracing")]
+        let start = Instant::now();
+        let res = self.http().send(req).await;
+        #[cfg(feature = "tracing")]
+        {
+            let latency_ms = start.elapsed().as_millis() as u64;
+            match &res {
+                Ok(res) => tracing::debug!(status = res.status.as_u16(), latency_ms, "response received"),
+                Err(e) => tracing::warn!(error = %e, latency_ms, "no response received"),
             }
-            tmp.push(format!("limit={}", limit.into().unwrap_or(100)));
-            tmp.join(",")
-        };
-        let url = format!("https://api.myanimelist.net/v2/forum/topics?{params}");
-        let res = self.do_request(url).await?;
-        Self::parse_response(&res)
+        }
+        res
     }
 
-    ///Gets the details for the current user
-    ///
-    ///# Example
-    ///
-    ///```no_run
-    /// # use lib_mal::{MALClient, MALError};
-    /// # async fn test() -> Result<(), MALError> {
-    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
-    ///     let me = client.get_my_user_info().await?;
-    ///     # Ok(())
-    /// # }
-    ///```
-    async fn get_my_user_info(&self) -> Result<User, MALError> {
-        let url = "https://api.myanimelist.net/v2/users/@me?fields=anime_statistics";
-        let res = self.do_request(url.to_owned()).await?;
-        Self::parse_response(&res)
+    ///Sends a request and returns the status and body of the response. Errors carry the
+    ///`endpoint` name, URL, and status of the request.
+    pub(crate) async fn fetch(
+        &self,
+        endpoint: &str,
+        method: Method,
+        url: &str,
+        form: Option<&[(&str, String)]>,
+    ) -> Result<(StatusCode, String), MALError> {
+        self.fetch_with_headers(endpoint, method, url, form, None).await
     }
 
-    /// Returns just the first page
-    async fn get_anime_episodes(
+    ///Same as `fetch`, but sends `headers` along with the request
+    pub(crate) async fn fetch_with_headers(
         &self,
-        id: usize,
-        precise_score: bool,
-    ) -> Result<EpisodesList, MALError> {
-        let page: usize = 1;
-        let url = format!("https://api.jikan.moe/v4/anime/{id}/episodes?page={page}",);
-        let res = self.do_request(url).await?;
-        let mut api: Result<EpisodesList, MALError> = match serde_json::from_str(&res) {
-            Ok(list) => Ok(list),
-            Err(e) => Err(MALError::new(
-                "unable to get anime episodes",
-                &format!("{e}"),
-                res.to_string(),
-            )),
+        endpoint: &str,
+        method: Method,
+        url: &str,
+        form: Option<&[(&str, String)]>,
+        headers: Option<&HeaderMap>,
+    ) -> Result<(StatusCode, String), MALError> {
+        let journaled = (method != Method::GET).then(|| method.to_string());
+        let start = Instant::now();
+        let sent = trace::instrument(endpoint, &method, url, self.send(method.clone(), url, form, headers));
+        let res = match sent.await {
+            Ok(res) => self.read_response(endpoint, url, res),
+            Err(e) => Err(e.with_context(endpoint, url, None)),
         };
-        if precise_score {
-            let offset = page.checked_sub(1).unwrap_or_default() * 100;
-            let extra = self.get_raw_episodes_score(id, offset).await?;
-            api.as_mut()
-                .map(|list| {
-                    list.data.iter_mut().for_each(|episode| {
-                        let score = extra
-                            .iter()
-                            .find(|ee| ee.mal_id == episode.mal_id)
-                            .map(|ee| ee.score)
-                            .unwrap_or_default();
-                        episode.score = score;
-                    });
-                })
-                .map_err(|e| e.clone())?;
+        let status = match &res {
+            Ok((status, _)) => Some(*status),
+            Err(e) => e.status(),
+        };
+        self.record_metrics(endpoint, method, status, start);
+        #[cfg(feature = "tracing")]
+        if let Err(e) = &res {
+            tracing::warn!(endpoint, url, error = %e, "request failed");
+        }
+        if let (Some(journal), Some(method)) = (&self.journal, journaled) {
+            let entry = JournalEntry {
+                at: self.now(),
+                endpoint: endpoint.to_owned(),
+                method,
+                url: url.to_owned(),
+                params: form
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(k, v)| ((*k).to_owned(), v.clone()))
+                    .collect(),
+                status: match &res {
+                    Ok((status, _)) => Some(status.as_u16()),
+                    Err(e) => e.status().map(|s| s.as_u16()),
+                },
+                response: res.as_ref().ok().map(|(_, body)| body.clone()),
+                error: res.as_ref().err().map(ToString::to_string),
+            };
+            // the journal is a debugging aid, failing to write it shouldn't fail the change
+            let _ = journal.append(&self.dirs, &entry);
         }
-        api
+        res
     }
 
-    fn need_auth(&self) -> bool {
-        self.need_auth
+    fn record_metrics(
+        &self,
+        endpoint: &str,
+        method: Method,
+        status: Option<StatusCode>,
+        start: Instant,
+    ) {
+        if let Some(sink) = &self.metrics {
+            sink.record(&RequestMetrics {
+                endpoint: endpoint.to_owned(),
+                method,
+                status,
+                duration: start.elapsed(),
+            });
+        }
     }
-}
-
-impl MALClient {
-    async fn get_tokens(&mut self, code: &str, verifier: &str) -> Result<(), MALError> {
-        let params = [
-            ("client_id", self.client_secret.as_str()),
-            ("grant_type", "authorization_code"),
-            ("code_verifier", verifier),
-            ("code", code),
-        ];
-        let rec = self
-            .client
-            .request(Method::POST, "https://myanimelist.net/v1/oauth2/token")
-            .form(&params)
-            .build()
-            .unwrap();
-        let response = self.client.execute(rec).await.unwrap();
-        let text = response.text().await.unwrap();
-        if let Ok(tokens) = serde_json::from_str::<TokenResponse>(&text) {
-            self.access_token = tokens.access_token.clone();
 
-            let tjson = Tokens {
-                access_token: tokens.access_token,
-                refresh_token: tokens.refresh_token,
-                expires_in: tokens.expires_in,
-                today: SystemTime::now()
-                    .duration_since(SystemTime::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-            };
-            if self.caching {
-                let mut f =
-                    File::create(self.dirs.join("tokens")).expect("Unable to create token file");
-                f.write_all(&encrypt_token(&tjson))
-                    .expect("Unable to write tokens");
-            }
-            Ok(())
+    ///Returns the status and body of `res`, turning non-success responses into errors
+    fn read_response(
+        &self,
+        endpoint: &str,
+        url: &str,
+        res: HttpResponse,
+    ) -> Result<(StatusCode, String), MALError> {
+        let HttpResponse {
+            status,
+            headers,
+            body,
+        } = res;
+        let retry_after = retry_after(&headers);
+        let content_type = headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        // the API always answers with JSON, pages come from a proxy or the maintenance page
+        let error_page = if status.is_server_error() {
+            !content_type.contains("json")
         } else {
-            Err(MALError::new("Unable to get tokens", "None", text))
+            status.is_success()
+                && url.starts_with(&self.api_base_url)
+                && content_type.contains("html")
+        };
+        if error_page {
+            return Err(MALError::ServerError {
+                status,
+                excerpt: excerpt(&body),
+                context: None,
+            }
+            .with_context(endpoint, url, Some(status)));
+        }
+        if !status.is_success() {
+            return Err(MALError::from_status(status, body, retry_after)
+                .with_context(endpoint, url, Some(status)));
         }
+        Ok((status, body))
     }
 
-    ///Sends a get request to the specified URL with the appropriate auth header
-    async fn do_request(&self, url: String) -> Result<String, MALError> {
-        match self
-            .client
-            .get(url)
-            .bearer_auth(&self.access_token)
-            .send()
-            .await
-        {
-            Ok(res) => Ok(res.text().await.unwrap()),
-            Err(e) => Err(MALError::new(
-                "Unable to send request",
-                &format!("{e}"),
-                None,
-            )),
+    ///Sends a get request to the specified URL with the appropriate auth header and parses the
+    ///response
+    async fn do_request<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        url: String,
+    ) -> Result<T, MALError> {
+        let (status, res) = self.fetch(endpoint, Method::GET, &url, None).await?;
+        Self::parse_response(&res).map_err(|e| e.with_context(endpoint, &url, Some(status)))
+    }
+
+    ///Same as `do_request`, but serves the response from the response cache if it's enabled and
+    ///has a fresh copy. Expired responses are revalidated with `If-None-Match` and
+    ///`If-Modified-Since` if the API sent an `ETag` or `Last-Modified` header.
+    pub(crate) async fn do_request_cached<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        url: String,
+    ) -> Result<T, MALError> {
+        let Some(cache) = &self.response_cache else {
+            return self.do_request(endpoint, url).await;
+        };
+        let mut headers = HeaderMap::new();
+        match cache.lookup(&url) {
+            Lookup::Fresh(body) => {
+                if let Ok(res) = Self::parse_response(&body) {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(endpoint, url, "served from the response cache");
+                    return Ok(res);
+                }
+            }
+            Lookup::Stale(validators) => {
+                let conditions = [
+                    (IF_NONE_MATCH, validators.etag),
+                    (IF_MODIFIED_SINCE, validators.last_modified),
+                ];
+                for (name, value) in conditions {
+                    if let Some(value) = value.and_then(|v| HeaderValue::from_str(&v).ok()) {
+                        headers.insert(name, value);
+                    }
+                }
+            }
+            Lookup::Missing => {}
+        }
+        let start = Instant::now();
+        let sent = self.send(Method::GET, &url, None, Some(&headers));
+        let res = trace::instrument(endpoint, &Method::GET, &url, sent).await;
+        let status = match &res {
+            Ok(res) => Some(res.status),
+            Err(e) => e.status(),
+        };
+        self.record_metrics(endpoint, Method::GET, status, start);
+        let res = res.map_err(|e| e.with_context(endpoint, &url, None))?;
+        if res.status == StatusCode::NOT_MODIFIED {
+            if let Some(body) = cache.revalidate(&url) {
+                return Self::parse_response(&body)
+                    .map_err(|e| e.with_context(endpoint, &url, StatusCode::NOT_MODIFIED));
+            }
         }
+        let header = |name| {
+            res.headers
+                .get(name)
+                .and_then(|v: &HeaderValue| v.to_str().ok())
+                .map(str::to_owned)
+        };
+        let validators = Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+        let (status, body) = self.read_response(endpoint, &url, res)?;
+        let parsed = Self::parse_response(&body)
+            .map_err(|e| e.with_context(endpoint, &url, Some(status)))?;
+        cache.insert(&url, body, validators);
+        Ok(parsed)
     }
 
     ///Sends a put request to the specified URL with the appropriate auth header and
-    ///form encoded parameters
-    async fn do_request_forms(
+    ///form encoded parameters and parses the response
+    async fn do_request_forms<T: DeserializeOwned>(
         &self,
+        endpoint: &str,
         url: String,
         params: Vec<(&str, String)>,
-    ) -> Result<String, MALError> {
-        match self
-            .client
-            .put(url)
-            .bearer_auth(&self.access_token)
-            .form(&params)
-            .send()
-            .await
-        {
-            Ok(res) => Ok(res.text().await.unwrap()),
-            Err(e) => Err(MALError::new(
-                "Unable to send request",
-                &format!("{e}"),
-                None,
-            )),
-        }
+    ) -> Result<T, MALError> {
+        let (status, res) = self
+            .fetch(endpoint, Method::PUT, &url, Some(&params))
+            .await?;
+        Self::parse_response(&res).map_err(|e| e.with_context(endpoint, &url, Some(status)))
     }
 
     ///Tries to parse a JSON response string into the type provided in the `::<>` turbofish
-    fn parse_response<'a, T: Serialize + Deserialize<'a>>(res: &'a str) -> Result<T, MALError> {
-        serde_json::from_str::<T>(res).map_or_else(
-            |_| {
-                Err(match serde_json::from_str::<MALError>(res) {
-                    Ok(o) => o,
-                    Err(e) => {
-                        MALError::new("unable to parse response", &format!("{e}"), res.to_string())
-                    }
-                })
-            },
-            |v| Ok(v),
-        )
+    fn parse_response<T: DeserializeOwned>(res: &str) -> Result<T, MALError> {
+        serde_json::from_str::<T>(res).map_err(|source| MALError::Parse {
+            source,
+            body: res.to_owned(),
+            context: None,
+        })
     }
 
-    /// Returns just the scores from the first page
-    async fn get_raw_episodes_score(
-        &self,
-        id: usize,
-        offset: usize,
-    ) -> Result<Vec<EpisodeNode>, MALError> {
-        let url = format!("https://myanimelist.net/anime/{id}/1/episode?offset={offset}");
-        let res = self.do_request(url).await?;
-
-        let mut episodes_range_iter = res
-            .lines()
-            .find(|line| line.contains("Episodes") && line.contains("h2_overwrite"))
-            .unwrap_or_default()
-            .split(">(")
-            .nth(1)
-            .unwrap_or_default()
-            .split(")<")
-            .next()
-            .unwrap_or_default()
-            .split('/')
-            .map(|value| value.replace(',', "").parse::<usize>().unwrap_or_default());
-
-        let present_episodes = episodes_range_iter.next().unwrap_or_default();
-
-        if present_episodes == 0 {
-            return Ok(Vec::new());
+    #[cfg(feature = "jikan")]
+    pub(crate) const fn scraping_allowed(&self) -> bool {
+        cfg!(feature = "scraping") && self.allow_scraping
+    }
+
+    #[cfg(feature = "scraping")]
+    pub(crate) fn site_session(&self) -> Option<&str> {
+        self.site_session.as_deref()
+    }
+}
+
+///The steps of `MALClientTrait::auth_interactive`
+#[cfg(feature = "open-browser")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthProgress {
+    OpeningBrowser { url: String },
+    ///No browser could be opened, the user has to visit `url` themselves
+    BrowserUnavailable { url: String },
+    WaitingForCallback,
+    Authenticated,
+}
+
+///Why a client needs the user to authorize it, see `MALClientTrait::reauth_reason`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReauthReason {
+    ///There's no token in the cache, the user hasn't authorized the app on this device yet or the
+    ///cache was cleared
+    NeverAuthorized,
+    ///Caching is off, so the token of an earlier authorization wasn't kept, see
+    ///`ClientBuilder::caching`
+    NoCachedToken,
+    ///MAL rejected the token and it couldn't be refreshed, because the refresh token expired or
+    ///the user revoked the app's access
+    TokenExpired,
+    ///The token cache was truncated, encrypted with another key, or written in a format this
+    ///version can't read. It was deleted when the client was built.
+    UnreadableCache(String),
+}
+
+impl std::fmt::Display for ReauthReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NeverAuthorized => write!(f, "the app hasn't been authorized yet"),
+            Self::NoCachedToken => write!(f, "the token isn't cached"),
+            Self::TokenExpired => write!(f, "the token expired"),
+            Self::UnreadableCache(e) => write!(f, "the token cache couldn't be read: {e}"),
         }
+    }
+}
 
-        let episodes_score: Vec<EpisodeNode> = res
-            .lines()
-            .filter(|line| line.contains("episode-poll") && line.contains("data-raw"))
-            .enumerate()
-            .map(|(i, line)| {
-                let score = line
-                    .split("data-raw=\"")
-                    .nth(1)
-                    .map(|v| v.split('"'))
-                    .and_then(|mut v| v.next())
-                    .map(str::parse::<f32>)
-                    .and_then(Result::ok);
-                (i + 1 + offset, score)
-            })
-            .filter(|(_, score)| score.is_some())
-            .map(|(k, score)| EpisodeNode {
-                mal_id: Some(k),
-                score,
-                ..Default::default()
-            })
-            .collect();
-        Ok(episodes_score)
+///Where the client is in the authorization process, see `MALClientTrait::auth_state`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthState {
+    ///The client has no usable token and the user has to authorize it
+    NoToken,
+    ///A token was loaded from the cache or provided by the app but hasn't been used yet
+    TokenCached,
+    ///The access token is being refreshed
+    Refreshing,
+    ///The token has been obtained from MAL or accepted by the API
+    Authenticated,
+}
+
+///The page shown in the browser when the auth callback arrives, see
+///`ClientBuilder::callback_page`
+///
+///# Example
+///
+///```
+/// use lib_mal::CallbackPage;
+/// let page = CallbackPage::html(
+///     "<h1>Logged in!</h1><script>window.close()</script>",
+///     "<h1>Something went wrong, please try again</h1>",
+/// );
+/// let page = CallbackPage::from_fn(|res| match res {
+///     Ok(()) => String::from("<h1>Logged in!</h1>"),
+///     Err(e) => format!("<h1>Login failed</h1><p>{e}</p>"),
+/// });
+///```
+#[derive(Clone)]
+pub struct CallbackPage(Arc<PageFn>);
+
+type PageFn = dyn Fn(Result<(), &MALError>) -> String + Send + Sync;
+
+impl CallbackPage {
+    ///Shows `success` once the user is logged in and `error` if the authorization failed
+    pub fn html(success: impl Into<String>, error: impl Into<String>) -> Self {
+        let (success, error) = (success.into(), error.into());
+        Self::from_fn(move |res| match res {
+            Ok(()) => success.clone(),
+            Err(_) => error.clone(),
+        })
+    }
+
+    ///Builds the HTML of the page from the result of the authorization
+    pub fn from_fn(page: impl Fn(Result<(), &MALError>) -> String + Send + Sync + 'static) -> Self {
+        Self(Arc::new(page))
+    }
+
+    pub fn render(&self, res: Result<(), &MALError>) -> String {
+        (self.0)(res)
+    }
+}
+
+impl Default for CallbackPage {
+    fn default() -> Self {
+        Self::html(
+            "You're logged in! You can now close this window",
+            "Unable to log in, you can close this window and try again",
+        )
+    }
+}
+
+///Returns the encrypted `FileTokenStore` in `dir`, the default token cache
+#[cfg(feature = "oauth")]
+pub(crate) fn default_store(dir: PathBuf) -> Arc<dyn TokenStore> {
+    Arc::new(FileTokenStore::new(dir))
+}
+
+///Without the `oauth` feature there's no encrypted cache, the tokens are only kept in memory
+#[cfg(not(feature = "oauth"))]
+pub(crate) fn default_store(_dir: PathBuf) -> Arc<dyn TokenStore> {
+    Arc::new(MemoryTokenStore::new())
+}
+
+///The parts needed to authorize a user, see `MALClientTrait::get_auth_parts`
+#[cfg(feature = "oauth")]
+#[derive(Debug, Clone)]
+pub struct AuthParts {
+    ///The URL the user has to visit to authorize the application
+    pub url: String,
+    ///Has to be passed to `auth` to complete the authorization
+    pub code_verifier: String,
+    ///Random value MAL sends back with the callback, used to discard unrelated requests
+    pub state: String,
+}
+
+#[cfg(feature = "oauth")]
+impl AuthParts {
+    ///Generates a new code verifier and state and builds the authorization URL from them
+    pub fn new(
+        authorize_url: &str,
+        client_id: &str,
+        redirect_uri: Option<&str>,
+        extra_params: &[(&str, &str)],
+    ) -> Self {
+        // MAL only supports the plain method, so the challenge doubles as the verifier
+        let code_verifier = pkce::code_challenge(&pkce::code_verifier(128));
+        let mut state = [0; 16];
+        OsRng.fill_bytes(&mut state);
+        let state: String = state.iter().map(|b| format!("{b:02x}")).collect();
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        query
+            .append_pair("response_type", "code")
+            .append_pair("client_id", client_id)
+            .append_pair("code_challenge", &code_verifier)
+            .append_pair("state", &state);
+        if let Some(redirect_uri) = redirect_uri {
+            query.append_pair("redirect_uri", redirect_uri);
+        }
+        query.extend_pairs(extra_params);
+        let query = query.finish();
+        // a URL that doesn't parse is passed through, so the user sees it instead of a panic
+        let url = match Url::parse(authorize_url) {
+            Ok(mut url) => {
+                let query = match url.query() {
+                    Some(existing) if !existing.is_empty() => format!("{existing}&{query}"),
+                    _ => query,
+                };
+                url.set_query(Some(&query));
+                url.into()
+            }
+            Err(_) => format!("{authorize_url}?{query}"),
+        };
+        Self {
+            url,
+            code_verifier,
+            state,
+        }
     }
 }
 
 #[derive(Deserialize)]
 pub struct TokenResponse {
-    pub token_type: String,
     pub expires_in: usize,
     pub access_token: String,
     pub refresh_token: String,
 }
 
-#[derive(Serialize, Deserialize)]
+///The tokens obtained from MAL during authorization
+///
+///Missing fields are filled with their defaults, so caches written before a field was added can
+///still be read.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct Tokens {
     pub access_token: String,
     pub refresh_token: String,
     pub expires_in: usize,
+    ///Unix timestamp of when the tokens were issued
     pub today: u64,
 }
 
-pub fn encrypt_token(toks: &Tokens) -> Vec<u8> {
-    let key = Key::<Aes256Gcm>::from_slice(b"one two three four five six seve");
-    let cypher = Aes256Gcm::new(key);
-    let nonce = Nonce::from_slice(b"but the eart");
-    let plain = serde_json::to_vec(&toks).unwrap();
-    let res = cypher.encrypt(nonce, plain.as_ref()).unwrap();
-    res
+impl Tokens {
+    ///Returns true if the access token has expired and can be refreshed
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(rt::now())
+    }
+
+    ///Returns true if the access token has expired at `now` and can be refreshed
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        self.can_refresh() && self.has_expired_at(now)
+    }
+
+    ///Returns true if the access token is past its expiry time, regardless of whether it can be
+    ///refreshed
+    pub fn has_expired(&self) -> bool {
+        self.has_expired_at(rt::now())
+    }
+
+    ///Returns true if the access token is past its expiry time at `now`
+    pub fn has_expired_at(&self, now: SystemTime) -> bool {
+        self.expires_at().is_some_and(|t| unix_secs(now) >= t)
+    }
+
+    ///Returns the Unix timestamp at which the access token expires, or `None` if the expiry is
+    ///unknown, e.g. for tokens passed in directly with `MALClient::with_access_token`
+    pub fn expires_at(&self) -> Option<u64> {
+        (self.today != 0).then(|| self.today.saturating_add(self.expires_in as u64))
+    }
+
+    ///Returns true if there's a refresh token available
+    pub fn can_refresh(&self) -> bool {
+        !self.refresh_token.is_empty()
+    }
+}
+
+impl From<TokenResponse> for Tokens {
+    fn from(res: TokenResponse) -> Self {
+        Self {
+            access_token: res.access_token,
+            refresh_token: res.refresh_token,
+            expires_in: res.expires_in,
+            today: unix_secs(rt::now()),
+        }
+    }
+}
+
+///A 404 for a specific anime means it was deleted, and a 403 that it's hidden by the NSFW filter
+fn classify_anime_error(e: MALError) -> MALError {
+    match e {
+        MALError::NotFound { context } => MALError::Gone { context },
+        MALError::Http {
+            status: StatusCode::FORBIDDEN,
+            context,
+            ..
+        } => MALError::AgeRestricted { context },
+        e => e,
+    }
+}
+
+///Crawls the relations of the anime with `id` level by level, up to `query.max_depth` relations
+///away, and returns its details followed by the related anime in the order they were found.
+///Only the relations `query` and `follow` accept are followed, and only through anime that match
+///the query.
+async fn crawl_relations<C: MALClientTrait + Sync + ?Sized>(
+    client: &C,
+    id: AnimeId,
+    query: &RelationQuery,
+    follow: impl Fn(&RelationType) -> bool + Send,
+) -> Result<Vec<AnimeDetails>, MALError> {
+    let fields = AnimeFields::ID
+        | AnimeFields::Title
+        | AnimeFields::MainPicture
+        | AnimeFields::StartDate
+        | AnimeFields::MediaType
+        | AnimeFields::RelatedAnime;
+    let mut seen = HashSet::from([id]);
+    let mut frontier = vec![id];
+    let mut anime: Vec<AnimeDetails> = Vec::new();
+    for depth in 0..=query.max_depth {
+        let mut fetched = client
+            .get_anime_details_batch(&frontier, fields, CRAWL_CONCURRENCY)
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        // the anime itself is always crawled
+        if depth > 0 {
+            fetched.retain(|details| query.includes(details.media_type.as_ref()));
+        }
+        frontier = Vec::new();
+        if depth < query.max_depth {
+            for details in &fetched {
+                for relation in details.related_anime.iter().flatten() {
+                    if query.follows(&relation.relation_type)
+                        && follow(&relation.relation_type)
+                        && seen.insert(relation.node.id)
+                    {
+                        frontier.push(relation.node.id);
+                    }
+                }
+            }
+        }
+        anime.extend(fetched);
+        if frontier.is_empty() {
+            break;
+        }
+    }
+    Ok(anime)
+}
+
+///Returns true if MAL refused a queued change for good, so sending it again can't succeed. A
+///rejected token, rate limiting, and server errors pass, so those changes stay queued.
+fn is_rejected(e: &MALError) -> bool {
+    match e {
+        MALError::Http { status, .. } => {
+            status.is_client_error()
+                && ![StatusCode::REQUEST_TIMEOUT, StatusCode::TOO_MANY_REQUESTS].contains(status)
+        }
+        MALError::NotFound { .. }
+        | MALError::Gone { .. }
+        | MALError::AgeRestricted { .. }
+        | MALError::Invalid { .. } => true,
+        _ => false,
+    }
+}
+
+///Returns the delay requested by the `Retry-After` header in `headers`, if any
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_secs)
+}
+
+///Returns the start of the text of an HTML page, or its title if it has one
+pub(crate) fn excerpt(body: &str) -> String {
+    let title = body
+        .split_once("<title>")
+        .and_then(|(_, rest)| rest.split_once("</title>"))
+        .map(|(title, _)| title);
+    let mut text = String::new();
+    let mut len = 0;
+    let mut in_tag = false;
+    for c in title.unwrap_or(body).chars() {
+        if len >= ERROR_EXCERPT_LEN {
+            break;
+        }
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if in_tag => {}
+            c if c.is_whitespace() => {
+                if !text.is_empty() && !text.ends_with(' ') {
+                    text.push(' ');
+                    len += 1;
+                }
+            }
+            c => {
+                text.push(c);
+                len += 1;
+            }
+        }
+    }
+    text.trim_end().to_owned()
+}
+
+///Maximum number of characters of `MALError::ServerError::excerpt`
+const ERROR_EXCERPT_LEN: usize = 200;
+
+///Number of search results `find_anime` scores
+const FIND_ANIME_CANDIDATES: usize = 10;
+
+///Number of pictures `download_pictures` downloads at a time
+const PICTURE_CONCURRENCY: usize = 4;
+
+///Number of requests `get_franchise` and `get_related_anime` send at a time
+const CRAWL_CONCURRENCY: usize = 4;
+
+///Number of requests `recommend_from_list` sends at a time
+#[cfg(not(feature = "minimal-models"))]
+const RECOMMENDATION_CONCURRENCY: usize = 4;
+
+///Stops `wait_for_callback` when it's dropped
+#[cfg(feature = "callback-server")]
+struct StopListener {
+    server: Arc<Server>,
+    stopped: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "callback-server")]
+impl Drop for StopListener {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.server.unblock();
+    }
 }
 
-pub fn decrypt_tokens(raw: &[u8]) -> Result<Tokens, MALError> {
-    let key = Key::<Aes256Gcm>::from_slice(b"one two three four five six seve");
-    let cypher = Aes256Gcm::new(key);
-    let nonce = Nonce::from_slice(b"but the eart");
-    match cypher.decrypt(nonce, raw.as_ref()) {
-        Ok(plain) => {
-            let text = String::from_utf8(plain).unwrap();
-            Ok(serde_json::from_str(&text).expect("couldn't parse decrypted tokens"))
+///Blocks until the auth callback with `state` arrives, `deadline` passes, or `stopped` is set
+#[cfg(feature = "callback-server")]
+fn wait_for_callback(
+    server: &Server,
+    state: &str,
+    deadline: Option<Instant>,
+    stopped: &AtomicBool,
+) -> Result<tiny_http::Request, MALError> {
+    loop {
+        let received = match deadline {
+            Some(deadline) => server.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => server.recv().map(Some),
+        };
+        if stopped.load(Ordering::SeqCst) {
+            return Err(MALError::Cancelled);
+        }
+        let request = received.map_err(|e| {
+            MALError::auth("Unable to receive the auth callback", e.to_string())
+        })?;
+        let Some(request) = request else {
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                return Err(MALError::AuthTimeout);
+            }
+            continue;
+        };
+        //if the state doesn't match, discard this response
+        if request.url().contains(&format!("state={state}")) {
+            return Ok(request);
         }
-        Err(e) => Err(MALError::new(
-            "Unable to decrypt encrypted tokens",
-            &format!("{e}"),
-            None,
-        )),
     }
 }
+
+///The list status fields `sync_list` compares, besides the ones MAL always sends
+const SYNC_FIELDS: &str =
+    "start_date,finish_date,priority,num_times_rewatched,rewatch_value,tags,comments";
+const MAL_API_URL: &str = "https://api.myanimelist.net/";
+pub(crate) const MAL_AUTH_URL: &str = "https://myanimelist.net/";
+const TOKEN_PATH: &str = "v1/oauth2/token";
+///The `error` of `MALError::Auth` when MAL rejects the refresh token, see
+///`MALError::is_refresh_rejected`
+pub(crate) const REFRESH_REJECTED: &str = "refresh_token_rejected";
+///Identifies the app in public client mode, see `ClientBuilder::public_client`
+const CLIENT_ID_HEADER: HeaderName = HeaderName::from_static("x-mal-client-id");
+
+///Returns `url` with its `limit` parameter set to `limit`, or `url` unchanged if it can't be
+///parsed
+fn with_limit(url: &str, limit: usize) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_owned();
+    };
+    let params: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(k, _)| k != "limit")
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    parsed
+        .query_pairs_mut()
+        .clear()
+        .extend_pairs(params)
+        .append_pair("limit", &limit.to_string());
+    parsed.into()
+}
+
+///How far the local clock can be off before token expiry is judged noticeably wrong
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(60);
+
+///Compares the local time `now` with `server_time`, the time MAL sent in the `Date` header
+fn check_clock(now: SystemTime, server_time: Option<SystemTime>) -> Check {
+    let Some(server_time) = server_time else {
+        return Check::new(
+            CheckKind::ClockSkew,
+            CheckStatus::Skipped,
+            "MAL didn't send its time",
+        );
+    };
+    let (skew, direction) = match now.duration_since(server_time) {
+        Ok(skew) => (skew, "ahead of"),
+        Err(e) => (e.duration(), "behind"),
+    };
+    if skew > MAX_CLOCK_SKEW {
+        Check::new(
+            CheckKind::ClockSkew,
+            CheckStatus::Warning,
+            format!(
+                "the local clock is {} seconds {direction} MAL's, tokens may be refreshed too early or too late",
+                skew.as_secs()
+            ),
+        )
+    } else {
+        Check::new(
+            CheckKind::ClockSkew,
+            CheckStatus::Ok,
+            format!("within {} seconds of MAL's clock", skew.as_secs()),
+        )
+    }
+}
+
+///Exchanges `refresh_token` for a new set of tokens at `auth_base_url`. `client_secret` is only
+///sent by confidential clients.
+pub async fn refresh_tokens(
+    transport: &dyn HttpTransport,
+    auth_base_url: &str,
+    client_id: &str,
+    client_secret: Option<&str>,
+    refresh_token: &str,
+) -> Result<Tokens, MALError> {
+    let mut params = vec![
+        ("client_id", client_id),
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token),
+    ];
+    if let Some(secret) = client_secret {
+        params.push(("client_secret", secret));
+    }
+    let url = format!("{auth_base_url}{TOKEN_PATH}");
+    let request = HttpRequest::new(Method::POST, &url).form(&params);
+    let res = transport.send(request).await?;
+    serde_json::from_str::<TokenResponse>(&res.body)
+        .map(Tokens::from)
+        .map_err(|_| match res.status {
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED => MALError::auth(REFRESH_REJECTED, res.body),
+            _ => MALError::auth("Unable to refresh token", res.body),
+        })
+}
+
+pub(crate) fn with_trailing_slash(mut url: String) -> String {
+    if !url.ends_with('/') {
+        url.push('/');
+    }
+    url
+}
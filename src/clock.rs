@@ -0,0 +1,74 @@
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+
+///Tells a `MALClient` the current time
+///
+///The client reads the time from its clock to decide when the access token expires, when cached
+///responses go stale, and which date to stamp list updates with. The default is the
+///[`SystemClock`], tests can use a [`MockClock`] to control the time.
+///
+///# Example
+///
+///```
+/// use std::time::{Duration, SystemTime};
+/// use lib_mal::{ClientBuilder, MALClient, MockClock};
+///
+/// let clock = MockClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+/// let client: MALClient = ClientBuilder::new().clock(clock.clone()).build_no_refresh();
+/// // an hour later, as far as the client can tell
+/// clock.advance(Duration::from_secs(3600));
+///```
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+///Reads the time from the system, the default clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        crate::rt::now()
+    }
+}
+
+///A clock that only moves when it's told to. Clones share the same time, so a test can keep a
+///clone after passing the clock to `ClientBuilder::clock`.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<SystemTime>>,
+}
+
+impl MockClock {
+    pub fn new(now: SystemTime) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    ///Creates a clock set to the Unix timestamp `secs`
+    pub fn from_unix(secs: u64) -> Self {
+        Self::new(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    pub fn set(&self, now: SystemTime) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) += by;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+///Returns `time` as a Unix timestamp
+pub(crate) fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
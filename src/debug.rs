@@ -0,0 +1,225 @@
+//! Dumping every request and response in full, for attaching traces to bug reports, see
+//! [`MALClient::debug_mode`](crate::MALClient::debug_mode)
+//!
+//! Secrets are redacted before a dump reaches the sink: the `Authorization` header, and the
+//! tokens, client secret, and auth code in form bodies and JSON responses. The dumps can be
+//! shared without leaking the user's login.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{ClientBuilder, MALClient, MALClientTrait, MALError};
+//! use lib_mal::debug::DebugWriter;
+//! use std::fs::File;
+//! # async fn test() -> Result<(), MALError> {
+//! let client: MALClient = ClientBuilder::new()
+//!     .access_token(String::from("[YOUR_SECRET_HERE]"))
+//!     .debug_sink(DebugWriter::new(File::create("mal-trace.log")?))
+//!     .build_no_refresh();
+//! client.get_anime_details(80, None).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::{self, Display};
+use std::io::Write;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use reqwest::header::{AUTHORIZATION, COOKIE, SET_COOKIE};
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+
+use crate::rt::Instant;
+use crate::{HttpRequest, HttpResponse, HttpTransport, MALError};
+
+///Replaces secrets in the dumps
+pub const REDACTED: &str = "[REDACTED]";
+
+///Form parameters and JSON keys whose values are redacted
+pub(crate) const SECRET_KEYS: [&str; 5] = [
+    "access_token",
+    "refresh_token",
+    "client_secret",
+    "code",
+    "code_verifier",
+];
+
+///Receives a dump of every request the client sends, see the module docs
+///
+///# Example
+///
+///```
+/// use lib_mal::debug::{DebugDump, DebugSink};
+///
+/// struct Failures;
+///
+/// impl DebugSink for Failures {
+///     fn record(&self, dump: &DebugDump) {
+///         if !dump.status.is_some_and(|s| s.is_success()) {
+///             eprintln!("{dump}");
+///         }
+///     }
+/// }
+///```
+pub trait DebugSink: Send + Sync {
+    fn record(&self, dump: &DebugDump);
+}
+
+///A request and its response with the secrets redacted
+#[derive(Debug, Clone)]
+pub struct DebugDump {
+    pub method: Method,
+    pub url: String,
+    pub request_headers: Vec<(String, String)>,
+    ///The form parameters of the request, if it had a body
+    pub request_body: Option<Vec<(String, String)>>,
+    ///`None` if no response was received
+    pub status: Option<StatusCode>,
+    ///The body of the response, or the error if no response was received. Binary bodies like
+    ///pictures are replaced by their size.
+    pub response_body: String,
+    pub duration: Duration,
+}
+
+impl DebugDump {
+    fn new(request: &HttpRequest, response: Result<(StatusCode, String), &MALError>, duration: Duration) -> Self {
+        let request_headers = request
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if [AUTHORIZATION, COOKIE, SET_COOKIE].contains(name) {
+                    REDACTED.to_owned()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+                (name.to_string(), value)
+            })
+            .collect();
+        let request_body = request.form.as_ref().map(|form| {
+            form.iter()
+                .map(|(key, value)| {
+                    let value = if SECRET_KEYS.contains(&key.as_str()) {
+                        REDACTED.to_owned()
+                    } else {
+                        value.clone()
+                    };
+                    (key.clone(), value)
+                })
+                .collect()
+        });
+        let (status, response_body) = match response {
+            Ok((status, body)) => (Some(status), redact_json(body)),
+            Err(e) => (None, e.to_string()),
+        };
+        Self {
+            method: request.method.clone(),
+            url: request.url.clone(),
+            request_headers,
+            request_body,
+            status,
+            response_body,
+            duration,
+        }
+    }
+}
+
+///Shows the dump like a raw HTTP exchange
+impl Display for DebugDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "> {} {}", self.method, self.url)?;
+        for (name, value) in &self.request_headers {
+            writeln!(f, "> {name}: {value}")?;
+        }
+        if let Some(form) = &self.request_body {
+            let body: Vec<String> = form.iter().map(|(k, v)| format!("{k}={v}")).collect();
+            writeln!(f, "> {}", body.join("&"))?;
+        }
+        match self.status {
+            Some(status) => writeln!(f, "< {status} in {:?}", self.duration)?,
+            None => writeln!(f, "< no response after {:?}", self.duration)?,
+        }
+        writeln!(f, "< {}", self.response_body)
+    }
+}
+
+///Replaces the secrets in a JSON body, bodies that aren't JSON are returned unchanged
+pub(crate) fn redact_json(body: String) -> String {
+    fn redact(value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if SECRET_KEYS.contains(&key.as_str()) {
+                        *value = Value::String(REDACTED.to_owned());
+                    } else {
+                        redact(value);
+                    }
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(redact),
+            _ => {}
+        }
+    }
+    match serde_json::from_str::<Value>(&body) {
+        Ok(mut value) => {
+            redact(&mut value);
+            value.to_string()
+        }
+        Err(_) => body,
+    }
+}
+
+///Writes every dump followed by an empty line to `writer`, e.g. a file or `std::io::stderr()`
+pub struct DebugWriter<W>(Mutex<W>);
+
+impl<W: Write + Send> DebugWriter<W> {
+    pub const fn new(writer: W) -> Self {
+        Self(Mutex::new(writer))
+    }
+}
+
+impl<W: Write + Send> DebugSink for DebugWriter<W> {
+    fn record(&self, dump: &DebugDump) {
+        let mut writer = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        // a broken log shouldn't break the requests
+        let _ = writeln!(writer, "{dump}");
+        let _ = writer.flush();
+    }
+}
+
+///Sends the requests through `inner` and dumps them to `sink`, if there is one
+pub(crate) struct Dumping<'a> {
+    pub(crate) inner: &'a dyn HttpTransport,
+    pub(crate) sink: Option<&'a dyn DebugSink>,
+}
+
+impl HttpTransport for Dumping<'_> {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, MALError>> {
+        Box::pin(async move {
+            let Some(sink) = self.sink else {
+                return self.inner.send(request).await;
+            };
+            let start = Instant::now();
+            let res = self.inner.send(request.clone()).await;
+            let response = res.as_ref().map(|res| (res.status, res.body.clone()));
+            sink.record(&DebugDump::new(&request, response, start.elapsed()));
+            res
+        })
+    }
+
+    fn send_bytes(&self, request: HttpRequest) -> BoxFuture<'_, Result<(StatusCode, Vec<u8>), MALError>> {
+        Box::pin(async move {
+            let Some(sink) = self.sink else {
+                return self.inner.send_bytes(request).await;
+            };
+            let start = Instant::now();
+            let res = self.inner.send_bytes(request.clone()).await;
+            let response = res
+                .as_ref()
+                .map(|(status, bytes)| (*status, format!("<{} bytes>", bytes.len())));
+            sink.record(&DebugDump::new(&request, response, start.elapsed()));
+            res
+        })
+    }
+}
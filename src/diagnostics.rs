@@ -0,0 +1,84 @@
+//! Connection troubleshooting, see [`MALClient::diagnose`](crate::MALClient::diagnose)
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait};
+//! use lib_mal::diagnostics::CheckStatus;
+//! # async fn test() {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let report = client.diagnose().await;
+//! for check in &report.checks {
+//!     if check.status != CheckStatus::Ok {
+//!         println!("{:?}: {}", check.kind, check.detail);
+//!     }
+//! }
+//! # }
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+///The results of every check run by `MALClient::diagnose`, in the order they ran
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Diagnostics {
+    pub checks: Vec<Check>,
+}
+
+impl Diagnostics {
+    ///Returns true if no check failed. Warnings don't count as failures.
+    pub fn is_healthy(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Failed)
+    }
+
+    ///Returns the result of the `kind` check
+    pub fn get(&self, kind: CheckKind) -> Option<&Check> {
+        self.checks.iter().find(|c| c.kind == kind)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Check {
+    pub kind: CheckKind,
+    pub status: CheckStatus,
+    ///Human readable explanation of the result
+    pub detail: String,
+}
+
+impl Check {
+    pub(crate) fn new(kind: CheckKind, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            kind,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum CheckKind {
+    ///The MAL API can be reached
+    MalReachable,
+    ///The access token is accepted by MAL
+    Token,
+    ///The local clock agrees with MAL's, token expiry is judged with the local clock
+    ClockSkew,
+    ///The token cache and response cache directories can be written to
+    CacheDir,
+    ///The Jikan API used by `MALClient::jikan` can be reached
+    JikanReachable,
+    ///The client is configured to stay below MAL's rate limit
+    RateLimit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    ///Works, but might cause problems
+    Warning,
+    Failed,
+    ///Couldn't run, e.g. because it depends on a check that failed
+    Skipped,
+}
@@ -0,0 +1,142 @@
+//! An object safe version of `MALClientTrait`, so real and mock clients can be swapped at
+//! runtime
+
+use futures_util::future::BoxFuture;
+
+use crate::limits::Limit;
+use crate::model::fields::{AnimeFields, UserFields};
+use crate::model::options::{
+    ForumTopicQuery, RankingType, SeasonYear, SeasonalSort, StatusParams, SuggestionQuery,
+    UserListQuery,
+};
+use crate::model::{
+    AnimeDetails, AnimeId, AnimeList, ForumBoards, ForumTopics, ListStatus, TopicDetails, TopicId,
+    User,
+};
+use crate::{AuthState, MALClientTrait, MALError, ReauthReason};
+
+///Generates the `DynMALClient` methods, which box the future of the `MALClientTrait` method of
+///the same name
+macro_rules! dyn_client {
+    ($(
+        $(#[$attr:meta])*
+        fn $name:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty;
+    )*) => {
+        ///`MALClientTrait` with concrete parameter types and boxed futures, so it can be used as
+        ///`Arc<dyn DynMALClient>`. Implemented for every `MALClientTrait`, the parameters that
+        ///take `impl Into<T>` there take `T` here.
+        ///
+        ///Only has the API calls and the auth state, configure the client before erasing its
+        ///type. Its methods have the same names as the ones of `MALClientTrait`, so calls on a
+        ///concrete client are ambiguous where both traits are imported.
+        ///
+        ///# Example
+        ///
+        ///```
+        /// # use std::sync::Arc;
+        /// # use lib_mal::{DynMALClient, MALClient, MALClientTrait, MALError, MockMALClient};
+        /// async fn title(client: &dyn DynMALClient) -> Result<String, MALError> {
+        ///     Ok(client.get_anime_details(30230.into(), None).await?.show.title)
+        /// }
+        ///
+        /// # async fn test() -> Result<(), MALError> {
+        /// let offline = true;
+        /// let client: Arc<dyn DynMALClient> = if offline {
+        ///     Arc::new(MockMALClient::with_access_token("token"))
+        /// } else {
+        ///     Arc::new(MALClient::with_access_token("[YOUR_SECRET_HERE]"))
+        /// };
+        /// println!("{}", title(&*client).await?);
+        /// # Ok(())
+        /// # }
+        ///```
+        pub trait DynMALClient: Send + Sync {
+            ///See `MALClientTrait::get_access_token`
+            fn get_access_token(&self) -> String;
+            ///See `MALClientTrait::auth_state`
+            fn auth_state(&self) -> AuthState;
+            ///See `MALClientTrait::need_auth`
+            fn need_auth(&self) -> bool;
+            ///See `MALClientTrait::reauth_reason`
+            fn reauth_reason(&self) -> Option<&ReauthReason>;
+            $(
+                $(#[$attr])*
+                fn $name<'a>(&'a self $(, $arg: $ty)*) -> BoxFuture<'a, Result<$ret, MALError>>;
+            )*
+        }
+
+        impl<C: MALClientTrait + Send + Sync> DynMALClient for C {
+            fn get_access_token(&self) -> String {
+                MALClientTrait::get_access_token(self)
+            }
+
+            fn auth_state(&self) -> AuthState {
+                MALClientTrait::auth_state(self)
+            }
+
+            fn need_auth(&self) -> bool {
+                MALClientTrait::need_auth(self)
+            }
+
+            fn reauth_reason(&self) -> Option<&ReauthReason> {
+                MALClientTrait::reauth_reason(self)
+            }
+
+            $(
+                fn $name<'a>(&'a self $(, $arg: $ty)*) -> BoxFuture<'a, Result<$ret, MALError>> {
+                    Box::pin(MALClientTrait::$name(self $(, $arg)*))
+                }
+            )*
+        }
+    };
+}
+
+dyn_client! {
+    ///See `MALClientTrait::get_anime_list`
+    fn get_anime_list(&self, query: &'a str, limit: Limit, fields: Option<AnimeFields>) -> AnimeList;
+    ///See `MALClientTrait::get_anime_details`
+    fn get_anime_details(&self, id: AnimeId, fields: Option<AnimeFields>) -> AnimeDetails;
+    ///See `MALClientTrait::get_anime_ranking`
+    fn get_anime_ranking(
+        &self,
+        ranking_type: RankingType,
+        limit: Limit,
+        fields: Option<AnimeFields>
+    ) -> AnimeList;
+    ///See `MALClientTrait::get_seasonal_anime`
+    fn get_seasonal_anime(
+        &self,
+        season: SeasonYear,
+        limit: Limit,
+        fields: Option<AnimeFields>,
+        sort: Option<SeasonalSort>
+    ) -> AnimeList;
+    ///See `MALClientTrait::get_suggested_anime`
+    fn get_suggested_anime(&self, limit: Limit, fields: Option<AnimeFields>) -> AnimeList;
+    ///See `MALClientTrait::get_suggestions`
+    fn get_suggestions(&self, query: SuggestionQuery) -> AnimeList;
+    ///See `MALClientTrait::update_user_anime_status`
+    fn update_user_anime_status(&self, id: AnimeId, update: StatusParams) -> ListStatus;
+    ///See `MALClientTrait::get_user_anime_list`
+    fn get_user_anime_list(&self, query: Option<UserListQuery>) -> AnimeList;
+    ///See `MALClientTrait::get_user_anime_list_for`
+    fn get_user_anime_list_for(&self, username: &'a str, query: Option<UserListQuery>) -> AnimeList;
+    ///See `MALClientTrait::increment_watched_episodes`
+    fn increment_watched_episodes(&self, id: AnimeId, by: usize) -> ListStatus;
+    ///See `MALClientTrait::mark_completed`
+    fn mark_completed(&self, id: AnimeId, score: Option<u8>) -> ListStatus;
+    ///See `MALClientTrait::delete_anime_list_item`
+    fn delete_anime_list_item(&self, id: AnimeId) -> ();
+    ///See `MALClientTrait::get_forum_boards`
+    fn get_forum_boards(&self) -> ForumBoards;
+    ///See `MALClientTrait::get_forum_topic_detail`
+    fn get_forum_topic_detail(&self, topic_id: TopicId, limit: Limit) -> TopicDetails;
+    ///See `MALClientTrait::get_forum_topic_posts`
+    fn get_forum_topic_posts(&self, topic_id: TopicId, limit: Limit, offset: usize) -> TopicDetails;
+    ///See `MALClientTrait::get_forum_topics`
+    fn get_forum_topics(&self, query: Option<ForumTopicQuery>) -> ForumTopics;
+    ///See `MALClientTrait::get_my_user_info`
+    fn get_my_user_info(&self, fields: Option<UserFields>) -> User;
+    ///See `MALClientTrait::get_user_info`
+    fn get_user_info(&self, username: &'a str, fields: Option<UserFields>) -> User;
+}
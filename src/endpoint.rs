@@ -0,0 +1,267 @@
+//! The API endpoints as values, for sending requests the crate has no method for, see
+//! [`MALClient::execute`](crate::MALClient::execute)
+//!
+//! Requests sent with `execute` go through the same token refresh, retries, rate limiting,
+//! middleware, and error mapping as the client's own methods, but skip the response cache and
+//! the offline queue.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::endpoint::Endpoint;
+//! use lib_mal::model::AnimeDetails;
+//! use serde_json::Value;
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let anime: AnimeDetails = client.execute(Endpoint::AnimeDetails { id: 80.into(), fields: None }).await?;
+//! // manga aren't modeled by the crate yet
+//! let manga: Value = client.execute(Endpoint::get("manga/2").query("fields", "num_volumes")).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use reqwest::Method;
+
+use crate::limits::{
+    Limit, MAX_FORUM_TOPICS_LIMIT, MAX_RANKING_LIMIT, MAX_SEARCH_LIMIT, MAX_SEASONAL_LIMIT,
+    MAX_TOPIC_POSTS_LIMIT, MAX_USER_LIST_LIMIT,
+};
+use crate::model::fields::{AnimeFields, UserFields};
+use crate::model::ids::{AnimeId, TopicId};
+use crate::model::options::{
+    ForumTopicQuery, Params, RankingType, SeasonYear, SeasonalSort, StatusParams, UserListQuery,
+};
+use crate::MALError;
+
+///The path segments after `v2` and the query parameters of a request
+type PathAndQuery = (Vec<String>, Vec<(String, String)>);
+
+///A request to the v2 API. `Limit::DEFAULT` and `None` fields mean the same as for the matching
+///`MALClientTrait` methods.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Endpoint {
+    AnimeDetails {
+        id: AnimeId,
+        fields: Option<AnimeFields>,
+    },
+    AnimeSearch {
+        query: String,
+        limit: Limit,
+        offset: Option<usize>,
+        fields: Option<AnimeFields>,
+    },
+    AnimeRanking {
+        ranking_type: RankingType,
+        limit: Limit,
+        fields: Option<AnimeFields>,
+    },
+    SeasonalAnime {
+        season: SeasonYear,
+        limit: Limit,
+        fields: Option<AnimeFields>,
+        sort: Option<SeasonalSort>,
+    },
+    ///The list of the user called `username`, or of the current user for `@me`
+    UserAnimeList {
+        username: String,
+        query: UserListQuery,
+    },
+    UpdateListStatus {
+        id: AnimeId,
+        update: StatusParams,
+    },
+    DeleteListStatus {
+        id: AnimeId,
+    },
+    UserInfo {
+        username: String,
+        fields: Option<UserFields>,
+    },
+    ForumBoards,
+    ForumTopic {
+        id: TopicId,
+        limit: Limit,
+        offset: usize,
+    },
+    ForumTopics(ForumTopicQuery),
+    ///Any other endpoint, `path` is relative to `https://api.myanimelist.net/v2/`. The `form`
+    ///parameters are sent as the body.
+    Custom {
+        method: Method,
+        path: String,
+        query: Vec<(String, String)>,
+        form: Vec<(String, String)>,
+    },
+}
+
+impl Endpoint {
+    ///A get request to `path`, see `Endpoint::Custom`
+    pub fn get(path: impl Into<String>) -> Self {
+        Self::custom(Method::GET, path)
+    }
+
+    ///A request to `path`, see `Endpoint::Custom`
+    pub fn custom(method: Method, path: impl Into<String>) -> Self {
+        Self::Custom {
+            method,
+            path: path.into(),
+            query: Vec::new(),
+            form: Vec::new(),
+        }
+    }
+
+    ///Adds a query parameter to a `Custom` endpoint, other endpoints are returned unchanged
+    #[must_use]
+    pub fn query(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        if let Self::Custom { query, .. } = &mut self {
+            query.push((key.into(), value.into()));
+        }
+        self
+    }
+
+    ///Adds a form parameter to a `Custom` endpoint, other endpoints are returned unchanged
+    #[must_use]
+    pub fn form(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        if let Self::Custom { form, .. } = &mut self {
+            form.push((key.into(), value.into()));
+        }
+        self
+    }
+
+    ///The name used for the endpoint in errors and `RequestMetrics`, the same as the client
+    ///method that sends it
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::AnimeDetails { .. } => "get_anime_details",
+            Self::AnimeSearch { .. } => "get_anime_list",
+            Self::AnimeRanking { .. } => "get_anime_ranking",
+            Self::SeasonalAnime { .. } => "get_seasonal_anime",
+            Self::UserAnimeList { .. } => "get_user_anime_list",
+            Self::UpdateListStatus { .. } => "update_user_anime_status",
+            Self::DeleteListStatus { .. } => "delete_anime_list_item",
+            Self::UserInfo { .. } => "get_user_info",
+            Self::ForumBoards => "get_forum_boards",
+            Self::ForumTopic { .. } => "get_forum_topic_detail",
+            Self::ForumTopics(_) => "get_forum_topics",
+            Self::Custom { .. } => "custom",
+        }
+    }
+
+    pub fn method(&self) -> Method {
+        match self {
+            Self::UpdateListStatus { .. } => Method::PUT,
+            Self::DeleteListStatus { .. } => Method::DELETE,
+            Self::Custom { method, .. } => method.clone(),
+            _ => Method::GET,
+        }
+    }
+
+    ///Returns true for the endpoints that list anime, which take the `nsfw` parameter
+    pub(crate) const fn lists_anime(&self) -> bool {
+        matches!(
+            self,
+            Self::AnimeSearch { .. } | Self::AnimeRanking { .. } | Self::SeasonalAnime { .. }
+        )
+    }
+
+    ///Returns the path segments after `v2` and the query parameters, `default_fields` are
+    ///requested for anime details without fields. Fails if a limit is above the endpoint's
+    ///maximum.
+    pub(crate) fn path(
+        &self,
+        default_fields: AnimeFields,
+    ) -> Result<PathAndQuery, MALError> {
+        let name = self.name();
+        let limit = |limit: Limit, max| -> Result<(String, String), MALError> {
+            Ok((String::from("limit"), limit.validate(name, max)?.to_string()))
+        };
+        let fields = |fields: Option<AnimeFields>| fields.map(|f| (String::from("fields"), f.to_string()));
+        let owned = |params: Vec<(&str, String)>| -> Vec<(String, String)> {
+            params.into_iter().map(|(k, v)| (k.to_owned(), v)).collect()
+        };
+        let segments = |s: &[&str]| s.iter().map(|s| (*s).to_owned()).collect();
+        Ok(match self {
+            Self::AnimeDetails { id, fields } => (
+                segments(&["anime", &id.to_string()]),
+                vec![(String::from("fields"), fields.unwrap_or(default_fields).to_string())],
+            ),
+            Self::AnimeSearch {
+                query,
+                limit: l,
+                offset,
+                fields: f,
+            } => {
+                let mut params = vec![(String::from("q"), query.clone()), limit(*l, MAX_SEARCH_LIMIT)?];
+                params.extend(offset.map(|o| (String::from("offset"), o.to_string())));
+                params.extend(fields(*f));
+                (segments(&["anime"]), params)
+            }
+            Self::AnimeRanking {
+                ranking_type,
+                limit: l,
+                fields: f,
+            } => {
+                let mut params = vec![
+                    (String::from("ranking_type"), ranking_type.to_string()),
+                    limit(*l, MAX_RANKING_LIMIT)?,
+                ];
+                params.extend(fields(*f));
+                (segments(&["anime", "ranking"]), params)
+            }
+            Self::SeasonalAnime {
+                season,
+                limit: l,
+                fields: f,
+                sort,
+            } => {
+                let mut params = vec![limit(*l, MAX_SEASONAL_LIMIT)?];
+                params.extend(fields(*f));
+                params.extend(sort.map(|s| (String::from("sort"), s.to_string())));
+                let (year, name) = (season.year.to_string(), season.season.to_string());
+                (segments(&["anime", "season", &year, &name]), params)
+            }
+            Self::UserAnimeList { username, query } => {
+                query.limit.validate(name, MAX_USER_LIST_LIMIT)?;
+                (segments(&["users", username, "animelist"]), owned(query.clone().get_params()))
+            }
+            Self::UpdateListStatus { id, .. } | Self::DeleteListStatus { id } => {
+                (segments(&["anime", &id.to_string(), "my_list_status"]), Vec::new())
+            }
+            Self::UserInfo { username, fields } => (
+                segments(&["users", username]),
+                vec![(String::from("fields"), fields.unwrap_or(UserFields::ALL).to_string())],
+            ),
+            Self::ForumBoards => (segments(&["forum", "boards"]), Vec::new()),
+            Self::ForumTopic { id, limit: l, offset } => (
+                segments(&["forum", "topic", &id.to_string()]),
+                vec![limit(*l, MAX_TOPIC_POSTS_LIMIT)?, (String::from("offset"), offset.to_string())],
+            ),
+            Self::ForumTopics(query) => {
+                query.limit.validate(name, MAX_FORUM_TOPICS_LIMIT)?;
+                (segments(&["forum", "topics"]), owned(query.clone().get_params()))
+            }
+            Self::Custom { path, query, .. } => (
+                path.split('/').filter(|s| !s.is_empty()).map(str::to_owned).collect(),
+                query.clone(),
+            ),
+        })
+    }
+
+    ///Returns the form parameters sent as the body, `None` for requests without a body
+    pub(crate) fn body(&self) -> Option<Vec<(String, String)>> {
+        match self {
+            Self::UpdateListStatus { update, .. } => Some(
+                update
+                    .clone()
+                    .get_params()
+                    .into_iter()
+                    .map(|(k, v)| (k.to_owned(), v))
+                    .collect(),
+            ),
+            Self::Custom { form, .. } if !form.is_empty() => Some(form.clone()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::fs;
+use std::path::PathBuf;
+
+use aes_gcm::aead::{Aead, AeadCore, OsRng};
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce};
+
+use crate::{MALError, TokenStore, Tokens};
+
+///The key every cache was encrypted with before keys could be configured
+const LEGACY_KEY: &[u8; 32] = b"one two three four five six seve";
+const LEGACY_NONCE: &[u8; 12] = b"but the eart";
+
+///Marks a versioned token cache, followed by a version byte
+const CACHE_MAGIC: &[u8; 4] = b"LMAL";
+///The version of the token cache format written by `encrypt_token`
+const CACHE_VERSION: u8 = 1;
+
+///Stores the tokens in an encrypted `tokens` file inside a directory
+///
+///The file starts with a version header, followed by a fresh random nonce for every write and
+///the ciphertext. Caches written by older versions, without a header or with the built-in key and
+///nonce, are still readable and get re-encrypted with the store's key the first time they're
+///loaded.
+pub struct FileTokenStore {
+    dir: PathBuf,
+    key: [u8; 32],
+}
+
+impl FileTokenStore {
+    ///Creates a store using the built-in key. The built-in key is shared by every application,
+    ///so prefer [`FileTokenStore::with_key`].
+    pub const fn new(dir: PathBuf) -> Self {
+        Self::with_key(dir, *LEGACY_KEY)
+    }
+
+    ///Creates a store that encrypts the tokens with `key`
+    pub const fn with_key(dir: PathBuf, key: [u8; 32]) -> Self {
+        Self { dir, key }
+    }
+
+    fn path(&self) -> PathBuf {
+        self.dir.join("tokens")
+    }
+
+    ///Reads the header of the stored cache without decrypting it, or returns `None` if nothing
+    ///has been stored yet
+    pub fn cache(&self) -> Result<Option<TokenCache>, MALError> {
+        match fs::read(self.path()) {
+            Ok(raw) => Ok(Some(TokenCache::from_bytes(&raw))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+///The format of a token cache written by `FileTokenStore`, see `FileTokenStore::cache`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenCache {
+    version: u8,
+}
+
+impl TokenCache {
+    ///The version written by this version of the library
+    pub const CURRENT_VERSION: u8 = CACHE_VERSION;
+
+    ///Reads the header of the contents of a `tokens` file
+    pub fn from_bytes(raw: &[u8]) -> Self {
+        Self {
+            version: cache_version(raw).unwrap_or(0),
+        }
+    }
+
+    ///Returns the format version, 0 for caches written before the version header was added
+    pub const fn version(&self) -> u8 {
+        self.version
+    }
+
+    ///Returns true if this version of the library can read the cache. Caches written by newer
+    ///versions can't be read.
+    pub const fn is_supported(&self) -> bool {
+        self.version <= CACHE_VERSION
+    }
+
+    ///Returns true if the cache is rewritten in the current format the next time it's loaded
+    pub const fn needs_migration(&self) -> bool {
+        self.version < CACHE_VERSION
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> Result<Option<Tokens>, MALError> {
+        if !self.path().exists() {
+            return Ok(None);
+        }
+        let raw = fs::read(self.path())?;
+        let cache = TokenCache::from_bytes(&raw);
+        if !cache.is_supported() {
+            return Err(MALError::TokenCache(format!(
+                "Unsupported token cache version {}",
+                cache.version()
+            )));
+        }
+        if !cache.needs_migration() {
+            if let Ok(tokens) = decrypt_tokens(&raw, &self.key) {
+                return Ok(Some(tokens));
+            }
+        }
+        let tokens = migrate(&raw, &self.key)?;
+        self.save(&tokens)?;
+        Ok(Some(tokens))
+    }
+
+    fn save(&self, tokens: &Tokens) -> Result<(), MALError> {
+        Ok(fs::write(self.path(), encrypt_token(tokens, &self.key)?)?)
+    }
+
+    fn clear(&self) -> Result<(), MALError> {
+        match fs::remove_file(self.path()) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+///Encrypts the tokens with a random nonce, the output starts with the cache version header
+///followed by the nonce and the ciphertext
+pub fn encrypt_token(toks: &Tokens, key: &[u8; 32]) -> Result<Vec<u8>, MALError> {
+    let cypher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let plain = serde_json::to_vec(&toks).map_err(|e| MALError::TokenCache(e.to_string()))?;
+    let mut res = CACHE_MAGIC.to_vec();
+    res.push(CACHE_VERSION);
+    res.extend_from_slice(&nonce);
+    res.append(
+        &mut cypher
+            .encrypt(&nonce, plain.as_ref())
+            .map_err(|e| MALError::TokenCache(e.to_string()))?,
+    );
+    Ok(res)
+}
+
+///Decrypts tokens written by `encrypt_token`
+pub fn decrypt_tokens(raw: &[u8], key: &[u8; 32]) -> Result<Tokens, MALError> {
+    match cache_version(raw) {
+        Some(CACHE_VERSION) => decrypt_nonce_prefixed(&raw[CACHE_MAGIC.len() + 1..], key),
+        Some(version) => Err(MALError::TokenCache(format!(
+            "Unsupported token cache version {version}"
+        ))),
+        None => Err(MALError::TokenCache(String::from(
+            "Token cache has no version header",
+        ))),
+    }
+}
+
+///Returns the version of a cache with a version header
+fn cache_version(raw: &[u8]) -> Option<u8> {
+    raw.strip_prefix(CACHE_MAGIC.as_slice())
+        .and_then(|rest| rest.first().copied())
+}
+
+///Reads a cache written by an older version or with the built-in key and returns its tokens so
+///they can be re-encrypted with `key`. Caches without a version header are tried, in order: with
+///`key`, with the built-in key, and with the built-in key and the built-in nonce. A new format
+///gets its own arm here that reads the previous version.
+fn migrate(raw: &[u8], key: &[u8; 32]) -> Result<Tokens, MALError> {
+    match TokenCache::from_bytes(raw).version() {
+        0 => decrypt_nonce_prefixed(raw, key)
+            .or_else(|_| decrypt_nonce_prefixed(raw, LEGACY_KEY))
+            .or_else(|_| decrypt_legacy_tokens(raw)),
+        CACHE_VERSION => decrypt_nonce_prefixed(&raw[CACHE_MAGIC.len() + 1..], LEGACY_KEY),
+        version => Err(MALError::TokenCache(format!(
+            "Unsupported token cache version {version}"
+        ))),
+    }
+}
+
+fn decrypt_nonce_prefixed(raw: &[u8], key: &[u8; 32]) -> Result<Tokens, MALError> {
+    if raw.len() < LEGACY_NONCE.len() {
+        return Err(MALError::TokenCache(String::from(
+            "Token cache is too short",
+        )));
+    }
+    let (nonce, cyphertext) = raw.split_at(LEGACY_NONCE.len());
+    decrypt(cyphertext, key, nonce)
+}
+
+///Decrypts a cache written before nonces were randomized
+fn decrypt_legacy_tokens(raw: &[u8]) -> Result<Tokens, MALError> {
+    decrypt(raw, LEGACY_KEY, LEGACY_NONCE)
+}
+
+fn decrypt(cyphertext: &[u8], key: &[u8; 32], nonce: &[u8]) -> Result<Tokens, MALError> {
+    let cypher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let plain = cypher
+        .decrypt(Nonce::from_slice(nonce), cyphertext)
+        .map_err(|e| MALError::TokenCache(e.to_string()))?;
+    serde_json::from_slice(&plain)
+        .map_err(|e| MALError::TokenCache(format!("couldn't parse decrypted tokens: {e}")))
+}
@@ -0,0 +1,76 @@
+//! The MAL forums, see [`MALClientTrait::get_forum_topics`](crate::MALClientTrait::get_forum_topics)
+//! for reading them
+//!
+//! The v2 API only has endpoints for reading the forums, there's no scope that allows creating
+//! topics, replying, or voting in polls, so the client can't do those either.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use futures_util::StreamExt;
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let mut posts = std::pin::pin!(client.stream_topic_posts(2_107_523));
+//! while let Some(post) = posts.next().await {
+//!     println!("{}", post?.body);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub use crate::model::{
+    ForumBoard, ForumBoards, ForumCategory, ForumPost, ForumTopic, ForumTopics, ForumUser, Poll,
+    PollOption, Subboard, Topic, TopicDetails,
+};
+pub use crate::model::options::{ForumSort, ForumTopicQuery};
+
+use futures_util::{stream, Stream, StreamExt};
+
+use crate::limits::MAX_TOPIC_POSTS_LIMIT;
+use crate::model::ids::TopicId;
+use crate::{MALClient, MALClientTrait, MALError};
+
+impl MALClient {
+    ///Returns every post of the topic with `id`, fetching `limits::MAX_TOPIC_POSTS_LIMIT` posts
+    ///at a time as the stream is read. The stream ends after the last post or the first error.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use futures_util::StreamExt;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let mut posts = std::pin::pin!(client.stream_topic_posts(2_107_523));
+    ///     while let Some(post) = posts.next().await {
+    ///         let post = post?;
+    ///         println!("#{} by {}", post.number, post.created_by.name);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub fn stream_topic_posts(
+        &self,
+        id: impl Into<TopicId>,
+    ) -> impl Stream<Item = Result<ForumPost, MALError>> + Send + '_ {
+        let id: TopicId = id.into();
+        stream::unfold(Some(0), move |offset| async move {
+            let offset = offset?;
+            let page = self
+                .get_forum_topic_posts(id, MAX_TOPIC_POSTS_LIMIT, offset)
+                .await;
+            Some(match page {
+                Ok(page) => {
+                    let more = page.has_next_page();
+                    let posts = page.data.posts;
+                    let next = (more && !posts.is_empty())
+                        .then_some(offset + posts.len());
+                    (posts.into_iter().map(Ok).collect(), next)
+                }
+                Err(e) => (vec![Err(e)], None),
+            })
+        })
+        .flat_map(stream::iter)
+    }
+}
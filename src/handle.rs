@@ -0,0 +1,67 @@
+use tokio::sync::OnceCell;
+
+#[cfg(feature = "jikan")]
+use crate::{model::EpisodesList, MALClient};
+use crate::model::{AnimeDetails, AnimeId};
+use crate::{MALClientTrait, MALError};
+
+///A lazily loaded anime, see `MALClientTrait::anime`
+///
+///Every accessor sends its request the first time it's awaited and returns the stored result
+///after that. Failed requests aren't stored, so awaiting the accessor again retries. Requests
+///still go through the client's response cache, if one is enabled.
+///
+///# Example
+///
+///```no_run
+/// # use lib_mal::{MALClient, MALClientTrait, MALError};
+/// # async fn test() -> Result<(), MALError> {
+///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+///     let anime = client.anime(30230);
+///     println!("{}", anime.details().await?.title);
+///     # #[cfg(feature = "jikan")]
+///     println!("{} episodes", anime.episodes().await?.data.len());
+///     # Ok(())
+/// # }
+///```
+pub struct AnimeHandle<'a, C> {
+    client: &'a C,
+    id: AnimeId,
+    details: OnceCell<AnimeDetails>,
+    #[cfg(feature = "jikan")]
+    episodes: OnceCell<EpisodesList>,
+}
+
+impl<'a, C: MALClientTrait + Sync> AnimeHandle<'a, C> {
+    pub const fn new(client: &'a C, id: AnimeId) -> Self {
+        Self {
+            client,
+            id,
+            details: OnceCell::const_new(),
+            #[cfg(feature = "jikan")]
+            episodes: OnceCell::const_new(),
+        }
+    }
+
+    pub const fn id(&self) -> AnimeId {
+        self.id
+    }
+
+    ///Returns the details of the anime with the client's default fields, see
+    ///`ClientBuilder::default_anime_fields`
+    pub async fn details(&self) -> Result<&AnimeDetails, MALError> {
+        self.details
+            .get_or_try_init(|| self.client.get_anime_details(self.id, None))
+            .await
+    }
+}
+
+#[cfg(feature = "jikan")]
+impl AnimeHandle<'_, MALClient> {
+    ///Returns the first page of episodes from Jikan, without the precise scores
+    pub async fn episodes(&self) -> Result<&EpisodesList, MALError> {
+        self.episodes
+            .get_or_try_init(|| async { self.client.jikan().episodes(self.id, None, false).await })
+            .await
+    }
+}
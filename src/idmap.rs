@@ -0,0 +1,155 @@
+//! Mapping MAL ids to the ids of the same anime on AniList, Kitsu, and AniDB, see
+//! [`MALClient::load_id_map`](crate::MALClient::load_id_map)
+//!
+//! The mappings come from the [anime-offline-database](https://github.com/manami-project/anime-offline-database),
+//! a community dump of every anime with its pages on each service. It's about 40 MB, so it's
+//! saved in a directory and only downloaded again once it's older than the given age.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::idmap::Service;
+//! use std::time::Duration;
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let map = client.load_id_map("cache", Duration::from_secs(7 * 24 * 60 * 60)).await?;
+//! let anime = client.get_anime_details_by_anilist_id(&map, 21, None).await?;
+//! println!("AniList 21 is {} on MAL", anime.show.id);
+//! println!("{:?} on Kitsu", map.external_id(anime.show.id, Service::Kitsu));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::model::ids::AnimeId;
+use crate::MALError;
+
+///Where `MALClient::load_id_map` downloads the database from
+pub const OFFLINE_DATABASE_URL: &str = "https://github.com/manami-project/anime-offline-database/releases/latest/download/anime-offline-database-minified.json";
+///The name the database is saved under in the directory passed to `MALClient::load_id_map`
+pub const ID_MAP_FILE: &str = "anime-offline-database.json";
+
+///An anime database other than MAL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Service {
+    AniList,
+    Kitsu,
+    AniDB,
+}
+
+impl Service {
+    ///Returns the service of an anime page URL and the id in it, like
+    ///`https://anilist.co/anime/21`
+    fn parse_source(url: &str) -> Option<(Self, u64)> {
+        let (host, id) = url.split_once("://")?.1.split_once("/anime/")?;
+        let service = match host {
+            "anilist.co" => Self::AniList,
+            "kitsu.app" | "kitsu.io" => Self::Kitsu,
+            "anidb.net" => Self::AniDB,
+            _ => return None,
+        };
+        Some((service, id.trim_end_matches('/').parse().ok()?))
+    }
+}
+
+///The ids of one anime on the other services, `None` where it isn't listed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ExternalIds {
+    pub anilist: Option<u64>,
+    pub kitsu: Option<u64>,
+    pub anidb: Option<u64>,
+}
+
+impl ExternalIds {
+    pub const fn get(&self, service: Service) -> Option<u64> {
+        match service {
+            Service::AniList => self.anilist,
+            Service::Kitsu => self.kitsu,
+            Service::AniDB => self.anidb,
+        }
+    }
+
+    fn set(&mut self, service: Service, id: u64) {
+        match service {
+            Service::AniList => self.anilist = Some(id),
+            Service::Kitsu => self.kitsu = Some(id),
+            Service::AniDB => self.anidb = Some(id),
+        }
+    }
+}
+
+///Maps MAL ids to the ids on other services and back, see the module docs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdMap {
+    external: HashMap<AnimeId, ExternalIds>,
+    mal: HashMap<(Service, u64), AnimeId>,
+}
+
+#[derive(Deserialize)]
+struct Database {
+    data: Vec<DatabaseEntry>,
+}
+
+#[derive(Deserialize)]
+struct DatabaseEntry {
+    #[serde(default)]
+    sources: Vec<String>,
+}
+
+impl IdMap {
+    ///Parses the JSON of the anime-offline-database. Anime that aren't on MAL are left out.
+    pub fn from_offline_database(json: &str) -> Result<Self, MALError> {
+        let database: Database = serde_json::from_str(json).map_err(|source| MALError::Parse {
+            source,
+            body: String::new(),
+            context: None,
+        })?;
+        let mut map = Self::default();
+        for entry in database.data {
+            let mal = entry.sources.iter().find_map(|url| {
+                let id = url.split_once("myanimelist.net/anime/")?.1;
+                id.trim_end_matches('/').parse().ok().map(AnimeId)
+            });
+            let Some(mal) = mal else { continue };
+            for (service, id) in entry.sources.iter().filter_map(|url| Service::parse_source(url)) {
+                map.insert(mal, service, id);
+            }
+        }
+        Ok(map)
+    }
+
+    ///Adds a mapping, replacing the id `mal` had on `service`
+    pub fn insert(&mut self, mal: impl Into<AnimeId>, service: Service, id: u64) {
+        let mal: AnimeId = mal.into();
+        self.external.entry(mal).or_default().set(service, id);
+        self.mal.insert((service, id), mal);
+    }
+
+    ///Returns the MAL id of the anime with `id` on `service`
+    pub fn mal_id(&self, service: Service, id: u64) -> Option<AnimeId> {
+        self.mal.get(&(service, id)).copied()
+    }
+
+    ///Returns the ids the anime with the MAL id `mal` has on the other services
+    pub fn external_ids(&self, mal: impl Into<AnimeId>) -> Option<ExternalIds> {
+        self.external.get(&mal.into()).copied()
+    }
+
+    ///Returns the id the anime with the MAL id `mal` has on `service`
+    pub fn external_id(&self, mal: impl Into<AnimeId>, service: Service) -> Option<u64> {
+        self.external_ids(mal)?.get(service)
+    }
+
+    ///Returns the number of MAL anime with at least one mapping
+    pub fn len(&self) -> usize {
+        self.external.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.external.is_empty()
+    }
+}
@@ -0,0 +1,349 @@
+//! Jikan (<https://jikan.moe>), an unofficial API with data the MAL API doesn't have, see
+//! [`MALClient::jikan`](crate::MALClient::jikan)
+
+use reqwest::Method;
+
+#[cfg(feature = "scraping")]
+use crate::limits::EPISODES_PAGE_SIZE;
+use crate::model::ids::AnimeId;
+use crate::{
+    limits::{JIKAN_REQUESTS_PER_MINUTE, JIKAN_REQUESTS_PER_SECOND, MAX_JIKAN_LIMIT},
+    model::{
+        options::{GenreSort, ReviewSort},
+        BrowsePage, Character, EpisodesList, Genre, GenreCatalog, JikanData,
+        JikanRelations, JikanThemes, NewsItem, ReviewsPage, SourceMaterial, StaffMember,
+        StreamingLink, ThemeSong,
+    },
+    rate_limit::RateLimiter,
+    MALClient, MALError,
+};
+
+pub(crate) const JIKAN_API_URL: &str = "https://api.jikan.moe/v4";
+
+///Returns the rate limiter every request to Jikan waits for, on top of the client's own
+pub(crate) fn rate_limiter() -> RateLimiter {
+    // one request per second stays within both the per second and the per minute limit
+    RateLimiter::new(JIKAN_REQUESTS_PER_SECOND.min(JIKAN_REQUESTS_PER_MINUTE / 60))
+}
+
+///Sends requests to Jikan through a `MALClient`, so they share its rate limit, response cache,
+///and middleware. Requests are also spaced out to stay within Jikan's own rate limit. The access
+///token isn't sent to Jikan.
+pub struct JikanClient<'a> {
+    client: &'a MALClient,
+}
+
+impl<'a> JikanClient<'a> {
+    pub(crate) const fn new(client: &'a MALClient) -> Self {
+        Self { client }
+    }
+
+    /// Returns one page of `limits::EPISODES_PAGE_SIZE` episodes, the first one if `page` is
+    /// `None`. Pages start at 1, see `all_episodes` for fetching every page.
+    ///
+    /// `precise_score` scrapes the unrounded scores from myanimelist.net, which fails with
+    /// `MALError::FeatureDisabled` if scraping is turned off, see `ClientBuilder::allow_scraping`
+    pub async fn episodes(
+        &self,
+        id: impl Into<AnimeId>,
+        page: impl Into<Option<usize>>,
+        precise_score: bool,
+    ) -> Result<EpisodesList, MALError> {
+        let id: AnimeId = id.into();
+        if precise_score && !self.client.scraping_allowed() {
+            return Err(MALError::FeatureDisabled {
+                feature: "scraping",
+            });
+        }
+        let page = page.into().unwrap_or(1).max(1);
+        let url = format!("{JIKAN_API_URL}/anime/{id}/episodes?page={page}");
+        let (status, res) = self
+            .client
+            .fetch("jikan_episodes", Method::GET, &url, None)
+            .await?;
+        let api: EpisodesList = serde_json::from_str(&res).map_err(|source| {
+            MALError::Parse {
+                source,
+                body: res.clone(),
+                context: None,
+            }
+            .with_context("jikan_episodes", &url, Some(status))
+        })?;
+        #[cfg(feature = "scraping")]
+        if precise_score {
+            return self.add_precise_scores(id, page, api).await;
+        }
+        Ok(api)
+    }
+
+    ///Fetches every page of episodes and returns them as one list, e.g. for long running shows
+    ///with more than `limits::EPISODES_PAGE_SIZE` episodes. Stops at the first error.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let episodes = client.jikan().all_episodes(21, false).await?;
+    ///     println!("{} episodes", episodes.data.len());
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn all_episodes(
+        &self,
+        id: impl Into<AnimeId>,
+        precise_score: bool,
+    ) -> Result<EpisodesList, MALError> {
+        let id: AnimeId = id.into();
+        let mut all = self.episodes(id, 1, precise_score).await?;
+        let mut page = 1;
+        while all.has_next_page() {
+            page += 1;
+            let next = self.episodes(id, page, precise_score).await?;
+            all.data.extend(next.data);
+            all.pagination = next.pagination;
+        }
+        Ok(all)
+    }
+
+    ///Gets the characters of the anime with their voice actors, ordered by role
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     for role in client.jikan().characters(30230).await? {
+    ///         let voice = role.voice_actors.iter().find(|va| va.language == "Japanese");
+    ///         println!("{} ({}): {:?}", role.character.name, role.role, voice.map(|va| &va.person.name));
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn characters(&self, id: impl Into<AnimeId>) -> Result<Vec<Character>, MALError> {
+        let id: AnimeId = id.into();
+        let url = format!("{JIKAN_API_URL}/anime/{id}/characters");
+        let res: JikanData<_> = self
+            .client
+            .do_request_cached("jikan_characters", url)
+            .await?;
+        Ok(res.data)
+    }
+
+    ///Gets the people who worked on the anime and their positions
+    pub async fn staff(&self, id: impl Into<AnimeId>) -> Result<Vec<StaffMember>, MALError> {
+        let id: AnimeId = id.into();
+        let url = format!("{JIKAN_API_URL}/anime/{id}/staff");
+        let res: JikanData<_> = self.client.do_request_cached("jikan_staff", url).await?;
+        Ok(res.data)
+    }
+
+    ///Gets the services the anime can be legally streamed on
+    pub async fn streaming(&self, id: impl Into<AnimeId>) -> Result<Vec<StreamingLink>, MALError> {
+        let id: AnimeId = id.into();
+        let url = format!("{JIKAN_API_URL}/anime/{id}/streaming");
+        let res: JikanData<_> = self
+            .client
+            .do_request_cached("jikan_streaming", url)
+            .await?;
+        Ok(res.data)
+    }
+
+    ///Returns one page of news about the anime, newest first, the first one if `page` is `None`.
+    ///Pages start at 1, a page past the last one is empty.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     for news in client.jikan().news(30230, None).await? {
+    ///         println!("{} ({}): {}", news.title, news.date, news.url);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn news(
+        &self,
+        id: impl Into<AnimeId>,
+        page: impl Into<Option<usize>>,
+    ) -> Result<Vec<NewsItem>, MALError> {
+        let id: AnimeId = id.into();
+        let page = page.into().unwrap_or(1).max(1);
+        let url = format!("{JIKAN_API_URL}/anime/{id}/news?page={page}");
+        let res: JikanData<_> = self.client.do_request_cached("jikan_news", url).await?;
+        Ok(res.data)
+    }
+
+    ///Returns one page of reviews of the anime, the first one if `page` is `None`, with pages
+    ///starting at 1. The reviews of the page are sorted by `sort`, newest first when `None`.
+    ///Preliminary reviews and reviews with spoilers are included, see `Review::is_preliminary`
+    ///and `Review::is_spoiler`.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::model::options::ReviewSort;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let reviews = client.jikan().reviews(30230, None, ReviewSort::MostReactions).await?;
+    ///     for review in reviews.data.iter().filter(|r| !r.is_spoiler) {
+    ///         println!("{}/10 by {}: {}", review.score, review.author.username, review.text);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn reviews(
+        &self,
+        id: impl Into<AnimeId>,
+        page: impl Into<Option<usize>>,
+        sort: impl Into<Option<ReviewSort>>,
+    ) -> Result<ReviewsPage, MALError> {
+        let id: AnimeId = id.into();
+        let page = page.into().unwrap_or(1).max(1);
+        let url = format!("{JIKAN_API_URL}/anime/{id}/reviews?page={page}&preliminary=true&spoilers=true");
+        let mut reviews: ReviewsPage = self.client.do_request_cached("jikan_reviews", url).await?;
+        sort.into().unwrap_or_default().sort(&mut reviews.data);
+        Ok(reviews)
+    }
+
+    ///Returns the openings and then the endings of the anime. Songs MAL lists in a format that
+    ///can't be parsed are left out.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::model::ThemeKind;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     for song in client.jikan().themes(30230).await? {
+    ///         if song.kind == ThemeKind::Opening {
+    ///             println!("OP{}: {} by {:?}", song.number.unwrap_or(1), song.title, song.artist);
+    ///         }
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn themes(&self, id: impl Into<AnimeId>) -> Result<Vec<ThemeSong>, MALError> {
+        let id: AnimeId = id.into();
+        let url = format!("{JIKAN_API_URL}/anime/{id}/themes");
+        let res: JikanData<JikanThemes> = self.client.do_request_cached("jikan_themes", url).await?;
+        Ok(res.data.into_songs())
+    }
+
+    ///Returns every anime genre, theme, and demographic with the id `browse_by_genre` takes.
+    ///Takes four requests, one for each group.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let catalog = client.jikan().genres().await?;
+    ///     if let Some(sports) = catalog.find("sports") {
+    ///         println!("{} has id {}", sports.name, sports.mal_id);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn genres(&self) -> Result<GenreCatalog, MALError> {
+        let group = |filter: &str| {
+            let url = format!("{JIKAN_API_URL}/genres/anime?filter={filter}");
+            async move {
+                let res: JikanData<Vec<Genre>> = self.client.do_request_cached("jikan_genres", url).await?;
+                Ok::<_, MALError>(res.data)
+            }
+        };
+        Ok(GenreCatalog {
+            genres: group("genres").await?,
+            explicit_genres: group("explicit_genres").await?,
+            themes: group("themes").await?,
+            demographics: group("demographics").await?,
+        })
+    }
+
+    ///Returns one page of the anime with the genre, theme, or demographic `genre_id`, highest
+    ///score first when `sort` is `None`, the first page if `page` is `None`. Pages start at 1
+    ///and have up to `MAX_JIKAN_LIMIT` anime. See `genres` for the ids.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::model::options::GenreSort;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let jikan = client.jikan();
+    ///     let sports = jikan.genres().await?.find("Sports").map(|g| g.mal_id).unwrap_or(30);
+    ///     let page = jikan.browse_by_genre(sports, GenreSort::Popularity, None).await?;
+    ///     for anime in &page.data {
+    ///         println!("{} ({:?})", anime.title, anime.score);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn browse_by_genre(
+        &self,
+        genre_id: usize,
+        sort: impl Into<Option<GenreSort>>,
+        page: impl Into<Option<usize>>,
+    ) -> Result<BrowsePage, MALError> {
+        let page = page.into().unwrap_or(1).max(1);
+        let (order_by, sort) = sort.into().unwrap_or_default().params();
+        let url = format!(
+            "{JIKAN_API_URL}/anime?genres={genre_id}&order_by={order_by}&sort={sort}&page={page}&limit={MAX_JIKAN_LIMIT}"
+        );
+        self.client.do_request_cached("jikan_browse", url).await
+    }
+
+    ///Gets the manga or novel entries the anime was adapted from. Returns an empty list for
+    ///original anime and for adaptations of games and other unreadable sources.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     for manga in client.jikan().source_material(30230).await? {
+    ///         println!("Read the manga: {} ({})", manga.title, manga.url);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn source_material(&self, id: impl Into<AnimeId>) -> Result<Vec<SourceMaterial>, MALError> {
+        let id: AnimeId = id.into();
+        let url = format!("{JIKAN_API_URL}/anime/{id}/relations");
+        let relations: JikanRelations = self
+            .client
+            .do_request_cached("jikan_source_material", url)
+            .await?;
+        Ok(relations.into_source_material())
+    }
+
+    ///Replaces the scores of `api`, the episodes on `page`, with the unrounded ones scraped from
+    ///myanimelist.net
+    #[cfg(feature = "scraping")]
+    async fn add_precise_scores(
+        &self,
+        id: AnimeId,
+        page: usize,
+        mut api: EpisodesList,
+    ) -> Result<EpisodesList, MALError> {
+        let offset = page.saturating_sub(1) * EPISODES_PAGE_SIZE;
+        let scores = self.client.episode_scores_page(id, offset).await?;
+        api.data.iter_mut().for_each(|episode| {
+            episode.score = scores
+                .iter()
+                .find(|s| Some(s.mal_id) == episode.mal_id)
+                .and_then(|s| s.score);
+        });
+        Ok(api)
+    }
+}
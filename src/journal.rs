@@ -0,0 +1,90 @@
+//! A log of every change the client sent to the user's anime list, see
+//! [`ClientBuilder::journal`](crate::ClientBuilder::journal)
+//!
+//! The journal is written as JSON lines to `journal.jsonl` in the cache directory and is only
+//! ever appended to, which makes it useful for finding out how a list ended up in a state the
+//! user didn't expect.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALError};
+//! # fn test(client: &MALClient) -> Result<(), MALError> {
+//! for entry in client.read_journal()? {
+//!     println!("{} {} -> {:?}", entry.method, entry.url, entry.status);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+
+use serde::{Deserialize, Serialize};
+
+use crate::MALError;
+
+///Name of the journal file in the cache directory
+pub const JOURNAL_FILE: &str = "journal.jsonl";
+
+///A change sent to MAL and what MAL answered
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    ///Unix timestamp of when the request finished
+    pub at: u64,
+    ///Name of the API function that sent the request
+    pub endpoint: String,
+    pub method: String,
+    pub url: String,
+    ///Form parameters sent with the request
+    pub params: Vec<(String, String)>,
+    ///`None` if no response was received
+    pub status: Option<u16>,
+    ///Body of the response if the request succeeded
+    pub response: Option<String>,
+    ///Why the request failed
+    pub error: Option<String>,
+}
+
+///Reads every entry of the journal at `path`. A missing journal is empty, and lines that can't
+///be parsed, like one cut off by a crash, are skipped.
+pub fn read(path: impl AsRef<Path>) -> Result<Vec<JournalEntry>, MALError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+///Appends entries to the journal, one line at a time so concurrent writes don't interleave
+#[derive(Debug, Default)]
+pub(crate) struct Journal {
+    lock: Mutex<()>,
+}
+
+impl Journal {
+    pub(crate) fn append(&self, dir: &Path, entry: &JournalEntry) -> Result<(), MALError> {
+        let mut line = serde_json::to_string(entry).map_err(|source| MALError::Parse {
+            source,
+            body: String::new(),
+            context: None,
+        })?;
+        line.push('\n');
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        if !dir.as_os_str().is_empty() {
+            fs::create_dir_all(dir)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(JOURNAL_FILE))?
+            .write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
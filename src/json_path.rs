@@ -0,0 +1,129 @@
+//! Locating where in a response body parsing failed, see `MALError::json_path`
+//!
+//! `serde_path_to_error` would track the path while deserializing, but every response would have
+//! to go through its wrapper to get a path for the few that fail. Instead the body is scanned up
+//! to the line and column serde_json reports, only once parsing failed. The scan doesn't
+//! validate the JSON, on a malformed body it returns the path of the containers that are open
+//! at the error, which is what the parser saw too.
+
+///Characters of the body shown on each side of the error by `MALError::snippet`
+const SNIPPET_RADIUS: usize = 40;
+
+enum Frame {
+    Object { key: Option<String>, in_value: bool },
+    Array { index: usize },
+}
+
+///Returns the byte offset of the 1-based `line` and `column` serde_json reports, clamped to
+///the body
+pub(crate) fn offset_of(body: &str, line: usize, column: usize) -> usize {
+    let line_start = body
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum::<usize>();
+    let mut offset = (line_start + column).min(body.len());
+    while !body.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+///Returns the path to the value being parsed when the parser stopped at `offset`, like
+///`data[3].node.status`. Empty at the top level.
+pub(crate) fn path_at(body: &str, offset: usize) -> String {
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut chars = body[..offset].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => stack.push(Frame::Object {
+                key: None,
+                in_value: false,
+            }),
+            '[' => stack.push(Frame::Array { index: 0 }),
+            '}' | ']' => {
+                stack.pop();
+            }
+            ':' => {
+                if let Some(Frame::Object { in_value, .. }) = stack.last_mut() {
+                    *in_value = true;
+                }
+            }
+            ',' => match stack.last_mut() {
+                Some(Frame::Object { key, in_value }) => {
+                    *key = None;
+                    *in_value = false;
+                }
+                Some(Frame::Array { index }) => *index += 1,
+                None => {}
+            },
+            '"' => {
+                let mut raw = String::new();
+                let mut closed = false;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => {
+                            closed = true;
+                            break;
+                        }
+                        '\\' => {
+                            raw.push(c);
+                            raw.extend(chars.next());
+                        }
+                        c => raw.push(c),
+                    }
+                }
+                // a key that's cut off by the error isn't part of the path yet
+                if let (true, Some(Frame::Object { key, in_value: false })) = (closed, stack.last_mut()) {
+                    *key = Some(unescape(raw));
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut path = String::new();
+    for frame in &stack {
+        match frame {
+            Frame::Object { key: Some(key), .. } => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+            }
+            Frame::Object { key: None, .. } => {}
+            Frame::Array { index } => path.push_str(&format!("[{index}]")),
+        }
+    }
+    path
+}
+
+///Decodes the escapes in the text of a JSON string, like `\"` and `\u00e9`. Invalid escapes are
+///kept as they are.
+fn unescape(raw: String) -> String {
+    if !raw.contains('\\') {
+        return raw;
+    }
+    serde_json::from_str(&format!("\"{raw}\"")).unwrap_or(raw)
+}
+
+///Returns the part of the line around `offset`, with `…` where it was cut
+pub(crate) fn snippet_at(body: &str, offset: usize) -> String {
+    let line_start = body[..offset].rfind('\n').map_or(0, |i| i + 1);
+    // indentation of pretty-printed bodies isn't worth showing
+    let line_start = offset - body[line_start..offset].trim_start().len();
+    let line_end = body[offset..].find('\n').map_or(body.len(), |i| offset + i);
+    let before: Vec<char> = body[line_start..offset].chars().collect();
+    let after: Vec<char> = body[offset..line_end].chars().collect();
+    let start = before.len().saturating_sub(SNIPPET_RADIUS);
+    let end = after.len().min(SNIPPET_RADIUS);
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    snippet.extend(&before[start..]);
+    snippet.extend(&after[..end]);
+    if end < after.len() {
+        snippet.push('…');
+    }
+    snippet.trim_end().to_owned()
+}
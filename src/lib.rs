@@ -2,7 +2,7 @@
 //! To use `lib-mal` you will need an API key from [MyAnimeList.net](https://myanimelist.net), and a callback URL. An example of how to use `lib-mal` might look like this:
 //!
 //! ```no_run
-//! use lib_mal::ClientBuilder;
+//! use lib_mal::{ClientBuilder, MALClient, MALClientTrait};
 //! use std::path::PathBuf;
 //! use lib_mal::MALError;
 //!
@@ -10,12 +10,12 @@
 //!     //this has to exactly match a URI that's been registered with the MAL api
 //!     let redirect = "[YOUR_REDIRECT_URI_HERE]";
 //!     //the MALClient will attempt to refresh the cached access_token, if applicable
-//!     let mut client = ClientBuilder::new().secret("[YOUR_SECRET_HERE]".to_string()).caching(true).cache_dir(PathBuf::from("[SOME_CACHE_DIR]")).build_with_refresh().await?;
-//!     let (auth_url, challenge, state) = client.get_auth_parts();
+//!     let mut client: MALClient = ClientBuilder::new().secret("[YOUR_SECRET_HERE]".to_string()).caching(true).cache_dir(PathBuf::from("[SOME_CACHE_DIR]")).build_with_refresh().await?;
+//!     let parts = client.get_auth_parts_with(Some(redirect), &[]);
 //!     //the user will have to have access to a browser in order to log in and give your application permission
-//!     println!("Go here to log in :) -> {}", auth_url);
+//!     println!("Go here to log in :) -> {}", parts.url);
 //!     //once the user has the URL, be sure to call client.auth to listen for the callback and complete the OAuth2 handshake
-//!     client.auth(&redirect, &challenge, &state).await?;
+//!     client.auth(&redirect, &parts.code_verifier, &parts.state).await?;
 //!     //once the user is authorized, the API should be usable
 //!     //this will get the details, including all fields, for Mobile Suit Gundam
 //!     let anime = client.get_anime_details(80, None).await?;
@@ -27,54 +27,430 @@
 
 #[cfg(test)]
 mod test;
+#[cfg(test)]
+mod test_server;
 
+pub mod affinity;
+pub mod archive;
+pub mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 mod builder;
+mod cache;
+pub mod cancel;
+pub mod clear;
 mod client;
+mod clock;
+pub mod debug;
+pub mod diagnostics;
+mod dyn_client;
+pub mod endpoint;
+#[cfg(feature = "oauth")]
+mod file_store;
+pub mod forum;
+mod handle;
+pub mod idmap;
+#[cfg(feature = "jikan")]
+pub mod jikan;
+pub mod journal;
+mod json_path;
+pub mod library;
+pub mod limits;
+pub mod list_cache;
+mod middleware;
 #[allow(unused_variables)]
 mod mock;
 pub mod model;
+pub mod offline;
+pub mod pictures;
+pub mod prioritize;
+pub mod ranking;
+mod rate_limit;
+mod rt;
+pub mod replay;
+#[cfg(feature = "scraping")]
+pub mod scrape;
+pub mod schedule;
+pub mod scrobble;
+mod session;
+pub mod stats;
+mod store;
+pub mod sync;
+pub mod titles;
+mod trace;
+mod transport;
+pub mod watch;
+pub mod watcher;
+pub mod wire;
 
-pub use builder::ClientBuilder;
-pub use client::{MALClient, MALClientTrait};
-pub use mock::MockMALClient;
+pub use builder::{ClientBuilder, HttpVersion};
+pub use cache::{CacheCodec, CacheConfig, CacheStats};
+#[cfg(feature = "open-browser")]
+pub use client::AuthProgress;
+pub use handle::AnimeHandle;
+#[cfg(feature = "jikan")]
+pub use jikan::JikanClient;
+#[cfg(feature = "oauth")]
+pub use client::AuthParts;
+pub use client::{AuthState, CallbackPage, MALClient, MALClientTrait, ReauthReason, Tokens};
+pub use clock::{Clock, MockClock, SystemClock};
+pub use dyn_client::DynMALClient;
+pub use middleware::{MetricsSink, RequestEvent, RequestInterceptor, RequestMetrics};
+pub use session::ListSession;
+pub use mock::{MockCall, MockMALClient, MockResponse};
+#[cfg(feature = "jikan")]
+pub use mock::MockJikanTransport;
+#[cfg(feature = "oauth")]
+pub use file_store::{FileTokenStore, TokenCache};
+pub use store::{MemoryTokenStore, TokenStore};
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use store::LocalStorageTokenStore;
+pub use transport::{HttpRequest, HttpResponse, HttpTransport, ReqwestTransport};
+#[cfg(target_arch = "wasm32")]
+pub use rt::Instant;
+use model::ListStatus;
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
-use std::fmt::{Debug, Display};
+use std::fmt::Display;
+use std::time::Duration;
+
+///The errors returned by `lib-mal`
+///
+///Errors caused by a request to the API carry the [`RequestContext`] of that request, see
+///[`MALError::context`].
+///
+///# Example
+///
+///```no_run
+/// # use lib_mal::{MALClient, MALClientTrait, MALError};
+/// # async fn test() -> Result<(), MALError> {
+///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+///     match client.get_anime_details(80, None).await {
+///         Ok(anime) => println!("{}", anime.show.title),
+///         Err(MALError::Gone { .. }) => println!("No such anime"),
+///         Err(MALError::AgeRestricted { .. }) => println!("Hidden by the NSFW filter"),
+///         Err(MALError::RateLimited { retry_after, .. }) => println!("Slow down for {retry_after:?}"),
+///         Err(e) => return Err(e),
+///     }
+///     # Ok(())
+/// # }
+///```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum MALError {
+    ///The API responded with an unexpected HTTP status, `api_error` is the error MAL sent in the
+    ///body, if any
+    Http {
+        status: StatusCode,
+        body: String,
+        api_error: Option<ApiError>,
+        context: Option<Box<RequestContext>>,
+    },
+    ///The API rejected the access token and it couldn't be refreshed
+    Unauthorized { context: Option<Box<RequestContext>> },
+    ///Too many requests were sent, `retry_after` is how long the API asked to wait, if it did
+    RateLimited {
+        retry_after: Option<Duration>,
+        context: Option<Box<RequestContext>>,
+    },
+    ///The server responded with a page instead of JSON, like a gateway timeout or maintenance
+    ///page. `excerpt` is the start of the page's text.
+    ServerError {
+        status: StatusCode,
+        excerpt: String,
+        context: Option<Box<RequestContext>>,
+    },
+    ///The requested resource doesn't exist
+    NotFound { context: Option<Box<RequestContext>> },
+    ///The requested anime was deleted or never existed
+    Gone { context: Option<Box<RequestContext>> },
+    ///The requested anime is hidden because it's NSFW
+    AgeRestricted { context: Option<Box<RequestContext>> },
+    ///The response couldn't be parsed
+    Parse {
+        source: serde_json::Error,
+        body: String,
+        context: Option<Box<RequestContext>>,
+    },
+    ///The request couldn't be sent or the response couldn't be read
+    Network {
+        source: reqwest::Error,
+        context: Option<Box<RequestContext>>,
+    },
+    ///A custom `HttpTransport` couldn't send the request or read the response
+    Transport {
+        source: Box<dyn Error + Send + Sync>,
+        context: Option<Box<RequestContext>>,
+    },
+    ///The token cache couldn't be read or written
+    Io(std::io::Error),
+    ///The token cache is corrupted, encrypted with a different key, or from a newer version
+    TokenCache(String),
+    ///A list export isn't valid XML in MAL's format, see `model::export`
+    Export(String),
+    ///Authorizing the user or refreshing the tokens failed
+    Auth {
+        error: String,
+        message: Option<String>,
+    },
+    ///The auth callback didn't arrive in time, see `MALClientTrait::auth_with_timeout`
+    AuthTimeout,
+    ///The request needs a feature that was disabled, either at compile time or with a setting
+    ///like `ClientBuilder::allow_scraping`
+    FeatureDisabled { feature: &'static str },
+    ///A destructive helper was called without the right confirmation, see `clear::ListClearOptions`
+    NotConfirmed,
+    ///MAL couldn't be reached, so the change was put in the offline queue, see the `offline`
+    ///module
+    Queued,
+    ///The list entry changed since the version an edit was based on, see
+    ///`MALClient::update_if_unchanged`. `current` is the entry on MAL, `None` if the anime isn't
+    ///on the list.
+    Conflict { current: Option<Box<ListStatus>> },
+    ///An environment variable `ClientBuilder::from_env` needs isn't set or isn't valid unicode
+    MissingEnv { var: &'static str },
+    ///A value is outside what MAL accepts, like a score above 10
+    Invalid { field: &'static str, reason: String },
+    ///The operation was stopped with a `CancellationToken`, see the `cancel` module
+    Cancelled,
+}
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct MALError {
+///The error payload MAL sends with failed requests
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ApiError {
+    ///Short error code like `invalid_parameters`
     pub error: String,
     pub message: Option<String>,
-    pub info: Option<String>,
+}
+
+///Metadata of the request that caused a `MALError`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RequestContext {
+    ///Name of the API function that failed
+    pub endpoint: String,
+    ///URL of the failed request, without the query string
+    pub url: String,
+    ///HTTP status of the response, if one was received
+    pub status: Option<u16>,
 }
 
 impl Display for MALError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "lib_mal encountered an error: {}", self.error)
+        write!(f, "lib_mal encountered an error: ")?;
+        match self {
+            Self::Http {
+                status, api_error, ..
+            } => {
+                write!(f, "unexpected response {status}")?;
+                if let Some(api_error) = api_error {
+                    write!(f, ": {}", api_error.error)?;
+                    if let Some(message) = &api_error.message {
+                        write!(f, " ({message})")?;
+                    }
+                }
+            }
+            Self::ServerError { status, excerpt, .. } => {
+                write!(f, "server error {status}")?;
+                if !excerpt.is_empty() {
+                    write!(f, ": {excerpt}")?;
+                }
+            }
+            Self::Unauthorized { .. } => write!(f, "unauthorized")?,
+            Self::RateLimited { .. } => write!(f, "rate limited")?,
+            Self::NotFound { .. } => write!(f, "not found")?,
+            Self::Gone { .. } => write!(f, "anime was deleted or doesn't exist")?,
+            Self::AgeRestricted { .. } => write!(f, "anime is age restricted")?,
+            Self::Parse { source, .. } => {
+                write!(f, "unable to parse response")?;
+                if let Some(path) = self.json_path() {
+                    write!(f, " at `{path}`")?;
+                }
+                write!(f, ": {source}")?;
+            }
+            Self::Network { source, .. } => write!(f, "network error: {source}")?,
+            Self::Transport { source, .. } => write!(f, "transport error: {source}")?,
+            Self::Io(e) => write!(f, "unable to access token cache: {e}")?,
+            Self::TokenCache(e) => write!(f, "unable to decrypt token cache: {e}")?,
+            Self::Export(e) => write!(f, "invalid list export: {e}")?,
+            Self::Auth { error, message } => {
+                write!(f, "auth failed: {error}")?;
+                if let Some(message) = message {
+                    write!(f, " ({message})")?;
+                }
+            }
+            Self::AuthTimeout => write!(f, "timed out waiting for the auth callback")?,
+            Self::FeatureDisabled { feature } => write!(f, "the {feature} feature is disabled")?,
+            Self::NotConfirmed => write!(f, "the destructive action wasn't confirmed")?,
+            Self::Queued => write!(f, "MAL couldn't be reached, the change was queued")?,
+            Self::Conflict { .. } => write!(f, "the list entry was changed in the meantime")?,
+            Self::MissingEnv { var } => write!(f, "the environment variable {var} isn't set")?,
+            Self::Invalid { field, reason } => write!(f, "invalid {field}: {reason}")?,
+            Self::Cancelled => write!(f, "the operation was cancelled")?,
+        }
+        if let Some(context) = self.context() {
+            write!(f, " in {}", context.endpoint)?;
+            if let Some(status) = context.status {
+                write!(f, " (HTTP {status})")?;
+            }
+            write!(f, " at {}", context.url)?;
+        }
+        Ok(())
     }
 }
 
-impl Debug for MALError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "error: {} message: {} info: {}",
-            self.error,
-            self.message.as_ref().unwrap_or(&"none".to_string()),
-            self.info.as_ref().unwrap_or(&"none".to_string())
-        )
+impl Error for MALError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Parse { source, .. } => Some(source),
+            Self::Network { source, .. } => Some(source),
+            Self::Transport { source, .. } => Some(source.as_ref()),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
     }
 }
 
-impl Error for MALError {}
+impl From<std::io::Error> for MALError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
 
 impl MALError {
-    pub fn new(msg: &str, error: &str, info: impl Into<Option<String>>) -> Self {
-        Self {
+    ///Builds the error for a response with a non-success `status` and its `body`
+    pub fn from_status(status: StatusCode, body: String, retry_after: Option<Duration>) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => Self::Unauthorized { context: None },
+            StatusCode::NOT_FOUND => Self::NotFound { context: None },
+            StatusCode::GONE => Self::Gone { context: None },
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited {
+                retry_after,
+                context: None,
+            },
+            _ => Self::Http {
+                status,
+                api_error: serde_json::from_str(&body).ok(),
+                body,
+                context: None,
+            },
+        }
+    }
+
+    ///Wraps an error of a custom `HttpTransport`
+    pub fn transport(source: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        Self::Transport {
+            source: source.into(),
+            context: None,
+        }
+    }
+
+    pub fn auth(error: &str, message: impl Into<Option<String>>) -> Self {
+        Self::Auth {
             error: error.to_owned(),
-            message: Some(msg.to_owned()),
-            info: info.into(),
+            message: message.into(),
+        }
+    }
+
+    ///Returns true if MAL rejected the refresh token, e.g. because it expired or the user revoked
+    ///the app's access. The user has to authorize the client again, see
+    ///`MALClientTrait::reauthorize`.
+    pub fn is_refresh_rejected(&self) -> bool {
+        matches!(self, Self::Auth { error, .. } if error == client::REFRESH_REJECTED)
+    }
+
+    ///Returns true if the error means that the anime can't be fetched at all, i.e. it's `Gone` or
+    ///`AgeRestricted`. Batch helpers like `enrich_list` skip these anime instead of failing.
+    pub const fn is_unavailable(&self) -> bool {
+        matches!(self, Self::Gone { .. } | Self::AgeRestricted { .. })
+    }
+
+    ///Returns true if the request didn't get a response, e.g. because there's no connection
+    pub const fn is_offline(&self) -> bool {
+        matches!(self, Self::Network { .. } | Self::Transport { .. })
+    }
+
+    ///Returns the HTTP status of the response that caused the error, if any
+    pub fn status(&self) -> Option<StatusCode> {
+        self.context()
+            .and_then(|c| c.status)
+            .and_then(|s| StatusCode::from_u16(s).ok())
+    }
+
+    ///Returns the path to the value of the response that couldn't be parsed, like
+    ///`data[3].node.status`, so a field MAL changed can be found without reading the whole body.
+    ///`None` for other errors and if the body itself wasn't valid JSON.
+    ///
+    ///Fields of flattened structs, like the basic `Anime` fields of `AnimeDetails`, are only
+    ///checked once their object was read, so the path ends at the object for them.
+    pub fn json_path(&self) -> Option<String> {
+        let Self::Parse { source, body, .. } = self else {
+            return None;
+        };
+        if !source.is_data() || body.is_empty() {
+            return None;
+        }
+        let path = json_path::path_at(body, json_path::offset_of(body, source.line(), source.column()));
+        (!path.is_empty()).then_some(path)
+    }
+
+    ///Returns the part of the response around where parsing failed, `None` for other errors
+    pub fn snippet(&self) -> Option<String> {
+        let Self::Parse { source, body, .. } = self else {
+            return None;
+        };
+        if body.is_empty() {
+            return None;
+        }
+        Some(json_path::snippet_at(body, json_path::offset_of(body, source.line(), source.column())))
+    }
+
+    ///Returns the request that caused the error, if any
+    pub fn context(&self) -> Option<&RequestContext> {
+        match self {
+            Self::Http { context, .. }
+            | Self::ServerError { context, .. }
+            | Self::Unauthorized { context }
+            | Self::RateLimited { context, .. }
+            | Self::NotFound { context }
+            | Self::Gone { context }
+            | Self::AgeRestricted { context }
+            | Self::Parse { context, .. }
+            | Self::Network { context, .. }
+            | Self::Transport { context, .. } => context.as_deref(),
+            _ => None,
+        }
+    }
+
+    ///Attaches the metadata of the request that caused the error. The query string is stripped
+    ///from `url` since it can contain user input. Errors that aren't caused by requests are
+    ///returned unchanged.
+    pub fn with_context(
+        mut self,
+        endpoint: &str,
+        url: &str,
+        status: impl Into<Option<StatusCode>>,
+    ) -> Self {
+        match &mut self {
+            Self::Http { context, .. }
+            | Self::ServerError { context, .. }
+            | Self::Unauthorized { context }
+            | Self::RateLimited { context, .. }
+            | Self::NotFound { context }
+            | Self::Gone { context }
+            | Self::AgeRestricted { context }
+            | Self::Parse { context, .. }
+            | Self::Network { context, .. }
+            | Self::Transport { context, .. } => {
+                *context = Some(Box::new(RequestContext {
+                    endpoint: endpoint.to_owned(),
+                    url: url.split(['?', '#']).next().unwrap_or_default().to_owned(),
+                    status: status.into().map(|s| s.as_u16()),
+                }));
+            }
+            _ => {}
         }
+        self
     }
 }
 
@@ -0,0 +1,93 @@
+//! Integration point for media libraries that keep anime episodes locally
+//!
+//! Implement [`LibraryProvider`] for your media server or file scanner and use
+//! [`unwatched_episodes`] to find out which of the downloaded episodes haven't been watched yet
+//! according to the user's MAL progress.
+//!
+//! # Example
+//!
+//! ```
+//! use lib_mal::library::{LibraryProvider, LocalEpisode};
+//! use lib_mal::model::AnimeId;
+//! use std::path::PathBuf;
+//!
+//! struct Downloads;
+//!
+//! impl LibraryProvider for Downloads {
+//!     fn episodes(&self, anime_id: AnimeId) -> Vec<LocalEpisode> {
+//!         match anime_id.0 {
+//!             21 => vec![LocalEpisode::new(1, PathBuf::from("one_piece_001.mkv"))],
+//!             _ => Vec::new(),
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::path::PathBuf;
+
+use crate::model::{Anime, AnimeId, AnimeList};
+
+///An episode file available in the local library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalEpisode {
+    ///The episode number, starting from 1
+    pub number: usize,
+    pub path: PathBuf,
+}
+
+impl LocalEpisode {
+    pub const fn new(number: usize, path: PathBuf) -> Self {
+        Self { number, path }
+    }
+}
+
+///Supplies the locally available episodes for an anime
+pub trait LibraryProvider {
+    ///Returns the episodes of the anime with `anime_id` that are available locally
+    fn episodes(&self, anime_id: AnimeId) -> Vec<LocalEpisode>;
+}
+
+///Downloaded episodes of an anime that haven't been watched yet
+#[derive(Debug, Clone)]
+pub struct UnwatchedEpisodes {
+    pub anime: Anime,
+    ///How many episodes the user has watched according to MAL
+    pub num_episodes_watched: usize,
+    ///Sorted by episode number
+    pub episodes: Vec<LocalEpisode>,
+}
+
+///Compares the episodes supplied by `provider` with the progress in `list` and returns the
+///downloaded episodes that haven't been watched yet.
+///
+///Entries without a `list_status` are treated as having no watched episodes, so the list should
+///be fetched with the `list_status` field. Anime without any unwatched local episodes are left out.
+pub fn unwatched_episodes(
+    provider: &impl LibraryProvider,
+    list: &AnimeList,
+) -> Vec<UnwatchedEpisodes> {
+    list.data
+        .iter()
+        .filter_map(|entry| {
+            let watched = entry
+                .list_status
+                .as_ref()
+                .and_then(|s| s.num_episodes_watched)
+                .unwrap_or_default();
+            let mut episodes: Vec<LocalEpisode> = provider
+                .episodes(entry.node.id)
+                .into_iter()
+                .filter(|e| e.number > watched)
+                .collect();
+            if episodes.is_empty() {
+                return None;
+            }
+            episodes.sort_by_key(|e| e.number);
+            Some(UnwatchedEpisodes {
+                anime: entry.node.clone(),
+                num_episodes_watched: watched,
+                episodes,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,129 @@
+//! Limits and defaults of the MyAnimeList and Jikan APIs
+//!
+//! Requesting more items than an endpoint's maximum makes MAL respond with an error or silently
+//! return fewer items, so use these instead of hardcoding the numbers. The client checks every
+//! [`Limit`] against the maximum of the endpoint it's sent to before sending the request.
+
+use crate::MALError;
+
+///Default number of items requested when an API function is passed `None` as the limit
+pub const DEFAULT_LIMIT: usize = 100;
+
+///Maximum limit for anime search, see `get_anime_list`
+pub const MAX_SEARCH_LIMIT: usize = 100;
+///Maximum limit for anime rankings, see `get_anime_ranking`
+pub const MAX_RANKING_LIMIT: usize = 500;
+///Maximum limit for seasonal anime, see `get_seasonal_anime`
+pub const MAX_SEASONAL_LIMIT: usize = 500;
+///Maximum limit for suggested anime, see `get_suggested_anime`
+pub const MAX_SUGGESTIONS_LIMIT: usize = 100;
+///Maximum limit for user anime lists, see `get_user_anime_list`
+pub const MAX_USER_LIST_LIMIT: usize = 1000;
+///Maximum limit for forum topics, see `get_forum_topics`
+pub const MAX_FORUM_TOPICS_LIMIT: usize = 100;
+///Maximum limit for the posts of a forum topic, see `get_forum_topic_detail`
+pub const MAX_TOPIC_POSTS_LIMIT: usize = 100;
+
+///Number of episodes per page returned by Jikan and the MAL episode pages
+pub const EPISODES_PAGE_SIZE: usize = 100;
+///Maximum number of anime per page Jikan returns when browsing, see `JikanClient::browse_by_genre`
+pub const MAX_JIKAN_LIMIT: usize = 25;
+
+///MAL doesn't document its rate limit, staying at or below this many requests per second avoids
+///getting throttled in practice
+pub const RECOMMENDED_REQUESTS_PER_SECOND: u32 = 1;
+///Jikan allows at most this many requests per second
+pub const JIKAN_REQUESTS_PER_SECOND: u32 = 3;
+///Jikan allows at most this many requests per minute
+pub const JIKAN_REQUESTS_PER_MINUTE: u32 = 60;
+
+///How many times requests rejected with 429 Too Many Requests or a server error are retried by
+///default, see `ClientBuilder::max_retries`
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+///How many items to request from an endpoint, either a number or `Limit::DEFAULT`. Plain
+///integers and `Option<usize>` convert into it, `None` is the default.
+///
+///The client checks the limit against the endpoint's maximum, like `MAX_SEARCH_LIMIT`, and fails
+///with `MALError::Invalid` instead of sending a request MAL would reject.
+///
+///# Example
+///
+///```
+/// use lib_mal::limits::{Limit, DEFAULT_LIMIT, MAX_RANKING_LIMIT, MAX_SEARCH_LIMIT};
+///
+/// assert_eq!(Limit::from(300).validate("get_anime_ranking", MAX_RANKING_LIMIT).unwrap(), 300);
+/// assert!(Limit::from(300).validate("get_anime_list", MAX_SEARCH_LIMIT).is_err());
+/// assert_eq!(Limit::from(None).validate("get_anime_list", MAX_SEARCH_LIMIT).unwrap(), DEFAULT_LIMIT);
+///```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Limit(Option<usize>);
+
+impl Limit {
+    ///Requests `DEFAULT_LIMIT` items, or the endpoint's maximum if it's lower
+    pub const DEFAULT: Self = Self(None);
+
+    pub const fn new(limit: usize) -> Self {
+        Self(Some(limit))
+    }
+
+    ///Returns the number of items, `None` for `Limit::DEFAULT`
+    pub const fn get(self) -> Option<usize> {
+        self.0
+    }
+
+    ///Returns the number of items to request from `endpoint`, failing if the limit is 0 or above
+    ///`max`
+    pub fn validate(self, endpoint: &str, max: usize) -> Result<usize, MALError> {
+        match self.0 {
+            None => Ok(DEFAULT_LIMIT.min(max)),
+            Some(0) => Err(MALError::Invalid {
+                field: "limit",
+                reason: format!("{endpoint} needs a limit of at least 1"),
+            }),
+            Some(limit) if limit > max => Err(MALError::Invalid {
+                field: "limit",
+                reason: format!("{limit} is above the maximum of {max} for {endpoint}"),
+            }),
+            Some(limit) => Ok(limit),
+        }
+    }
+}
+
+impl From<Option<usize>> for Limit {
+    fn from(limit: Option<usize>) -> Self {
+        Self(limit)
+    }
+}
+
+macro_rules! limit_from {
+    ($($int:ty),*) => {$(
+        impl From<$int> for Limit {
+            fn from(limit: $int) -> Self {
+                Self::new(limit.into())
+            }
+        }
+    )*};
+}
+
+limit_from!(u8, u16, usize);
+
+impl From<u32> for Limit {
+    fn from(limit: u32) -> Self {
+        Self::new(usize::try_from(limit).unwrap_or(usize::MAX))
+    }
+}
+
+impl From<u64> for Limit {
+    fn from(limit: u64) -> Self {
+        Self::new(usize::try_from(limit).unwrap_or(usize::MAX))
+    }
+}
+
+///Integer literals are `i32` unless they're given a type, negative limits are treated as 0 and
+///rejected like it
+impl From<i32> for Limit {
+    fn from(limit: i32) -> Self {
+        Self::new(usize::try_from(limit).unwrap_or_default())
+    }
+}
@@ -0,0 +1,141 @@
+//! A local copy of the user's whole anime list that's kept up to date with incremental refreshes
+//!
+//! Loading a long list takes a request for every 1000 entries. A [`ListCache`] is saved to a file
+//! instead, and [`ListCache::refresh_incremental`] only fetches the entries updated since the
+//! last refresh, which is usually a single request.
+//!
+//! MAL doesn't report entries that were removed from the list, so an incremental refresh keeps
+//! them. Use [`ListCache::refresh_full`] every now and then to drop them.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::list_cache::ListCache;
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let mut cache = ListCache::open("anime-list.json.gz")?;
+//! let changed = cache.refresh_incremental(&client).await?;
+//! println!("{} entries, {} changed", cache.entries.len(), changed.len());
+//! cache.save("anime-list.json.gz")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use crate::clock::unix_secs;
+use crate::model::options::parse_timestamp;
+use crate::model::{AnimeId, ListNode};
+use crate::sync::ListSnapshot;
+use crate::{MALClient, MALError};
+
+///The user's anime list by anime id, stored as gzip compressed JSON
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ListCache {
+    ///Unix timestamp of the last refresh, 0 if the cache was never refreshed
+    pub fetched_at: u64,
+    ///Unix timestamp of the latest update of an entry, where the next incremental refresh starts
+    pub updated_until: Option<u64>,
+    pub entries: BTreeMap<AnimeId, ListNode>,
+}
+
+impl ListCache {
+    ///Reads a cache written by [`ListCache::save`]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MALError> {
+        let reader = GzDecoder::new(BufReader::new(File::open(path)?));
+        serde_json::from_reader(reader).map_err(|source| MALError::Parse {
+            source,
+            body: String::new(),
+            context: None,
+        })
+    }
+
+    ///Same as `load`, but returns an empty cache if the file doesn't exist yet
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, MALError> {
+        match Self::load(path) {
+            Err(MALError::Io(e)) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            res => res,
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MALError> {
+        let mut writer =
+            GzEncoder::new(BufWriter::new(File::create(path)?), Compression::default());
+        serde_json::to_writer(&mut writer, self).map_err(|source| MALError::Parse {
+            source,
+            body: String::new(),
+            context: None,
+        })?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    ///Returns the list status of every entry, for diffing with the `sync` module
+    pub fn snapshot(&self) -> ListSnapshot {
+        self.entries
+            .iter()
+            .filter_map(|(id, entry)| Some((*id, entry.list_status.clone()?)))
+            .collect()
+    }
+
+    ///Fetches the entries updated since the last refresh and merges them into the cache. Returns
+    ///the ids of the entries that were added or changed. The first refresh fetches the whole
+    ///list.
+    pub async fn refresh_incremental(
+        &mut self,
+        client: &MALClient,
+    ) -> Result<Vec<AnimeId>, MALError> {
+        let entries = client.list_updated_since(self.updated_until).await?;
+        let changed = self.merge(entries);
+        self.fetched_at = client.now();
+        Ok(changed)
+    }
+
+    ///Fetches the whole list and replaces the cache with it. Returns the ids of the entries that
+    ///were added, changed, or removed.
+    pub async fn refresh_full(&mut self, client: &MALClient) -> Result<Vec<AnimeId>, MALError> {
+        let entries = client.list_updated_since(None).await?;
+        let mut removed: Vec<AnimeId> = self.entries.keys().copied().collect();
+        removed.retain(|id| !entries.iter().any(|e| e.node.id == *id));
+        for id in &removed {
+            self.entries.remove(id);
+        }
+        let mut changed = self.merge(entries);
+        changed.extend(removed);
+        changed.sort();
+        self.fetched_at = client.now();
+        Ok(changed)
+    }
+
+    ///Adds or replaces `entries` and moves `updated_until` to the latest update among them.
+    ///Returns the ids of the entries that weren't in the cache or were different.
+    fn merge(&mut self, entries: Vec<ListNode>) -> Vec<AnimeId> {
+        let mut changed = Vec::new();
+        for entry in entries {
+            if let Some(updated) = updated_at(&entry) {
+                self.updated_until = Some(self.updated_until.map_or(updated, |u| u.max(updated)));
+            }
+            let id = entry.node.id;
+            if self.entries.get(&id) != Some(&entry) {
+                self.entries.insert(id, entry);
+                changed.push(id);
+            }
+        }
+        changed
+    }
+}
+
+///Returns when the list entry was last updated as a Unix timestamp
+pub(crate) fn updated_at(entry: &ListNode) -> Option<u64> {
+    let status = entry.list_status.as_ref()?;
+    parse_timestamp(status.updated_at.as_deref()?).map(unix_secs)
+}
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use reqwest::{Method, StatusCode};
+
+use crate::rt::Instant;
+use crate::{HttpRequest, HttpResponse, HttpTransport, MALError};
+
+///Hooks called around every request the client sends, e.g. for logging, tracing, or metrics
+///
+///Both methods do nothing by default. Retries, token refreshes, the exchange of an auth code for
+///tokens, and picture downloads are separate requests, so the hooks are called for each of them.
+///Responses served from the response cache aren't requests.
+///
+///# Example
+///
+///```
+/// use lib_mal::{ClientBuilder, MALClient, RequestEvent, RequestInterceptor};
+///
+/// struct Logger;
+///
+/// impl RequestInterceptor for Logger {
+///     fn after_response(&self, event: &RequestEvent) {
+///         println!("{} {} -> {:?} in {:?}", event.method, event.url, event.status, event.duration);
+///     }
+/// }
+///
+/// let client: MALClient = ClientBuilder::new().with_middleware(Logger).build_no_refresh();
+///```
+#[allow(unused_variables)]
+pub trait RequestInterceptor: Send + Sync {
+    ///Called right before a request is sent
+    fn before_request(&self, method: &Method, url: &str) {}
+    ///Called once the response arrives, or the request fails
+    fn after_response(&self, event: &RequestEvent) {}
+}
+
+///Calls the hooks of every `RequestInterceptor` around the requests sent through `inner`
+pub(crate) struct Hooked<'a, T> {
+    pub(crate) inner: T,
+    pub(crate) middleware: &'a [Arc<dyn RequestInterceptor>],
+}
+
+impl<T: HttpTransport> Hooked<'_, T> {
+    fn before(&self, request: &HttpRequest) -> Instant {
+        for middleware in self.middleware {
+            middleware.before_request(&request.method, &request.url);
+        }
+        Instant::now()
+    }
+
+    fn after(&self, method: Method, url: String, status: Option<StatusCode>, start: Instant) {
+        if self.middleware.is_empty() {
+            return;
+        }
+        let event = RequestEvent {
+            method,
+            url,
+            status,
+            duration: start.elapsed(),
+        };
+        for middleware in self.middleware {
+            middleware.after_response(&event);
+        }
+    }
+}
+
+impl<T: HttpTransport> HttpTransport for Hooked<'_, T> {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, MALError>> {
+        Box::pin(async move {
+            let (method, url) = (request.method.clone(), request.url.clone());
+            let start = self.before(&request);
+            let res = self.inner.send(request).await;
+            self.after(method, url, res.as_ref().ok().map(|res| res.status), start);
+            res
+        })
+    }
+
+    fn send_bytes(&self, request: HttpRequest) -> BoxFuture<'_, Result<(StatusCode, Vec<u8>), MALError>> {
+        Box::pin(async move {
+            let (method, url) = (request.method.clone(), request.url.clone());
+            let start = self.before(&request);
+            let res = self.inner.send_bytes(request).await;
+            self.after(method, url, res.as_ref().ok().map(|(status, _)| *status), start);
+            res
+        })
+    }
+}
+
+///A finished request, see [`RequestInterceptor::after_response`]
+#[derive(Debug, Clone)]
+pub struct RequestEvent {
+    pub method: Method,
+    pub url: String,
+    ///`None` if no response was received
+    pub status: Option<StatusCode>,
+    pub duration: Duration,
+}
+
+///Receives the outcome of every API call, e.g. for exporting request counts, error rates, and
+///latency histograms
+///
+///Unlike a `RequestInterceptor`, the sink is called once per call with the name of the endpoint,
+///after retries and token refreshes. Responses served from the response cache aren't requests,
+///so they aren't recorded.
+///
+///The crate doesn't ship a sink for the `metrics` crate or any other metrics library, implement
+///`record` to forward the calls to the one the app uses.
+///
+///# Example
+///
+///```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use lib_mal::{ClientBuilder, MALClient, MetricsSink, RequestMetrics};
+///
+/// #[derive(Default)]
+/// struct ErrorCounter(AtomicUsize);
+///
+/// impl MetricsSink for ErrorCounter {
+///     fn record(&self, metrics: &RequestMetrics) {
+///         if !metrics.is_success() {
+///             self.0.fetch_add(1, Ordering::Relaxed);
+///         }
+///     }
+/// }
+///
+/// let client: MALClient = ClientBuilder::new()
+///     .metrics_sink(ErrorCounter::default())
+///     .build_no_refresh();
+///```
+pub trait MetricsSink: Send + Sync {
+    fn record(&self, metrics: &RequestMetrics);
+}
+
+///A finished API call, see [`MetricsSink`]
+#[derive(Debug, Clone)]
+pub struct RequestMetrics {
+    ///The name of the client method, like `get_anime_details`
+    pub endpoint: String,
+    pub method: Method,
+    ///The status of the last response, `None` if no response was received
+    pub status: Option<StatusCode>,
+    ///How long the call took, including retries
+    pub duration: Duration,
+}
+
+impl RequestMetrics {
+    ///Returns true if the call got a successful response, including 304 Not Modified
+    pub fn is_success(&self) -> bool {
+        self.status
+            .is_some_and(|s| s.is_success() || s == StatusCode::NOT_MODIFIED)
+    }
+}
@@ -1,36 +1,271 @@
-use std::{path::PathBuf, collections::HashMap, str::FromStr};
-use reqwest::Client;
-use crate::{MALClientTrait, MALError, prelude::{AnimeList, fields::AnimeFields, AnimeDetails, options::{RankingType, Season, StatusUpdate, Params}, ListStatus, ForumBoards, TopicDetails, ForumTopics, User, EpisodesList}};
+use std::{path::PathBuf, collections::HashMap, fmt::Debug, str::FromStr, sync::{Arc, Mutex}, time::Duration};
+#[cfg(feature = "jikan")]
+use futures_util::future::BoxFuture;
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+#[cfg(feature = "jikan")]
+use crate::{jikan::JIKAN_API_URL, HttpRequest, HttpResponse, HttpTransport};
+#[cfg(feature = "callback-server")]
+use crate::cancel::CancellationToken;
+use crate::client::Configure;
+use crate::limits::Limit;
+#[cfg(feature = "oauth")]
+use crate::AuthParts;
+use crate::{Clock, SystemClock, AuthState, ReauthReason, MALClientTrait, MALError, Tokens, prelude::{AnimeId, TopicId, AnimeList, AnimeStatistics, fields::{AnimeFields, UserFields}, AnimeDetails, options::{date_of, ForumTopicQuery, RankingType, SeasonYear, SeasonalSort, StatusParams, StatusUpdate, SuggestionQuery, Params, UserListQuery}, ListStatus, ForumBoards, TopicDetails, Topic, ForumTopics, Paging, User}};
 
+///A `MALClientTrait` that answers without sending requests
+///
+///Every API call answers with a fixture unless a response was registered for it with
+///[`MockMALClient::on`] or one of the `on_*` shortcuts. Calls are recorded, so tests can check
+///which were made with [`MockMALClient::calls_to`].
+///
+///# Example
+///
+///```
+/// # use lib_mal::{MALClientTrait, MALError, MockMALClient};
+/// # async fn test() {
+///     let mock = MockMALClient::with_access_token("token");
+///     mock.on_get_anime_details(80).return_json(r#"{"id": 80, "title": "Mobile Suit Gundam", "main_picture": {}}"#);
+///     mock.on_get_anime_details(81).return_error(|| MALError::NotFound { context: None });
+///
+///     assert_eq!(mock.get_anime_details(80, None).await.unwrap().show.title, "Mobile Suit Gundam");
+///     assert!(mock.get_anime_details(81, None).await.is_err());
+///     assert_eq!(mock.calls_to("get_anime_details").len(), 2);
+/// # }
+///```
 #[allow(clippy::module_name_repetitions)]
 pub struct MockMALClient {
+    ///only used as the client id of the auth URL
+    #[cfg_attr(not(feature = "oauth"), allow(dead_code))]
     client_secret: String,
     dirs: PathBuf,
     access_token: String,
     caching: bool,
-    pub need_auth: bool,
+    pub auth_state: AuthState,
+    pub reauth_reason: Option<ReauthReason>,
+    ///Makes every API call fail with `MALError::ServerError`, even the ones with a registered
+    ///response
     pub give_error: bool,
+    ///Added to every API call before it answers
+    pub latency: Duration,
+    clock: Arc<dyn Clock>,
+    stubs: Mutex<Vec<Stub>>,
+    calls: Mutex<Vec<MockCall>>,
+}
+
+///An API call made on a `MockMALClient`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockCall {
+    ///Name of the `MALClientTrait` function, like `get_anime_details`
+    pub method: &'static str,
+    ///The arguments in their `Debug` format, with `impl Into<Option<T>>` arguments converted
+    pub args: Vec<String>,
+}
+
+#[derive(Clone)]
+enum Reply {
+    Json(String),
+    Error(Arc<dyn Fn() -> MALError + Send + Sync>),
+}
+
+struct Stub {
+    method: &'static str,
+    arg: Option<String>,
+    delay: Duration,
+    reply: Reply,
+}
+
+///A response being registered on a `MockMALClient`, see [`MockMALClient::on`]. Nothing is
+///registered until `return_json` or `return_error` is called.
+#[must_use]
+pub struct MockResponse<'a> {
+    mock: &'a MockMALClient,
+    method: &'static str,
+    arg: Option<String>,
+    delay: Duration,
+}
+
+impl MockResponse<'_> {
+    ///Only answers calls whose first argument is `arg`, like the id in `get_anime_details`. The
+    ///argument is compared by its `Debug` output, so ids have to be passed typed, as `AnimeId(21)`.
+    pub fn with_arg(mut self, arg: impl Debug) -> Self {
+        self.arg = Some(format!("{arg:?}"));
+        self
+    }
+
+    ///Waits `delay` before answering, on top of `MockMALClient::latency`
+    pub const fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    ///Answers with `json` parsed as the return type of the call. JSON that doesn't parse makes
+    ///the call fail with `MALError::Parse`, like a bad API response would.
+    pub fn return_json(self, json: impl Into<String>) {
+        self.register(Reply::Json(json.into()));
+    }
+
+    ///Fails with the error returned by `error`, which is called for every answered call
+    pub fn return_error(self, error: impl Fn() -> MALError + Send + Sync + 'static) {
+        self.register(Reply::Error(Arc::new(error)));
+    }
+
+    fn register(self, reply: Reply) {
+        self.mock.stubs.lock().unwrap().push(Stub {
+            method: self.method,
+            arg: self.arg,
+            delay: self.delay,
+            reply,
+        });
+    }
+}
+
+impl MockMALClient {
+    ///Registers a response for every call of `method`, like `get_forum_boards`. Responses
+    ///registered later take precedence.
+    pub fn on(&self, method: &'static str) -> MockResponse<'_> {
+        MockResponse {
+            mock: self,
+            method,
+            arg: None,
+            delay: Duration::ZERO,
+        }
+    }
+
+    pub fn on_get_anime_list(&self, query: &str) -> MockResponse<'_> {
+        self.on("get_anime_list").with_arg(query)
+    }
+
+    pub fn on_get_anime_details(&self, id: impl Into<AnimeId>) -> MockResponse<'_> {
+        self.on("get_anime_details").with_arg(id.into())
+    }
+
+    pub fn on_update_user_anime_status(&self, id: impl Into<AnimeId>) -> MockResponse<'_> {
+        self.on("update_user_anime_status").with_arg(id.into())
+    }
+
+    pub fn on_delete_anime_list_item(&self, id: impl Into<AnimeId>) -> MockResponse<'_> {
+        self.on("delete_anime_list_item").with_arg(id.into())
+    }
+
+    pub fn on_get_user_info(&self, username: &str) -> MockResponse<'_> {
+        self.on("get_user_info").with_arg(username)
+    }
+
+    ///Removes every registered response
+    pub fn reset(&self) {
+        self.stubs.lock().unwrap().clear();
+    }
+
+    ///Returns every API call made so far, oldest first
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    ///Returns the arguments of every call of `method` made so far, oldest first
+    pub fn calls_to(&self, method: &str) -> Vec<Vec<String>> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|c| c.method == method)
+            .map(|c| c.args.clone())
+            .collect()
+    }
+
+    pub fn clear_calls(&self) {
+        self.calls.lock().unwrap().clear();
+    }
+
+    ///Records the call, waits out the latency, and returns the registered answer, if any
+    async fn respond<T: DeserializeOwned>(&self, method: &'static str, args: Vec<String>) -> Option<Result<T, MALError>> {
+        let stub = self
+            .stubs
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|s| s.method == method && s.arg.as_ref().is_none_or(|a| args.first() == Some(a)))
+            .map(|s| (s.delay, s.reply.clone()));
+        self.calls.lock().unwrap().push(MockCall { method, args });
+        let delay = self.latency + stub.as_ref().map_or(Duration::ZERO, |(d, _)| *d);
+        if !delay.is_zero() {
+            crate::rt::sleep(delay).await;
+        }
+        if self.give_error {
+            return Some(Err(MALError::ServerError {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                excerpt: String::from("mocked error"),
+                context: None,
+            }));
+        }
+        match stub?.1 {
+            Reply::Json(body) => Some(serde_json::from_str(&body).map_err(|source| MALError::Parse { source, body, context: None })),
+            Reply::Error(error) => Some(Err(error())),
+        }
+    }
+}
+
+///Formats the arguments of a call for `MockCall::args`
+macro_rules! args {
+    ($($arg:expr),*) => { vec![$(format!("{:?}", $arg)),*] };
+}
+
+///Answers from the registered responses if there is one for the call
+macro_rules! respond {
+    ($self:ident, $method:literal $(, $arg:expr)*) => {
+        if let Some(res) = $self.respond($method, args![$($arg),*]).await {
+            return res;
+        }
+    };
+}
+
+impl Configure for MockMALClient {
+    fn set_reauth_reason(&mut self, reason: ReauthReason) {
+        self.reauth_reason = Some(reason);
+    }
+    ///used for the dates of list updates
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
 }
 
 impl MALClientTrait for MockMALClient {
     fn new(
         client_secret: String,
         dirs: PathBuf,
-        access_token: String,
+        tokens: Tokens,
         client: Client,
         caching: bool,
-        need_auth: bool,
+        auth_state: AuthState,
     ) -> Self {
-        Self { client_secret, dirs, access_token, caching, need_auth, give_error: false }
+        Self {
+            client_secret,
+            dirs,
+            access_token: tokens.access_token,
+            caching,
+            auth_state,
+            reauth_reason: None,
+            give_error: false,
+            latency: Duration::ZERO,
+            clock: Arc::new(SystemClock),
+            stubs: Mutex::default(),
+            calls: Mutex::default(),
+        }
     }
     fn with_access_token(token: &str) -> Self {
         Self {
             client_secret: String::new(),
-            need_auth: false,
+            auth_state: AuthState::TokenCached,
+            reauth_reason: None,
             dirs: PathBuf::new(),
             access_token: token.to_owned(),
             caching: false,
             give_error: false,
+            latency: Duration::ZERO,
+            clock: Arc::new(SystemClock),
+            stubs: Mutex::default(),
+            calls: Mutex::default(),
         }
     }
     fn set_cache_dir(&mut self, dir: PathBuf) {
@@ -39,122 +274,177 @@ impl MALClientTrait for MockMALClient {
     fn set_caching(&mut self, caching: bool) {
         self.caching = caching;
     }
-    fn get_auth_parts(&self) -> (String, String, String) {
-        let verifier = pkce::code_verifier(128);
-        let challenge = pkce::code_challenge(&verifier);
-        let state = String::new();
-        let url = format!("https://example.com/&client_id={}&code_challenge={}", self.client_secret, challenge);
-        (url, challenge, state)
+    #[cfg(feature = "oauth")]
+    fn get_auth_parts_with(&self, redirect_uri: Option<&str>, extra_params: &[(&str, &str)]) -> AuthParts {
+        AuthParts::new("https://example.com/", &self.client_secret, redirect_uri, extra_params)
     }
+    #[cfg(feature = "callback-server")]
     async fn auth(
         &mut self,
         callback_url: &str,
         challenge: &str,
         state: &str,
     ) -> Result<(), MALError> {
-        self.need_auth = false;
+        self.auth_with_timeout(callback_url, challenge, state, None)
+            .await
+    }
+    #[cfg(feature = "callback-server")]
+    async fn auth_with_timeout(
+        &mut self,
+        callback_url: &str,
+        challenge: &str,
+        state: &str,
+        timeout: Option<Duration>,
+    ) -> Result<(), MALError> {
+        respond!(self, "auth_with_timeout", callback_url, challenge, state, timeout);
+        self.auth_state = AuthState::Authenticated;
+        self.reauth_reason = None;
+        self.access_token = String::from("Auth done");
+        Ok(())
+    }
+    #[cfg(feature = "callback-server")]
+    async fn auth_with_cancellation(
+        &mut self,
+        callback_url: &str,
+        challenge: &str,
+        state: &str,
+        cancel: CancellationToken,
+    ) -> Result<(), MALError> {
+        respond!(self, "auth_with_cancellation", callback_url, challenge, state);
+        if cancel.is_cancelled() {
+            return Err(MALError::Cancelled);
+        }
+        self.auth_state = AuthState::Authenticated;
+        self.reauth_reason = None;
+        self.access_token = String::from("Auth done");
+        Ok(())
+    }
+    #[cfg(feature = "oauth")]
+    async fn auth_with_code(&mut self, code: &str, code_verifier: &str) -> Result<(), MALError> {
+        respond!(self, "auth_with_code", code, code_verifier);
+        self.auth_state = AuthState::Authenticated;
+        self.reauth_reason = None;
         self.access_token = String::from("Auth done");
         Ok(())
     }
-    fn get_access_token(&self) -> &str {
-        &self.access_token
+    fn get_access_token(&self) -> String {
+        self.access_token.clone()
     }
-    /// answers for `get_anime_list("one", Some(4))`
+    /// answers for `get_anime_list("one", Some(4), None)`
     async fn get_anime_list(
         &self,
         query: &str,
-        limit: impl Into<Option<u8>> + Send,
+        limit: impl Into<Limit> + Send,
+        fields: impl Into<Option<AnimeFields>> + Send,
     ) -> Result<AnimeList, MALError> {
+        let (limit, fields): (Option<usize>, Option<AnimeFields>) = (limit.into().get(), fields.into());
+        respond!(self, "get_anime_list", query, limit, fields);
         let anime_list = serde_json::from_str::<AnimeList>(include_str!("test-data/anime_list.json")).unwrap();
         Ok(anime_list)
     }
     /// answers for `get_anime_details(30230, AnimeFields::ALL)`
     async fn get_anime_details(
         &self,
-        id: usize,
+        id: impl Into<AnimeId> + Send,
         fields: impl Into<Option<AnimeFields>> + Send,
     ) -> Result<AnimeDetails, MALError> {
-        match id {
+        let id: AnimeId = id.into();
+        let fields: Option<AnimeFields> = fields.into();
+        respond!(self, "get_anime_details", id, fields);
+        match id.0 {
             21 => Ok(serde_json::from_str::<AnimeDetails>(include_str!("test-data/one_piece_details.json")).unwrap()),
             30230 => Ok(serde_json::from_str::<AnimeDetails>(include_str!("test-data/anime_details.json")).unwrap()),
-            _ => Err(MALError::new("Not found", "error", Some(String::from("info")))),
+            _ => Err(MALError::Gone { context: None }),
         }
     }
-    /// answers for `get_anime_ranking(RankingType::All, Some(4))`
+    /// answers for `get_anime_ranking(RankingType::All, Some(4), None)`
     async fn get_anime_ranking(
         &self,
         ranking_type: RankingType,
-        limit: impl Into<Option<u8>> + Send,
+        limit: impl Into<Limit> + Send,
+        fields: impl Into<Option<AnimeFields>> + Send,
     ) -> Result<AnimeList, MALError> {
+        let (limit, fields): (Option<usize>, Option<AnimeFields>) = (limit.into().get(), fields.into());
+        respond!(self, "get_anime_ranking", ranking_type, limit, fields);
         let anime_ranking = serde_json::from_str::<AnimeList>(include_str!("test-data/anime_ranking.json")).unwrap();
         Ok(anime_ranking)
     }
-    /// likely answers for `get_seasonal_anime(Season::Summer, 2017, Some(4))`
+    /// likely answers for `get_seasonal_anime((Season::Summer, 2017), Some(4), None, None)`
     async fn get_seasonal_anime(
         &self,
-        season: Season,
-        year: usize,
-        limit: impl Into<Option<u8>> + Send,
+        season: impl Into<SeasonYear> + Send,
+        limit: impl Into<Limit> + Send,
+        fields: impl Into<Option<AnimeFields>> + Send,
+        sort: impl Into<Option<SeasonalSort>> + Send,
     ) -> Result<AnimeList, MALError> {
+        let (season, limit, fields, sort): (SeasonYear, Option<usize>, Option<AnimeFields>, Option<SeasonalSort>) =
+            (season.into(), limit.into().get(), fields.into(), sort.into());
+        respond!(self, "get_seasonal_anime", season, limit, fields, sort);
         let seasonal_anime = serde_json::from_str::<AnimeList>(include_str!("test-data/seasonal_anime.json")).unwrap();
         Ok(seasonal_anime)
     }
-    /// WARNING: answers like `get_anime_list("one", Some(4))` would
+    /// WARNING: answers like `get_anime_list("one", Some(4), None)` would
     async fn get_suggested_anime(
         &self,
-        limit: impl Into<Option<u8>> + Send,
+        limit: impl Into<Limit> + Send,
+        fields: impl Into<Option<AnimeFields>> + Send,
     ) -> Result<AnimeList, MALError> {
+        let (limit, fields): (Option<usize>, Option<AnimeFields>) = (limit.into().get(), fields.into());
+        respond!(self, "get_suggested_anime", limit, fields);
+        let anime_list = serde_json::from_str::<AnimeList>(include_str!("test-data/anime_list.json")).unwrap();
+        Ok(anime_list)
+    }
+    /// WARNING: ignores `exclude_on_list`, answers like `get_suggested_anime`
+    async fn get_suggestions(&self, query: SuggestionQuery) -> Result<AnimeList, MALError> {
+        respond!(self, "get_suggestions", query);
         let anime_list = serde_json::from_str::<AnimeList>(include_str!("test-data/anime_list.json")).unwrap();
         Ok(anime_list)
     }
     /// return back given status
     async fn update_user_anime_status(
         &self,
-        id: usize,
-        update: StatusUpdate,
+        id: impl Into<AnimeId> + Send,
+        update: StatusParams,
     ) -> Result<ListStatus, MALError> {
-        let update_params: HashMap<&str, String> =
-            update.get_params().iter().map(|(k, v)| (*k, v.clone())).collect();
-        let list_status = ListStatus {
-            status: update_params.get("status").cloned(),
-            num_episodes_watched: update_params
-                .get("num_episodes_watched")
-                .map(|v| v.parse().unwrap_or_default()),
-            score: update_params
-                .get("score")
-                .map(|v| v.parse().unwrap_or_default()),
-            updated_at: update_params.get("updated_at").cloned(),
-            is_rewatching: update_params
-                .get("is_rewatching")
-                .map(|v| FromStr::from_str(v).unwrap_or_default()),
-            num_times_rewatched: update_params
-                .get("num_times_rewatched")
-                .map(|v| v.parse().unwrap_or_default()),
-            priority: update_params
-                .get("priority")
-                .map(|v| v.parse().unwrap_or_default()),
-            rewatch_value: update_params
-                .get("rewatch_value")
-                .map(|v| v.parse().unwrap_or_default()),
-            tags: update_params
-                .get("tags")
-                .map(|str| str.split(',').map(String::from).collect()),
-            comments: update_params.get("status").cloned(),
-            start_date: update_params.get("status").cloned(),
-            finish_date: update_params.get("status").cloned(),
-        };
-        Ok(list_status)
+        let id: AnimeId = id.into();
+        respond!(self, "update_user_anime_status", id, update);
+        Ok(status_from(update))
+    }
+    /// WARNING: answers like `get_anime_list("one", Some(4), None)` would
+    async fn get_user_anime_list(&self, query: impl Into<Option<UserListQuery>> + Send) -> Result<AnimeList, MALError> {
+        let query: Option<UserListQuery> = query.into();
+        respond!(self, "get_user_anime_list", query);
+        let anime_list = serde_json::from_str::<AnimeList>(include_str!("test-data/anime_list.json")).unwrap();
+        Ok(anime_list)
     }
-    /// WARNING: answers like `get_anime_list("one", Some(4))` would
-    async fn get_user_anime_list(&self) -> Result<AnimeList, MALError> {
+    /// WARNING: answers like `get_anime_list("one", Some(4), None)` would
+    async fn get_user_anime_list_for(&self, username: &str, query: impl Into<Option<UserListQuery>> + Send) -> Result<AnimeList, MALError> {
+        let query: Option<UserListQuery> = query.into();
+        respond!(self, "get_user_anime_list_for", username, query);
         let anime_list = serde_json::from_str::<AnimeList>(include_str!("test-data/anime_list.json")).unwrap();
         Ok(anime_list)
     }
-    async fn delete_anime_list_item(&self, id: usize) -> Result<(), MALError> {
+    /// treats the anime as not being on the list yet
+    async fn increment_watched_episodes(&self, id: impl Into<AnimeId> + Send, by: usize) -> Result<ListStatus, MALError> {
+        let id: AnimeId = id.into();
+        respond!(self, "increment_watched_episodes", id, by);
+        Ok(status_from(StatusUpdate::watched_episodes(None, None, by, &date_of(self.clock.now()))))
+    }
+    /// treats the anime as not being on the list yet
+    async fn mark_completed(&self, id: impl Into<AnimeId> + Send, score: impl Into<Option<u8>> + Send) -> Result<ListStatus, MALError> {
+        let id: AnimeId = id.into();
+        let score: Option<u8> = score.into();
+        respond!(self, "mark_completed", id, score);
+        Ok(status_from(StatusUpdate::completed(None, None, score, &date_of(self.clock.now()))?))
+    }
+    async fn delete_anime_list_item(&self, id: impl Into<AnimeId> + Send) -> Result<(), MALError> {
+        let id: AnimeId = id.into();
+        respond!(self, "delete_anime_list_item", id);
         Ok(())
     }
     /// WARNING: returns an empty struct
     async fn get_forum_boards(&self) -> Result<ForumBoards, MALError> {
+        respond!(self, "get_forum_boards");
         let forum_boards = ForumBoards {
             categories: Vec::new()
         };
@@ -163,11 +453,38 @@ impl MALClientTrait for MockMALClient {
     /// WARNING: returns an empty struct
     async fn get_forum_topic_detail(
         &self,
-        topic_id: usize,
-        limit: impl Into<Option<u8>> + Send,
+        topic_id: impl Into<TopicId> + Send,
+        limit: impl Into<Limit> + Send,
     ) -> Result<TopicDetails, MALError> {
+        let topic_id: TopicId = topic_id.into();
+        let limit: Option<usize> = limit.into().get();
+        respond!(self, "get_forum_topic_detail", topic_id, limit);
         let topic_details = TopicDetails {
-            data: Vec::new(),
+            data: Topic {
+                title: String::new(),
+                posts: Vec::new(),
+                poll: None,
+            },
+            paging: HashMap::new(),
+        };
+        Ok(topic_details)
+    }
+    /// WARNING: returns an empty struct
+    async fn get_forum_topic_posts(
+        &self,
+        topic_id: impl Into<TopicId> + Send,
+        limit: impl Into<Limit> + Send,
+        offset: usize,
+    ) -> Result<TopicDetails, MALError> {
+        let topic_id: TopicId = topic_id.into();
+        let limit: Option<usize> = limit.into().get();
+        respond!(self, "get_forum_topic_posts", topic_id, limit, offset);
+        let topic_details = TopicDetails {
+            data: Topic {
+                title: String::new(),
+                posts: Vec::new(),
+                poll: None,
+            },
             paging: HashMap::new(),
         };
         Ok(topic_details)
@@ -175,39 +492,167 @@ impl MALClientTrait for MockMALClient {
     /// WARNING: returns an empty struct
     async fn get_forum_topics(
         &self,
-        board_id: impl Into<Option<usize>> + Send,
-        subboard_id: impl Into<Option<usize>> + Send,
-        query: impl Into<Option<String>> + Send,
-        topic_user_name: impl Into<Option<String>> + Send,
-        user_name: impl Into<Option<String>> + Send,
-        limit: impl Into<Option<usize>> + Send,
+        query: impl Into<Option<ForumTopicQuery>> + Send,
     ) -> Result<ForumTopics, MALError> {
+        let query: Option<ForumTopicQuery> = query.into();
+        respond!(self, "get_forum_topics", query);
         let forum_topics = ForumTopics {
             data: Vec::new(),
-            paging: Vec::new(),
+            paging: Paging::default(),
         };
         Ok(forum_topics)
     }
-    /// WARNING: `anime_statistics` are empty
-    async fn get_my_user_info(&self) -> Result<User, MALError> {
-        let user = User {
-            id: 727,
-            name: String::from("Mocked user"),
-            location: String::from("Space"),
-            joined_at: String::from("2016-01-02T06:03:11+00:00"),
-            anime_statistics: HashMap::new(),
-        };
-        Ok(user)
+    /// WARNING: `anime_statistics` are all zero
+    async fn get_my_user_info(
+        &self,
+        fields: impl Into<Option<UserFields>> + Send,
+    ) -> Result<User, MALError> {
+        let fields: Option<UserFields> = fields.into();
+        respond!(self, "get_my_user_info", fields);
+        Ok(mocked_user("Mocked user"))
     }
-    /// WARNING: returns an empty struct
-    async fn get_anime_episodes(&self, id: usize, precise_score: bool) -> Result<EpisodesList, MALError> {
-        let episodes_list = EpisodesList {
-            data: Vec::new(),
-            pagination: HashMap::new(),
-        };
-        Ok(episodes_list)
+    /// WARNING: `anime_statistics` are all zero
+    async fn get_user_info(
+        &self,
+        username: &str,
+        fields: impl Into<Option<UserFields>> + Send,
+    ) -> Result<User, MALError> {
+        let fields: Option<UserFields> = fields.into();
+        respond!(self, "get_user_info", username, fields);
+        Ok(mocked_user(username))
+    }
+    fn auth_state(&self) -> AuthState {
+        self.auth_state
     }
-    fn need_auth(&self) -> bool {
-        self.need_auth
+    fn reauth_reason(&self) -> Option<&ReauthReason> {
+        self.reauth_reason.as_ref()
     }
-}
\ No newline at end of file
+}
+
+///The list status `update` would result in for an anime that isn't on the list yet
+fn status_from(update: StatusParams) -> ListStatus {
+    let update_params: HashMap<&str, String> =
+        update.get_params().iter().map(|(k, v)| (*k, v.clone())).collect();
+    ListStatus {
+        status: update_params.get("status").cloned(),
+        num_episodes_watched: update_params
+            .get("num_episodes_watched")
+            .map(|v| v.parse().unwrap_or_default()),
+        score: update_params
+            .get("score")
+            .map(|v| v.parse().unwrap_or_default()),
+        updated_at: update_params.get("updated_at").cloned(),
+        is_rewatching: update_params
+            .get("is_rewatching")
+            .map(|v| FromStr::from_str(v).unwrap_or_default()),
+        num_times_rewatched: update_params
+            .get("num_times_rewatched")
+            .map(|v| v.parse().unwrap_or_default()),
+        priority: update_params
+            .get("priority")
+            .map(|v| v.parse().unwrap_or_default()),
+        rewatch_value: update_params
+            .get("rewatch_value")
+            .map(|v| v.parse().unwrap_or_default()),
+        tags: update_params
+            .get("tags")
+            .map(|str| str.split(',').collect()),
+        comments: update_params.get("comments").cloned(),
+        start_date: update_params.get("start_date").cloned(),
+        finish_date: update_params.get("finish_date").cloned(),
+    }
+}
+
+fn mocked_user(username: &str) -> User {
+    User {
+        id: 727,
+        name: username.to_owned(),
+        picture: None,
+        gender: None,
+        birthday: None,
+        location: String::from("Space"),
+        joined_at: String::from("2016-01-02T06:03:11+00:00"),
+        anime_statistics: Some(AnimeStatistics::default()),
+        time_zone: None,
+        is_supporter: None,
+        extra: serde_json::Map::new(),
+    }
+}
+
+#[cfg(feature = "jikan")]
+const JIKAN_EPISODES: &str = include_str!("test-data/jikan_episodes.json");
+#[cfg(feature = "jikan")]
+const JIKAN_CHARACTERS: &str = include_str!("test-data/jikan_characters.json");
+#[cfg(feature = "jikan")]
+const JIKAN_STAFF: &str = include_str!("test-data/jikan_staff.json");
+#[cfg(feature = "jikan")]
+const JIKAN_NEWS: &str = include_str!("test-data/jikan_news.json");
+#[cfg(feature = "jikan")]
+const JIKAN_REVIEWS: &str = include_str!("test-data/jikan_reviews.json");
+#[cfg(feature = "jikan")]
+const JIKAN_THEMES_SONGS: &str = include_str!("test-data/jikan_theme_songs.json");
+#[cfg(feature = "jikan")]
+const JIKAN_GENRES: &str = include_str!("test-data/jikan_genres.json");
+#[cfg(feature = "jikan")]
+const JIKAN_EXPLICIT_GENRES: &str = include_str!("test-data/jikan_explicit_genres.json");
+#[cfg(feature = "jikan")]
+const JIKAN_THEMES: &str = include_str!("test-data/jikan_themes.json");
+#[cfg(feature = "jikan")]
+const JIKAN_DEMOGRAPHICS: &str = include_str!("test-data/jikan_demographics.json");
+#[cfg(feature = "jikan")]
+const JIKAN_BROWSE: &str = include_str!("test-data/jikan_browse.json");
+
+///Answers the Jikan requests of a `MALClient` with fixtures, so `MALClient::jikan` can be used
+///offline. Has the episodes, characters, staff, news, reviews, and themes of anime 30230, the genres,
+///and the first page of the anime of genre 30, every other request gets a 404 Not Found.
+///
+///# Example
+///
+///```
+/// # use lib_mal::{ClientBuilder, MALClient, MALError, MockJikanTransport};
+/// # async fn test() -> Result<(), MALError> {
+///     let client: MALClient = ClientBuilder::new().transport(MockJikanTransport).build_no_refresh();
+///     let episodes = client.jikan().episodes(30230, None, false).await?;
+///     assert_eq!(episodes.data.len(), 2);
+///     # Ok(())
+/// # }
+///```
+#[cfg(feature = "jikan")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MockJikanTransport;
+
+#[cfg(feature = "jikan")]
+impl HttpTransport for MockJikanTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, MALError>> {
+        let (path, query) = request.url.split_once('?').unwrap_or((&request.url, ""));
+        let param = |name: &str| {
+            query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+        };
+        let fixture = match path.strip_prefix(JIKAN_API_URL) {
+            Some("/anime/30230/episodes") => Some(JIKAN_EPISODES),
+            Some("/anime/30230/characters") => Some(JIKAN_CHARACTERS),
+            Some("/anime/30230/staff") => Some(JIKAN_STAFF),
+            Some("/anime/30230/news") => Some(JIKAN_NEWS),
+            Some("/anime/30230/reviews") => Some(JIKAN_REVIEWS),
+            Some("/anime/30230/themes") => Some(JIKAN_THEMES_SONGS),
+            Some("/genres/anime") => match param("filter") {
+                Some("genres") => Some(JIKAN_GENRES),
+                Some("explicit_genres") => Some(JIKAN_EXPLICIT_GENRES),
+                Some("themes") => Some(JIKAN_THEMES),
+                Some("demographics") => Some(JIKAN_DEMOGRAPHICS),
+                _ => None,
+            },
+            Some("/anime") if param("genres") == Some("30") && param("page") == Some("1") => {
+                Some(JIKAN_BROWSE)
+            }
+            _ => None,
+        };
+        let res = match fixture {
+            Some(json) => HttpResponse::new(StatusCode::OK, json),
+            None => HttpResponse::new(StatusCode::NOT_FOUND, r#"{"status": 404, "type": "BadResponseException"}"#),
+        };
+        Box::pin(async move { Ok(res) })
+    }
+}
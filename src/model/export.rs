@@ -0,0 +1,576 @@
+//! MAL's XML list export, for backups and moving a list to another account, see
+//! [`MALClient::import_list`](crate::MALClient::import_list)
+//!
+//! Files written by [`MalExport::save`] can be imported on myanimelist.net, and files exported
+//! from there can be read with [`MalExport::load`].
+//!
+//! For spreadsheets and other trackers, lists can also be written as CSV with
+//! [`AnimeList::to_csv`], with the columns picked by `AnimeFields`, or as JSON with
+//! [`AnimeList::to_json_pretty`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::model::export::{ConflictPolicy, MalExport};
+//! use lib_mal::model::options::UserListQuery;
+//! use lib_mal::model::fields::AnimeFields;
+//! # async fn test(old: &MALClient, new: &MALClient) -> Result<(), MALError> {
+//! let query = UserListQuery::new().fields(AnimeFields::MediaType | AnimeFields::NumEpisodes);
+//! let list = old.get_user_anime_list(query).await?;
+//! MalExport::from_list("[OLD_USERNAME]", &list).save("animelist.xml")?;
+//!
+//! let export = MalExport::load("animelist.xml")?;
+//! let report = new.import_list(&export.anime, ConflictPolicy::Skip).await?;
+//! println!("{} imported, {} skipped", report.imported.len(), report.skipped.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Map, Value};
+
+use super::fields::{AnimeFields, ListStatusFields};
+use super::options::{MediaType, Status, StatusUpdate};
+use super::{AnimeId, AnimeList, ListNode, ListStatus, Tags};
+use crate::MALError;
+
+///Date MAL writes for missing start and finish dates
+const NO_DATE: &str = "0000-00-00";
+
+///A list in the format of MAL's XML export
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename = "myanimelist")]
+pub struct MalExport {
+    pub myinfo: ExportInfo,
+    #[serde(default)]
+    pub anime: Vec<ExportEntry>,
+}
+
+///The owner of an exported list and how many entries it has per status
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ExportInfo {
+    pub user_id: usize,
+    pub user_name: String,
+    ///1 for anime lists
+    pub user_export_type: u8,
+    pub user_total_anime: usize,
+    pub user_total_watching: usize,
+    pub user_total_completed: usize,
+    pub user_total_onhold: usize,
+    pub user_total_dropped: usize,
+    pub user_total_plantowatch: usize,
+}
+
+///An entry of an exported list
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExportEntry {
+    pub series_animedb_id: AnimeId,
+    #[serde(default)]
+    pub series_title: String,
+    ///Like `TV` or `Movie`
+    #[serde(default)]
+    pub series_type: String,
+    ///0 if unknown
+    #[serde(default)]
+    pub series_episodes: usize,
+    #[serde(default)]
+    pub my_watched_episodes: usize,
+    ///`0000-00-00` if not set
+    #[serde(default = "no_date")]
+    pub my_start_date: String,
+    ///`0000-00-00` if not set
+    #[serde(default = "no_date")]
+    pub my_finish_date: String,
+    ///0 if not scored
+    #[serde(default)]
+    pub my_score: u8,
+    #[serde(with = "export_status")]
+    pub my_status: Status,
+    #[serde(default)]
+    pub my_comments: String,
+    #[serde(default)]
+    pub my_times_watched: usize,
+    ///`LOW`, `MEDIUM`, or `HIGH`
+    #[serde(default)]
+    pub my_priority: String,
+    ///Comma separated
+    #[serde(default)]
+    pub my_tags: String,
+    #[serde(default, with = "flag")]
+    pub my_rewatching: bool,
+    ///Whether importing the entry replaces an entry that's already on the list, see
+    ///`ConflictPolicy::UpdateOnImport`
+    #[serde(default, with = "flag")]
+    pub update_on_import: bool,
+}
+
+impl MalExport {
+    ///Builds an export of `list`, which belongs to `user_name`. Entries without a list status
+    ///are left out. The type and episode count of every anime are only filled in if the list
+    ///was fetched with the `media_type` and `num_episodes` fields.
+    pub fn from_list(user_name: &str, list: &AnimeList) -> Self {
+        let anime: Vec<ExportEntry> = list
+            .data
+            .iter()
+            .filter_map(|entry| {
+                let status = entry
+                    .list_status
+                    .as_ref()
+                    .or(entry.details.my_list_status.as_ref())?;
+                ExportEntry::new(
+                    entry.node.id,
+                    &entry.node.title,
+                    entry.details.media_type.as_ref(),
+                    entry.details.num_episodes,
+                    status,
+                )
+            })
+            .collect();
+        let count = |status| anime.iter().filter(|e| e.my_status == status).count();
+        let myinfo = ExportInfo {
+            user_name: user_name.to_owned(),
+            user_export_type: 1,
+            user_total_anime: anime.len(),
+            user_total_watching: count(Status::Watching),
+            user_total_completed: count(Status::Completed),
+            user_total_onhold: count(Status::OnHold),
+            user_total_dropped: count(Status::Dropped),
+            user_total_plantowatch: count(Status::PlanToWatch),
+            ..ExportInfo::default()
+        };
+        Self { myinfo, anime }
+    }
+
+    ///Parses an XML export
+    pub fn from_xml(xml: &str) -> Result<Self, MALError> {
+        quick_xml::de::from_str(xml).map_err(|e| MALError::Export(e.to_string()))
+    }
+
+    ///Writes the export as XML, including the XML declaration
+    pub fn to_xml(&self) -> Result<String, MALError> {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" ?>\n");
+        let mut serializer = quick_xml::se::Serializer::new(&mut xml);
+        serializer.indent(' ', 2);
+        self.serialize(serializer)
+            .map_err(|e| MALError::Export(e.to_string()))?;
+        Ok(xml)
+    }
+
+    ///Reads an export written by [`MalExport::save`] or downloaded from myanimelist.net
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MALError> {
+        Self::from_xml(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MALError> {
+        fs::write(path, self.to_xml()?)?;
+        Ok(())
+    }
+}
+
+impl ExportEntry {
+    fn new(
+        id: AnimeId,
+        title: &str,
+        media_type: Option<&MediaType>,
+        num_episodes: Option<usize>,
+        status: &ListStatus,
+    ) -> Option<Self> {
+        Some(Self {
+            series_animedb_id: id,
+            series_title: title.to_owned(),
+            series_type: media_type.map(export_type).unwrap_or_default().to_owned(),
+            series_episodes: num_episodes.unwrap_or_default(),
+            my_watched_episodes: status.num_episodes_watched.unwrap_or_default(),
+            my_start_date: status.start_date.clone().unwrap_or_else(no_date),
+            my_finish_date: status.finish_date.clone().unwrap_or_else(no_date),
+            my_score: status.score.unwrap_or_default().get(),
+            my_status: status.status.as_deref()?.parse().ok()?,
+            my_comments: status.comments.clone().unwrap_or_default(),
+            my_times_watched: status.num_times_rewatched.unwrap_or_default(),
+            my_priority: match status.priority {
+                Some(2) => "HIGH",
+                Some(1) => "MEDIUM",
+                _ => "LOW",
+            }
+            .to_owned(),
+            my_tags: status.tags.as_deref().unwrap_or_default().join(", "),
+            my_rewatching: status.is_rewatching.unwrap_or_default(),
+            update_on_import: false,
+        })
+    }
+
+    ///Returns the update that puts the entry on a list, see `MALClientTrait::update_user_anime_status`
+    pub fn to_update(&self) -> StatusUpdate {
+        let mut update = StatusUpdate::new()
+            .status(self.my_status)
+            .num_watched_episodes(self.my_watched_episodes)
+            .score(self.my_score)
+            .num_times_rewatched(self.my_times_watched)
+            .is_rewatching(self.my_rewatching)
+            .priority(match self.my_priority.to_ascii_uppercase().as_str() {
+                "HIGH" => 2,
+                "MEDIUM" => 1,
+                _ => 0,
+            });
+        if self.my_start_date != NO_DATE && !self.my_start_date.is_empty() {
+            update = update.start_date(self.my_start_date.clone());
+        }
+        if self.my_finish_date != NO_DATE && !self.my_finish_date.is_empty() {
+            update = update.finish_date(self.my_finish_date.clone());
+        }
+        if !self.my_comments.is_empty() {
+            update = update.comments(self.my_comments.clone());
+        }
+        let tags: Tags = self.my_tags.split(',').collect();
+        if !tags.is_empty() {
+            update = update.tags(tags);
+        }
+        update
+    }
+}
+
+///What `MALClient::import_list` does with anime that are already on the list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    ///Replaces the entry on the list
+    Overwrite,
+    ///Keeps the entry on the list
+    Skip,
+    ///Replaces the entry on the list if `ExportEntry::update_on_import` is set, like MAL's
+    ///importer does
+    UpdateOnImport,
+}
+
+impl ConflictPolicy {
+    pub(crate) const fn overwrites(self, entry: &ExportEntry) -> bool {
+        match self {
+            Self::Overwrite => true,
+            Self::Skip => false,
+            Self::UpdateOnImport => entry.update_on_import,
+        }
+    }
+}
+
+///The outcome of `MALClient::import_list`, by anime id
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub imported: Vec<AnimeId>,
+    ///Already on the list and left alone because of the `ConflictPolicy`
+    pub skipped: Vec<AnimeId>,
+    pub failed: Vec<(AnimeId, MALError)>,
+}
+
+fn no_date() -> String {
+    NO_DATE.to_owned()
+}
+
+///The names MAL's export uses for media types
+const fn export_type(media_type: &MediaType) -> &'static str {
+    match media_type {
+        MediaType::TV => "TV",
+        MediaType::OVA => "OVA",
+        MediaType::Movie => "Movie",
+        MediaType::Special => "Special",
+        MediaType::ONA => "ONA",
+        MediaType::Music => "Music",
+        MediaType::TVSpecial => "TV Special",
+        MediaType::CM => "CM",
+        MediaType::PV => "PV",
+        MediaType::Unknown => "Unknown",
+    }
+}
+
+///Prefix of the CSV columns of the list status, so `my_status` and `status` don't clash
+const LIST_STATUS_PREFIX: &str = "my_";
+
+///How a CSV column is turned back into JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Text,
+    Number,
+    Float,
+    Flag,
+    ///Objects with a `name`, like genres, written as `Action, Drama`
+    Names,
+    ///`start_season`, written as `2019 fall`
+    Season,
+    Tags,
+}
+
+impl Column {
+    ///Returns `None` for the fields that don't fit in a cell, like pictures and related anime
+    fn of(field: &str) -> Option<Self> {
+        Some(match field {
+            "title" | "start_date" | "end_date" | "synopsis" | "nsfw" | "created_at"
+            | "updated_at" | "media_type" | "status" | "source" | "rating" | "background"
+            | "comments" | "finish_date" => Self::Text,
+            "id" | "rank" | "popularity" | "num_list_users" | "num_scoring_users"
+            | "num_episodes" | "average_episode_duration" | "score" | "num_episodes_watched"
+            | "priority" | "num_times_rewatched" | "rewatch_value" => Self::Number,
+            "mean" => Self::Float,
+            "is_rewatching" => Self::Flag,
+            "genres" | "studios" => Self::Names,
+            "start_season" => Self::Season,
+            "tags" => Self::Tags,
+            _ => return None,
+        })
+    }
+
+    fn to_cell(value: &Value) -> String {
+        match value {
+            Value::Null => String::new(),
+            Value::String(s) => s.clone(),
+            Value::Array(items) => {
+                let names: Vec<&str> = items
+                    .iter()
+                    .filter_map(|item| item.get("name").unwrap_or(item).as_str())
+                    .collect();
+                // tags are comma separated like MAL sends them, names are easier to read with spaces
+                let named = items.iter().any(|item| item.get("name").is_some());
+                names.join(if named { ", " } else { "," })
+            }
+            Value::Object(season) => match (season.get("year"), season.get("season")) {
+                (Some(year), Some(Value::String(name))) => format!("{year} {name}"),
+                _ => value.to_string(),
+            },
+            other => other.to_string(),
+        }
+    }
+
+    fn parse(self, cell: &str) -> Option<Value> {
+        let cell = cell.trim();
+        if cell.is_empty() {
+            return None;
+        }
+        Some(match self {
+            Self::Text => Value::from(cell),
+            Self::Number => Value::from(cell.parse::<u64>().ok()?),
+            Self::Float => Value::from(cell.parse::<f64>().ok()?),
+            Self::Flag => Value::from(matches!(cell, "true" | "1")),
+            Self::Names => cell
+                .split(", ")
+                .map(|name| json!({ "name": name.trim() }))
+                .collect(),
+            Self::Season => {
+                let (year, season) = cell.split_once(' ')?;
+                json!({ "year": year.parse::<u64>().ok()?, "season": season })
+            }
+            Self::Tags => serde_json::to_value(cell.split(',').collect::<Tags>()).ok()?,
+        })
+    }
+}
+
+impl AnimeList {
+    ///Writes the list as CSV with a header row. `columns` picks the anime fields and, with
+    ///`AnimeFields::with_list_status`, the list status fields, which get a `my_` prefix like
+    ///`my_score`. `AnimeFields::MyListStatus` alone writes every list status field. The id and
+    ///title are always written first, fields that don't fit in a cell, like pictures, are left
+    ///out.
+    ///
+    ///# Example
+    ///
+    ///```
+    /// use lib_mal::model::AnimeList;
+    /// use lib_mal::model::fields::{AnimeFields, ListStatusFields};
+    /// let list: AnimeList = serde_json::from_str(r#"{"data": [{"node": {"id": 1, "title": "Cowboy Bebop",
+    ///     "main_picture": {}}, "list_status": {"score": 10}}], "paging": {}}"#).unwrap();
+    /// let mut csv = Vec::new();
+    /// list.to_csv(&mut csv, AnimeFields::Title.with_list_status(ListStatusFields::Score)).unwrap();
+    /// assert_eq!(String::from_utf8(csv).unwrap(), "id,title,my_score\n1,Cowboy Bebop,10\n");
+    ///```
+    pub fn to_csv(&self, mut writer: impl Write, columns: AnimeFields) -> Result<(), MALError> {
+        let anime: Vec<&str> = ["id", "title"]
+            .into_iter()
+            .chain(
+                columns
+                    .get_fields_names()
+                    .into_iter()
+                    .filter(|f| !["id", "title"].contains(f) && Column::of(f).is_some()),
+            )
+            .collect();
+        let list_status = if columns.contains(AnimeFields::MyListStatus) {
+            let nested = columns.list_status_fields();
+            if nested.is_empty() { ListStatusFields::ALL } else { nested }.get_fields_names()
+        } else {
+            Vec::new()
+        };
+
+        let header: Vec<String> = anime
+            .iter()
+            .map(|f| (*f).to_owned())
+            .chain(list_status.iter().map(|f| format!("{LIST_STATUS_PREFIX}{f}")))
+            .collect();
+        write_row(&mut writer, &header)?;
+        for entry in &self.data {
+            let node = serde_json::to_value(&entry.details).unwrap_or_default();
+            let status = entry
+                .list_status
+                .as_ref()
+                .or(entry.details.my_list_status.as_ref())
+                .and_then(|s| serde_json::to_value(s).ok())
+                .unwrap_or_default();
+            let row: Vec<String> = anime
+                .iter()
+                .map(|f| Column::to_cell(&node[f]))
+                .chain(list_status.iter().map(|f| Column::to_cell(&status[f])))
+                .collect();
+            write_row(&mut writer, &row)?;
+        }
+        Ok(())
+    }
+
+    ///Reads a list written by [`AnimeList::to_csv`], or any CSV with an `id` column and the
+    ///same column names. Unknown columns are ignored.
+    pub fn from_csv(mut reader: impl Read) -> Result<Self, MALError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let mut rows = parse_csv(&text).into_iter();
+        let header = rows.next().unwrap_or_default();
+        if !header.iter().any(|h| h.trim() == "id") {
+            return Err(MALError::Export(String::from("the CSV has no id column")));
+        }
+        let mut data = Vec::new();
+        for (i, row) in rows.enumerate() {
+            if row.len() != header.len() {
+                return Err(MALError::Export(format!(
+                    "row {} has {} cells, the header has {}",
+                    i + 2,
+                    row.len(),
+                    header.len()
+                )));
+            }
+            let mut node = Map::from_iter([
+                (String::from("title"), Value::from("")),
+                (String::from("main_picture"), json!({})),
+            ]);
+            let mut status = Map::new();
+            for (name, cell) in header.iter().zip(&row) {
+                let name = name.trim();
+                let (map, field) = match name.strip_prefix(LIST_STATUS_PREFIX) {
+                    Some(field) => (&mut status, field),
+                    None => (&mut node, name),
+                };
+                if let Some(value) = Column::of(field).and_then(|c| c.parse(cell)) {
+                    map.insert(field.to_owned(), value);
+                }
+            }
+            let entry = json!({
+                "node": node,
+                "list_status": if status.is_empty() { Value::Null } else { Value::Object(status) },
+            });
+            let entry: ListNode = serde_json::from_value(entry)
+                .map_err(|e| MALError::Export(format!("row {}: {e}", i + 2)))?;
+            data.push(entry);
+        }
+        Ok(Self {
+            data,
+            paging: HashMap::new(),
+            season: None,
+            extra: Map::new(),
+        })
+    }
+
+    ///Writes the list as indented JSON, in the same format MAL sends it in
+    pub fn to_json_pretty(&self) -> Result<String, MALError> {
+        serde_json::to_string_pretty(self).map_err(|e| MALError::Export(e.to_string()))
+    }
+
+    ///Reads a list written by [`AnimeList::to_json_pretty`] or saved from the API
+    pub fn from_json(json: &str) -> Result<Self, MALError> {
+        serde_json::from_str(json).map_err(|e| MALError::Export(e.to_string()))
+    }
+}
+
+fn write_row(writer: &mut impl Write, cells: &[String]) -> Result<(), MALError> {
+    let line: Vec<String> = cells
+        .iter()
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.clone()
+            }
+        })
+        .collect();
+    writeln!(writer, "{}", line.join(","))?;
+    Ok(())
+}
+
+///Splits CSV text into rows of cells, cells in quotes can have commas, quotes, and line breaks
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let (mut row, mut cell) = (Vec::new(), String::new());
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut cell)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => cell.push(c),
+        }
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+    rows
+}
+
+///Statuses are written out like `Plan to Watch`, older exports use numbers
+mod export_status {
+    use super::{Deserialize, Deserializer, Serializer, Status};
+    use serde::de::Error;
+
+    pub fn serialize<S: Serializer>(status: &Status, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match status {
+            Status::Watching => "Watching",
+            Status::Completed => "Completed",
+            Status::OnHold => "On-Hold",
+            Status::Dropped => "Dropped",
+            Status::PlanToWatch => "Plan to Watch",
+        })
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Status, D::Error> {
+        let status = String::deserialize(deserializer)?;
+        match status.trim() {
+            "Watching" | "1" => Ok(Status::Watching),
+            "Completed" | "2" => Ok(Status::Completed),
+            "On-Hold" | "3" => Ok(Status::OnHold),
+            "Dropped" | "4" => Ok(Status::Dropped),
+            "Plan to Watch" | "6" => Ok(Status::PlanToWatch),
+            other => Err(D::Error::custom(format!("unknown status {other}"))),
+        }
+    }
+}
+
+///Flags are written as 0 and 1
+mod flag {
+    use super::{Deserialize, Deserializer, Serializer};
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub fn serialize<S: Serializer>(flag: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(u8::from(*flag))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        let flag = String::deserialize(deserializer)?;
+        Ok(matches!(flag.trim(), "1" | "true"))
+    }
+}
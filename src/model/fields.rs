@@ -2,8 +2,10 @@ use bitflags::bitflags;
 use std::fmt::Display;
 
 bitflags! {
-    #[derive(Copy, Clone)]
-    pub struct AnimeFields: usize {
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+    ///The top-level fields of an anime. The upper 32 bits hold the `my_list_status` fields
+    ///selected with `AnimeFields::with_list_status`.
+    pub struct AnimeFields: u64 {
         const ID                        = 0b0000_0000_0000_0000_0000_0000_0000_0001;
         const Title                     = 0b0000_0000_0000_0000_0000_0000_0000_0010;
         const MainPicture               = 0b0000_0000_0000_0000_0000_0000_0000_0100;
@@ -141,7 +143,143 @@ bits!(
     statistics => Statistics,
 );
 
+impl AnimeFields {
+    const LIST_STATUS_SHIFT: u32 = 32;
+
+    ///Requests `my_list_status` with only `fields`, rendered as `my_list_status{...}`. Replaces
+    ///the fields selected by an earlier call.
+    ///
+    ///# Example
+    ///
+    ///```
+    /// use lib_mal::model::fields::{AnimeFields, ListStatusFields};
+    /// let fields = AnimeFields::Title
+    ///     .with_list_status(ListStatusFields::NumTimesRewatched | ListStatusFields::Comments);
+    /// assert_eq!(fields.to_string(), "title,my_list_status{num_times_rewatched,comments}");
+    ///```
+    #[must_use]
+    pub const fn with_list_status(self, fields: ListStatusFields) -> Self {
+        let top_level = self.bits() & ((1 << Self::LIST_STATUS_SHIFT) - 1);
+        Self::from_bits_retain(
+            top_level
+                | Self::MyListStatus.bits()
+                | ((fields.bits() as u64) << Self::LIST_STATUS_SHIFT),
+        )
+    }
+
+    ///Returns the `my_list_status` fields selected with `with_list_status`, empty if MAL's
+    ///default ones are requested
+    pub const fn list_status_fields(self) -> ListStatusFields {
+        ListStatusFields::from_bits_truncate((self.bits() >> Self::LIST_STATUS_SHIFT) as u32)
+    }
+
+    ///The fields the `minimal-models` feature leaves out of `AnimeDetails`. They're never
+    ///requested with the feature enabled, even if they're part of the requested fields.
+    pub const HEAVY: Self = Self::Pictures
+        .union(Self::Background)
+        .union(Self::Recommendations)
+        .union(Self::Statistics);
+}
+
 impl Display for AnimeFields {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        #[cfg(feature = "minimal-models")]
+        let fields = self.difference(Self::HEAVY);
+        #[cfg(not(feature = "minimal-models"))]
+        let fields = *self;
+        let nested = fields.list_status_fields();
+        for (i, name) in fields.get_fields_names().into_iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            f.write_str(name)?;
+            if name == "my_list_status" && !nested.is_empty() {
+                write!(f, "{{{nested}}}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+bitflags! {
+    ///The fields of a list status, for selecting the ones `my_list_status` and the user's list
+    ///return, see `AnimeFields::with_list_status`
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+    pub struct ListStatusFields: u32 {
+        const Status                = 0b0000_0000_0001;
+        const Score                 = 0b0000_0000_0010;
+        const NumEpisodesWatched    = 0b0000_0000_0100;
+        const IsRewatching          = 0b0000_0000_1000;
+        const StartDate             = 0b0000_0001_0000;
+        const FinishDate            = 0b0000_0010_0000;
+        const Priority              = 0b0000_0100_0000;
+        const NumTimesRewatched     = 0b0000_1000_0000;
+        const RewatchValue          = 0b0001_0000_0000;
+        const Tags                  = 0b0010_0000_0000;
+        const Comments              = 0b0100_0000_0000;
+        const UpdatedAt             = 0b1000_0000_0000;
+        const ALL                   = 0b1111_1111_1111;
+    }
+}
+
+impl ListStatusFields {
+    /// Returns a list of names of all contained fields.
+    pub fn get_fields_names(self) -> Vec<&'static str> {
+        [
+            (Self::Status, "status"),
+            (Self::Score, "score"),
+            (Self::NumEpisodesWatched, "num_episodes_watched"),
+            (Self::IsRewatching, "is_rewatching"),
+            (Self::StartDate, "start_date"),
+            (Self::FinishDate, "finish_date"),
+            (Self::Priority, "priority"),
+            (Self::NumTimesRewatched, "num_times_rewatched"),
+            (Self::RewatchValue, "rewatch_value"),
+            (Self::Tags, "tags"),
+            (Self::Comments, "comments"),
+            (Self::UpdatedAt, "updated_at"),
+        ]
+        .into_iter()
+        .filter(|(field, _)| self.contains(*field))
+        .map(|(_, name)| name)
+        .collect()
+    }
+}
+
+impl Display for ListStatusFields {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.get_fields_names().join(","))
+    }
+}
+
+bitflags! {
+    ///The optional fields of `get_my_user_info`. The id, name, picture, gender, birthday,
+    ///location, and join date are always returned.
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+    pub struct UserFields: usize {
+        const AnimeStatistics   = 0b001;
+        const TimeZone          = 0b010;
+        const IsSupporter       = 0b100;
+        const ALL               = 0b111;
+    }
+}
+
+impl UserFields {
+    /// Returns a list of names of all contained fields.
+    pub fn get_fields_names(self) -> Vec<&'static str> {
+        [
+            (Self::AnimeStatistics, "anime_statistics"),
+            (Self::TimeZone, "time_zone"),
+            (Self::IsSupporter, "is_supporter"),
+        ]
+        .into_iter()
+        .filter(|(field, _)| self.contains(*field))
+        .map(|(_, name)| name)
+        .collect()
+    }
+}
+
+impl Display for UserFields {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.get_fields_names().join(","))
     }
@@ -0,0 +1,77 @@
+//! Typed ids, so an id of one kind can't be passed where another is expected
+//!
+//! Functions taking an id accept `impl Into<AnimeId>` and the like, so plain integers still work:
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::model::ids::AnimeId;
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let details = client.get_anime_details(30230, None).await?;
+//! let same = client.get_anime_details(AnimeId(30230), None).await?;
+//! assert_eq!(details.id, same.id);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Display;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+macro_rules! ids {
+    ($($(#[$doc:meta])* $id:ident),* $(,)?) => {
+        $(
+            $(#[$doc])*
+            #[derive(
+                Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+            )]
+            #[serde(transparent)]
+            pub struct $id(pub usize);
+
+            impl From<usize> for $id {
+                fn from(id: usize) -> Self {
+                    Self(id)
+                }
+            }
+
+            impl From<$id> for usize {
+                fn from(id: $id) -> Self {
+                    id.0
+                }
+            }
+
+            impl PartialEq<usize> for $id {
+                fn eq(&self, other: &usize) -> bool {
+                    self.0 == *other
+                }
+            }
+
+            impl Display for $id {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    self.0.fmt(f)
+                }
+            }
+
+            impl FromStr for $id {
+                type Err = ParseIntError;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    s.parse().map(Self)
+                }
+            }
+        )*
+    };
+}
+
+ids!(
+    ///Id of an anime, like the `30230` in `myanimelist.net/anime/30230`
+    AnimeId,
+    ///Id of a manga
+    MangaId,
+    ///Id of a forum topic
+    TopicId,
+    ///Id of a forum board or subboard
+    BoardId,
+);
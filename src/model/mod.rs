@@ -1,49 +1,672 @@
+pub mod export;
 #[allow(non_upper_case_globals)]
 pub mod fields;
+pub mod ids;
 pub mod options;
+pub mod paging;
+pub use ids::{AnimeId, BoardId, MangaId, TopicId};
+#[allow(deprecated)]
 pub use options::StatusBuilder;
+pub use paging::{PageCursor, Paging};
+use options::{parse_date, parse_timestamp, MediaType, RelationType, Source, Status};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::collections::HashMap;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::time::{Duration, SystemTime};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct AnimeList {
     pub data: Vec<ListNode>,
-    paging: HashMap<String, Value>,
+    pub(crate) paging: HashMap<String, Value>,
     pub season: Option<HashMap<String, Value>>,
+    ///Fields this version of the crate doesn't know about, kept so that serializing the list
+    ///again doesn't lose them
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl AnimeList {
+    ///Returns the cursor of the page after this one, or `None` if this is the last page
+    pub fn next_page(&self) -> Option<PageCursor> {
+        self.paging
+            .get("next")
+            .and_then(Value::as_str)
+            .map(|url| PageCursor::new(url.to_owned()))
+    }
+
+    ///Looks for impossible or conflicting data in the list, like duplicate entries, more
+    ///episodes watched than the anime has, completed entries without any progress, or entries
+    ///finished before they were started.
+    ///
+    ///Checks involving the episode count need the list to be fetched with the `num_episodes`
+    ///field.
+    pub fn find_inconsistencies(&self) -> Vec<Inconsistency> {
+        let mut issues = Vec::new();
+        let mut seen = HashSet::new();
+        for entry in &self.data {
+            let mut report = |kind| {
+                issues.push(Inconsistency {
+                    anime: entry.node.clone(),
+                    kind,
+                });
+            };
+            if !seen.insert(entry.node.id) {
+                report(InconsistencyKind::DuplicateEntry);
+            }
+            let Some(status) = entry.list_status.as_ref().or(entry.details.my_list_status.as_ref())
+            else {
+                continue;
+            };
+            let watched = status.num_episodes_watched.unwrap_or_default();
+            // MAL reports 0 episodes for anime with an unknown episode count
+            if let Some(total) = entry.details.num_episodes.filter(|t| *t > 0) {
+                if watched > total {
+                    report(InconsistencyKind::WatchedExceedsTotal { watched, total });
+                }
+            }
+            if status.status.as_deref() == Some("completed") && watched == 0 {
+                report(InconsistencyKind::CompletedWithoutProgress);
+            }
+            if let (Some(start), Some(finish)) = (&status.start_date, &status.finish_date) {
+                // dates can be partial, like 2017-05, so only compare the common precision
+                let len = start.len().min(finish.len());
+                if finish.as_bytes()[..len] < start.as_bytes()[..len] {
+                    report(InconsistencyKind::FinishedBeforeStarted {
+                        start_date: start.clone(),
+                        finish_date: finish.clone(),
+                    });
+                }
+            }
+        }
+        issues
+    }
+
+    ///Compares `anime` against the completed entries of the list, e.g. to show that it "scores
+    ///higher than 80% of what you've finished".
+    ///
+    ///The mean is compared with the scores the user gave, so entries without a score are ignored.
+    ///The rank is compared with the rank of each completed entry, which needs the list to be
+    ///fetched with the `rank` field. Returns `None` if there's nothing to compare with.
+    pub fn percentile_of(&self, anime: &AnimeDetails) -> Option<ListPercentile> {
+        let completed: Vec<&ListNode> = self
+            .data
+            .iter()
+            .filter(|e| {
+                e.list_status
+                    .as_ref()
+                    .or(e.details.my_list_status.as_ref())
+                    .and_then(|s| s.status.as_deref())
+                    == Some("completed")
+            })
+            .collect();
+        let percent = |below: usize, total: usize| {
+            #[allow(clippy::cast_precision_loss)]
+            (total > 0).then(|| below as f32 / total as f32 * 100.0)
+        };
+
+        let scores: Vec<u8> = completed
+            .iter()
+            .filter_map(|e| {
+                e.list_status
+                    .as_ref()
+                    .or(e.details.my_list_status.as_ref())
+                    .and_then(|s| s.score)
+            })
+            .filter(|s| s.is_scored())
+            .map(Score::get)
+            .collect();
+        let mean = anime.mean.and_then(|mean| {
+            percent(
+                scores.iter().filter(|s| f32::from(**s) < mean).count(),
+                scores.len(),
+            )
+        });
+
+        let ranks: Vec<usize> = completed.iter().filter_map(|e| e.details.rank).collect();
+        let rank = anime.rank.and_then(|rank| {
+            percent(ranks.iter().filter(|r| **r > rank).count(), ranks.len())
+        });
+
+        (mean.is_some() || rank.is_some()).then_some(ListPercentile { mean, rank })
+    }
+
+    ///Returns the Watching entries that weren't updated for longer than `older_than`, like shows
+    ///the user forgot about, oldest first. Entries idle for more than twice `older_than` are
+    ///suggested to be dropped, the rest to be put on hold. The suggestions can be applied with
+    ///`MALClientTrait::apply_stale_suggestions`.
+    ///
+    ///Entries without a list status or `updated_at` are skipped.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # use std::time::Duration;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let list = client.get_user_anime_list(None).await?;
+    ///     let stale = list.stale_entries(Duration::from_secs(90 * 24 * 60 * 60));
+    ///     for entry in &stale {
+    ///         println!("{}: move to {}", entry.anime.title, entry.suggestion);
+    ///     }
+    ///     client.apply_stale_suggestions(&stale).await;
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub fn stale_entries(&self, older_than: Duration) -> Vec<StaleEntry> {
+        self.stale_entries_at(older_than, crate::rt::now())
+    }
+
+    ///Same as `stale_entries`, but measures the time since the last update from `now` instead of
+    ///the system clock
+    pub fn stale_entries_at(&self, older_than: Duration, now: SystemTime) -> Vec<StaleEntry> {
+        let mut stale: Vec<StaleEntry> = self
+            .data
+            .iter()
+            .filter_map(|entry| {
+                let status = entry.list_status.as_ref().or(entry.details.my_list_status.as_ref())?;
+                if status.status.as_deref() != Some("watching") {
+                    return None;
+                }
+                let updated_at = status.updated_at.as_ref()?;
+                let idle = now.duration_since(parse_timestamp(updated_at)?).ok()?;
+                (idle > older_than).then(|| StaleEntry {
+                    anime: entry.node.clone(),
+                    updated_at: updated_at.clone(),
+                    idle,
+                    suggestion: if idle > older_than * 2 {
+                        Status::Dropped
+                    } else {
+                        Status::OnHold
+                    },
+                })
+            })
+            .collect();
+        stale.sort_by_key(|e| std::cmp::Reverse(e.idle));
+        stale
+    }
+
+    ///Returns the anime that are in every one of `lists`, ordered by how interested the users
+    ///are in them. Each user adds the priority of their entry plus one to the interest, ties are
+    ///broken by the mean score.
+    ///
+    ///The priorities need the lists to be fetched with the `list_status{priority}` field.
+    pub fn shared_entries(lists: &[Self]) -> Vec<SharedEntry> {
+        let Some((first, rest)) = lists.split_first() else {
+            return Vec::new();
+        };
+        let interest = |entry: &ListNode| {
+            entry
+                .list_status
+                .as_ref()
+                .and_then(|s| s.priority)
+                .unwrap_or_default()
+                + 1
+        };
+        let mut seen = HashSet::new();
+        let mut shared: Vec<SharedEntry> = first
+            .data
+            .iter()
+            .filter(|entry| seen.insert(entry.node.id))
+            .filter_map(|entry| {
+                let mut total = interest(entry);
+                for list in rest {
+                    total += interest(list.data.iter().find(|e| e.node.id == entry.node.id)?);
+                }
+                Some(SharedEntry {
+                    anime: entry.details.clone(),
+                    interest: total,
+                })
+            })
+            .collect();
+        shared.sort_by(|a, b| {
+            b.interest.cmp(&a.interest).then_with(|| {
+                b.anime
+                    .mean
+                    .partial_cmp(&a.anime.mean)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+        });
+        shared
+    }
+}
+
+///An anime that's on several users' lists, see `AnimeList::shared_entries`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedEntry {
+    pub anime: AnimeDetails,
+    ///The combined interest of all users, higher is better
+    pub interest: usize,
+}
+
+///A search result scored against the title that was looked for, see
+///`MALClientTrait::find_anime`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimeMatch {
+    pub anime: AnimeDetails,
+    ///The title of the anime that matched best, which can be an English title or a synonym
+    pub title: String,
+    ///How well `title` matches, from 0 to 1, see `titles::similarity`
+    pub confidence: f32,
+}
+
+///A Watching entry that wasn't updated in a while, see `AnimeList::stale_entries`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleEntry {
+    pub anime: Anime,
+    pub updated_at: String,
+    ///Time since the entry was last updated
+    pub idle: Duration,
+    ///`Status::OnHold` or `Status::Dropped`
+    pub suggestion: Status,
+}
+
+///Where an anime falls among the completed entries of a list, see `AnimeList::percentile_of`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ListPercentile {
+    ///Percentage of the user's scores that are lower than the anime's mean
+    pub mean: Option<f32>,
+    ///Percentage of the completed anime that are ranked lower than the anime
+    pub rank: Option<f32>,
+}
+
+///An entry of an `AnimeList` with impossible or conflicting data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inconsistency {
+    pub anime: Anime,
+    pub kind: InconsistencyKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InconsistencyKind {
+    ///The anime appears more than once in the list
+    DuplicateEntry,
+    WatchedExceedsTotal { watched: usize, total: usize },
+    ///The entry is marked as completed but has no watched episodes
+    CompletedWithoutProgress,
+    FinishedBeforeStarted {
+        start_date: String,
+        finish_date: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "RawListNode", into = "RawListNode")]
 pub struct ListNode {
     pub node: Anime,
+    ///Every field requested from the list endpoint, `details.show` is the same as `node`
+    pub details: AnimeDetails,
     pub list_status: Option<ListStatus>,
     pub ranking: Option<HashMap<String, usize>>,
+    ///Fields this version of the crate doesn't know about
+    pub extra: Map<String, Value>,
+}
+
+///A `ListNode` as MAL sends it, with every requested field in `node`
+#[derive(Serialize, Deserialize)]
+struct RawListNode {
+    node: AnimeDetails,
+    list_status: Option<ListStatus>,
+    ranking: Option<HashMap<String, usize>>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+impl From<RawListNode> for ListNode {
+    fn from(raw: RawListNode) -> Self {
+        Self {
+            node: raw.node.show.clone(),
+            details: raw.node,
+            list_status: raw.list_status,
+            ranking: raw.ranking,
+            extra: raw.extra,
+        }
+    }
+}
+
+impl From<ListNode> for RawListNode {
+    fn from(entry: ListNode) -> Self {
+        Self {
+            node: entry.details,
+            list_status: entry.list_status,
+            ranking: entry.ranking,
+            extra: entry.extra,
+        }
+    }
+}
+
+impl ListNode {
+    ///Returns how many episodes the user hasn't watched yet, see
+    ///`AnimeDetails::remaining_episodes`. Uses `list_status`, which the list endpoints return
+    ///instead of `my_list_status`.
+    pub fn remaining_episodes(&self) -> Option<usize> {
+        let status = self.list_status.as_ref().or(self.details.my_list_status.as_ref());
+        remaining_episodes(self.details.num_episodes, status)
+    }
+
+    ///Returns how long watching the remaining episodes takes, see
+    ///`AnimeDetails::estimated_time_to_finish`
+    pub fn estimated_time_to_finish(&self) -> Option<Duration> {
+        time_for(self.remaining_episodes()?, self.details.average_episode_duration)
+    }
+}
+
+///Returns the episodes of `num_episodes` that weren't watched according to `status`, `None` if
+///the episode count isn't known
+fn remaining_episodes(num_episodes: Option<usize>, status: Option<&ListStatus>) -> Option<usize> {
+    // MAL sends 0 while the episode count isn't announced
+    let total = num_episodes.filter(|n| *n > 0)?;
+    Some(status.map_or(total, |s| s.remaining_episodes(total)))
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+///Returns how long `episodes` episodes of `seconds_each` take
+pub(crate) fn time_for(episodes: usize, seconds_each: Option<usize>) -> Option<Duration> {
+    let seconds = u64::try_from(episodes.checked_mul(seconds_each.filter(|s| *s > 0)?)?).ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+///A list with the full details of every entry, see `MALClientTrait::enrich_list`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EnrichedList {
+    pub data: Vec<EnrichedNode>,
+    ///Ids of the anime that were deleted or are age restricted
+    #[serde(default)]
+    pub skipped: Vec<AnimeId>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EnrichedNode {
+    pub entry: ListNode,
+    pub details: AnimeDetails,
+}
+
+impl EnrichedList {
+    ///Returns every entry joined with its details
+    pub fn into_pairs(self) -> Vec<(ListNode, AnimeDetails)> {
+        self.data
+            .into_iter()
+            .map(|node| (node.entry, node.details))
+            .collect()
+    }
+}
+
+///Passed to the progress callback of `MALClientTrait::enrich_list_with_progress` after every
+///anime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnrichProgress {
+    ///Id of the anime whose details were just fetched
+    pub id: AnimeId,
+    ///Anime fetched so far, including skipped ones
+    pub done: usize,
+    ///Number of different anime on the list
+    pub total: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ListStatus {
     pub status: Option<String>,
     pub num_episodes_watched: Option<usize>,
-    pub score: Option<u8>,
+    pub score: Option<Score>,
     pub updated_at: Option<String>,
     pub is_rewatching: Option<bool>,
     pub num_times_rewatched: Option<usize>,
     pub priority: Option<usize>,
     pub rewatch_value: Option<usize>,
-    pub tags: Option<Vec<String>>,
+    pub tags: Option<Tags>,
     pub comments: Option<String>,
     pub start_date: Option<String>,
     pub finish_date: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl ListStatus {
+    ///Returns `start_date` as the start of that day in UTC, `None` if it isn't set or only has
+    ///the year or month
+    pub fn started_at(&self) -> Option<SystemTime> {
+        parse_date(self.start_date.as_deref()?)
+    }
+
+    ///Returns `finish_date` as the start of that day in UTC
+    pub fn finished_at(&self) -> Option<SystemTime> {
+        parse_date(self.finish_date.as_deref()?)
+    }
+
+    ///Returns `updated_at` as a `SystemTime`
+    pub fn updated_time(&self) -> Option<SystemTime> {
+        parse_timestamp(self.updated_at.as_deref()?)
+    }
+
+    ///Returns how many of the `num_episodes` episodes of the anime haven't been watched, none
+    ///for completed anime unless they're being rewatched
+    pub fn remaining_episodes(&self, num_episodes: usize) -> usize {
+        let watched = self.num_episodes_watched.unwrap_or_default();
+        if self.status.as_deref() == Some("completed") && self.is_rewatching != Some(true) {
+            return 0;
+        }
+        num_episodes.saturating_sub(watched)
+    }
+}
+
+///A score on the user's list, from 1 to 10, or 0 for an entry that wasn't scored
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(try_from = "u8", into = "u8")]
+pub struct Score(u8);
+
+impl Score {
+    pub const MAX: u8 = 10;
+
+    ///Returns `None` if `score` is above 10
+    pub const fn new(score: u8) -> Option<Self> {
+        if score <= Self::MAX {
+            Some(Self(score))
+        } else {
+            None
+        }
+    }
+
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+
+    ///Returns false for the 0 MAL uses for entries without a score
+    pub const fn is_scored(self) -> bool {
+        self.0 > 0
+    }
+}
+
+impl TryFrom<u8> for Score {
+    type Error = crate::MALError;
+
+    fn try_from(score: u8) -> Result<Self, Self::Error> {
+        Self::new(score).ok_or_else(|| crate::MALError::Invalid {
+            field: "score",
+            reason: format!("{score} isn't between 0 and {}", Self::MAX),
+        })
+    }
+}
+
+impl From<Score> for u8 {
+    fn from(score: Score) -> Self {
+        score.0
+    }
+}
+
+impl PartialEq<u8> for Score {
+    fn eq(&self, other: &u8) -> bool {
+        self.0 == *other
+    }
+}
+
+impl std::str::FromStr for Score {
+    type Err = crate::MALError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let score = s.parse::<u8>().map_err(|e| crate::MALError::Invalid {
+            field: "score",
+            reason: e.to_string(),
+        })?;
+        Self::try_from(score)
+    }
+}
+
+impl std::fmt::Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+///The tags of a list entry, in the order they were added and without duplicates
+///
+///MAL sends the tags as one comma separated string and has no way to escape a comma, so a tag
+///with commas is added as one tag per part. Tags are trimmed and empty ones are dropped.
+///
+///# Example
+///
+///```
+/// use lib_mal::model::Tags;
+/// let mut tags: Tags = ["space", "jazz"].into_iter().collect();
+/// assert!(tags.insert("noir, western"));
+/// assert!(tags.remove("jazz"));
+/// assert_eq!(tags.to_string(), "space,noir,western");
+///```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct Tags(Vec<String>);
+
+impl Tags {
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    ///Adds `tag`, returns false if it was already there
+    pub fn insert(&mut self, tag: &str) -> bool {
+        let mut added = false;
+        for part in split_tags(tag) {
+            if !self.contains(part) {
+                self.0.push(part.to_owned());
+                added = true;
+            }
+        }
+        added
+    }
+
+    ///Removes `tag`, returns false if it wasn't there
+    pub fn remove(&mut self, tag: &str) -> bool {
+        let before = self.0.len();
+        let parts: Vec<&str> = split_tags(tag).collect();
+        self.0.retain(|t| !parts.contains(&t.as_str()));
+        self.0.len() != before
+    }
+
+    pub fn contains(&self, tag: &str) -> bool {
+        self.0.iter().any(|t| t == tag.trim())
+    }
+
+    ///Returns the tags of `self` followed by the ones of `other` that aren't in `self`
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        let mut tags = self.clone();
+        tags.extend(other.iter());
+        tags
+    }
+
+    ///Returns the tags of `self` that are in `other` too
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.iter().filter(|t| other.contains(t)).collect()
+    }
+
+    ///Returns the tags of `self` that aren't in `other`
+    #[must_use]
+    pub fn difference(&self, other: &Self) -> Self {
+        self.iter().filter(|t| !other.contains(t)).collect()
+    }
+}
+
+fn split_tags(tags: &str) -> impl Iterator<Item = &str> {
+    tags.split(',').map(str::trim).filter(|t| !t.is_empty())
+}
+
+impl Deref for Tags {
+    type Target = [String];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<S: AsRef<str>> Extend<S> for Tags {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        for tag in iter {
+            self.insert(tag.as_ref());
+        }
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for Tags {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut tags = Self::new();
+        tags.extend(iter);
+        tags
+    }
+}
+
+impl From<Vec<String>> for Tags {
+    fn from(tags: Vec<String>) -> Self {
+        tags.into_iter().collect()
+    }
+}
+
+impl From<Tags> for Vec<String> {
+    fn from(tags: Tags) -> Self {
+        tags.0
+    }
+}
+
+impl IntoIterator for Tags {
+    type Item = String;
+    type IntoIter = std::vec::IntoIter<String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Tags {
+    type Item = &'a String;
+    type IntoIter = std::slice::Iter<'a, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+///The comma separated form MAL takes in updates
+impl std::fmt::Display for Tags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0.join(","))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Anime {
-    pub id: usize,
+    pub id: AnimeId,
     pub title: String,
     pub main_picture: HashMap<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+///Hashes only the id, since the pictures can't be hashed. Anime that are equal have the same id,
+///so they still hash the same.
+impl Hash for Anime {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+///Every field of an anime, the ones that weren't requested are `None`. The `minimal-models`
+///feature leaves out the fields in `AnimeFields::HEAVY` to save memory.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct AnimeDetails {
     #[serde(flatten)]
     pub show: Anime,
@@ -58,85 +681,1154 @@ pub struct AnimeDetails {
     pub nsfw: Option<String>,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
-    pub media_type: Option<String>,
+    pub media_type: Option<MediaType>,
     pub status: Option<String>,
     pub genres: Option<Vec<HashMap<String, Value>>>,
     pub my_list_status: Option<ListStatus>,
     pub num_episodes: Option<usize>,
     pub start_season: Option<HashMap<String, Value>>,
     pub broadcast: Option<HashMap<String, String>>,
-    pub source: Option<String>,
+    pub source: Option<Source>,
     pub average_episode_duration: Option<usize>,
     pub rating: Option<String>,
+    #[cfg(not(feature = "minimal-models"))]
     pub pictures: Option<Vec<HashMap<String, String>>>,
+    #[cfg(not(feature = "minimal-models"))]
     pub background: Option<String>,
     pub related_anime: Option<Vec<Related>>,
-    pub related_manga: Option<Vec<HashMap<String, Value>>>,
+    pub related_manga: Option<Vec<RelatedManga>>,
+    #[cfg(not(feature = "minimal-models"))]
     pub recommendations: Option<Vec<Recommnendation>>,
     pub studios: Option<Vec<HashMap<String, Value>>>,
+    #[cfg(not(feature = "minimal-models"))]
     pub statistics: Option<Stats>,
+    ///Fields this version of the crate doesn't know about, kept so that an anime stored locally
+    ///can be serialized again without losing them. Not available with `minimal-models`, which
+    ///drops them to save memory.
+    #[cfg(not(feature = "minimal-models"))]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+///Maximum number of characters of the synopsis in a `ShareCard`
+const SHARE_CARD_SYNOPSIS_LEN: usize = 200;
+
+impl AnimeDetails {
+    ///Returns the main title followed by the alternative titles, if they were requested with
+    ///`AnimeFields::AlternativeTitles`. Empty titles are left out.
+    pub fn titles(&self) -> Vec<&str> {
+        let mut titles = vec![self.title.as_str()];
+        if let Some(alt) = &self.alternative_titles {
+            let mut languages: Vec<_> = alt.languages.iter().collect();
+            languages.sort();
+            titles.extend(languages.into_iter().map(|(_, title)| title.as_str()));
+            titles.extend(alt.synonyms.iter().map(String::as_str));
+        }
+        titles.retain(|title| !title.trim().is_empty());
+        titles
+    }
+
+    ///Returns the English title, if it was requested with `AnimeFields::AlternativeTitles` and
+    ///the anime has one
+    pub fn title_english(&self) -> Option<&str> {
+        self.alternative_titles.as_ref()?.english()
+    }
+
+    ///Returns the title in Japanese script, if it was requested with
+    ///`AnimeFields::AlternativeTitles` and the anime has one
+    pub fn title_japanese(&self) -> Option<&str> {
+        self.alternative_titles.as_ref()?.japanese()
+    }
+
+    ///Returns the title in `language`, or in the first language of English, romaji, and
+    ///Japanese that the anime has a title in. Only the romaji title is known without
+    ///`AnimeFields::AlternativeTitles`.
+    ///
+    ///MAL doesn't translate synopses, they're always in English.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::model::fields::AnimeFields;
+    /// use lib_mal::model::TitleLanguage;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let anime = client.get_anime_details(16498, AnimeFields::AlternativeTitles).await?;
+    ///     // "Attack on Titan" rather than "Shingeki no Kyojin"
+    ///     println!("{}", anime.preferred_title(TitleLanguage::English));
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub fn preferred_title(&self, language: TitleLanguage) -> &str {
+        let title = |language| match language {
+            TitleLanguage::English => self.title_english(),
+            TitleLanguage::Romaji => Some(self.title.as_str()).filter(|t| !t.trim().is_empty()),
+            TitleLanguage::Japanese => self.title_japanese(),
+        };
+        [
+            language,
+            TitleLanguage::English,
+            TitleLanguage::Romaji,
+            TitleLanguage::Japanese,
+        ]
+        .into_iter()
+        .find_map(title)
+        .unwrap_or(&self.title)
+    }
+
+    ///Returns the data for a Discord embed or an OpenGraph card of this anime. The synopsis is
+    ///shortened to about 200 characters.
+    ///
+    ///Fetch the anime with the `main_picture`, `mean`, `start_season`, and `synopsis` fields to
+    ///fill in the whole card.
+    pub fn share_card(&self) -> ShareCard {
+        let picture_url = ["large", "medium"]
+            .iter()
+            .find_map(|size| self.main_picture.get(*size)?.as_str())
+            .map(str::to_owned);
+        let season = self.start_season.as_ref().and_then(|s| {
+            let season = s.get("season")?.as_str()?;
+            let year = s.get("year")?.as_u64()?;
+            let mut chars = season.chars();
+            let first = chars.next()?.to_uppercase();
+            Some(format!("{first}{} {year}", chars.as_str()))
+        });
+        ShareCard {
+            title: self.title.clone(),
+            url: format!("https://myanimelist.net/anime/{}", self.id),
+            picture_url,
+            mean: self.mean,
+            season,
+            synopsis: self
+                .synopsis
+                .as_deref()
+                .map(|s| truncate(s.trim(), SHARE_CARD_SYNOPSIS_LEN)),
+        }
+    }
+
+    ///Returns when the next episode airs after `after`, computed from the weekly broadcast time.
+    ///Returns `None` if the anime isn't currently airing or has no regular broadcast time.
+    ///
+    ///Needs the anime to be fetched with the `status` and `broadcast` fields.
+    pub fn next_broadcast(&self, after: SystemTime) -> Option<SystemTime> {
+        if self.status.as_deref() != Some("currently_airing") {
+            return None;
+        }
+        let (weekday, time_of_day) = self.broadcast_slot()?;
+
+        // broadcast times are in JST, count from the Monday of the current week in JST
+        let now = after.duration_since(SystemTime::UNIX_EPOCH).ok()?.as_secs() + JST_OFFSET;
+        let days = now / DAY;
+        // the Unix epoch was a Thursday
+        let monday = days.checked_sub((days + 3) % 7)? * DAY;
+        let mut airs = monday + weekday * DAY + time_of_day;
+        if airs <= now {
+            airs += WEEK;
+        }
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(airs - JST_OFFSET))
+    }
+
+    ///Returns how many episodes have aired by `now`, counting one episode a week from the first
+    ///broadcast on or after the start date. Anime without a broadcast time are counted from the
+    ///start date. Capped at the episode count, if MAL knows it.
+    ///
+    ///Breaks and double episodes aren't in the data MAL provides, so it's an estimate. Returns
+    ///`None` if the start date isn't known to the day.
+    ///
+    ///Needs the anime to be fetched with the `status`, `start_date`, `broadcast`, and
+    ///`num_episodes` fields.
+    pub fn episodes_aired(&self, now: SystemTime) -> Option<usize> {
+        let total = self.num_episodes.filter(|n| *n > 0);
+        match self.status.as_deref() {
+            Some("not_yet_aired") => return Some(0),
+            Some("finished_airing") if total.is_some() => return total,
+            _ => {}
+        }
+        let first = self.first_broadcast()?;
+        let now = now.duration_since(SystemTime::UNIX_EPOCH).ok()?.as_secs() + JST_OFFSET;
+        let aired = match now.checked_sub(first) {
+            Some(since) => usize::try_from(since / WEEK + 1).ok()?,
+            None => 0,
+        };
+        Some(total.map_or(aired, |total| aired.min(total)))
+    }
+
+    ///Returns how many episodes the user hasn't watched yet according to `my_list_status`, every
+    ///episode if the anime isn't on the list. Returns `None` while the episode count isn't known,
+    ///like for most airing anime.
+    ///
+    ///Needs the anime to be fetched with the `num_episodes` and `my_list_status` fields.
+    pub fn remaining_episodes(&self) -> Option<usize> {
+        remaining_episodes(self.num_episodes, self.my_list_status.as_ref())
+    }
+
+    ///Returns how long watching the remaining episodes takes, using the average episode length.
+    ///Returns `None` if the episode count or length isn't known.
+    ///
+    ///Needs the anime to be fetched with the `num_episodes`, `average_episode_duration`, and
+    ///`my_list_status` fields.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// use lib_mal::model::fields::AnimeFields;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     let fields = AnimeFields::NumEpisodes | AnimeFields::AverageEpisodeDuration | AnimeFields::MyListStatus;
+    ///     let anime = client.get_anime_details(21, fields).await?;
+    ///     if let Some(left) = anime.estimated_time_to_finish() {
+    ///         println!("{} hours left", left.as_secs() / 3600);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub fn estimated_time_to_finish(&self) -> Option<Duration> {
+        time_for(self.remaining_episodes()?, self.average_episode_duration)
+    }
+
+    ///Returns when the first episode aired or airs, the first broadcast on or after the start
+    ///date. Anime without a broadcast time are counted from midnight of the start date in JST.
+    ///Returns `None` if the start date isn't known to the day.
+    ///
+    ///Needs the anime to be fetched with the `start_date` and `broadcast` fields.
+    pub fn first_episode_at(&self) -> Option<SystemTime> {
+        let first = self.first_broadcast()?.checked_sub(JST_OFFSET)?;
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(first))
+    }
+
+    ///Returns the first broadcast on or after the start date in seconds since the Unix epoch,
+    ///shifted to JST
+    fn first_broadcast(&self) -> Option<u64> {
+        // the start date is a day in JST
+        let start = parse_date(self.start_date.as_deref()?)?
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+        Some(match self.broadcast_slot() {
+            Some((weekday, time_of_day)) => {
+                let days = start / DAY;
+                // the Unix epoch was a Thursday
+                let start_weekday = (days + 3) % 7;
+                let offset = (weekday + 7 - start_weekday) % 7;
+                start + offset * DAY + time_of_day
+            }
+            None => start,
+        })
+    }
+
+    ///Returns the weekday, counting from Monday as 0, and the second of the day the anime is
+    ///broadcast at in JST
+    pub(crate) fn broadcast_slot(&self) -> Option<(u64, u64)> {
+        let broadcast = self.broadcast.as_ref()?;
+        let weekday = match broadcast.get("day_of_the_week")?.as_str() {
+            "monday" => 0,
+            "tuesday" => 1,
+            "wednesday" => 2,
+            "thursday" => 3,
+            "friday" => 4,
+            "saturday" => 5,
+            "sunday" => 6,
+            _ => return None,
+        };
+        let (hours, minutes) = broadcast.get("start_time")?.split_once(':')?;
+        let time_of_day = hours.parse::<u64>().ok()? * 3600 + minutes.parse::<u64>().ok()? * 60;
+        Some((weekday, time_of_day))
+    }
+}
+
+pub(crate) const DAY: u64 = 24 * 60 * 60;
+pub(crate) const WEEK: u64 = 7 * DAY;
+///MAL's broadcast times are in Japan Standard Time, UTC+9
+pub(crate) const JST_OFFSET: u64 = 9 * 60 * 60;
+
+///Shortens `text` to at most `max` characters, cutting at the last whitespace if possible and
+///appending an ellipsis
+fn truncate(text: &str, max: usize) -> String {
+    let Some((end, _)) = text.char_indices().nth(max) else {
+        return text.to_owned();
+    };
+    // leave room for the ellipsis
+    let cut = &text[..text[..end].char_indices().last().map_or(0, |(i, _)| i)];
+    let cut = cut
+        .rfind(char::is_whitespace)
+        .map_or(cut, |i| &cut[..i])
+        .trim_end_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation());
+    format!("{cut}…")
+}
+
+///An episode of an anime on the user's Watching list is about to air, see
+///`MALClient::countdowns_for_watchlist`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AiringEvent {
+    pub anime: Anime,
+    pub airs_at: SystemTime,
+}
+
+///An airing anime on the user's Watching list, see `MALClient::get_watching_airing`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AiringProgress {
+    pub anime: AnimeDetails,
+    pub list_status: ListStatus,
+    ///Estimated from the start date and broadcast time, see `AnimeDetails::episodes_aired`
+    pub episodes_aired: usize,
+}
+
+impl AiringProgress {
+    pub fn episodes_watched(&self) -> usize {
+        self.list_status.num_episodes_watched.unwrap_or_default()
+    }
+
+    ///How many aired episodes the user hasn't watched yet
+    pub fn behind(&self) -> usize {
+        self.episodes_aired.saturating_sub(self.episodes_watched())
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+///An anime on the user's Plan to Watch list that started airing this season, see
+///`MALClient::plan_to_watch_now_airing`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NowAiring {
+    pub anime: AnimeDetails,
+    ///See `AnimeDetails::first_episode_at`
+    pub first_episode_at: Option<SystemTime>,
+    ///Estimated from the start date and broadcast time, see `AnimeDetails::episodes_aired`
+    pub episodes_aired: Option<usize>,
+}
+
+///Returns the anime of `plan_to_watch` that are currently airing according to `season`, with
+///the data from `season`. The ones that started most recently come first.
+pub(crate) fn now_airing(plan_to_watch: &[ListNode], season: Vec<ListNode>, now: SystemTime) -> Vec<NowAiring> {
+    let mut planned: HashSet<AnimeId> = plan_to_watch.iter().map(|entry| entry.node.id).collect();
+    // removing the id also skips anime that are in the season's list twice
+    let mut res: Vec<NowAiring> = season
+        .into_iter()
+        .map(|entry| entry.details)
+        .filter(|anime| {
+            anime.status.as_deref() == Some("currently_airing") && planned.remove(&anime.id)
+        })
+        .map(|anime| NowAiring {
+            first_episode_at: anime.first_episode_at(),
+            episodes_aired: anime.episodes_aired(now),
+            anime,
+        })
+        .collect();
+    res.sort_by_key(|entry| std::cmp::Reverse(entry.first_episode_at));
+    res
+}
+
+///Compact data of an anime for sharing it, see `AnimeDetails::share_card`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ShareCard {
+    pub title: String,
+    ///Link to the anime's page on MAL
+    pub url: String,
+    ///The largest version of the main picture
+    pub picture_url: Option<String>,
+    pub mean: Option<f32>,
+    ///The season the anime started airing in, like `Fall 2020`
+    pub season: Option<String>,
+    pub synopsis: Option<String>,
+}
+
+impl Deref for AnimeDetails {
+    type Target = Anime;
+
+    fn deref(&self) -> &Self::Target {
+        &self.show
+    }
+}
+
+#[cfg(not(feature = "minimal-models"))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Stats {
-    pub status: HashMap<String, String>,
+    pub status: StatusCounts,
     pub num_list_users: usize,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+///How many users have the anime on their list with each status, see `Stats::status`
+#[cfg(not(feature = "minimal-models"))]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct StatusCounts {
+    #[serde(deserialize_with = "count::deserialize")]
+    pub watching: u64,
+    #[serde(deserialize_with = "count::deserialize")]
+    pub completed: u64,
+    #[serde(deserialize_with = "count::deserialize")]
+    pub on_hold: u64,
+    #[serde(deserialize_with = "count::deserialize")]
+    pub dropped: u64,
+    #[serde(deserialize_with = "count::deserialize")]
+    pub plan_to_watch: u64,
+}
+
+#[cfg(not(feature = "minimal-models"))]
+impl StatusCounts {
+    ///Returns the number of users with the anime on their list
+    pub const fn total(&self) -> u64 {
+        self.watching + self.completed + self.on_hold + self.dropped + self.plan_to_watch
+    }
+}
+
+///MAL sends the status counts as strings, like `"7799"`, but they're written as numbers
+#[cfg(not(feature = "minimal-models"))]
+mod count {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Count {
+        Number(u64),
+        Text(String),
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        match Count::deserialize(deserializer)? {
+            Count::Number(n) => Ok(n),
+            Count::Text(s) => s
+                .trim()
+                .parse()
+                .map_err(|_| D::Error::custom(format!("invalid count {s:?}"))),
+        }
+    }
+}
+
+///The other titles of an anime. MAL only has the English and Japanese titles in `languages`,
+///keyed by `en` and `ja`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct AlternativeTitles {
     pub synonyms: Vec<String>,
     #[serde(flatten)]
     pub languages: HashMap<String, String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl AlternativeTitles {
+    ///Returns the title in the language with the code `language`, like `en`, if it isn't empty
+    pub fn get(&self, language: &str) -> Option<&str> {
+        self.languages
+            .get(language)
+            .map(String::as_str)
+            .filter(|title| !title.trim().is_empty())
+    }
+
+    pub fn english(&self) -> Option<&str> {
+        self.get("en")
+    }
+
+    ///Returns the title in Japanese script
+    pub fn japanese(&self) -> Option<&str> {
+        self.get("ja")
+    }
+}
+
+///The language to show anime titles in, see `AnimeDetails::preferred_title`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TitleLanguage {
+    English,
+    ///The main MAL title, the Japanese title written in the Latin alphabet
+    #[default]
+    Romaji,
+    ///The Japanese title in Japanese script
+    Japanese,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Related {
     pub node: Anime,
-    pub relation_type: String,
+    pub relation_type: RelationType,
+    pub relation_type_formatted: String,
+}
+
+///A relation between two anime of a `Franchise`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FranchiseEdge {
+    pub from: AnimeId,
+    pub to: AnimeId,
+    ///What `to` is to `from`, like `Sequel`
+    pub relation: RelationType,
+}
+
+///The anime connected to one through prequels, sequels, and side stories, see
+///`MALClientTrait::get_franchise`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Franchise {
+    ///The anime the crawl started from
+    pub root: AnimeId,
+    ///Every anime found, including the root, ordered by start date. Anime without one are last.
+    pub anime: Vec<AnimeDetails>,
+    ///The relations between the anime in `anime`, relations to anime outside it are left out
+    pub edges: Vec<FranchiseEdge>,
+}
+
+impl Franchise {
+    ///Orders `anime` chronologically and collects the relations between them
+    pub fn new(root: impl Into<AnimeId>, mut anime: Vec<AnimeDetails>) -> Self {
+        let mut seen = HashSet::new();
+        anime.retain(|a| seen.insert(a.id));
+        anime.sort_by(|a, b| {
+            // dates are YYYY-MM-DD, or shorter when MAL only knows the month or year, so they
+            // sort as strings
+            (a.start_date.is_none(), &a.start_date, a.id).cmp(&(
+                b.start_date.is_none(),
+                &b.start_date,
+                b.id,
+            ))
+        });
+        let edges = anime
+            .iter()
+            .flat_map(|a| {
+                a.related_anime
+                    .iter()
+                    .flatten()
+                    .filter(|r| r.relation_type.is_franchise() && seen.contains(&r.node.id))
+                    .map(|r| FranchiseEdge {
+                        from: a.id,
+                        to: r.node.id,
+                        relation: r.relation_type.clone(),
+                    })
+            })
+            .collect();
+        Self {
+            root: root.into(),
+            anime,
+            edges,
+        }
+    }
+
+    pub fn get(&self, id: impl Into<AnimeId>) -> Option<&AnimeDetails> {
+        let id = id.into();
+        self.anime.iter().find(|a| a.id == id)
+    }
+
+    ///Returns the anime `id` is related to, with how they're related
+    pub fn relations_of(
+        &self,
+        id: impl Into<AnimeId>,
+    ) -> impl Iterator<Item = (&RelationType, &AnimeDetails)> {
+        let id = id.into();
+        self.edges
+            .iter()
+            .filter(move |e| e.from == id)
+            .filter_map(|e| Some((&e.relation, self.get(e.to)?)))
+    }
+}
+
+///A manga related to an anime, like the one it's an adaptation of
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RelatedManga {
+    pub node: Manga,
+    pub relation_type: RelationType,
     pub relation_type_formatted: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Manga {
+    pub id: MangaId,
+    pub title: String,
+    pub main_picture: HashMap<String, Value>,
+}
+
+#[cfg(not(feature = "minimal-models"))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Recommnendation {
     pub node: Anime,
     pub num_recommendations: usize,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+///An anime recommended for the shows the user rated highest, see
+///`MALClient::recommend_from_list`
+#[cfg(not(feature = "minimal-models"))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ListRecommendation {
+    pub anime: Anime,
+    ///The sum of the user's score for every show recommending this one, times how many MAL users
+    ///recommended it for that show
+    pub weight: u64,
+    ///The user's shows that led to this recommendation, the highest rated first
+    pub because: Vec<AnimeId>,
+}
+
+///Adds up the recommendations of `seeds`, which are the user's scores and the anime with their
+///`recommendations`, leaving out anime in `exclude`. Sorted by weight, highest first.
+#[cfg(not(feature = "minimal-models"))]
+pub(crate) fn rank_recommendations(
+    seeds: &[(u8, AnimeDetails)],
+    exclude: &HashSet<AnimeId>,
+) -> Vec<ListRecommendation> {
+    let mut ranked: HashMap<AnimeId, ListRecommendation> = HashMap::new();
+    for (score, seed) in seeds {
+        // unscored shows still count a little
+        let score = u64::from((*score).max(1));
+        for rec in seed.recommendations.iter().flatten() {
+            if exclude.contains(&rec.node.id) {
+                continue;
+            }
+            let entry = ranked
+                .entry(rec.node.id)
+                .or_insert_with(|| ListRecommendation {
+                    anime: rec.node.clone(),
+                    weight: 0,
+                    because: Vec::new(),
+                });
+            entry.weight += score * rec.num_recommendations as u64;
+            entry.because.push(seed.id);
+        }
+    }
+    let mut ranked: Vec<ListRecommendation> = ranked.into_values().collect();
+    ranked.sort_by(|a, b| {
+        b.weight
+            .cmp(&a.weight)
+            .then(b.because.len().cmp(&a.because.len()))
+            .then(a.anime.id.cmp(&b.anime.id))
+    });
+    ranked
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct User {
     pub id: usize,
     pub name: String,
+    pub picture: Option<String>,
+    pub gender: Option<String>,
+    pub birthday: Option<String>,
     pub location: String,
     pub joined_at: String,
-    pub anime_statistics: HashMap<String, f32>,
+    pub anime_statistics: Option<AnimeStatistics>,
+    pub time_zone: Option<String>,
+    pub is_supporter: Option<bool>,
+    ///Fields this version of the crate doesn't know about
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-//TODO: Improve struct coverage for forum fucntions
-#[derive(Serialize, Deserialize, Debug)]
+///The statistics of a user's anime list, see `User::anime_statistics`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct AnimeStatistics {
+    #[serde(rename = "num_items_watching")]
+    pub num_watching: usize,
+    #[serde(rename = "num_items_completed")]
+    pub num_completed: usize,
+    #[serde(rename = "num_items_on_hold")]
+    pub num_on_hold: usize,
+    #[serde(rename = "num_items_dropped")]
+    pub num_dropped: usize,
+    #[serde(rename = "num_items_plan_to_watch")]
+    pub num_plan_to_watch: usize,
+    #[serde(rename = "num_items")]
+    pub num_total: usize,
+    pub num_days_watched: f32,
+    pub num_days_watching: f32,
+    pub num_days_completed: f32,
+    pub num_days_on_hold: f32,
+    pub num_days_dropped: f32,
+    ///Days spent on every entry of the list
+    pub num_days: f32,
+    pub num_episodes: usize,
+    pub num_times_rewatched: usize,
+    pub mean_score: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ForumBoards {
-    pub categories: Vec<HashMap<String, Value>>,
+    pub categories: Vec<ForumCategory>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ForumCategory {
+    pub title: String,
+    pub boards: Vec<ForumBoard>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ForumBoard {
+    pub id: BoardId,
+    pub title: String,
+    pub description: String,
+    pub subboards: Vec<Subboard>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Subboard {
+    pub id: BoardId,
+    pub title: String,
+}
+
+///The author of a forum topic or post
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ForumUser {
+    pub id: usize,
+    pub name: String,
+    ///Only sent with posts
+    #[serde(rename = "forum_avator")]
+    pub forum_avatar: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct TopicDetails {
-    pub data: Vec<HashMap<String, Value>>,
+    pub data: Topic,
     pub paging: HashMap<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl TopicDetails {
+    ///Returns true if the topic has more posts after this page
+    pub fn has_next_page(&self) -> bool {
+        self.paging.get("next").is_some_and(Value::is_string)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Topic {
+    pub title: String,
+    pub posts: Vec<ForumPost>,
+    pub poll: Option<Poll>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ForumPost {
+    pub id: usize,
+    ///Position of the post in the topic, starting at 1
+    pub number: usize,
+    pub created_at: String,
+    pub created_by: ForumUser,
+    ///BBCode formatted
+    pub body: String,
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Poll {
+    pub id: usize,
+    pub question: String,
+    #[serde(rename = "close")]
+    pub closed: bool,
+    pub options: Vec<PollOption>,
+}
+
+impl Poll {
+    ///Returns the total number of votes cast
+    pub fn total_votes(&self) -> usize {
+        self.options.iter().map(|o| o.votes).sum()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PollOption {
+    pub id: usize,
+    pub text: String,
+    pub votes: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ForumTopics {
-    pub data: Vec<HashMap<String, Value>>,
-    pub paging: Vec<HashMap<String, Value>>,
+    pub data: Vec<ForumTopic>,
+    pub paging: Paging,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ForumTopic {
+    pub id: TopicId,
+    pub title: String,
+    pub created_at: String,
+    pub created_by: ForumUser,
+    pub number_of_posts: usize,
+    pub last_post_created_at: String,
+    pub last_post_created_by: ForumUser,
+    pub is_locked: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct EpisodesList {
     pub data: Vec<EpisodeNode>,
     pub pagination: HashMap<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+impl EpisodesList {
+    ///Returns true if Jikan has more episodes after this page
+    pub fn has_next_page(&self) -> bool {
+        self.pagination
+            .get("has_next_page")
+            .and_then(Value::as_bool)
+            .unwrap_or_default()
+    }
+}
+
+///The manga or novel entry an anime was adapted from, see `JikanClient::source_material`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceMaterial {
+    pub mal_id: usize,
+    #[serde(rename = "name")]
+    pub title: String,
+    ///The manga's page on myanimelist.net
+    pub url: String,
+}
+
+///The `data` of Jikan's themes endpoint, MAL's list of songs as written on the anime's page
+#[cfg(feature = "jikan")]
+#[derive(Deserialize, Debug)]
+pub(crate) struct JikanThemes {
+    #[serde(default)]
+    pub openings: Vec<String>,
+    #[serde(default)]
+    pub endings: Vec<String>,
+}
+
+#[cfg(feature = "jikan")]
+impl JikanThemes {
+    pub(crate) fn into_songs(self) -> Vec<ThemeSong> {
+        let openings = self
+            .openings
+            .iter()
+            .filter_map(|s| ThemeSong::parse(ThemeKind::Opening, s));
+        let endings = self
+            .endings
+            .iter()
+            .filter_map(|s| ThemeSong::parse(ThemeKind::Ending, s));
+        openings.chain(endings).collect()
+    }
+}
+
+#[cfg(feature = "jikan")]
+#[derive(Deserialize, Debug)]
+pub(crate) struct JikanRelations {
+    pub data: Vec<JikanRelation>,
+}
+
+#[cfg(feature = "jikan")]
+#[derive(Deserialize, Debug)]
+pub(crate) struct JikanRelation {
+    pub relation: RelationType,
+    pub entry: Vec<JikanRelationEntry>,
+}
+
+#[cfg(feature = "jikan")]
+#[derive(Deserialize, Debug)]
+pub(crate) struct JikanRelationEntry {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(flatten)]
+    pub material: SourceMaterial,
+}
+
+#[cfg(feature = "jikan")]
+impl JikanRelations {
+    ///Returns the manga entries the anime is an adaptation of
+    pub(crate) fn into_source_material(self) -> Vec<SourceMaterial> {
+        self.data
+            .into_iter()
+            .filter(|r| r.relation == RelationType::Adaptation)
+            .flat_map(|r| r.entry)
+            .filter(|e| e.kind == "manga")
+            .map(|e| e.material)
+            .collect()
+    }
+}
+
+///A character or person on Jikan
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub mal_id: usize,
+    pub name: String,
+    ///The page on myanimelist.net
+    pub url: String,
+    #[serde(default)]
+    pub images: HashMap<String, Value>,
+}
+
+///A character of an anime, see `JikanClient::characters`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Character {
+    pub character: Profile,
+    ///`Main` or `Supporting`
+    pub role: String,
+    #[serde(default)]
+    pub favorites: usize,
+    #[serde(default)]
+    pub voice_actors: Vec<VoiceActor>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct VoiceActor {
+    pub person: Profile,
+    pub language: String,
+}
+
+///A person who worked on an anime, see `JikanClient::staff`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StaffMember {
+    pub person: Profile,
+    ///Like `Director` or `Sound Director`
+    pub positions: Vec<String>,
+}
+
+///A service an anime can be streamed on, see `JikanClient::streaming`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StreamingLink {
+    pub name: String,
+    pub url: String,
+}
+
+///A news article about an anime, see `MALClientTrait::get_anime_news`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NewsItem {
+    pub title: String,
+    ///The article on myanimelist.net
+    pub url: String,
+    ///When it was published, like `2024-01-06T06:00:00+00:00`
+    pub date: String,
+    ///The start of the article
+    #[serde(default)]
+    pub excerpt: String,
+    ///URL of the article's thumbnail
+    #[serde(default, alias = "images", deserialize_with = "news_image::deserialize")]
+    pub image: Option<String>,
+}
+
+impl NewsItem {
+    ///Returns `date` as a `SystemTime`, or `None` if it can't be parsed
+    pub fn published_at(&self) -> Option<SystemTime> {
+        parse_timestamp(&self.date)
+    }
+}
+
+///Jikan sends the thumbnail as `images.jpg.image_url`, it's written as just the URL
+mod news_image {
+    use serde::{Deserialize, Deserializer};
+    use serde_json::Value;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+        let url = match Value::deserialize(deserializer)? {
+            Value::String(url) => Some(url),
+            images => images["jpg"]["image_url"].as_str().map(str::to_owned),
+        };
+        Ok(url.filter(|url| !url.is_empty()))
+    }
+}
+
+///A user's review of an anime, see `MALClientTrait::get_anime_reviews`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Review {
+    pub mal_id: usize,
+    ///The review on myanimelist.net
+    pub url: String,
+    #[serde(rename = "user")]
+    pub author: ReviewAuthor,
+    ///The score the author gave the anime, from 1 to 10
+    pub score: u8,
+    ///When it was posted, like `2009-06-03T00:00:00+00:00`
+    pub date: String,
+    #[serde(rename = "review")]
+    pub text: String,
+    #[serde(default)]
+    pub reactions: ReviewReactions,
+    ///Like `Recommended`, `Mixed Feelings`, or `Not Recommended`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub is_spoiler: bool,
+    ///The author hadn't finished the anime when they wrote the review
+    #[serde(default)]
+    pub is_preliminary: bool,
+    ///How many episodes the author had watched, only set for preliminary reviews
+    pub episodes_watched: Option<usize>,
+}
+
+impl Review {
+    ///Returns `date` as a `SystemTime`, or `None` if it can't be parsed
+    pub fn posted_at(&self) -> Option<SystemTime> {
+        parse_timestamp(&self.date)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReviewAuthor {
+    pub username: String,
+    ///The author's profile on myanimelist.net
+    pub url: String,
+}
+
+///How many users reacted to a review with each reaction
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct ReviewReactions {
+    ///All reactions together
+    pub overall: usize,
+    pub nice: usize,
+    pub love_it: usize,
+    pub funny: usize,
+    pub confusing: usize,
+    pub informative: usize,
+    pub well_written: usize,
+    pub creative: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReviewsPage {
+    pub data: Vec<Review>,
+    pub pagination: HashMap<String, Value>,
+}
+
+impl ReviewsPage {
+    ///Returns true if Jikan has more reviews after this page
+    pub fn has_next_page(&self) -> bool {
+        self.pagination
+            .get("has_next_page")
+            .and_then(Value::as_bool)
+            .unwrap_or_default()
+    }
+}
+
+///Whether a theme song plays at the start or the end of the episodes
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ThemeKind {
+    Opening,
+    Ending,
+}
+
+///An opening or ending of an anime, see `MALClientTrait::get_anime_themes`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ThemeSong {
+    pub kind: ThemeKind,
+    ///Like the 2 of OP2, `None` for anime with only one opening or ending
+    pub number: Option<usize>,
+    pub title: String,
+    pub artist: Option<String>,
+    ///The episodes it plays in as MAL writes them, like `1-26` or `1, 13`
+    pub episodes: Option<String>,
+}
+
+#[cfg(feature = "jikan")]
+impl ThemeSong {
+    ///Parses a song the way MAL lists them, like `1: "Go EXCEED!!" by Tomohisa Sako (eps 1-26)`
+    pub(crate) fn parse(kind: ThemeKind, text: &str) -> Option<Self> {
+        let mut rest = text.trim().trim_start_matches('#');
+        let mut number = None;
+        if let Some((n, after)) = rest.split_once(':') {
+            if let Ok(n) = n.trim().parse() {
+                number = Some(n);
+                rest = after.trim();
+            }
+        }
+        let mut episodes = None;
+        if let Some(start) = rest.rfind("(ep").filter(|_| rest.ends_with(')')) {
+            let range = rest[start + 3..rest.len() - 1].trim_start_matches('s').trim();
+            episodes = Some(range.to_owned()).filter(|r| !r.is_empty());
+            rest = rest[..start].trim_end();
+        }
+        let (title, artist) = match rest.strip_prefix('"') {
+            Some(quoted) => match quoted.rfind("\" by ") {
+                Some(end) => (&quoted[..end], Some(quoted[end + 5..].trim())),
+                None => (quoted.trim_end_matches('"'), None),
+            },
+            None => match rest.rsplit_once(" by ") {
+                Some((title, artist)) => (title, Some(artist.trim())),
+                None => (rest, None),
+            },
+        };
+        let title = title.trim();
+        if title.is_empty() {
+            return None;
+        }
+        Some(Self {
+            kind,
+            number,
+            title: title.to_owned(),
+            artist: artist.filter(|a| !a.is_empty()).map(str::to_owned),
+            episodes,
+        })
+    }
+}
+
+///A genre, theme, or demographic, see `MALClientTrait::get_genres`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Genre {
+    ///The id to pass to `MALClientTrait::browse_by_genre`
+    pub mal_id: usize,
+    pub name: String,
+    ///The genre's page on myanimelist.net
+    pub url: String,
+    ///How many anime have it
+    #[serde(default)]
+    pub count: usize,
+}
+
+///Every genre MAL sorts anime into, grouped like on the site
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct GenreCatalog {
+    ///Like Action or Sports
+    pub genres: Vec<Genre>,
+    ///Hentai, Ecchi, and Erotica
+    pub explicit_genres: Vec<Genre>,
+    ///Like School or Time Travel
+    pub themes: Vec<Genre>,
+    ///Like Shounen or Josei
+    pub demographics: Vec<Genre>,
+}
+
+impl GenreCatalog {
+    ///Returns every genre, theme, and demographic
+    pub fn all(&self) -> impl Iterator<Item = &Genre> {
+        self.genres
+            .iter()
+            .chain(&self.explicit_genres)
+            .chain(&self.themes)
+            .chain(&self.demographics)
+    }
+
+    ///Returns the genre, theme, or demographic called `name`, ignoring case
+    pub fn find(&self, name: &str) -> Option<&Genre> {
+        self.all().find(|g| g.name.eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+///An anime found by `MALClientTrait::browse_by_genre`, with the data Jikan sends in lists. See
+///`get_anime_details` for everything else.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BrowsedAnime {
+    pub mal_id: AnimeId,
+    pub title: String,
+    ///The anime's page on myanimelist.net
+    pub url: String,
+    ///URL of the anime's poster
+    #[serde(default, alias = "images", deserialize_with = "news_image::deserialize")]
+    pub image: Option<String>,
+    ///Like `TV` or `Movie`
+    #[serde(rename = "type")]
+    pub media_type: Option<String>,
+    pub episodes: Option<usize>,
+    pub score: Option<f32>,
+    ///How many users have the anime on their list
+    pub members: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BrowsePage {
+    pub data: Vec<BrowsedAnime>,
+    pub pagination: HashMap<String, Value>,
+}
+
+impl BrowsePage {
+    ///Returns true if Jikan has more anime after this page
+    pub fn has_next_page(&self) -> bool {
+        self.pagination
+            .get("has_next_page")
+            .and_then(Value::as_bool)
+            .unwrap_or_default()
+    }
+}
+
+///The envelope of Jikan responses
+#[cfg(feature = "jikan")]
+#[derive(Deserialize, Debug)]
+pub(crate) struct JikanData<T> {
+    pub data: T,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct EpisodeNode {
     pub mal_id: Option<usize>,
     pub url: Option<String>,
@@ -150,3 +1842,27 @@ pub struct EpisodeNode {
     pub recap: Option<bool>,
     pub forum_url: Option<String>,
 }
+
+///A typed response together with the JSON it was parsed from, for fields MAL sends that the
+///crate doesn't model yet, see `MALClient::get_anime_details_with_raw`
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithRaw<T> {
+    pub data: T,
+    pub raw: Value,
+}
+
+impl<'de, T: serde::de::DeserializeOwned> Deserialize<'de> for WithRaw<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = Value::deserialize(deserializer)?;
+        let data = T::deserialize(&raw).map_err(serde::de::Error::custom)?;
+        Ok(Self { data, raw })
+    }
+}
+
+impl<T> Deref for WithRaw<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
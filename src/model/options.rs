@@ -1,6 +1,15 @@
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+use std::str::FromStr;
+use std::time::SystemTime;
 
-#[derive(Debug)]
+use super::fields::{AnimeFields, ListStatusFields};
+use super::ids::BoardId;
+use super::{ListStatus, Review, Score, Tags};
+use crate::clock::unix_secs;
+use crate::limits::{Limit, DEFAULT_LIMIT};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RankingType {
     All,
     Airing,
@@ -30,7 +39,8 @@ impl Display for RankingType {
     }
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
 pub enum Season {
     Winter,
     Spring,
@@ -50,7 +60,263 @@ impl Display for Season {
     }
 }
 
-#[derive(Debug, Clone)]
+impl Season {
+    ///Returns the season anime starting in `month`, from 1 for January to 12 for December
+    pub const fn from_month(month: u32) -> Self {
+        match month {
+            1..=3 => Self::Winter,
+            4..=6 => Self::Spring,
+            7..=9 => Self::Summer,
+            _ => Self::Fall,
+        }
+    }
+}
+
+///A season of a specific year, see `get_seasonal_anime`
+///
+///# Example
+///
+///```
+/// use lib_mal::model::options::{Season, SeasonYear};
+/// let season = SeasonYear::new(Season::Fall, 2023);
+/// assert_eq!(season.next(), SeasonYear::new(Season::Winter, 2024));
+/// assert_eq!(season.prev().to_string(), "summer 2023");
+///```
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SeasonYear {
+    pub season: Season,
+    pub year: usize,
+}
+
+impl SeasonYear {
+    ///The earliest season on MAL's seasonal pages
+    pub const FIRST: Self = Self::new(Season::Winter, 1917);
+
+    pub const fn new(season: Season, year: usize) -> Self {
+        Self { season, year }
+    }
+
+    ///Returns the current season according to the system clock, in UTC
+    pub fn now() -> Self {
+        Self::at(crate::rt::now())
+    }
+
+    ///Returns the season `time` falls in, in UTC
+    pub fn at(time: SystemTime) -> Self {
+        let (year, month, _) = civil_from_days(unix_secs(time) / 86400);
+        Self::new(Season::from_month(month), year)
+    }
+
+    pub const fn next(self) -> Self {
+        match self.season {
+            Season::Winter => Self::new(Season::Spring, self.year),
+            Season::Spring => Self::new(Season::Summer, self.year),
+            Season::Summer => Self::new(Season::Fall, self.year),
+            Season::Fall => Self::new(Season::Winter, self.year + 1),
+        }
+    }
+
+    pub const fn prev(self) -> Self {
+        match self.season {
+            Season::Winter => Self::new(Season::Fall, self.year.saturating_sub(1)),
+            Season::Spring => Self::new(Season::Winter, self.year),
+            Season::Summer => Self::new(Season::Spring, self.year),
+            Season::Fall => Self::new(Season::Summer, self.year),
+        }
+    }
+
+    ///Returns up to `n` seasons starting with this one and going back, stopping at
+    ///`SeasonYear::FIRST`
+    pub fn iter_back(self, n: usize) -> impl Iterator<Item = Self> {
+        std::iter::successors(Some(self), |s| (*s > Self::FIRST).then(|| s.prev()))
+            .take_while(|s| *s >= Self::FIRST)
+            .take(n)
+    }
+
+    ///Returns every season in MAL's archive, from the current one back to `SeasonYear::FIRST`,
+    ///e.g. for a season picker
+    pub fn archive() -> impl Iterator<Item = Self> {
+        Self::now().iter_back(usize::MAX)
+    }
+
+    const fn index(self) -> usize {
+        self.year * 4 + self.season as usize
+    }
+}
+
+impl PartialOrd for SeasonYear {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SeasonYear {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.index().cmp(&other.index())
+    }
+}
+
+impl From<(Season, usize)> for SeasonYear {
+    fn from((season, year): (Season, usize)) -> Self {
+        Self::new(season, year)
+    }
+}
+
+impl Display for SeasonYear {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.season, self.year)
+    }
+}
+
+///Converts days since the Unix epoch to the year, month, and day, using the algorithm from
+///<https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+#[allow(clippy::cast_possible_truncation)]
+const fn civil_from_days(days: u64) -> (usize, u32, u32) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year as usize, month as u32, day as u32)
+}
+
+///Returns the date of `time` in UTC in the `YYYY-MM-DD` format MAL uses for list dates
+pub(crate) fn date_of(time: SystemTime) -> String {
+    let (year, month, day) = civil_from_days(unix_secs(time) / 86400);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+///Returns `time` in UTC in the format iCalendar uses, like `20240106T150000Z`
+pub(crate) fn ical_time_of(time: SystemTime) -> String {
+    let secs = unix_secs(time);
+    let (year, month, day) = civil_from_days(secs / 86400);
+    let (hour, minute, second) = (secs % 86400 / 3600, secs % 3600 / 60, secs % 60);
+    format!("{year:04}{month:02}{day:02}T{hour:02}{minute:02}{second:02}Z")
+}
+
+///Converts a date to days since the Unix epoch, using the algorithm from
+///<https://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+const fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+///Parses a full `YYYY-MM-DD` date, returning midnight UTC of that day. MAL leaves out the day
+///or month of some dates, those return `None`.
+pub(crate) fn parse_date(s: &str) -> Option<SystemTime> {
+    if s.len() != 10 {
+        return None;
+    }
+    let num = |range: std::ops::Range<usize>| s.get(range)?.parse::<i64>().ok();
+    let days = days_from_civil(num(0..4)?, num(5..7)?, num(8..10)?);
+    let secs = u64::try_from(days * 86400).ok()?;
+    Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+}
+
+///Parses the RFC 3339 timestamps MAL uses, like `2017-11-11T19:51:22+00:00`
+pub(crate) fn parse_timestamp(s: &str) -> Option<SystemTime> {
+    let num = |range: std::ops::Range<usize>| s.get(range)?.parse::<i64>().ok();
+    let days = days_from_civil(num(0..4)?, num(5..7)?, num(8..10)?);
+    let mut secs = days * 86400 + num(11..13)? * 3600 + num(14..16)? * 60 + num(17..19)?;
+    // skip fractional seconds, then apply the offset to get UTC
+    let offset = s[19..].trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    if offset != "Z" {
+        let sign = match offset.chars().next()? {
+            '+' => -1,
+            '-' => 1,
+            _ => return None,
+        };
+        let hours = offset.get(1..3)?.parse::<i64>().ok()?;
+        let minutes = offset.get(4..6)?.parse::<i64>().ok()?;
+        secs += sign * (hours * 3600 + minutes * 60);
+    }
+    let secs = u64::try_from(secs).ok()?;
+    Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+}
+
+///Order of the anime returned by `get_seasonal_anime`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SeasonalSort {
+    ///Highest mean score first
+    AnimeScore,
+    ///Most list users first
+    AnimeNumListUsers,
+}
+
+impl Display for SeasonalSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let me = match self {
+            Self::AnimeScore => "anime_score".to_owned(),
+            Self::AnimeNumListUsers => "anime_num_list_users".to_owned(),
+        };
+        write!(f, "{me}")
+    }
+}
+
+///Order of the anime returned by `browse_by_genre`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GenreSort {
+    ///Highest mean score first
+    #[default]
+    Score,
+    ///Most list users first
+    Popularity,
+    ///Most recently started first
+    Newest,
+    ///Alphabetically by title
+    Title,
+}
+
+impl GenreSort {
+    ///Returns Jikan's `order_by` and `sort` parameters
+    #[cfg(feature = "jikan")]
+    pub(crate) const fn params(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Score => ("score", "desc"),
+            Self::Popularity => ("members", "desc"),
+            Self::Newest => ("start_date", "desc"),
+            Self::Title => ("title", "asc"),
+        }
+    }
+}
+
+///Order of the reviews of a page of `get_anime_reviews`. Jikan can't sort reviews, so only the
+///reviews of the page are sorted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ReviewSort {
+    ///Newest first, the order Jikan sends them in
+    #[default]
+    Newest,
+    Oldest,
+    ///Most reactions first
+    MostReactions,
+    HighestScore,
+    LowestScore,
+}
+
+impl ReviewSort {
+    ///Sorts `reviews` in this order, reviews that are tied keep their order
+    pub fn sort(self, reviews: &mut [Review]) {
+        match self {
+            Self::Newest => reviews.sort_by_key(|r| std::cmp::Reverse(r.posted_at())),
+            Self::Oldest => reviews.sort_by_key(Review::posted_at),
+            Self::MostReactions => reviews.sort_by_key(|r| std::cmp::Reverse(r.reactions.overall)),
+            Self::HighestScore => reviews.sort_by_key(|r| std::cmp::Reverse(r.score)),
+            Self::LowestScore => reviews.sort_by_key(|r| r.score),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Status {
     Watching,
     Completed,
@@ -72,109 +338,648 @@ impl Display for Status {
     }
 }
 
-pub trait Params {
-    fn get_params<'a>(self) -> Vec<(&'a str, String)>;
+impl FromStr for Status {
+    type Err = String;
+
+    ///Parses the status names the API uses, like `plan_to_watch`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "watching" => Ok(Self::Watching),
+            "completed" => Ok(Self::Completed),
+            "on_hold" => Ok(Self::OnHold),
+            "dropped" => Ok(Self::Dropped),
+            "plan_to_watch" => Ok(Self::PlanToWatch),
+            _ => Err(format!("unknown status {s}")),
+        }
+    }
 }
 
-#[derive(Debug, Default)]
-pub struct StatusUpdate {
-    status: Option<Status>,
-    is_rewatching: Option<bool>,
-    score: Option<u8>,
-    num_watched_episodes: Option<usize>,
-    priority: Option<u8>,
-    num_times_rewatched: Option<usize>,
-    rewatch_value: Option<u8>,
-    tags: Option<Vec<String>>,
-    comments: Option<String>,
-    start_date: Option<String>,
-    finish_date: Option<String>,
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaType {
+    TV,
+    OVA,
+    Movie,
+    Special,
+    ONA,
+    Music,
+    #[serde(rename = "tv_special")]
+    TVSpecial,
+    CM,
+    PV,
+    #[serde(other)]
+    Unknown,
 }
 
-impl StatusUpdate {
-    #[must_use] pub fn new() -> Self {
-        Self::default()
+impl Display for MediaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let me = match self {
+            Self::TV => "tv".to_owned(),
+            Self::OVA => "ova".to_owned(),
+            Self::Movie => "movie".to_owned(),
+            Self::Special => "special".to_owned(),
+            Self::ONA => "ona".to_owned(),
+            Self::Music => "music".to_owned(),
+            Self::TVSpecial => "tv_special".to_owned(),
+            Self::CM => "cm".to_owned(),
+            Self::PV => "pv".to_owned(),
+            Self::Unknown => "unknown".to_owned(),
+        };
+        write!(f, "{me}")
     }
+}
 
-    pub fn status(&mut self, status: Status) {
-        self.status = Some(status);
+///The original work an anime is based on
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Original,
+    Manga,
+    #[serde(rename = "4_koma_manga")]
+    YonKomaManga,
+    WebManga,
+    DigitalManga,
+    Novel,
+    LightNovel,
+    VisualNovel,
+    Game,
+    CardGame,
+    Book,
+    PictureBook,
+    Radio,
+    Music,
+    WebNovel,
+    MixedMedia,
+    #[serde(other)]
+    Other,
+}
+
+impl Source {
+    ///Returns true if the source is a manga or a novel, which MAL lists as a manga entry that
+    ///`JikanClient::source_material` can find
+    pub const fn is_readable(self) -> bool {
+        matches!(
+            self,
+            Self::Manga
+                | Self::YonKomaManga
+                | Self::WebManga
+                | Self::DigitalManga
+                | Self::Novel
+                | Self::LightNovel
+                | Self::WebNovel
+                | Self::Book
+                | Self::PictureBook
+        )
     }
+}
 
-    pub fn is_rewatching(&mut self, is_rewatching: bool) {
-        self.is_rewatching = Some(is_rewatching);
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let me = match self {
+            Self::Original => "original",
+            Self::Manga => "manga",
+            Self::YonKomaManga => "4_koma_manga",
+            Self::WebManga => "web_manga",
+            Self::DigitalManga => "digital_manga",
+            Self::Novel => "novel",
+            Self::LightNovel => "light_novel",
+            Self::VisualNovel => "visual_novel",
+            Self::Game => "game",
+            Self::CardGame => "card_game",
+            Self::Book => "book",
+            Self::PictureBook => "picture_book",
+            Self::Radio => "radio",
+            Self::Music => "music",
+            Self::WebNovel => "web_novel",
+            Self::MixedMedia => "mixed_media",
+            Self::Other => "other",
+        };
+        write!(f, "{me}")
     }
+}
 
-    pub fn score(&mut self, score: u8) {
-        self.score = Some(score);
+///How a related anime or manga is connected to the one it's listed on
+///
+///Parses both MAL's names like `side_story` and the formatted ones Jikan uses like `Side Story`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum RelationType {
+    Sequel,
+    Prequel,
+    SideStory,
+    ParentStory,
+    Summary,
+    FullStory,
+    SpinOff,
+    AlternativeSetting,
+    AlternativeVersion,
+    Adaptation,
+    ///Shares characters with it
+    Character,
+    ///MAL's `other`, and relation types this crate doesn't know about, by their name
+    Other(String),
+}
+
+impl RelationType {
+    ///Returns true for the relations that continue or precede the story, the ones a watch order
+    ///follows
+    pub const fn is_story(&self) -> bool {
+        matches!(
+            self,
+            Self::Sequel | Self::Prequel | Self::ParentStory | Self::FullStory
+        )
     }
-    pub fn num_watched_episodes(&mut self, num_watched_episodes: usize) {
-        self.num_watched_episodes = Some(num_watched_episodes);
+
+    ///Returns true for the relations `get_franchise` follows, the story ones and side stories
+    pub const fn is_franchise(&self) -> bool {
+        self.is_story() || matches!(self, Self::SideStory)
     }
-    pub fn priority(&mut self, priority: u8) {
-        self.priority = Some(priority);
+}
+
+impl From<&str> for RelationType {
+    fn from(name: &str) -> Self {
+        match name.to_lowercase().replace([' ', '-'], "_").as_str() {
+            "sequel" => Self::Sequel,
+            "prequel" => Self::Prequel,
+            "side_story" => Self::SideStory,
+            "parent_story" => Self::ParentStory,
+            "summary" => Self::Summary,
+            "full_story" => Self::FullStory,
+            "spin_off" => Self::SpinOff,
+            "alternative_setting" => Self::AlternativeSetting,
+            "alternative_version" => Self::AlternativeVersion,
+            "adaptation" => Self::Adaptation,
+            "character" => Self::Character,
+            _ => Self::Other(name.to_owned()),
+        }
     }
-    pub fn num_times_rewatched(&mut self, num_times_rewatched: usize) {
-        self.num_times_rewatched = Some(num_times_rewatched);
+}
+
+impl From<String> for RelationType {
+    fn from(name: String) -> Self {
+        Self::from(name.as_str())
     }
-    pub fn rewatch_value(&mut self, rewatch_value: u8) {
-        self.rewatch_value = Some(rewatch_value);
+}
+
+impl From<RelationType> for String {
+    fn from(relation: RelationType) -> Self {
+        relation.to_string()
     }
-    pub fn tags(&mut self, tags: Vec<String>) {
-        self.tags = Some(tags);
+}
+
+impl Display for RelationType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let me = match self {
+            Self::Sequel => "sequel",
+            Self::Prequel => "prequel",
+            Self::SideStory => "side_story",
+            Self::ParentStory => "parent_story",
+            Self::Summary => "summary",
+            Self::FullStory => "full_story",
+            Self::SpinOff => "spin_off",
+            Self::AlternativeSetting => "alternative_setting",
+            Self::AlternativeVersion => "alternative_version",
+            Self::Adaptation => "adaptation",
+            Self::Character => "character",
+            Self::Other(name) => name,
+        };
+        write!(f, "{me}")
     }
-    pub fn comments(&mut self, comments: &str) {
-        self.comments = Some(comments.to_owned());
-    }    
-    pub fn start_date(&mut self, start_date: &str) {
-        self.start_date = Some(start_date.to_owned());
-    }    
-    pub fn finish_date(&mut self, finish_date: &str) {
-        self.finish_date = Some(finish_date.to_owned());
+}
+
+///Controls which related anime are returned when crawling relations
+///
+///An empty `include_types` includes every media type, and an empty `relation_types` follows
+///every relation.
+///
+///# Example
+///
+///```
+/// use lib_mal::model::options::{MediaType, RelationQuery, RelationType};
+/// // only TV series and movies, up to two relations away
+/// let query = RelationQuery::new()
+///     .include_types(vec![MediaType::TV, MediaType::Movie])
+///     .max_depth(2);
+/// // only sequels and prequels
+/// let query = RelationQuery::new().relation_types(vec![RelationType::Sequel, RelationType::Prequel]);
+///```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelationQuery {
+    pub include_types: Vec<MediaType>,
+    pub relation_types: Vec<RelationType>,
+    pub max_depth: usize,
+}
+
+impl Default for RelationQuery {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl Params for StatusUpdate {
+impl RelationQuery {
+    ///Includes every media type, only following direct relations
+    pub const fn new() -> Self {
+        Self {
+            include_types: Vec::new(),
+            relation_types: Vec::new(),
+            max_depth: 1,
+        }
+    }
+
+    ///Only includes TV series and movies, which is what most watch orders care about
+    pub fn watch_order() -> Self {
+        Self::new().include_types(vec![MediaType::TV, MediaType::Movie])
+    }
+
+    pub fn include_types(mut self, include_types: Vec<MediaType>) -> Self {
+        self.include_types = include_types;
+        self
+    }
+
+    pub fn relation_types(mut self, relation_types: Vec<RelationType>) -> Self {
+        self.relation_types = relation_types;
+        self
+    }
+
+    pub const fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    ///Returns true if an anime with `media_type` passes the filter
+    pub fn includes(&self, media_type: Option<&MediaType>) -> bool {
+        self.include_types.is_empty()
+            || media_type.is_some_and(|t| self.include_types.contains(t))
+    }
+
+    ///Returns true if the crawl follows relations of `relation_type`
+    pub fn follows(&self, relation_type: &RelationType) -> bool {
+        self.relation_types.is_empty() || self.relation_types.contains(relation_type)
+    }
+}
+
+impl From<usize> for RelationQuery {
+    fn from(max_depth: usize) -> Self {
+        Self::new().max_depth(max_depth)
+    }
+}
+
+///Order of the entries returned by `get_user_anime_list`
+///
+///MAL picks the direction of each order, the API has no option to reverse it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ListSort {
+    ///Highest score first
+    ListScore,
+    ///Most recently updated first
+    ListUpdatedAt,
+    ///Alphabetically by the main title
+    AnimeTitle,
+    ///Most recently started airing first
+    AnimeStartDate,
+    ///Lowest id first, which is roughly the order the anime were added to MAL. MAL lists it as
+    ///under development.
+    AnimeId,
+}
+
+impl Display for ListSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let me = match self {
+            Self::ListScore => "list_score".to_owned(),
+            Self::ListUpdatedAt => "list_updated_at".to_owned(),
+            Self::AnimeTitle => "anime_title".to_owned(),
+            Self::AnimeStartDate => "anime_start_date".to_owned(),
+            Self::AnimeId => "anime_id".to_owned(),
+        };
+        write!(f, "{me}")
+    }
+}
+
+///Filters and options for `get_user_anime_list`
+///
+///`limit` defaults to `limits::DEFAULT_LIMIT`. The `list_status` field is always requested,
+///`fields` adds to it.
+///
+///# Example
+///
+///```
+/// use lib_mal::model::fields::AnimeFields;
+/// use lib_mal::model::options::{ListSort, Status, UserListQuery};
+/// // the 50 most recently updated shows the user is watching
+/// let query = UserListQuery::new()
+///     .status(Status::Watching)
+///     .sort(ListSort::ListUpdatedAt)
+///     .limit(50)
+///     .fields(AnimeFields::NumEpisodes);
+///```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserListQuery {
+    pub status: Option<Status>,
+    pub sort: Option<ListSort>,
+    pub limit: Limit,
+    pub offset: Option<usize>,
+    pub fields: Option<AnimeFields>,
+    ///The fields of `list_status`, MAL's default ones when `None`
+    pub list_status_fields: Option<ListStatusFields>,
+    pub nsfw: bool,
+}
+
+impl UserListQuery {
+    pub const fn new() -> Self {
+        Self {
+            status: None,
+            sort: None,
+            limit: Limit::DEFAULT,
+            offset: None,
+            fields: None,
+            list_status_fields: None,
+            nsfw: false,
+        }
+    }
+
+    pub fn status(mut self, status: impl Into<Option<Status>>) -> Self {
+        self.status = status.into();
+        self
+    }
+
+    pub fn sort(mut self, sort: impl Into<Option<ListSort>>) -> Self {
+        self.sort = sort.into();
+        self
+    }
+
+    ///Maximum of `limits::MAX_USER_LIST_LIMIT`
+    pub fn limit(mut self, limit: impl Into<Limit>) -> Self {
+        self.limit = limit.into();
+        self
+    }
+
+    pub fn offset(mut self, offset: impl Into<Option<usize>>) -> Self {
+        self.offset = offset.into();
+        self
+    }
+
+    pub fn fields(mut self, fields: impl Into<Option<AnimeFields>>) -> Self {
+        self.fields = fields.into();
+        self
+    }
+
+    pub fn list_status_fields(mut self, fields: impl Into<Option<ListStatusFields>>) -> Self {
+        self.list_status_fields = fields.into();
+        self
+    }
+
+    ///Includes anime that are hidden by default because they're NSFW
+    pub const fn nsfw(mut self, nsfw: bool) -> Self {
+        self.nsfw = nsfw;
+        self
+    }
+}
+
+impl Params for UserListQuery {
     fn get_params<'a>(self) -> Vec<(&'a str, String)> {
-        let mut params = vec![];
+        let mut fields = String::from("list_status");
+        if let Some(f) = self.list_status_fields {
+            fields.push_str(&format!("{{{f}}}"));
+        }
+        if let Some(f) = self.fields {
+            fields.push_str(&format!(",{f}"));
+        }
+        let mut params = vec![
+            ("fields", fields),
+            ("limit", self.limit.get().unwrap_or(DEFAULT_LIMIT).to_string()),
+        ];
         if let Some(s) = self.status {
             params.push(("status", s.to_string()));
         }
-        if let Some(rw) = self.is_rewatching {
-            params.push(("is_rewatching", rw.to_string()));
+        if let Some(s) = self.sort {
+            params.push(("sort", s.to_string()));
         }
-        if let Some(t) = self.score {
-            params.push(("score", t.to_string()));
+        if let Some(o) = self.offset {
+            params.push(("offset", o.to_string()));
         }
-        if let Some(t) = self.num_watched_episodes {
-            params.push(("num_watched_episodes", t.to_string()));
+        if self.nsfw {
+            params.push(("nsfw", String::from("true")));
         }
-        if let Some(t) = self.priority {
-            params.push(("priority", t.to_string()));
+        params
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ForumSort {
+    ///Most recently posted in first, the only order MAL supports
+    Recent,
+}
+
+impl Display for ForumSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let me = match self {
+            Self::Recent => "recent".to_owned(),
+        };
+        write!(f, "{me}")
+    }
+}
+
+///Filters and options for `get_forum_topics`
+///
+///`limit` defaults to `limits::DEFAULT_LIMIT`. MAL requires at least one of `board_id`,
+///`subboard_id`, or `query`.
+///
+///# Example
+///
+///```
+/// use lib_mal::model::options::{ForumSort, ForumTopicQuery};
+/// // the latest topics about episode discussions
+/// let query = ForumTopicQuery::new()
+///     .query("episode 1 discussion")
+///     .sort(ForumSort::Recent)
+///     .limit(20);
+///```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForumTopicQuery {
+    pub board_id: Option<BoardId>,
+    pub subboard_id: Option<BoardId>,
+    pub query: Option<String>,
+    pub topic_user_name: Option<String>,
+    pub user_name: Option<String>,
+    pub sort: Option<ForumSort>,
+    pub limit: Limit,
+    pub offset: Option<usize>,
+}
+
+impl ForumTopicQuery {
+    pub const fn new() -> Self {
+        Self {
+            board_id: None,
+            subboard_id: None,
+            query: None,
+            topic_user_name: None,
+            user_name: None,
+            sort: None,
+            limit: Limit::DEFAULT,
+            offset: None,
         }
-        if let Some(t) = self.num_times_rewatched {
-            params.push(("num_times_rewatched", t.to_string()));
+    }
+
+    pub fn board_id(mut self, board_id: impl Into<BoardId>) -> Self {
+        self.board_id = Some(board_id.into());
+        self
+    }
+
+    pub fn subboard_id(mut self, subboard_id: impl Into<BoardId>) -> Self {
+        self.subboard_id = Some(subboard_id.into());
+        self
+    }
+
+    ///Searches the topic titles
+    pub fn query(mut self, query: impl Into<String>) -> Self {
+        self.query = Some(query.into());
+        self
+    }
+
+    ///Only topics started by this user
+    pub fn topic_user_name(mut self, name: impl Into<String>) -> Self {
+        self.topic_user_name = Some(name.into());
+        self
+    }
+
+    ///Only topics this user posted in
+    pub fn user_name(mut self, name: impl Into<String>) -> Self {
+        self.user_name = Some(name.into());
+        self
+    }
+
+    pub fn sort(mut self, sort: impl Into<Option<ForumSort>>) -> Self {
+        self.sort = sort.into();
+        self
+    }
+
+    ///Maximum of `limits::MAX_FORUM_TOPICS_LIMIT`
+    pub fn limit(mut self, limit: impl Into<Limit>) -> Self {
+        self.limit = limit.into();
+        self
+    }
+
+    pub fn offset(mut self, offset: impl Into<Option<usize>>) -> Self {
+        self.offset = offset.into();
+        self
+    }
+}
+
+impl Params for ForumTopicQuery {
+    fn get_params<'a>(self) -> Vec<(&'a str, String)> {
+        let mut params = vec![("limit", self.limit.get().unwrap_or(DEFAULT_LIMIT).to_string())];
+        if let Some(id) = self.board_id {
+            params.push(("board_id", id.to_string()));
         }
-        if let Some(t) = self.rewatch_value {
-            params.push(("rewatch_value", t.to_string()));
+        if let Some(id) = self.subboard_id {
+            params.push(("subboard_id", id.to_string()));
         }
-        if let Some(t) = self.tags {
-            params.push(("tags", t.join(",")));
+        if let Some(q) = self.query {
+            params.push(("q", q));
         }
-        if let Some(t) = self.comments {
-            params.push(("comments", t));
+        if let Some(name) = self.topic_user_name {
+            params.push(("topic_user_name", name));
         }
-        if let Some(t) = self.start_date {
-            params.push(("start_date", t));
+        if let Some(name) = self.user_name {
+            params.push(("user_name", name));
         }
-        if let Some(t) = self.finish_date {
-            params.push(("finish_date", t));
+        if let Some(s) = self.sort {
+            params.push(("sort", s.to_string()));
+        }
+        if let Some(o) = self.offset {
+            params.push(("offset", o.to_string()));
+        }
+        params
+    }
+}
+
+///Options for `get_suggestions`
+///
+///`limit` defaults to `limits::DEFAULT_LIMIT`. With `exclude_on_list`, anime that are already on
+///the user's list are left out of the page, so it can have fewer than `limit` anime.
+///
+///# Example
+///
+///```
+/// use lib_mal::model::fields::AnimeFields;
+/// use lib_mal::model::options::SuggestionQuery;
+/// // the second page of 20 suggestions the user hasn't added yet
+/// let query = SuggestionQuery::new()
+///     .limit(20)
+///     .offset(20)
+///     .fields(AnimeFields::Mean)
+///     .exclude_on_list(true);
+///```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SuggestionQuery {
+    pub limit: Limit,
+    pub offset: Option<usize>,
+    pub fields: Option<AnimeFields>,
+    ///Leaves out anime that are on the user's list, see `MALClientTrait::get_suggestions`
+    pub exclude_on_list: bool,
+}
+
+impl SuggestionQuery {
+    pub const fn new() -> Self {
+        Self {
+            limit: Limit::DEFAULT,
+            offset: None,
+            fields: None,
+            exclude_on_list: false,
         }
+    }
 
+    ///Maximum of `limits::MAX_SUGGESTIONS_LIMIT`
+    pub fn limit(mut self, limit: impl Into<Limit>) -> Self {
+        self.limit = limit.into();
+        self
+    }
+
+    pub fn offset(mut self, offset: impl Into<Option<usize>>) -> Self {
+        self.offset = offset.into();
+        self
+    }
+
+    pub fn fields(mut self, fields: impl Into<Option<AnimeFields>>) -> Self {
+        self.fields = fields.into();
+        self
+    }
+
+    pub const fn exclude_on_list(mut self, exclude_on_list: bool) -> Self {
+        self.exclude_on_list = exclude_on_list;
+        self
+    }
+}
+
+impl Params for SuggestionQuery {
+    fn get_params<'a>(self) -> Vec<(&'a str, String)> {
+        let mut params = vec![("limit", self.limit.get().unwrap_or(DEFAULT_LIMIT).to_string())];
+        if let Some(o) = self.offset {
+            params.push(("offset", o.to_string()));
+        }
+        if let Some(f) = self.fields {
+            params.push(("fields", f.to_string()));
+        }
         params
     }
 }
 
-pub struct StatusBuilder {
+pub trait Params {
+    fn get_params<'a>(self) -> Vec<(&'a str, String)>;
+}
+
+///The changes to a list entry, only the fields that are set are sent. `build` checks them and
+///returns the `StatusParams` the client sends.
+///
+///# Example
+///
+///```
+/// use lib_mal::model::options::{Status, StatusUpdate};
+/// let update = StatusUpdate::new().status(Status::Watching).num_watched_episodes(3).build();
+/// assert!(update.is_ok());
+///```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatusUpdate {
     status: Option<Status>,
     is_rewatching: Option<bool>,
     score: Option<u8>,
@@ -182,19 +987,22 @@ pub struct StatusBuilder {
     priority: Option<u8>,
     num_times_rewatched: Option<usize>,
     rewatch_value: Option<u8>,
-    tags: Option<Vec<String>>,
+    tags: Option<Tags>,
     comments: Option<String>,
     start_date: Option<String>,
     finish_date: Option<String>,
 }
 
-impl Default for StatusBuilder {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+///The fluent builder `StatusUpdate` used to be split from
+#[deprecated(note = "use `StatusUpdate`, which has the same fluent methods")]
+pub type StatusBuilder = StatusUpdate;
 
-impl StatusBuilder {
+///The changes to a list entry as they're sent to MAL. Only `StatusUpdate::build`, which checks
+///the values first, and `StatusUpdate::build_unchecked` create them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusParams(StatusUpdate);
+
+impl StatusUpdate {
     pub const fn new() -> Self {
         Self {
             status: None,
@@ -211,75 +1019,329 @@ impl StatusBuilder {
         }
     }
 
+    #[must_use]
     pub fn status(mut self, status: impl Into<Option<Status>>) -> Self {
         self.status = status.into();
         self
     }
 
+    #[must_use]
     pub fn is_rewatching(mut self, is_rewatching: impl Into<Option<bool>>) -> Self {
         self.is_rewatching = is_rewatching.into();
         self
     }
 
+    #[must_use]
     pub fn score(mut self, score: impl Into<Option<u8>>) -> Self {
         self.score = score.into();
         self
     }
 
+    #[must_use]
     pub fn num_watched_episodes(mut self, num_watched_episodes: impl Into<Option<usize>>) -> Self {
         self.num_watched_episodes = num_watched_episodes.into();
         self
     }
 
+    #[must_use]
     pub fn priority(mut self, priority: impl Into<Option<u8>>) -> Self {
         self.priority = priority.into();
         self
     }
 
+    #[must_use]
     pub fn num_times_rewatched(mut self, num_times_rewatched: impl Into<Option<usize>>) -> Self {
         self.num_times_rewatched = num_times_rewatched.into();
         self
     }
 
+    #[must_use]
     pub fn rewatch_value(mut self, rewatch_value: impl Into<Option<u8>>) -> Self {
         self.rewatch_value = rewatch_value.into();
         self
     }
 
-    pub fn tags(mut self, tags: impl Into<Option<Vec<String>>>) -> Self {
+    ///Replaces all tags of the entry, start from the entry's current tags to add or remove some
+    #[must_use]
+    pub fn tags(mut self, tags: impl Into<Option<Tags>>) -> Self {
         self.tags = tags.into();
         self
     }
 
+    ///Adds `tag` to the tags sent with the update. MAL replaces all tags of the entry with them,
+    ///so set the entry's current tags with `tags` first to keep them.
+    #[must_use]
+    pub fn add_tag(mut self, tag: &str) -> Self {
+        self.tags.get_or_insert_with(Tags::new).insert(tag);
+        self
+    }
+
+    ///Removes `tag` from the tags sent with the update, see `add_tag`
+    #[must_use]
+    pub fn remove_tag(mut self, tag: &str) -> Self {
+        self.tags.get_or_insert_with(Tags::new).remove(tag);
+        self
+    }
+
+    #[must_use]
     pub fn comments(mut self, comments: impl Into<Option<String>>) -> Self {
         self.comments = comments.into();
         self
     }
 
+    ///A date in the `YYYY-MM-DD` format
+    #[must_use]
     pub fn start_date(mut self, start_date: impl Into<Option<String>>) -> Self {
         self.start_date = start_date.into();
         self
     }
 
+    ///A date in the `YYYY-MM-DD` format
+    #[must_use]
     pub fn finish_date(mut self, finish_date: impl Into<Option<String>>) -> Self {
         self.finish_date = finish_date.into();
         self
     }
 
-    #[allow(clippy::missing_const_for_fn)]
-    pub fn build(self) -> Self {
-        Self {
-            status: self.status,
-            is_rewatching: self.is_rewatching,
-            score: self.score,
-            num_watched_episodes: self.num_watched_episodes,
-            priority: self.priority,
-            num_times_rewatched: self.num_times_rewatched,
-            rewatch_value: self.rewatch_value,
-            tags: self.tags,
-            comments: self.comments,
-            start_date: self.start_date,
-            finish_date: self.finish_date,
+    ///Removes the score from the entry, MAL treats a score of 0 as not scored
+    #[must_use]
+    pub fn clear_score(self) -> Self {
+        self.score(0)
+    }
+
+    ///Removes all tags from the entry
+    #[must_use]
+    pub fn clear_tags(self) -> Self {
+        self.tags(Tags::new())
+    }
+
+    ///Removes the start and finish dates from the entry
+    #[must_use]
+    pub fn clear_dates(self) -> Self {
+        self.start_date(String::new()).finish_date(String::new())
+    }
+
+    ///Checks the values against the ranges MAL accepts: a score from 0 to 10, a priority from 0
+    ///to 2, a rewatch value from 0 to 5, and dates in the `YYYY-MM-DD` format or empty to clear
+    ///them
+    ///
+    ///# Example
+    ///
+    ///```
+    /// use lib_mal::model::options::StatusUpdate;
+    /// assert!(StatusUpdate::new().score(9).build().is_ok());
+    /// let err = StatusUpdate::new().start_date(String::from("yesterday")).build().unwrap_err();
+    /// assert_eq!(err.field, "start_date");
+    ///```
+    pub fn build(self) -> Result<StatusParams, ValidationError> {
+        let in_range = |field, value: Option<u8>, max: u8| match value {
+            Some(v) if v > max => Err(ValidationError {
+                field,
+                reason: format!("{v} isn't between 0 and {max}"),
+            }),
+            _ => Ok(()),
+        };
+        in_range("score", self.score, Score::MAX)?;
+        in_range("priority", self.priority, 2)?;
+        in_range("rewatch_value", self.rewatch_value, 5)?;
+        for (field, date) in [("start_date", &self.start_date), ("finish_date", &self.finish_date)] {
+            if let Some(date) = date.as_deref().filter(|d| !d.is_empty() && !is_date(d)) {
+                return Err(ValidationError {
+                    field,
+                    reason: format!("{date:?} isn't a YYYY-MM-DD date"),
+                });
+            }
         }
+        Ok(self.build_unchecked())
     }
+
+    ///Returns the update without checking the values, MAL rejects the ones it doesn't accept
+    pub const fn build_unchecked(self) -> StatusParams {
+        StatusParams(self)
+    }
+
+    #[deprecated(note = "use the fluent `status`, e.g. `StatusUpdate::new().status(Status::Watching)`")]
+    pub fn set_status(&mut self, status: Status) {
+        self.status = Some(status);
+    }
+
+    #[deprecated(note = "use the fluent `is_rewatching`")]
+    pub fn set_is_rewatching(&mut self, is_rewatching: bool) {
+        self.is_rewatching = Some(is_rewatching);
+    }
+
+    #[deprecated(note = "use the fluent `score`")]
+    pub fn set_score(&mut self, score: u8) {
+        self.score = Some(score);
+    }
+
+    #[deprecated(note = "use the fluent `num_watched_episodes`")]
+    pub fn set_num_watched_episodes(&mut self, num_watched_episodes: usize) {
+        self.num_watched_episodes = Some(num_watched_episodes);
+    }
+
+    #[deprecated(note = "use the fluent `priority`")]
+    pub fn set_priority(&mut self, priority: u8) {
+        self.priority = Some(priority);
+    }
+
+    #[deprecated(note = "use the fluent `num_times_rewatched`")]
+    pub fn set_num_times_rewatched(&mut self, num_times_rewatched: usize) {
+        self.num_times_rewatched = Some(num_times_rewatched);
+    }
+
+    #[deprecated(note = "use the fluent `rewatch_value`")]
+    pub fn set_rewatch_value(&mut self, rewatch_value: u8) {
+        self.rewatch_value = Some(rewatch_value);
+    }
+
+    #[deprecated(note = "use the fluent `tags`, which takes `Tags`")]
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = Some(tags.into());
+    }
+
+    #[deprecated(note = "use the fluent `comments`, which takes a `String`")]
+    pub fn set_comments(&mut self, comments: &str) {
+        self.comments = Some(comments.to_owned());
+    }
+
+    #[deprecated(note = "use the fluent `start_date`, which takes a `String`")]
+    pub fn set_start_date(&mut self, start_date: &str) {
+        self.start_date = Some(start_date.to_owned());
+    }
+
+    #[deprecated(note = "use the fluent `finish_date`, which takes a `String`")]
+    pub fn set_finish_date(&mut self, finish_date: &str) {
+        self.finish_date = Some(finish_date.to_owned());
+    }
+
+    ///Builds the update for watching `by` more episodes of an anime with `total` episodes. The
+    ///entry is moved to Watching if it's new or planned, and to Completed with a finish date
+    ///once the last episode is watched. Finishing a rewatch counts it instead.
+    pub(crate) fn watched_episodes(
+        current: Option<&ListStatus>,
+        total: Option<usize>,
+        by: usize,
+        today: &str,
+    ) -> StatusParams {
+        let total = total.filter(|t| *t > 0);
+        let watched = current
+            .and_then(|c| c.num_episodes_watched)
+            .unwrap_or_default()
+            .saturating_add(by);
+        let watched = total.map_or(watched, |t| watched.min(t));
+        let update = Self::new().num_watched_episodes(watched);
+        let update = if total.is_some_and(|t| watched >= t) {
+            update.complete(current, today)
+        } else if matches!(
+            current.and_then(|c| c.status.as_deref()),
+            None | Some("plan_to_watch")
+        ) {
+            update
+                .status(Status::Watching)
+                .start_date(today.to_owned())
+        } else {
+            update
+        };
+        // the counts come from MAL's own entry, only the score below is the caller's
+        update.build_unchecked()
+    }
+
+    ///Builds the update for finishing an anime with `total` episodes, failing if `score` is
+    ///out of range
+    pub(crate) fn completed(
+        current: Option<&ListStatus>,
+        total: Option<usize>,
+        score: Option<u8>,
+        today: &str,
+    ) -> Result<StatusParams, ValidationError> {
+        Self::new()
+            .num_watched_episodes(total.filter(|t| *t > 0))
+            .score(score)
+            .complete(current, today)
+            .build()
+    }
+
+    fn complete(self, current: Option<&ListStatus>, today: &str) -> Self {
+        let update = self.status(Status::Completed);
+        match current {
+            Some(c) if c.is_rewatching == Some(true) => update
+                .is_rewatching(false)
+                .num_times_rewatched(c.num_times_rewatched.unwrap_or_default() + 1),
+            Some(c) if c.finish_date.is_some() => update,
+            _ => update.finish_date(today.to_owned()),
+        }
+    }
+}
+
+impl Params for StatusParams {
+    fn get_params<'a>(self) -> Vec<(&'a str, String)> {
+        let Self(update) = self;
+        let mut params = vec![];
+        if let Some(s) = update.status {
+            params.push(("status", s.to_string()));
+        }
+        if let Some(rw) = update.is_rewatching {
+            params.push(("is_rewatching", rw.to_string()));
+        }
+        if let Some(t) = update.score {
+            params.push(("score", t.to_string()));
+        }
+        if let Some(t) = update.num_watched_episodes {
+            params.push(("num_watched_episodes", t.to_string()));
+        }
+        if let Some(t) = update.priority {
+            params.push(("priority", t.to_string()));
+        }
+        if let Some(t) = update.num_times_rewatched {
+            params.push(("num_times_rewatched", t.to_string()));
+        }
+        if let Some(t) = update.rewatch_value {
+            params.push(("rewatch_value", t.to_string()));
+        }
+        if let Some(t) = update.tags {
+            params.push(("tags", t.to_string()));
+        }
+        if let Some(t) = update.comments {
+            params.push(("comments", t));
+        }
+        if let Some(t) = update.start_date {
+            params.push(("start_date", t));
+        }
+        if let Some(t) = update.finish_date {
+            params.push(("finish_date", t));
+        }
+
+        params
+    }
+}
+
+///A value `StatusUpdate::build` rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub field: &'static str,
+    pub reason: String,
 }
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid {}: {}", self.field, self.reason)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl From<ValidationError> for crate::MALError {
+    fn from(e: ValidationError) -> Self {
+        Self::Invalid {
+            field: e.field,
+            reason: e.reason,
+        }
+    }
+}
+
+///Returns true if `date` is a real day in the `YYYY-MM-DD` format
+fn is_date(date: &str) -> bool {
+    parse_date(date).is_some_and(|t| date_of(t) == date)
+}
+
@@ -0,0 +1,101 @@
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::Display;
+use std::str::FromStr;
+
+///Points at a page of a paginated list, so fetching every page can be resumed after it was
+///interrupted, see `MALClient::fetch_remaining`
+///
+///Cursors can be stored as strings with `to_string` and read back with `parse`. Any URL parses,
+///the client only follows cursors that point at its API, see `ClientBuilder::api_base_url`,
+///since the access token is sent to them.
+///
+///# Example
+///
+///```
+/// use lib_mal::model::PageCursor;
+///
+/// let saved = "https://api.myanimelist.net/v2/users/@me/animelist?offset=300&limit=100";
+/// let cursor: PageCursor = saved.parse().unwrap();
+/// assert_eq!(cursor.offset(), 300);
+///```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PageCursor {
+    url: String,
+}
+
+impl PageCursor {
+    pub(crate) fn new(url: String) -> Self {
+        Self { url }
+    }
+
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+
+    ///Returns how many entries come before the page, 0 for the first page
+    pub fn offset(&self) -> usize {
+        Url::parse(&self.url)
+            .ok()
+            .and_then(|url| {
+                url.query_pairs()
+                    .find(|(k, _)| k == "offset")
+                    .and_then(|(_, v)| v.parse().ok())
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Display for PageCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.url)
+    }
+}
+
+impl FromStr for PageCursor {
+    type Err = InvalidCursor;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if Url::parse(s).is_ok_and(|url| url.has_host()) {
+            Ok(Self::new(s.to_owned()))
+        } else {
+            Err(InvalidCursor)
+        }
+    }
+}
+
+impl TryFrom<String> for PageCursor {
+    type Error = InvalidCursor;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<PageCursor> for String {
+    fn from(cursor: PageCursor) -> Self {
+        cursor.url
+    }
+}
+
+///The previous and next pages of a paginated response
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Paging {
+    pub previous: Option<PageCursor>,
+    pub next: Option<PageCursor>,
+}
+
+///The string isn't a URL, so it can't be a cursor created by `PageCursor::to_string`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidCursor;
+
+impl Display for InvalidCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "not a page URL")
+    }
+}
+
+impl Error for InvalidCursor {}
@@ -0,0 +1,142 @@
+//! Queueing changes to the user's list while there's no connection, see
+//! [`ClientBuilder::offline_queue`](crate::ClientBuilder::offline_queue)
+//!
+//! With the queue enabled, `update_user_anime_status` and `delete_anime_list_item` calls that
+//! can't reach MAL are appended to `offline-queue.jsonl` in the cache directory and fail with
+//! `MALError::Queued`. [`MALClient::flush_pending`](crate::MALClient::flush_pending) sends them
+//! once the connection is back, skipping the ones whose entry was changed on MAL after they were
+//! queued.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! # async fn test(client: &MALClient) -> Result<(), MALError> {
+//! match client.increment_watched_episodes(80, 1).await {
+//!     Ok(_) | Err(MALError::Queued) => {}
+//!     Err(e) => return Err(e),
+//! }
+//! // later, once the connection is back
+//! let report = client.flush_pending().await?;
+//! for change in &report.conflicts {
+//!     println!("anime {} was changed on MAL in the meantime", change.id);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::AnimeId;
+use crate::MALError;
+
+///Name of the queue file in the cache directory
+pub const QUEUE_FILE: &str = "offline-queue.jsonl";
+
+///A change to the user's list that couldn't be sent yet
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PendingChange {
+    ///Unix timestamp of when the change was queued
+    pub queued_at: u64,
+    pub id: AnimeId,
+    pub action: PendingAction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PendingAction {
+    ///The form parameters of a `StatusParams`
+    Update {
+        params: Vec<(String, String)>,
+    },
+    Delete,
+}
+
+///The outcome of `MALClient::flush_pending`
+#[derive(Debug, Default)]
+pub struct FlushReport {
+    pub applied: Vec<PendingChange>,
+    ///Changes that were dropped because the entry was updated on MAL after they were queued
+    pub conflicts: Vec<PendingChange>,
+    ///Changes MAL rejected as invalid, they're dropped from the queue
+    pub failed: Vec<(PendingChange, MALError)>,
+    ///Changes that are still queued because MAL couldn't be reached, the token was rejected, the
+    ///requests were rate limited, or MAL had a server error
+    pub pending: Vec<PendingChange>,
+}
+
+///Reads every change in the queue at `path`, oldest first. A missing queue is empty, and lines
+///that can't be parsed, like one cut off by a crash, are skipped.
+pub fn read(path: impl AsRef<Path>) -> Result<Vec<PendingChange>, MALError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+///Appends changes to the queue and removes them once they're sent
+#[derive(Debug, Default)]
+pub(crate) struct OfflineQueue {
+    lock: Mutex<()>,
+}
+
+impl OfflineQueue {
+    pub(crate) fn append(&self, dir: &Path, change: &PendingChange) -> Result<(), MALError> {
+        let mut line = to_line(change)?;
+        line.push('\n');
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        if !dir.as_os_str().is_empty() {
+            fs::create_dir_all(dir)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(QUEUE_FILE))?
+            .write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    ///Replaces the first `handled` changes with `keep`. Changes queued while the first ones were
+    ///being sent stay after them.
+    pub(crate) fn finish(
+        &self,
+        dir: &Path,
+        handled: usize,
+        keep: &[PendingChange],
+    ) -> Result<(), MALError> {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let path = dir.join(QUEUE_FILE);
+        let queued = read(&path)?;
+        let mut contents = String::new();
+        for change in keep.iter().chain(queued.iter().skip(handled)) {
+            contents.push_str(&to_line(change)?);
+            contents.push('\n');
+        }
+        if contents.is_empty() {
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+                _ => return Ok(()),
+            }
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+fn to_line(change: &PendingChange) -> Result<String, MALError> {
+    serde_json::to_string(change).map_err(|source| MALError::Parse {
+        source,
+        body: String::new(),
+        context: None,
+    })
+}
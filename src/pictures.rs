@@ -0,0 +1,82 @@
+//! Downloading cover art, see [`MALClient::download_picture`](crate::MALClient::download_picture)
+//!
+//! Pictures are saved in a directory under a name derived from their URL, so a picture that was
+//! already downloaded is returned without another request. MAL never changes the picture behind
+//! a URL, a new picture gets a new URL.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::model::fields::AnimeFields;
+//! use lib_mal::pictures::PictureSize;
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let anime = client.get_anime_details(30230, AnimeFields::MainPicture).await?;
+//! let path = client.download_picture(&anime.main_picture, PictureSize::Large, "covers").await?;
+//! println!("saved to {}", path.display());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::cache::fnv1a;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PictureSize {
+    Medium,
+    Large,
+}
+
+impl PictureSize {
+    const fn key(self) -> &'static str {
+        match self {
+            Self::Medium => "medium",
+            Self::Large => "large",
+        }
+    }
+
+    const fn other(self) -> Self {
+        match self {
+            Self::Medium => Self::Large,
+            Self::Large => Self::Medium,
+        }
+    }
+}
+
+///A picture MAL sends as URLs by size, like `main_picture` and the entries of `pictures`
+pub trait Picture {
+    ///Returns the URL of the picture in `size`, or in the other size if MAL didn't send that one
+    fn url(&self, size: PictureSize) -> Option<&str>;
+}
+
+impl Picture for HashMap<String, Value> {
+    fn url(&self, size: PictureSize) -> Option<&str> {
+        self.get(size.key())
+            .or_else(|| self.get(size.other().key()))?
+            .as_str()
+    }
+}
+
+impl Picture for HashMap<String, String> {
+    fn url(&self, size: PictureSize) -> Option<&str> {
+        self.get(size.key())
+            .or_else(|| self.get(size.other().key()))
+            .map(String::as_str)
+    }
+}
+
+///Returns the name a picture is saved under, the hash of its URL with the URL's extension
+pub(crate) fn file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let extension = path
+        .rsplit_once('/')
+        .and_then(|(_, name)| name.rsplit_once('.'))
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .filter(|ext| !ext.is_empty() && ext.len() <= 4 && ext.chars().all(char::is_alphanumeric))
+        .unwrap_or_else(|| String::from("jpg"));
+    format!("{:016x}.{extension}", fnv1a(url))
+}
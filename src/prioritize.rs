@@ -0,0 +1,107 @@
+//! Ordering the plan to watch list by what to watch next, see
+//! [`MALClient::plan_to_watch_ranked`](crate::MALClient::plan_to_watch_ranked)
+//!
+//! Every entry gets a score from the anime's mean score, its popularity, how short it is, and
+//! the priority the user gave the entry. Each is scaled to between 0 and 1 and multiplied by its
+//! weight in [`PriorityWeights`], values MAL doesn't have, like the mean of an unaired anime,
+//! count as 0.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::prioritize::PriorityWeights;
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! // prefer short, well rated anime regardless of how popular they are
+//! let weights = PriorityWeights { popularity: 0.0, length: 1.0, ..PriorityWeights::default() };
+//! for ranked in client.plan_to_watch_ranked(&weights).await?.iter().take(5) {
+//!     println!("{:.2} {}", ranked.score, ranked.entry.node.title);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::model::ListNode;
+
+///The fields the list has to be fetched with for every part of the score
+pub const FIELDS: &str = "list_status{priority},mean,num_list_users,num_episodes";
+
+///Number of users at which the popularity part of the score is 1
+const POPULAR_USERS: f32 = 1_000_000.0;
+///Number of episodes at which the length part of the score is 0.5
+const HALF_LENGTH_EPISODES: f32 = 12.0;
+
+///How much each part of the score counts, a weight of 0 leaves the part out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriorityWeights {
+    ///The anime's mean score, 10 counts as 1
+    pub mean: f32,
+    ///How many users have the anime on their list, which MAL's popularity rank is based on. Grows
+    ///logarithmically up to 1 at a million users.
+    pub popularity: f32,
+    ///Shorter anime score higher, 12 episodes count as 0.5 and anime with an unknown number of
+    ///episodes as 0
+    pub length: f32,
+    ///The priority the user gave the entry, High counts as 1
+    pub priority: f32,
+}
+
+impl Default for PriorityWeights {
+    fn default() -> Self {
+        Self {
+            mean: 1.0,
+            popularity: 0.5,
+            length: 0.5,
+            priority: 1.0,
+        }
+    }
+}
+
+impl PriorityWeights {
+    ///Returns the score of a plan to watch entry, higher is better
+    #[allow(clippy::cast_precision_loss)]
+    pub fn score(&self, entry: &ListNode) -> f32 {
+        let anime = &entry.details;
+        let mean = anime.mean.unwrap_or_default() / 10.0;
+        let popularity = anime.num_list_users.map_or(0.0, |users| {
+            ((users as f32 + 1.0).log10() / POPULAR_USERS.log10()).min(1.0)
+        });
+        let length = match anime.num_episodes {
+            Some(episodes) if episodes > 0 => {
+                HALF_LENGTH_EPISODES / (HALF_LENGTH_EPISODES + episodes as f32)
+            }
+            _ => 0.0,
+        };
+        let priority = entry
+            .list_status
+            .as_ref()
+            .and_then(|s| s.priority)
+            .map_or(0.0, |p| p.min(2) as f32 / 2.0);
+        self.mean * mean
+            + self.popularity * popularity
+            + self.length * length
+            + self.priority * priority
+    }
+}
+
+///A plan to watch entry with its score, see `PriorityWeights::score`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedEntry {
+    pub entry: ListNode,
+    pub score: f32,
+}
+
+///Scores `entries` with `weights` and returns them best first, entries with the same score keep
+///their order
+pub fn rank(entries: Vec<ListNode>, weights: &PriorityWeights) -> Vec<RankedEntry> {
+    let mut ranked: Vec<RankedEntry> = entries
+        .into_iter()
+        .map(|entry| RankedEntry {
+            score: weights.score(&entry),
+            entry,
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked
+}
@@ -0,0 +1,177 @@
+//! Changes in MAL's rankings between two fetches, for "weekly top anime" posts, see
+//! [`MALClient::ranking_digest`](crate::MALClient::ranking_digest)
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::model::options::RankingType;
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let digest = client.ranking_digest(RankingType::Airing, 50).await?;
+//! for new in &digest.new_entries {
+//!     println!("NEW #{} {}", new.rank, new.title);
+//! }
+//! for mover in &digest.movers {
+//!     println!("{:+} {}", mover.places(), mover.title);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{AnimeId, AnimeList};
+use crate::MALError;
+
+///A ranking as it was when it was fetched
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RankingSnapshot {
+    ///Like `airing`, see `RankingType`
+    pub ranking_type: String,
+    ///Unix timestamp of when the ranking was fetched
+    pub fetched_at: u64,
+    ///Ordered by rank
+    pub entries: Vec<RankedAnime>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RankedAnime {
+    ///Id of the anime
+    pub id: AnimeId,
+    pub title: String,
+    pub rank: usize,
+}
+
+impl RankingSnapshot {
+    ///Builds a snapshot of a list fetched with `MALClientTrait::get_anime_ranking`. Entries
+    ///without a rank are left out.
+    pub fn from_list(ranking_type: &str, fetched_at: u64, list: &AnimeList) -> Self {
+        let mut entries: Vec<RankedAnime> = list
+            .data
+            .iter()
+            .filter_map(|entry| {
+                Some(RankedAnime {
+                    id: entry.node.id,
+                    title: entry.node.title.clone(),
+                    rank: *entry.ranking.as_ref()?.get("rank")?,
+                })
+            })
+            .collect();
+        entries.sort_by_key(|e| e.rank);
+        Self {
+            ranking_type: ranking_type.to_owned(),
+            fetched_at,
+            entries,
+        }
+    }
+
+    ///Reads a snapshot written by [`RankingSnapshot::save`], returns `None` if there is none
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<Self>, MALError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|source| MALError::Parse {
+                source,
+                body: contents,
+                context: None,
+            })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MALError> {
+        let json = serde_json::to_string(self).map_err(|source| MALError::Parse {
+            source,
+            body: String::new(),
+            context: None,
+        })?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+///How a ranking changed between two snapshots
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RankingDigest {
+    pub ranking_type: String,
+    ///Unix timestamp of the previous snapshot, `None` if there was none
+    pub since: Option<u64>,
+    pub fetched_at: u64,
+    ///Anime whose rank changed, ordered by how far they moved, biggest climbs first
+    pub movers: Vec<RankMove>,
+    ///Anime that weren't in the previous snapshot, ordered by rank
+    pub new_entries: Vec<RankedAnime>,
+    ///Anime that are no longer in the ranking, ordered by their previous rank
+    pub dropped: Vec<RankedAnime>,
+}
+
+impl RankingDigest {
+    ///Returns true if nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.movers.is_empty() && self.new_entries.is_empty() && self.dropped.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankMove {
+    ///Id of the anime
+    pub id: AnimeId,
+    pub title: String,
+    pub from: usize,
+    pub to: usize,
+}
+
+impl RankMove {
+    ///How many places the anime climbed, negative if it fell
+    #[allow(clippy::cast_possible_wrap)]
+    pub const fn places(&self) -> isize {
+        self.from as isize - self.to as isize
+    }
+}
+
+///Compares two snapshots of the same ranking. Without a previous snapshot every entry is new.
+pub fn digest(previous: Option<&RankingSnapshot>, current: &RankingSnapshot) -> RankingDigest {
+    let old: HashMap<AnimeId, &RankedAnime> = previous
+        .map(|p| p.entries.iter().map(|e| (e.id, e)).collect())
+        .unwrap_or_default();
+    let mut movers = Vec::new();
+    let mut new_entries = Vec::new();
+    for entry in &current.entries {
+        match old.get(&entry.id) {
+            None => new_entries.push(entry.clone()),
+            Some(before) if before.rank != entry.rank => movers.push(RankMove {
+                id: entry.id,
+                title: entry.title.clone(),
+                from: before.rank,
+                to: entry.rank,
+            }),
+            Some(_) => {}
+        }
+    }
+    movers.sort_by_key(|m| (-m.places(), m.to));
+    let dropped = previous
+        .map(|p| {
+            p.entries
+                .iter()
+                .filter(|e| !current.entries.iter().any(|c| c.id == e.id))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    RankingDigest {
+        ranking_type: current.ranking_type.clone(),
+        since: previous.map(|p| p.fetched_at),
+        fetched_at: current.fetched_at,
+        movers,
+        new_entries,
+        dropped,
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::rt::{self, Instant};
+
+///The delay before the first retry, doubled for every retry after it
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+///Spaces requests out evenly so no more than the configured number are sent per second
+pub struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / requests_per_second.max(1),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    ///Returns the time between two requests
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    ///Waits until the next request is allowed to be sent
+    pub async fn acquire(&self) {
+        let wait = {
+            let mut next = self.next.lock().unwrap_or_else(PoisonError::into_inner);
+            let now = Instant::now();
+            let slot = (*next).max(now);
+            *next = slot + self.interval;
+            slot - now
+        };
+        if !wait.is_zero() {
+            rt::sleep(wait).await;
+        }
+    }
+}
+
+///Returns how long to wait before retry number `attempt`, starting from 0. Uses exponential
+///backoff with jitter so clients throttled at the same time don't retry in lockstep.
+pub fn backoff(attempt: u32) -> Duration {
+    let max = BASE_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_BACKOFF);
+    let half = max / 2;
+    // every `RandomState` is seeded differently, which is random enough for jitter
+    let random = RandomState::new().build_hasher().finish();
+    let jitter = random % u64::try_from(half.as_millis()).unwrap_or(1).max(1);
+    half + Duration::from_millis(jitter)
+}
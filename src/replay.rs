@@ -0,0 +1,183 @@
+//! Recording real API responses to fixture files and replaying them in tests, see
+//! [`ReplayTransport`]
+//!
+//! Run the tests once with `MAL_RECORD=1` against the real API to write a fixture for every
+//! request, then commit the fixtures. Without the variable, requests are answered from the
+//! fixtures only, so the tests are deterministic and work offline. A request without a fixture
+//! fails with `MALError::Transport`.
+//!
+//! Fixtures are named after the method, the path, and a hash of the whole request, and hold the
+//! status, headers, and body of the response. Request headers, like the access token, aren't
+//! stored, and the tokens, codes, and secrets in request forms, cookies, and response bodies are
+//! replaced with `[REDACTED]`, so the fixtures of OAuth requests are safe to commit too.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{ClientBuilder, MALClient, MALClientTrait, MALError};
+//! use lib_mal::replay::ReplayTransport;
+//! # async fn test() -> Result<(), MALError> {
+//! let client: MALClient = ClientBuilder::new()
+//!     .access_token(std::env::var("MAL_TOKEN").unwrap_or_default())
+//!     .transport(ReplayTransport::from_env("tests/fixtures"))
+//!     .build_no_refresh();
+//! let anime = client.get_anime_details(30230, None).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, COOKIE, SET_COOKIE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::fnv1a;
+use crate::debug::{redact_json, REDACTED, SECRET_KEYS};
+use crate::{HttpRequest, HttpResponse, HttpTransport, MALError, ReqwestTransport};
+
+///Environment variable that makes `ReplayTransport::from_env` record, if it's set to `1`
+pub const RECORD_VAR: &str = "MAL_RECORD";
+
+///A recorded response
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Fixture {
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+///Records responses to fixture files or answers requests from them, see the module docs
+#[derive(Clone)]
+pub struct ReplayTransport {
+    dir: PathBuf,
+    ///Sends the requests that are recorded, `None` when replaying
+    recorder: Option<Arc<dyn HttpTransport>>,
+}
+
+impl ReplayTransport {
+    ///Sends every request with `inner` and writes the response to a fixture in `dir`, replacing
+    ///an older fixture of the same request
+    pub fn record(dir: impl Into<PathBuf>, inner: impl HttpTransport + 'static) -> Self {
+        Self {
+            dir: dir.into(),
+            recorder: Some(Arc::new(inner)),
+        }
+    }
+
+    ///Answers every request from the fixtures in `dir` without sending anything
+    pub fn replay(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            recorder: None,
+        }
+    }
+
+    ///Records with a default `ReqwestTransport` if `MAL_RECORD` is set to `1`, replays otherwise
+    pub fn from_env(dir: impl Into<PathBuf>) -> Self {
+        if std::env::var(RECORD_VAR).is_ok_and(|v| v == "1") {
+            Self::record(dir, ReqwestTransport::default())
+        } else {
+            Self::replay(dir)
+        }
+    }
+
+    pub const fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    ///Returns the path of the fixture of `request`
+    pub fn fixture_path(&self, request: &HttpRequest) -> PathBuf {
+        let path = request.url.split(['?', '#']).next().unwrap_or_default();
+        let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+        let name: String = path
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let mut key = format!("{} {}", request.method, request.url);
+        for (k, v) in request.form.iter().flatten() {
+            let v = if SECRET_KEYS.contains(&k.as_str()) { REDACTED } else { v };
+            key.push_str(&format!("&{k}={v}"));
+        }
+        self.dir.join(format!(
+            "{}-{name}-{:016x}.json",
+            request.method.as_str().to_ascii_lowercase(),
+            fnv1a(&key)
+        ))
+    }
+
+    async fn record_request(
+        &self,
+        inner: &dyn HttpTransport,
+        request: HttpRequest,
+    ) -> Result<HttpResponse, MALError> {
+        let path = self.fixture_path(&request);
+        let (method, url) = (request.method.to_string(), request.url.clone());
+        let res = inner.send(request).await?;
+        let fixture = Fixture {
+            method,
+            url,
+            status: res.status.as_u16(),
+            headers: res
+                .headers
+                .iter()
+                .filter(|(k, _)| ![AUTHORIZATION, COOKIE, SET_COOKIE].contains(k))
+                .filter_map(|(k, v)| Some((k.to_string(), v.to_str().ok()?.to_owned())))
+                .collect(),
+            body: redact_json(res.body.clone()),
+        };
+        let json = serde_json::to_string_pretty(&fixture).map_err(MALError::transport)?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(path, json)?;
+        Ok(res)
+    }
+
+    fn replay_request(&self, request: &HttpRequest) -> Result<HttpResponse, MALError> {
+        let path = self.fixture_path(request);
+        let json = match fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Err(MALError::transport(format!(
+                    "no fixture for {} {} at {}, record it with {RECORD_VAR}=1",
+                    request.method,
+                    request.url,
+                    path.display()
+                )))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let fixture: Fixture = serde_json::from_str(&json).map_err(MALError::transport)?;
+        let mut headers = HeaderMap::new();
+        for (k, v) in &fixture.headers {
+            if let (Ok(k), Ok(v)) = (
+                HeaderName::from_bytes(k.as_bytes()),
+                HeaderValue::from_str(v),
+            ) {
+                headers.append(k, v);
+            }
+        }
+        Ok(HttpResponse {
+            status: StatusCode::from_u16(fixture.status).map_err(MALError::transport)?,
+            headers,
+            body: fixture.body,
+        })
+    }
+}
+
+impl HttpTransport for ReplayTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, MALError>> {
+        Box::pin(async move {
+            match &self.recorder {
+                Some(inner) => self.record_request(inner.as_ref(), request).await,
+                None => self.replay_request(&request),
+            }
+        })
+    }
+}
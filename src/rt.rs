@@ -0,0 +1,171 @@
+//! The clock, timers, and tasks the client uses, which come from the browser on wasm32
+//!
+//! `SystemTime::now`, `Instant::now`, and the tokio timers panic on `wasm32-unknown-unknown`,
+//! and tokio can't spawn tasks there, so everything in the crate goes through these instead.
+
+use std::future::Future;
+use std::time::{Duration, SystemTime};
+
+use futures_util::future::BoxFuture;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+pub use wasm::Instant;
+
+///Returns the current time
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now() -> SystemTime {
+    SystemTime::now()
+}
+
+///Returns the current time, as `Date.now()` tells it
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now() -> SystemTime {
+    std::time::UNIX_EPOCH + Duration::from_millis(js_sys::Date::now() as u64)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+///Waits with `setTimeout`, which browsers and workers both have
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    wasm::AssertSend(wasm::sleep(duration)).await;
+}
+
+///Runs `task` in the background and returns a future that resolves to its output. The task keeps
+///running if the returned future is dropped.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn spawn<T: Send + 'static>(
+    task: impl Future<Output = T> + Send + 'static,
+) -> BoxFuture<'static, T> {
+    let handle = tokio::spawn(task);
+    Box::pin(async move {
+        handle
+            .await
+            .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+    })
+}
+
+///Runs `task` on the browser's event loop and returns a future that resolves to its output. The
+///task keeps running if the returned future is dropped.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn spawn<T: Send + 'static>(
+    task: impl Future<Output = T> + Send + 'static,
+) -> BoxFuture<'static, T> {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    wasm_bindgen_futures::spawn_local(async move {
+        let _ = sender.send(task.await);
+    });
+    Box::pin(async move { receiver.await.expect("the spawned task panicked") })
+}
+
+///Boxes a future of a `HttpTransport`. The futures of the browser's `fetch` aren't `Send`, which
+///doesn't matter on wasm32, where there's only one thread.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn boxed<'a, T>(future: impl Future<Output = T> + Send + 'a) -> BoxFuture<'a, T> {
+    Box::pin(future)
+}
+
+///Boxes a future of a `HttpTransport`. The futures of the browser's `fetch` aren't `Send`, which
+///doesn't matter on wasm32, where there's only one thread.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn boxed<'a, T>(future: impl Future<Output = T> + 'a) -> BoxFuture<'a, T> {
+    Box::pin(wasm::AssertSend(future))
+}
+
+#[cfg(target_arch = "wasm32")]
+mod wasm {
+    use std::future::Future;
+    use std::ops::{Add, AddAssign, Sub};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use std::time::Duration;
+
+    use wasm_bindgen::{JsCast, JsValue};
+
+    ///Stands in for `std::time::Instant` on wasm32, measured with `Date.now()`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Instant {
+        millis: u64,
+    }
+
+    impl Instant {
+        pub fn now() -> Self {
+            Self {
+                millis: js_sys::Date::now() as u64,
+            }
+        }
+
+        pub fn duration_since(&self, earlier: Self) -> Duration {
+            self.saturating_duration_since(earlier)
+        }
+
+        pub fn saturating_duration_since(&self, earlier: Self) -> Duration {
+            Duration::from_millis(self.millis.saturating_sub(earlier.millis))
+        }
+
+        pub fn elapsed(&self) -> Duration {
+            Self::now().saturating_duration_since(*self)
+        }
+    }
+
+    impl Add<Duration> for Instant {
+        type Output = Self;
+
+        fn add(self, rhs: Duration) -> Self {
+            Self {
+                millis: self.millis + rhs.as_millis() as u64,
+            }
+        }
+    }
+
+    impl AddAssign<Duration> for Instant {
+        fn add_assign(&mut self, rhs: Duration) {
+            *self = *self + rhs;
+        }
+    }
+
+    impl Sub for Instant {
+        type Output = Duration;
+
+        fn sub(self, rhs: Self) -> Duration {
+            self.saturating_duration_since(rhs)
+        }
+    }
+
+    pub(super) async fn sleep(duration: Duration) {
+        let millis = i32::try_from(duration.as_millis()).unwrap_or(i32::MAX);
+        let promise = js_sys::Promise::new(&mut |resolve, _| {
+            let set_timeout = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+                .ok()
+                .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+            match set_timeout {
+                Some(set_timeout) => {
+                    let _ = set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from(millis));
+                }
+                None => {
+                    let _ = resolve.call0(&JsValue::NULL);
+                }
+            }
+        });
+        let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+    }
+
+    ///Marks a future as `Send`, which is sound on wasm32 without threads
+    pub(super) struct AssertSend<F>(pub(super) F);
+
+    // SAFETY: wasm32-unknown-unknown runs on a single thread, the future never moves to another
+    unsafe impl<F> Send for AssertSend<F> {}
+
+    impl<F: Future> Future for AssertSend<F> {
+        type Output = F::Output;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+            // SAFETY: the inner future is never moved out of the pinned wrapper
+            unsafe { self.map_unchecked_mut(|s| &mut s.0) }.poll(cx)
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Weekly airing schedules in the user's time zone, see
+//! [`MALClient::get_airing_schedule`](crate::MALClient::get_airing_schedule)
+//!
+//! MAL reports when an anime is broadcast as a weekday and a time in Japan Standard Time. The
+//! schedule converts them to a [`UtcOffset`], which can move a broadcast to another weekday.
+//!
+//! [`to_ics`] turns a schedule into an iCalendar file with a weekly event for every anime, which
+//! calendar apps like Google Calendar can import or subscribe to, see
+//! [`MALClient::watching_calendar`](crate::MALClient::watching_calendar).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::model::options::SeasonYear;
+//! use lib_mal::schedule::{UtcOffset, Weekday};
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let schedule = client.get_airing_schedule(SeasonYear::now(), UtcOffset::from_hours(1)).await?;
+//! for slot in schedule.day(Weekday::Saturday) {
+//!     println!("{:02}:{:02} {}", slot.hour, slot.minute, slot.anime.title);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::options::ical_time_of;
+use crate::model::{AnimeDetails, DAY, JST_OFFSET, WEEK};
+
+///Length of the calendar events of anime fetched without `average_episode_duration`
+const DEFAULT_EPISODE_LENGTH: Duration = Duration::from_secs(24 * 60);
+///iCalendar lines longer than this many bytes are folded
+const ICS_LINE_LENGTH: usize = 75;
+
+///The difference between a time zone and UTC, like `+01:00`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct UtcOffset {
+    minutes: i32,
+}
+
+impl UtcOffset {
+    pub const UTC: Self = Self::from_minutes(0);
+    ///Japan Standard Time, which MAL's broadcast times are in
+    pub const JST: Self = Self::from_hours(9);
+
+    pub const fn from_hours(hours: i32) -> Self {
+        Self::from_minutes(hours * 60)
+    }
+
+    ///For offsets that aren't whole hours, like India's `+05:30`
+    pub const fn from_minutes(minutes: i32) -> Self {
+        Self { minutes }
+    }
+
+    pub const fn minutes(&self) -> i32 {
+        self.minutes
+    }
+}
+
+impl Display for UtcOffset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.minutes < 0 { '-' } else { '+' };
+        let minutes = self.minutes.unsigned_abs();
+        write!(f, "{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    ///Every weekday, starting from Monday
+    pub const ALL: [Self; 7] = [
+        Self::Monday,
+        Self::Tuesday,
+        Self::Wednesday,
+        Self::Thursday,
+        Self::Friday,
+        Self::Saturday,
+        Self::Sunday,
+    ];
+}
+
+impl Display for Weekday {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let me = match self {
+            Self::Monday => "Monday",
+            Self::Tuesday => "Tuesday",
+            Self::Wednesday => "Wednesday",
+            Self::Thursday => "Thursday",
+            Self::Friday => "Friday",
+            Self::Saturday => "Saturday",
+            Self::Sunday => "Sunday",
+        };
+        write!(f, "{me}")
+    }
+}
+
+///When an anime is broadcast every week, in the schedule's time zone
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AiringSlot {
+    pub anime: AnimeDetails,
+    pub weekday: Weekday,
+    pub hour: u8,
+    pub minute: u8,
+    ///When the next episode airs, `None` if the anime hasn't started airing yet
+    pub next_airing: Option<SystemTime>,
+}
+
+///The anime of a season grouped by the weekday they air on, see
+///`MALClient::get_airing_schedule`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AiringSchedule {
+    pub offset: UtcOffset,
+    ///Sorted by the time of day, weekdays without any anime are left out
+    pub days: BTreeMap<Weekday, Vec<AiringSlot>>,
+}
+
+impl AiringSchedule {
+    ///Builds the schedule of the anime that have a broadcast time and haven't finished airing.
+    ///The next air times are counted from `now`.
+    ///
+    ///Needs the anime to be fetched with the `status` and `broadcast` fields.
+    pub fn from_anime(
+        anime: impl IntoIterator<Item = AnimeDetails>,
+        offset: UtcOffset,
+        now: SystemTime,
+    ) -> Self {
+        let mut days: BTreeMap<Weekday, Vec<AiringSlot>> = BTreeMap::new();
+        for anime in anime {
+            if anime.status.as_deref() == Some("finished_airing") {
+                continue;
+            }
+            let Some((weekday, time_of_day)) = anime.broadcast_slot() else {
+                continue;
+            };
+            // seconds since Monday 00:00 in the offset, wrapping around the week
+            let jst = i64::try_from(weekday * DAY + time_of_day).unwrap_or_default();
+            let local = (jst - JST_OFFSET as i64 + i64::from(offset.minutes) * 60)
+                .rem_euclid(WEEK as i64)
+                .unsigned_abs();
+            let weekday = Weekday::ALL[(local / DAY) as usize];
+            let time_of_day = local % DAY;
+            let slot = AiringSlot {
+                next_airing: anime.next_broadcast(now),
+                anime,
+                weekday,
+                hour: (time_of_day / 3600) as u8,
+                minute: (time_of_day % 3600 / 60) as u8,
+            };
+            days.entry(weekday).or_default().push(slot);
+        }
+        for slots in days.values_mut() {
+            slots.sort_by(|a, b| {
+                (a.hour, a.minute, &a.anime.title).cmp(&(b.hour, b.minute, &b.anime.title))
+            });
+        }
+        Self { offset, days }
+    }
+
+    ///Returns the anime airing on `weekday`, sorted by the time of day
+    pub fn day(&self, weekday: Weekday) -> &[AiringSlot] {
+        self.days.get(&weekday).map_or(&[], Vec::as_slice)
+    }
+}
+
+///Returns an iCalendar file with a weekly recurring event for every slot that has a next
+///airing time, starting from that episode. The events recur for the episodes that are left if
+///the episode count is known, and forever otherwise.
+///
+///The events are in UTC, calendar apps show them in the user's time zone. Every anime gets the
+///same `UID` each time, so importing a newer calendar updates the events instead of adding
+///them again.
+///
+///# Example
+///
+///```
+/// use lib_mal::schedule::{to_ics, AiringSchedule, UtcOffset};
+///
+/// let schedule = AiringSchedule::from_anime([], UtcOffset::UTC, std::time::SystemTime::now());
+/// let ics = to_ics(schedule.days.values().flatten());
+/// assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+///```
+pub fn to_ics<'a>(slots: impl IntoIterator<Item = &'a AiringSlot>) -> String {
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//lib-mal//Airing schedule//EN"),
+        String::from("CALSCALE:GREGORIAN"),
+        String::from("METHOD:PUBLISH"),
+    ];
+    for slot in slots {
+        let Some(first) = slot.next_airing else {
+            continue;
+        };
+        let anime = &slot.anime;
+        let length = anime
+            .average_episode_duration
+            .filter(|secs| *secs > 0)
+            .map_or(DEFAULT_EPISODE_LENGTH, |secs| Duration::from_secs(secs as u64));
+        let mut rule = String::from("RRULE:FREQ=WEEKLY");
+        if let Some(left) = episodes_left(anime, first) {
+            rule.push_str(&format!(";COUNT={left}"));
+        }
+        let start = ical_time_of(first);
+        lines.extend([
+            String::from("BEGIN:VEVENT"),
+            format!("UID:anime-{}@lib-mal", anime.id),
+            // the calendar is generated from the schedule, so it's stamped with the airing time
+            // to keep the output the same for the same schedule
+            format!("DTSTAMP:{start}"),
+            format!("DTSTART:{start}"),
+            format!("DTEND:{}", ical_time_of(first + length)),
+            rule,
+            format!("SUMMARY:{}", escape_ics(&anime.title)),
+            format!("URL:https://myanimelist.net/anime/{}", anime.id),
+            String::from("END:VEVENT"),
+        ]);
+    }
+    lines.push(String::from("END:VCALENDAR"));
+    lines.iter().map(|line| fold_ics(line)).collect()
+}
+
+///Returns how many episodes are left counting the one airing at `next`, `None` if the episode
+///count or start date isn't known
+fn episodes_left(anime: &AnimeDetails, next: SystemTime) -> Option<usize> {
+    let total = anime.num_episodes.filter(|n| *n > 0)?;
+    let aired = anime.episodes_aired(next)?;
+    Some((total + 1).saturating_sub(aired).max(1))
+}
+
+///Escapes the characters that have a meaning in iCalendar text values
+fn escape_ics(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+///Splits a line into lines of at most `ICS_LINE_LENGTH` bytes, continued with a space, and ends
+///it with the CRLF iCalendar requires
+fn fold_ics(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > ICS_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
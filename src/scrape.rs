@@ -0,0 +1,233 @@
+//! Data scraped from myanimelist.net that the API doesn't have, only available with the
+//! `scraping` feature, see `ClientBuilder::allow_scraping`
+//!
+//! Pages with the user's own data, like the watch history, are only shown to a logged in site
+//! session, see `ClientBuilder::site_session`. The API's access token doesn't work for them.
+
+use std::time::SystemTime;
+
+use reqwest::header::{HeaderMap, HeaderValue, COOKIE};
+use reqwest::Method;
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+
+use crate::limits::EPISODES_PAGE_SIZE;
+use crate::model::ids::AnimeId;
+use crate::model::options::parse_timestamp;
+use crate::schedule::UtcOffset;
+use crate::{MALClient, MALError};
+
+///The score users gave an episode in the episode polls
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EpisodeScore {
+    ///The episode number
+    pub mal_id: usize,
+    ///The unrounded average, `None` if nobody voted yet
+    pub score: Option<f32>,
+    ///How many users voted, if the page shows it
+    pub votes: Option<usize>,
+}
+
+///An episode the user marked as watched, see `MALClient::get_watch_history`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEvent {
+    pub episode: usize,
+    ///When the episode was marked as watched, like `2024-01-23T21:04:00`. MAL shows the time in
+    ///the time zone of the user's site settings, without saying which one it is.
+    pub watched_at: String,
+}
+
+impl HistoryEvent {
+    ///Returns when the episode was marked as watched, `offset` is the time zone of the user's
+    ///site settings
+    pub fn watched_at_in(&self, offset: UtcOffset) -> Option<SystemTime> {
+        parse_timestamp(&format!("{}{offset}", self.watched_at))
+    }
+}
+
+impl MALClient {
+    ///Scrapes the score of every episode of the anime with `id`, fetching one page of
+    ///`limits::EPISODES_PAGE_SIZE` episodes at a time. Fails with `MALError::FeatureDisabled` if
+    ///scraping is turned off.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{MALClient, MALClientTrait, MALError};
+    /// # async fn test() -> Result<(), MALError> {
+    ///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+    ///     for episode in client.episode_scores(21).await? {
+    ///         println!("Episode {}: {:?}", episode.mal_id, episode.score);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn episode_scores(&self, id: impl Into<AnimeId>) -> Result<Vec<EpisodeScore>, MALError> {
+        let id: AnimeId = id.into();
+        let mut scores: Vec<EpisodeScore> = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = self.episode_scores_page(id, offset).await?;
+            let full = page.len() >= EPISODES_PAGE_SIZE;
+            // stop if MAL ignored the offset and sent a page we already have
+            let last = scores.last().map(|e| e.mal_id);
+            if page.first().is_some_and(|e| Some(e.mal_id) <= last) {
+                break;
+            }
+            scores.extend(page);
+            if !full {
+                break;
+            }
+            offset += EPISODES_PAGE_SIZE;
+        }
+        Ok(scores)
+    }
+
+    ///Scrapes the scores of the episodes after the first `offset` episodes
+    pub(crate) async fn episode_scores_page(
+        &self,
+        id: AnimeId,
+        offset: usize,
+    ) -> Result<Vec<EpisodeScore>, MALError> {
+        if !self.scraping_allowed() {
+            return Err(MALError::FeatureDisabled {
+                feature: "scraping",
+            });
+        }
+        let url = format!("https://myanimelist.net/anime/{id}/_/episode?offset={offset}");
+        let (_, html) = self
+            .fetch("episode_scores", Method::GET, &url, None)
+            .await?;
+        Ok(parse_episode_scores(&html))
+    }
+}
+
+///Reads the episode table of an episode list page
+pub(crate) fn parse_episode_scores(html: &str) -> Vec<EpisodeScore> {
+    let document = Html::parse_document(html);
+    let rows = selector("tr.episode-list-data");
+    let number = selector("td.episode-number");
+    let poll = selector("td.episode-poll");
+    document
+        .select(&rows)
+        .filter_map(|row| {
+            let number = row.select(&number).next()?;
+            let mal_id = number
+                .value()
+                .attr("data-raw")
+                .map_or_else(|| text(number), str::to_owned)
+                .trim()
+                .parse()
+                .ok()?;
+            let poll = row.select(&poll).next();
+            let score = poll
+                .and_then(|p| p.value().attr("data-raw"))
+                .and_then(|s| s.trim().parse::<f32>().ok())
+                .filter(|s| *s > 0.0);
+            let votes = poll.and_then(|p| votes(&text(p)));
+            Some(EpisodeScore {
+                mal_id,
+                score,
+                votes,
+            })
+        })
+        .collect()
+}
+
+fn selector(selector: &str) -> Selector {
+    Selector::parse(selector).expect("selector should be valid")
+}
+
+fn text(element: ElementRef) -> String {
+    element.text().collect()
+}
+
+///Finds the vote count in text like `4.52 (1,234 votes)`
+fn votes(text: &str) -> Option<usize> {
+    let before = &text[..text.find("vote")?];
+    let count = before
+        .trim_end()
+        .rsplit(|c: char| !(c.is_ascii_digit() || c == ','))
+        .next()?
+        .replace(',', "");
+    count.parse().ok()
+}
+
+impl MALClient {
+    ///Scrapes the user's watch history of the anime with `id`: every episode they marked as
+    ///watched and when, oldest first. The API only has the current progress, so this is what a
+    ///"watched on" timeline needs.
+    ///
+    ///MAL only shows the history to a logged in site session, so this fails with
+    ///`MALError::Auth` if `ClientBuilder::site_session` wasn't set, and with
+    ///`MALError::FeatureDisabled` if scraping is turned off. An anime that isn't on the list has
+    ///an empty history.
+    ///
+    ///# Example
+    ///
+    ///```no_run
+    /// # use lib_mal::{ClientBuilder, MALClient, MALError};
+    /// use lib_mal::schedule::UtcOffset;
+    /// # async fn test() -> Result<(), MALError> {
+    ///     let client: MALClient = ClientBuilder::new()
+    ///         .site_session("MALSESSIONID=[YOUR_SESSION_ID]; is_logged_in=1")
+    ///         .build_no_refresh();
+    ///     for event in client.get_watch_history(21).await? {
+    ///         println!("Episode {} on {:?}", event.episode, event.watched_at_in(UtcOffset::UTC));
+    ///     }
+    ///     # Ok(())
+    /// # }
+    ///```
+    pub async fn get_watch_history(&self, id: impl Into<AnimeId>) -> Result<Vec<HistoryEvent>, MALError> {
+        let id: AnimeId = id.into();
+        if !self.scraping_allowed() {
+            return Err(MALError::FeatureDisabled {
+                feature: "scraping",
+            });
+        }
+        let Some(cookie) = self.site_session() else {
+            return Err(MALError::auth(
+                "missing_site_session",
+                String::from("the watch history needs a logged in session, see ClientBuilder::site_session"),
+            ));
+        };
+        let cookie = HeaderValue::from_str(cookie)
+            .map_err(|e| MALError::auth("invalid_site_session", e.to_string()))?;
+        let headers = HeaderMap::from_iter([(COOKIE, cookie)]);
+        let url = format!("https://myanimelist.net/ajaxtb.php?detailedaid={id}");
+        let (_, html) = self
+            .fetch_with_headers("get_watch_history", Method::GET, &url, None, Some(&headers))
+            .await?;
+        Ok(parse_watch_history(&html))
+    }
+}
+
+///Reads the entries of a history popup, like `Ep 12, watched on 01/23/2024 at 21:04`, and
+///returns them oldest first
+pub(crate) fn parse_watch_history(html: &str) -> Vec<HistoryEvent> {
+    let document = Html::parse_document(html);
+    let mut events: Vec<HistoryEvent> = document
+        .select(&selector("div.spaceit_pad"))
+        .filter_map(|entry| parse_history_entry(&text(entry)))
+        .collect();
+    // MAL lists the newest first
+    events.reverse();
+    events.sort_by(|a, b| a.watched_at.cmp(&b.watched_at));
+    events
+}
+
+fn parse_history_entry(text: &str) -> Option<HistoryEvent> {
+    let (episode, rest) = text.trim().strip_prefix("Ep ")?.split_once(',')?;
+    let (date, time) = rest.trim().strip_prefix("watched on ")?.split_once(" at ")?;
+    let mut date = date.trim().split('/').map(|n| n.parse::<u32>().ok());
+    let (month, day, year) = (date.next()??, date.next()??, date.next()??);
+    let (hour, minute) = time.trim().get(..5)?.split_once(':')?;
+    let (hour, minute) = (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return None;
+    }
+    Some(HistoryEvent {
+        episode: episode.trim().parse().ok()?,
+        watched_at: format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:00"),
+    })
+}
@@ -0,0 +1,213 @@
+//! Recognizing the anime and episode in the file names media players and servers report, see
+//! [`MALClient::scrobble`](crate::MALClient::scrobble)
+//!
+//! [`parse`] understands the usual release names, like `[SubsPlease] Frieren - 12 (1080p).mkv`,
+//! `One.Piece.E1071.1080p.WEB.mkv`, or `Shingeki no Kyojin S03E05`, and plain strings like
+//! `Frieren episode 12`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! // mpv finished playing a file
+//! if let Some(report) = client.scrobble("/anime/[SubsPlease] Frieren - 12 (1080p).mkv").await? {
+//!     if report.counted.is_some() {
+//!         println!("marked episode 12 of {} as watched", report.media.title);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::Path;
+
+use crate::model::{AnimeMatch, ListStatus};
+
+///How well the title of a file has to match an anime for `MALClient::scrobble` to update the
+///list, see `titles::similarity`
+pub const MIN_CONFIDENCE: f32 = 0.8;
+
+///Extensions of files whose name is parsed without the directory and extension
+const VIDEO_EXTENSIONS: [&str; 9] = [
+    "mkv", "mp4", "avi", "webm", "m4v", "mov", "ts", "wmv", "flv",
+];
+
+///Words of release names that describe the encode rather than the episode
+const RELEASE_TAGS: [&str; 14] = [
+    "web", "webrip", "web-dl", "bd", "bdrip", "bluray", "hevc", "x264", "x265", "h264", "h265",
+    "aac", "flac", "10bit",
+];
+
+///The show and episode a file name or title refers to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedMedia {
+    pub title: String,
+    ///The season, if the name has a marker like `S02E05`
+    pub season: Option<usize>,
+    ///The episode, starting from 1. `None` for names without an episode, like movies.
+    pub episode: Option<usize>,
+}
+
+impl ParsedMedia {
+    ///Returns the text to search MAL for. MAL lists later seasons as separate anime, so the
+    ///season is added to the title.
+    pub fn search_query(&self) -> String {
+        match self.season {
+            Some(season) if season > 1 => format!("{} Season {season}", self.title),
+            _ => self.title.clone(),
+        }
+    }
+}
+
+///The outcome of `MALClient::scrobble`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScrobbleReport {
+    pub media: ParsedMedia,
+    ///The best search result for the title, `None` if nothing was found
+    pub matched: Option<AnimeMatch>,
+    ///The updated list entry if the match was confident and the episode wasn't watched yet
+    pub counted: Option<ListStatus>,
+}
+
+///Returns the title and episode in `name`, a file name, a path, or a title followed by an
+///episode. Returns `None` if there's no title left after removing the release tags.
+///
+///A number at the end of the name is taken as the episode, so titles ending in a number need an
+///explicit episode, like `Mob Psycho 100 - 03`.
+pub fn parse(name: &str) -> Option<ParsedMedia> {
+    let name = strip_file_path(name.trim());
+    let name = strip_brackets(name);
+    let name = name.replace('_', " ");
+    // dots only separate words if there are no spaces, `Dr. Stone` keeps them
+    let name = if name.contains(' ') {
+        name
+    } else {
+        name.replace('.', " ")
+    };
+    let words: Vec<&str> = name
+        .split_whitespace()
+        .filter(|w| !is_release_tag(w))
+        .collect();
+
+    let (title, season, episode) = split_episode(&words);
+    let title = title
+        .join(" ")
+        .trim_matches(|c: char| c == '-' || c == ':' || c.is_whitespace())
+        .to_owned();
+    if title.is_empty() {
+        return None;
+    }
+    Some(ParsedMedia {
+        title,
+        season,
+        episode,
+    })
+}
+
+///Returns the file name without the extension if `name` is the path of a video, `name` as it is
+///otherwise, since titles like `Fate/Zero` contain slashes
+fn strip_file_path(name: &str) -> &str {
+    let path = Path::new(name);
+    let is_video = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| VIDEO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()));
+    if !is_video {
+        return name;
+    }
+    // Windows paths end up here on other systems too
+    let file = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    file.rsplit_once('.').map_or(file, |(stem, _)| stem)
+}
+
+///Removes the release group, resolution, checksum, and other tags in brackets
+fn strip_brackets(name: &str) -> String {
+    let mut res = String::with_capacity(name.len());
+    let mut depth = 0usize;
+    for c in name.chars() {
+        match c {
+            '[' | '(' | '{' | '【' => depth += 1,
+            ']' | ')' | '}' | '】' => {
+                depth = depth.saturating_sub(1);
+                res.push(' ');
+            }
+            c if depth == 0 => res.push(c),
+            _ => {}
+        }
+    }
+    res
+}
+
+fn is_release_tag(word: &str) -> bool {
+    let word = word.to_ascii_lowercase();
+    let resolution = word
+        .strip_suffix('p')
+        .is_some_and(|n| matches!(n, "480" | "576" | "720" | "1080" | "2160"));
+    resolution || RELEASE_TAGS.contains(&word.as_str())
+}
+
+///Splits `words` into the title and the season and episode after it
+fn split_episode<'a>(words: &'a [&'a str]) -> (&'a [&'a str], Option<usize>, Option<usize>) {
+    // S03E05
+    for (i, word) in words.iter().enumerate().skip(1) {
+        let lower = word.to_ascii_lowercase();
+        if let Some((season, episode)) = lower.strip_prefix('s').and_then(|w| w.split_once('e')) {
+            if let (Some(season), Some(episode)) = (number(season), episode_number(episode)) {
+                return (&words[..i], Some(season), Some(episode));
+            }
+        }
+    }
+    // Title - 12, taking the last dash since titles can contain one
+    for i in (1..words.len().saturating_sub(1)).rev() {
+        if words[i] == "-" {
+            if let Some(episode) = episode_number(words[i + 1]) {
+                return (&words[..i], None, Some(episode));
+            }
+        }
+    }
+    // Title Episode 12, Title Ep 12, Title E12, Title #12
+    for (i, word) in words.iter().enumerate().skip(1) {
+        let lower = word.to_ascii_lowercase();
+        let marker = lower.trim_end_matches('.');
+        if matches!(marker, "episode" | "ep") {
+            if let Some(episode) = words.get(i + 1).and_then(|w| episode_number(w)) {
+                return (&words[..i], None, Some(episode));
+            }
+        }
+        let attached = ["episode", "ep.", "ep", "e", "#"]
+            .iter()
+            .find_map(|prefix| lower.strip_prefix(prefix))
+            .and_then(episode_number);
+        if attached.is_some() {
+            return (&words[..i], None, attached);
+        }
+    }
+    // Title 12
+    if let [title @ .., last] = words {
+        if !title.is_empty() {
+            if let Some(episode) = episode_number(last).filter(|e| *e > 0) {
+                return (title, None, Some(episode));
+            }
+        }
+    }
+    (words, None, None)
+}
+
+fn number(s: &str) -> Option<usize> {
+    if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+///Parses an episode number with an optional release version, like `12v2`
+fn episode_number(s: &str) -> Option<usize> {
+    let s = s.to_ascii_lowercase();
+    let s = match s.split_once('v') {
+        Some((episode, version)) if number(version).is_some() => episode.to_owned(),
+        _ => s,
+    };
+    number(&s)
+}
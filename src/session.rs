@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::model::{ids::AnimeId, options::StatusParams, ListStatus};
+use crate::{MALClientTrait, MALError};
+
+///Serializes writes to the user's anime list per entry, see `MALClientTrait::list_session`
+///
+///Two writes to the same anime wait for each other, so concurrent updates can't interleave and
+///leave the entry in a mix of both. Writes to different anime run in parallel, only limited by
+///the client's rate limit.
+///
+///# Example
+///
+///```no_run
+/// # use lib_mal::{MALClient, MALClientTrait, MALError};
+/// use lib_mal::model::options::{Status, StatusUpdate};
+/// # async fn test() -> Result<(), MALError> {
+///     # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+///     let session = client.list_session();
+///     let watching = StatusUpdate::new().status(Status::Watching).build()?;
+///     let episode = StatusUpdate::new().num_watched_episodes(3).build()?;
+///     // both target 21, so the second one is sent after the first one finished
+///     let (a, b) = tokio::join!(session.update(21, watching), session.update(21, episode));
+///     # Ok(())
+/// # }
+///```
+pub struct ListSession<'a, C> {
+    client: &'a C,
+    locks: Mutex<HashMap<AnimeId, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl<'a, C: MALClientTrait + Sync> ListSession<'a, C> {
+    pub fn new(client: &'a C) -> Self {
+        Self {
+            client,
+            locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    ///Updates the entry of the anime with `id`, see `MALClientTrait::update_user_anime_status`
+    pub async fn update(&self, id: impl Into<AnimeId>, update: StatusParams) -> Result<ListStatus, MALError> {
+        let id: AnimeId = id.into();
+        let lock = self.lock_for(id);
+        let res = {
+            let _guard = lock.lock().await;
+            self.client.update_user_anime_status(id, update).await
+        };
+        self.release(id, lock);
+        res
+    }
+
+    ///Adds `by` watched episodes to the entry of the anime with `id`, see
+    ///`MALClientTrait::increment_watched_episodes`. The entry can't change between reading and
+    ///updating it through this session.
+    pub async fn increment_watched_episodes(
+        &self,
+        id: impl Into<AnimeId>,
+        by: usize,
+    ) -> Result<ListStatus, MALError> {
+        let id: AnimeId = id.into();
+        let lock = self.lock_for(id);
+        let res = {
+            let _guard = lock.lock().await;
+            self.client.increment_watched_episodes(id, by).await
+        };
+        self.release(id, lock);
+        res
+    }
+
+    ///Marks the anime with `id` as Completed, see `MALClientTrait::mark_completed`
+    pub async fn mark_completed(
+        &self,
+        id: impl Into<AnimeId>,
+        score: impl Into<Option<u8>> + Send,
+    ) -> Result<ListStatus, MALError> {
+        let id: AnimeId = id.into();
+        let lock = self.lock_for(id);
+        let res = {
+            let _guard = lock.lock().await;
+            self.client.mark_completed(id, score).await
+        };
+        self.release(id, lock);
+        res
+    }
+
+    ///Deletes the entry of the anime with `id`, see `MALClientTrait::delete_anime_list_item`
+    pub async fn delete(&self, id: impl Into<AnimeId>) -> Result<(), MALError> {
+        let id: AnimeId = id.into();
+        let lock = self.lock_for(id);
+        let res = {
+            let _guard = lock.lock().await;
+            self.client.delete_anime_list_item(id).await
+        };
+        self.release(id, lock);
+        res
+    }
+
+    ///Returns the number of anime with a write in progress or waiting
+    pub fn pending(&self) -> usize {
+        self.locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    fn lock_for(&self, id: AnimeId) -> Arc<tokio::sync::Mutex<()>> {
+        let mut locks = self.locks.lock().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(locks.entry(id).or_default())
+    }
+
+    ///Forgets the lock of `id` once no other write holds or waits for it
+    fn release(&self, id: AnimeId, lock: Arc<tokio::sync::Mutex<()>>) {
+        let mut locks = self.locks.lock().unwrap_or_else(PoisonError::into_inner);
+        // one reference is in the map, the other one is `lock`
+        if Arc::strong_count(&lock) == 2 {
+            locks.remove(&id);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Statistics of an anime list like the ones on a MAL profile, computed from the list without
+//! extra requests
+//!
+//! The list has to be fetched with the fields in [`ListStats::FIELDS`], entries without them are
+//! still counted but left out of the stats that need them.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::model::options::UserListQuery;
+//! use lib_mal::stats::ListStats;
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let query = UserListQuery::new().fields(ListStats::FIELDS).limit(1000);
+//! let list = client.get_user_anime_list(query).await?;
+//! let stats = ListStats::from(&list);
+//! println!("{:.1} days watched, mean score {:?}", stats.days_watched(), stats.mean_score);
+//! for (genre, count) in stats.top_genres(5) {
+//!     println!("{genre}: {count}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::model::fields::AnimeFields;
+use crate::model::options::Status;
+use crate::model::ids::AnimeId;
+use crate::model::{time_for, AnimeList, ListNode, Score};
+
+///The stats of an anime list, see the module docs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListStats {
+    ///Number of entries on the list
+    pub entries: usize,
+    ///Number of entries with each status
+    pub by_status: HashMap<Status, usize>,
+    ///Average of the scores given, `None` if nothing was scored
+    pub mean_score: Option<f32>,
+    ///Number of entries with each score, entries without a score are left out
+    pub score_distribution: BTreeMap<Score, usize>,
+    ///Episodes watched, counting rewatches of anime with a known episode count
+    pub episodes_watched: usize,
+    ///Time spent on `episodes_watched`, only for anime with a known episode length
+    pub time_watched: Duration,
+    ///Number of entries with each genre
+    pub genres: HashMap<String, usize>,
+    ///Number of entries by the year the anime started airing
+    pub by_year: BTreeMap<u16, usize>,
+}
+
+impl ListStats {
+    ///The fields the list has to be fetched with for all stats
+    pub const FIELDS: AnimeFields = AnimeFields::MyListStatus
+        .union(AnimeFields::NumEpisodes)
+        .union(AnimeFields::AverageEpisodeDuration)
+        .union(AnimeFields::Genres)
+        .union(AnimeFields::StartSeason)
+        .union(AnimeFields::StartDate);
+
+    ///Returns `time_watched` in days, like MAL shows it
+    pub fn days_watched(&self) -> f64 {
+        self.time_watched.as_secs_f64() / 86_400.0
+    }
+
+    ///Returns the `n` most common genres, most common first and by name for ties
+    pub fn top_genres(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut genres: Vec<(&str, usize)> = self
+            .genres
+            .iter()
+            .map(|(genre, count)| (genre.as_str(), *count))
+            .collect();
+        genres.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        genres.truncate(n);
+        genres
+    }
+
+    fn add(&mut self, entry: &ListNode) {
+        self.entries += 1;
+        let anime = &entry.details;
+        if let Some(status) = &entry.list_status {
+            if let Some(s) = status.status.as_deref().and_then(|s| s.parse().ok()) {
+                *self.by_status.entry(s).or_default() += 1;
+            }
+            if let Some(score) = status.score.filter(|s| s.is_scored()) {
+                *self.score_distribution.entry(score).or_default() += 1;
+            }
+            let rewatched = status.num_times_rewatched.unwrap_or_default()
+                * anime.num_episodes.unwrap_or_default();
+            let episodes = status.num_episodes_watched.unwrap_or_default() + rewatched;
+            self.episodes_watched += episodes;
+            if let Some(seconds) = anime.average_episode_duration {
+                self.time_watched += Duration::from_secs((episodes * seconds) as u64);
+            }
+        }
+        for genre in anime.genres.iter().flatten() {
+            if let Some(name) = genre.get("name").and_then(Value::as_str) {
+                *self.genres.entry(name.to_owned()).or_default() += 1;
+            }
+        }
+        if let Some(year) = start_year(entry) {
+            *self.by_year.entry(year).or_default() += 1;
+        }
+    }
+}
+
+///What's left to watch on an anime list, the entries that are Watching, On Hold, or Plan to
+///Watch. The list has to be fetched with the fields in [`Backlog::FIELDS`].
+///
+///# Example
+///
+///```no_run
+/// # use lib_mal::{MALClient, MALClientTrait, MALError};
+/// use lib_mal::model::options::UserListQuery;
+/// use lib_mal::stats::Backlog;
+/// # async fn test() -> Result<(), MALError> {
+/// # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+/// let list = client.get_user_anime_list(UserListQuery::new().fields(Backlog::FIELDS).limit(1000)).await?;
+/// let backlog = Backlog::from(&list);
+/// println!("{} episodes, {:.1} days left", backlog.episodes, backlog.days());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Backlog {
+    ///Number of entries counted
+    pub entries: usize,
+    ///Episodes left, for anime with a known episode count
+    pub episodes: usize,
+    ///Time `episodes` take, for anime with a known episode length
+    pub time: Duration,
+    ///Entries left out of `time` because their episode count or length isn't known, so the
+    ///real time left is longer if it isn't empty
+    pub unknown: Vec<AnimeId>,
+}
+
+impl Backlog {
+    ///The fields the list has to be fetched with
+    pub const FIELDS: AnimeFields = AnimeFields::MyListStatus
+        .union(AnimeFields::NumEpisodes)
+        .union(AnimeFields::AverageEpisodeDuration);
+
+    ///Returns `time` in days
+    pub fn days(&self) -> f64 {
+        self.time.as_secs_f64() / 86_400.0
+    }
+}
+
+impl From<&AnimeList> for Backlog {
+    fn from(list: &AnimeList) -> Self {
+        let mut backlog = Self::default();
+        let unfinished = list.data.iter().filter(|entry| {
+            let status = entry.list_status.as_ref().and_then(|s| s.status.as_deref());
+            matches!(status, Some("watching" | "on_hold" | "plan_to_watch"))
+        });
+        for entry in unfinished {
+            backlog.entries += 1;
+            let Some(episodes) = entry.remaining_episodes() else {
+                backlog.unknown.push(entry.node.id);
+                continue;
+            };
+            backlog.episodes += episodes;
+            if episodes == 0 {
+                continue;
+            }
+            match time_for(episodes, entry.details.average_episode_duration) {
+                Some(time) => backlog.time += time,
+                None => backlog.unknown.push(entry.node.id),
+            }
+        }
+        backlog
+    }
+}
+
+///Returns the year from `start_season`, or from `start_date` if the season wasn't requested
+fn start_year(entry: &ListNode) -> Option<u16> {
+    let anime = &entry.details;
+    let from_season = anime
+        .start_season
+        .as_ref()
+        .and_then(|s| s.get("year"))
+        .and_then(Value::as_u64)
+        .and_then(|y| u16::try_from(y).ok());
+    from_season.or_else(|| anime.start_date.as_deref()?.get(..4)?.parse().ok())
+}
+
+impl From<&AnimeList> for ListStats {
+    #[allow(clippy::cast_precision_loss)]
+    fn from(list: &AnimeList) -> Self {
+        let mut stats = Self::default();
+        for entry in &list.data {
+            stats.add(entry);
+        }
+        let (count, sum) = stats
+            .score_distribution
+            .iter()
+            .fold((0, 0), |(count, sum), (score, n)| {
+                (count + n, sum + usize::from(score.get()) * n)
+            });
+        stats.mean_score = (count > 0).then(|| sum as f32 / count as f32);
+        stats
+    }
+}
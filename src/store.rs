@@ -0,0 +1,122 @@
+use std::sync::{Mutex, PoisonError};
+
+use crate::{MALError, Tokens};
+
+///Persists the tokens of a `MALClient` between runs
+///
+///The client uses a `FileTokenStore` in its cache directory by default, implement this trait
+///to keep the tokens in a keyring, a database, or your own config system instead. Without the
+///`oauth` feature there's no `FileTokenStore` and the default is a [`MemoryTokenStore`].
+///
+///# Example
+///
+///```
+/// use lib_mal::{ClientBuilder, MALClient, MemoryTokenStore};
+/// fn example() {
+///     let client: MALClient = ClientBuilder::new()
+///         .secret("[YOUR_CLIENT_ID]".to_string())
+///         .token_store(MemoryTokenStore::new())
+///         .build_no_refresh();
+/// }
+///```
+pub trait TokenStore: Send + Sync {
+    ///Returns the stored tokens, or `None` if nothing has been stored yet
+    fn load(&self) -> Result<Option<Tokens>, MALError>;
+    fn save(&self, tokens: &Tokens) -> Result<(), MALError>;
+    fn clear(&self) -> Result<(), MALError>;
+}
+
+///Keeps the tokens in memory only, they're lost once the store is dropped
+#[derive(Default)]
+pub struct MemoryTokenStore {
+    tokens: Mutex<Option<Tokens>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self) -> Result<Option<Tokens>, MALError> {
+        Ok(self
+            .tokens
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone())
+    }
+
+    fn save(&self, tokens: &Tokens) -> Result<(), MALError> {
+        *self.tokens.lock().unwrap_or_else(PoisonError::into_inner) = Some(tokens.clone());
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), MALError> {
+        *self.tokens.lock().unwrap_or_else(PoisonError::into_inner) = None;
+        Ok(())
+    }
+}
+
+///Keeps the tokens in the browser's `localStorage`, so they survive page reloads. Only available
+///on wasm32 with the `wasm` feature.
+///
+///`localStorage` isn't encrypted and any script on the page can read it, so this suits apps that
+///already trust every script they load.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub struct LocalStorageTokenStore {
+    key: String,
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+impl LocalStorageTokenStore {
+    ///Stores the tokens under the `lib-mal-tokens` key
+    pub fn new() -> Self {
+        Self::with_key("lib-mal-tokens")
+    }
+
+    ///Stores the tokens under `key`, for apps with more than one client
+    pub fn with_key(key: &str) -> Self {
+        Self {
+            key: key.to_string(),
+        }
+    }
+
+    fn storage() -> Result<web_sys::Storage, MALError> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| MALError::Io(std::io::Error::other("localStorage isn't available")))
+    }
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+impl Default for LocalStorageTokenStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+impl TokenStore for LocalStorageTokenStore {
+    fn load(&self) -> Result<Option<Tokens>, MALError> {
+        let stored = Self::storage()?
+            .get_item(&self.key)
+            .map_err(|_| MALError::Io(std::io::Error::other("couldn't read localStorage")))?;
+        stored
+            .map(|json| serde_json::from_str(&json).map_err(|e| MALError::TokenCache(e.to_string())))
+            .transpose()
+    }
+
+    fn save(&self, tokens: &Tokens) -> Result<(), MALError> {
+        let json = serde_json::to_string(tokens).map_err(|e| MALError::TokenCache(e.to_string()))?;
+        Self::storage()?
+            .set_item(&self.key, &json)
+            .map_err(|_| MALError::Io(std::io::Error::other("couldn't write localStorage")))
+    }
+
+    fn clear(&self) -> Result<(), MALError> {
+        Self::storage()?
+            .remove_item(&self.key)
+            .map_err(|_| MALError::Io(std::io::Error::other("couldn't write localStorage")))
+    }
+}
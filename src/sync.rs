@@ -0,0 +1,224 @@
+//! Diffing and syncing a local copy of the user's anime list with MAL, see
+//! [`MALClient::sync_list`](crate::MALClient::sync_list)
+//!
+//! A local copy is a [`ListSnapshot`], the list status of every anime on the list by id. Fields
+//! that are `None` in a snapshot are treated as unknown, so they're never synced, only fields
+//! with a value are.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::sync::{ListSnapshot, SyncDirection};
+//! # async fn test(mut local: ListSnapshot) -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! // see what pushing the local changes would do without sending anything
+//! let report = client.sync_list(&mut local, SyncDirection::Push, true).await?;
+//! for change in &report.diff.changes {
+//!     println!("{}: {:?}", change.id, change.kind);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+use crate::model::options::{Status, StatusUpdate};
+use crate::model::{AnimeId, AnimeList, ListStatus, Tags};
+use crate::MALError;
+
+///The list status of every anime on a list, by anime id
+pub type ListSnapshot = BTreeMap<AnimeId, ListStatus>;
+
+///Fields that change on every write, so they're never compared
+const IGNORED_FIELDS: [&str; 1] = ["updated_at"];
+
+///Returns the snapshot of every entry of `list` that has a list status
+pub fn snapshot(list: &AnimeList) -> ListSnapshot {
+    list.data
+        .iter()
+        .filter_map(|entry| {
+            let status = entry
+                .list_status
+                .as_ref()
+                .or(entry.details.my_list_status.as_ref())?;
+            Some((entry.node.id, status.clone()))
+        })
+        .collect()
+}
+
+///Returns the changes that turn `from` into `to`. Fields that are `None` in `to` are left out.
+pub fn diff(from: &ListSnapshot, to: &ListSnapshot) -> ListDiff {
+    let mut changes = Vec::new();
+    for (id, status) in to {
+        let kind = match from.get(id) {
+            None => ChangeKind::Added(status.clone()),
+            Some(old) => {
+                let fields = field_changes(old, status);
+                if fields.is_empty() {
+                    continue;
+                }
+                ChangeKind::Updated(fields)
+            }
+        };
+        changes.push(EntryChange { id: *id, kind });
+    }
+    for (id, status) in from {
+        if !to.contains_key(id) {
+            changes.push(EntryChange {
+                id: *id,
+                kind: ChangeKind::Removed(status.clone()),
+            });
+        }
+    }
+    changes.sort_by_key(|c| c.id);
+    ListDiff { changes }
+}
+
+///The changes between two snapshots, ordered by anime id
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListDiff {
+    pub changes: Vec<EntryChange>,
+}
+
+impl ListDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    ///Applies the changes to `snapshot`
+    pub fn apply(&self, snapshot: &mut ListSnapshot) {
+        for change in &self.changes {
+            match &change.kind {
+                ChangeKind::Added(status) => {
+                    snapshot.insert(change.id, status.clone());
+                }
+                ChangeKind::Removed(_) => {
+                    snapshot.remove(&change.id);
+                }
+                ChangeKind::Updated(fields) => {
+                    if let Some(status) = snapshot.get_mut(&change.id) {
+                        let mut map = to_map(status);
+                        for field in fields {
+                            map.insert(field.field.clone(), field.to.clone());
+                        }
+                        if let Ok(updated) = serde_json::from_value(Value::Object(map)) {
+                            *status = updated;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryChange {
+    ///Id of the anime
+    pub id: AnimeId,
+    pub kind: ChangeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    ///The anime isn't on the list yet
+    Added(ListStatus),
+    ///The anime is on the list but has to be removed, holds its current status
+    Removed(ListStatus),
+    ///The anime is on the list with different values in some fields
+    Updated(Vec<FieldChange>),
+}
+
+impl ChangeKind {
+    ///Returns the update that makes MAL's entry match, `None` for removals
+    pub(crate) fn to_update(&self) -> Option<StatusUpdate> {
+        let update = match self {
+            Self::Added(status) => to_map(status)
+                .into_iter()
+                .fold(StatusUpdate::new(), |update, (field, value)| {
+                    set_field(update, &field, &value)
+                }),
+            Self::Updated(fields) => fields.iter().fold(StatusUpdate::new(), |update, field| {
+                set_field(update, &field.field, &field.to)
+            }),
+            Self::Removed(_) => return None,
+        };
+        Some(update)
+    }
+}
+
+///A field of a list status that differs, with the JSON values MAL uses for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    ///Name of the field in `ListStatus`, like `num_episodes_watched`
+    pub field: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+///Which side of a sync is changed to match the other one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncDirection {
+    ///Sends the local changes to MAL, anime missing from the local snapshot are removed from
+    ///the user's list
+    Push,
+    ///Updates the local snapshot to match the user's list on MAL
+    Pull,
+}
+
+///The outcome of `MALClient::sync_list`
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    ///The changes that were applied, or would have been in a dry run
+    pub diff: ListDiff,
+    ///Changes MAL rejected, by anime id. Always empty for pulls and dry runs.
+    pub failed: Vec<(AnimeId, MALError)>,
+}
+
+fn to_map(status: &ListStatus) -> Map<String, Value> {
+    match serde_json::to_value(status) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+fn field_changes(from: &ListStatus, to: &ListStatus) -> Vec<FieldChange> {
+    let from = to_map(from);
+    to_map(to)
+        .into_iter()
+        .filter(|(field, value)| !value.is_null() && !IGNORED_FIELDS.contains(&field.as_str()))
+        .filter_map(|(field, value)| {
+            let old = from.get(&field).cloned().unwrap_or(Value::Null);
+            (old != value).then_some(FieldChange {
+                field,
+                from: old,
+                to: value,
+            })
+        })
+        .collect()
+}
+
+///Sets the field of `update` that corresponds to the `ListStatus` field called `field`, values
+///of the wrong type leave it unset
+#[allow(clippy::cast_possible_truncation)]
+fn set_field(update: StatusUpdate, field: &str, value: &Value) -> StatusUpdate {
+    let number = || value.as_u64();
+    let small = || number().and_then(|n| u8::try_from(n).ok());
+    let string = || value.as_str().map(str::to_owned);
+    match field {
+        "status" => update.status(value.as_str().and_then(|s| s.parse::<Status>().ok())),
+        "num_episodes_watched" => update.num_watched_episodes(number().map(|n| n as usize)),
+        "score" => update.score(small()),
+        "is_rewatching" => update.is_rewatching(value.as_bool()),
+        "num_times_rewatched" => update.num_times_rewatched(number().map(|n| n as usize)),
+        "priority" => update.priority(small()),
+        "rewatch_value" => update.rewatch_value(small()),
+        "tags" => update.tags(serde_json::from_value::<Tags>(value.clone()).ok()),
+        "comments" => update.comments(string()),
+        "start_date" => update.start_date(string()),
+        "finish_date" => update.finish_date(string()),
+        _ => update,
+    }
+}
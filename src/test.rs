@@ -1,24 +1,55 @@
 use std::env;
+use std::path::PathBuf;
 
-use crate::model::fields::AnimeFields;
-use crate::model::options::{RankingType, Season};
-use crate::model::{AnimeDetails, AnimeList};
-use crate::{MALClient, MALClientTrait};
+use crate::client::Configure;
+use crate::archive::SeasonArchive;
+use crate::batch::{BatchOptions, BatchOutcome};
+use crate::clear::{ListClearOptions, CLEAR_LIST_CONFIRMATION};
+use crate::cache::{Lookup, ResponseCache, Validators};
+use crate::ranking::{self, RankingSnapshot};
+use crate::affinity::AffinityReport;
+use crate::list_cache::ListCache;
+use crate::schedule::{self, AiringSchedule, UtcOffset, Weekday};
+use crate::scrobble;
+use crate::replay::ReplayTransport;
+use crate::rate_limit::{self, RateLimiter};
+use crate::titles;
+use crate::sync::{self, ChangeKind, FieldChange, ListSnapshot};
+use crate::wire;
+use crate::test_server::MockServer;
+use crate::pictures::PictureSize;
+use crate::watch;
+use crate::library::{unwatched_episodes, LibraryProvider, LocalEpisode};
+use crate::model::ids::AnimeId;
+use crate::model::export::{ConflictPolicy, MalExport};
+use crate::model::fields::{AnimeFields, UserFields};
+use crate::model::options::{
+    ForumSort, ForumTopicQuery, ListSort, MediaType, Params, RankingType, RelationQuery, RelationType, Season,
+    SeasonYear, Source, Status, StatusParams, StatusUpdate, SuggestionQuery, UserListQuery,
+};
+#[cfg(not(feature = "minimal-models"))]
+use crate::model::StatusCounts;
+use crate::model::{AiringProgress, AnimeDetails, AnimeList, Character, ListStatus, EpisodesList, ForumTopics, InconsistencyKind, MangaId, PageCursor, RelatedManga, Score, StaffMember, Tags, TitleLanguage, TopicDetails, User};
+use crate::{
+    AuthState, CacheCodec, CacheConfig, CacheStats, ClientBuilder, Clock, MALClient, MALError, MALClientTrait, MemoryTokenStore, MockClock, MockMALClient, ReauthReason, TokenStore, Tokens,
+};
+#[cfg(feature = "oauth")]
+use crate::{AuthParts, FileTokenStore, TokenCache};
 
 #[tokio::test]
 async fn anime_list() {
-    let client = setup();
+    let (_server, client) = setup();
     let expected =
         serde_json::from_str::<AnimeList>(include_str!("test-data/anime_list.json")).unwrap();
-    let result = client.get_anime_list("one", Some(4)).await.expect("Error performing request");
+    let result = client.get_anime_list("one", Some(4), None).await.expect("Error performing request");
     let first = expected.data[0].node.id;
     let res_first = result.data[0].node.id;
-    assert_eq!(first, res_first); //Really don't want to implement partial_eq for all these structs lol
+    assert_eq!(first, res_first);
 }
 
 #[tokio::test]
 async fn anime_details() {
-    let client = setup();
+    let (server, client) = setup();
     let expected =
         serde_json::from_str::<AnimeDetails>(include_str!("test-data/anime_details.json")).unwrap();
     let result = client.get_anime_details(30230, AnimeFields::ALL).await
@@ -26,14 +57,269 @@ async fn anime_details() {
     let left = expected.show.title;
     let right = result.show.title;
     assert_eq!(left, right);
+    assert!(server.requests.lock().unwrap()[0].starts_with("GET /v2/anime/30230?fields=id%2Ctitle"));
+    assert!(matches!(client.get_anime_details(1, None).await, Err(MALError::Gone { .. })));
+}
+
+#[tokio::test]
+async fn query_encoding() {
+    let mal = MALClient::with_access_token("token");
+    assert_eq!(
+        mal.api_url(&["anime"], [("q", "Re:Zero − Starting Life"), ("limit", "4")]),
+        "https://api.myanimelist.net/v2/anime?q=Re%3AZero+%E2%88%92+Starting+Life&limit=4"
+    );
+    assert_eq!(mal.api_url(&["users", "@me"], [] as [(&str, &str); 0]), "https://api.myanimelist.net/v2/users/@me");
+    let gateway: MALClient = ClientBuilder::new().api_base_url("http://localhost:8080/mal").build_no_refresh();
+    assert_eq!(gateway.api_url(&["anime", "5"], [("fields", "id")]), "http://localhost:8080/mal/v2/anime/5?fields=id");
+
+    let (server, client) = setup();
+    client.get_anime_list("Fate/Zero #2 & 100% ?", Some(4), None).await.unwrap();
+    let _ = client.get_user_info("a b/c?d", UserFields::TimeZone).await;
+    let _ = client.get_user_anime_list_for("名前#1", None).await;
+    let requests = server.requests.lock().unwrap();
+    assert_eq!(requests[0], "GET /v2/anime?q=Fate%2FZero+%232+%26+100%25+%3F&limit=4");
+    assert_eq!(requests[1], "GET /v2/users/a%20b%2Fc%3Fd?fields=time_zone");
+    assert!(requests[2].starts_with("GET /v2/users/%E5%90%8D%E5%89%8D%231/animelist?"));
+}
+
+#[tokio::test]
+async fn execute_endpoints() {
+    use crate::endpoint::Endpoint;
+
+    let (server, client) = setup();
+    let details: AnimeDetails = client
+        .execute(Endpoint::AnimeDetails { id: AnimeId(30230), fields: Some(AnimeFields::Title) })
+        .await
+        .unwrap();
+    assert_eq!(details.show.id, AnimeId(30230));
+    let ranking: AnimeList = client
+        .execute(Endpoint::AnimeRanking { ranking_type: RankingType::Airing, limit: 3.into(), fields: None })
+        .await
+        .unwrap();
+    assert!(!ranking.data.is_empty());
+    let raw: serde_json::Value = client.execute(Endpoint::get("anime/21").query("fields", "id,title")).await.unwrap();
+    assert_eq!(raw["id"], 21);
+    let gone = client.execute::<()>(Endpoint::AnimeDetails { id: AnimeId(1), fields: None }).await;
+    assert!(matches!(gone, Err(MALError::Gone { context: Some(c) }) if c.endpoint == "get_anime_details"));
+    let update = Endpoint::UpdateListStatus { id: AnimeId(5), update: StatusUpdate::new().score(7).build().unwrap() };
+    assert!(client.execute::<()>(update).await.is_err());
+    let too_many = Endpoint::AnimeSearch { query: "one".into(), limit: 101.into(), offset: None, fields: None };
+    assert!(matches!(client.execute::<()>(too_many).await, Err(MALError::Invalid { field: "limit", .. })));
+
+    let requests = server.requests.lock().unwrap();
+    assert_eq!(requests[0], "GET /v2/anime/30230?fields=title");
+    assert_eq!(requests[1], "GET /v2/anime/ranking?ranking_type=airing&limit=3");
+    assert_eq!(requests[2], "GET /v2/anime/21?fields=id%2Ctitle");
+    assert_eq!(requests[4], "PUT /v2/anime/5/my_list_status");
+}
+
+#[tokio::test]
+async fn raw_responses() {
+    let (server, client) = setup();
+    let raw = client.get_anime_details_raw(30230, None).await.unwrap();
+    assert_eq!(raw["id"], 30230);
+    let details = client.get_anime_details_with_raw(30230, AnimeFields::ALL).await.unwrap();
+    assert_eq!(raw, details.raw);
+    assert_eq!(details.show.title, raw["title"].as_str().unwrap());
+    assert_eq!(client.get_raw("/anime/30230").await.unwrap(), raw);
+    assert!(matches!(client.get_anime_details_raw(1, None).await, Err(MALError::Gone { .. })));
+    assert!(server.requests.lock().unwrap().iter().all(|r| r.starts_with("GET /v2/anime/")));
+
+    let unknown: crate::model::WithRaw<crate::model::Anime> = serde_json::from_str(
+        r#"{"id": 1, "title": "Cowboy Bebop", "main_picture": {}, "new_field": [1]}"#,
+    )
+    .unwrap();
+    assert_eq!(unknown.title, "Cowboy Bebop");
+    assert_eq!(unknown.raw["new_field"][0], 1);
+}
+
+#[tokio::test]
+async fn list_status_types() {
+    let (_server, client) = setup();
+    let real = client.get_anime_details(30230, None).await.unwrap();
+    let mock = MockMALClient::with_access_token("token").get_anime_details(30230, AnimeFields::ALL).await.unwrap();
+    assert_eq!(real, mock);
+    let status = real.my_list_status.unwrap();
+    assert_eq!(status.num_times_rewatched, Some(0));
+    assert!(!status.score.unwrap().is_scored());
+    assert_eq!(status.updated_time(), Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_510_429_882)));
+
+    let status: ListStatus = serde_json::from_str(r#"{"score": 8, "start_date": "2020-01-02", "finish_date": "2020"}"#).unwrap();
+    assert_eq!(status.score.map(u8::from), Some(8));
+    assert_eq!(status.started_at(), Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_577_923_200)));
+    assert_eq!(status.finished_at(), None);
+    assert!(serde_json::from_str::<ListStatus>(r#"{"score": 11}"#).is_err());
+    assert!(matches!(Score::try_from(11), Err(MALError::Invalid { field: "score", .. })));
+    assert_eq!("10".parse::<Score>().unwrap(), 10);
+}
+
+#[tokio::test]
+async fn limit_maximums() {
+    use crate::limits::{Limit, DEFAULT_LIMIT};
+
+    let client = MALClient::with_access_token("token");
+    // checked before anything is sent
+    let err = client.get_anime_list("one", 101, None).await.unwrap_err();
+    assert!(err.to_string().contains("101 is above the maximum of 100 for get_anime_list"));
+    assert!(client.get_anime_ranking(RankingType::All, 501, None).await.is_err());
+    assert!(client.get_forum_topic_detail(5, 0).await.unwrap_err().to_string().contains("get_forum_topic_detail"));
+    assert!(client.get_forum_topic_posts(5, 101, 0).await.unwrap_err().to_string().contains("get_forum_topic_posts"));
+    let query = UserListQuery::new().limit(1001);
+    assert!(matches!(client.get_user_anime_list(query).await, Err(MALError::Invalid { field: "limit", .. })));
+    assert!(Limit::from(-3).validate("get_anime_list", 100).is_err());
+    assert_eq!(Limit::from(500_u16).validate("get_seasonal_anime", 500).unwrap(), 500);
+    assert_eq!(Limit::DEFAULT.validate("test", 10).unwrap(), 10);
+    assert_eq!(Limit::from(None).validate("test", 1000).unwrap(), DEFAULT_LIMIT);
+}
+
+#[test]
+fn clear_status_fields() {
+    let update = StatusUpdate::new()
+        .score(8)
+        .add_tag("old")
+        .clear_score()
+        .clear_tags()
+        .clear_dates()
+        .build()
+        .unwrap();
+    assert_eq!(
+        update.get_params(),
+        [
+            ("score", String::from("0")),
+            ("tags", String::new()),
+            ("start_date", String::new()),
+            ("finish_date", String::new()),
+        ]
+    );
+}
+
+#[test]
+fn list_tags() {
+    let status: ListStatus = serde_json::from_str(r#"{"tags": ["space", "jazz"]}"#).unwrap();
+    let current = status.tags.unwrap();
+    assert!(current.contains("jazz"));
+    let update = StatusUpdate::new()
+        .tags(current.clone())
+        .add_tag("space")
+        .add_tag("noir, western")
+        .remove_tag("jazz");
+    assert_eq!(update.build_unchecked().get_params(), [("tags", String::from("space,noir,western"))]);
+
+    let other: Tags = ["jazz", "blues"].into_iter().collect();
+    assert_eq!(current.union(&other).to_vec(), ["space", "jazz", "blues"]);
+    assert_eq!(current.intersection(&other).to_vec(), ["jazz"]);
+    assert_eq!(current.difference(&other).to_vec(), ["space"]);
+}
+
+#[test]
+fn list_stats() {
+    use crate::stats::ListStats;
+
+    let list: AnimeList = serde_json::from_value(serde_json::json!({
+        "data": [
+            {"node": {"id": 1, "title": "A", "main_picture": {}, "num_episodes": 12, "average_episode_duration": 1440,
+                      "genres": [{"id": 1, "name": "Action"}, {"id": 2, "name": "Drama"}], "start_season": {"year": 2019, "season": "fall"}},
+             "list_status": {"status": "completed", "score": 8, "num_episodes_watched": 12, "num_times_rewatched": 1}},
+            {"node": {"id": 2, "title": "B", "main_picture": {}, "genres": [{"id": 1, "name": "Action"}], "start_date": "2021-04"},
+             "list_status": {"status": "watching", "score": 0, "num_episodes_watched": 3}},
+            {"node": {"id": 3, "title": "C", "main_picture": {}, "average_episode_duration": 600, "start_season": {"year": 2019, "season": "spring"}},
+             "list_status": {"status": "completed", "score": 10, "num_episodes_watched": 1}}
+        ],
+        "paging": {}
+    }))
+    .unwrap();
+    let stats = ListStats::from(&list);
+    assert_eq!(stats.entries, 3);
+    assert_eq!(stats.by_status[&Status::Completed], 2);
+    assert_eq!(stats.mean_score, Some(9.0));
+    assert_eq!(stats.score_distribution.len(), 2);
+    assert_eq!(stats.episodes_watched, 28);
+    assert_eq!(stats.time_watched, std::time::Duration::from_secs(24 * 1440 + 600));
+    assert_eq!(stats.top_genres(1), [("Action", 2)]);
+    assert_eq!(stats.by_year.into_iter().collect::<Vec<_>>(), [(2019, 2), (2021, 1)]);
+}
+
+#[test]
+fn backlog() {
+    use crate::stats::Backlog;
+
+    let list: AnimeList = serde_json::from_value(serde_json::json!({
+        "data": [
+            {"node": {"id": 1, "title": "A", "main_picture": {}, "num_episodes": 12, "average_episode_duration": 1440},
+             "list_status": {"status": "watching", "num_episodes_watched": 4}},
+            {"node": {"id": 2, "title": "B", "main_picture": {}, "num_episodes": 0, "average_episode_duration": 1440},
+             "list_status": {"status": "watching", "num_episodes_watched": 3}},
+            {"node": {"id": 3, "title": "C", "main_picture": {}, "num_episodes": 2},
+             "list_status": {"status": "plan_to_watch"}},
+            {"node": {"id": 4, "title": "D", "main_picture": {}, "num_episodes": 24, "average_episode_duration": 1440},
+             "list_status": {"status": "completed", "num_episodes_watched": 24}},
+            {"node": {"id": 5, "title": "E", "main_picture": {}, "num_episodes": 1, "average_episode_duration": 6000},
+             "list_status": {"status": "on_hold", "num_episodes_watched": 1}}
+        ],
+        "paging": {}
+    }))
+    .unwrap();
+    assert_eq!(list.data[0].remaining_episodes(), Some(8));
+    assert_eq!(list.data[0].estimated_time_to_finish(), Some(std::time::Duration::from_secs(8 * 1440)));
+    assert_eq!(list.data[1].remaining_episodes(), None);
+    assert_eq!(list.data[2].estimated_time_to_finish(), None);
+    assert_eq!(list.data[3].remaining_episodes(), Some(0));
+    let rewatching: ListStatus = serde_json::from_value(serde_json::json!({"status": "completed", "is_rewatching": true, "num_episodes_watched": 20})).unwrap();
+    assert_eq!(rewatching.remaining_episodes(24), 4);
+
+    let backlog = Backlog::from(&list);
+    assert_eq!((backlog.entries, backlog.episodes), (4, 10));
+    assert_eq!(backlog.time, std::time::Duration::from_secs(8 * 1440));
+    assert_eq!(backlog.unknown, [2, 3]);
+
+    let anime = serde_json::from_value::<AnimeDetails>(serde_json::json!({
+        "id": 6, "title": "F", "main_picture": {}, "num_episodes": 3, "average_episode_duration": 600
+    }))
+    .unwrap();
+    assert_eq!(anime.remaining_episodes(), Some(3));
+    assert_eq!(anime.estimated_time_to_finish(), Some(std::time::Duration::from_secs(1800)));
+}
+
+#[test]
+fn csv_and_json_export() {
+    use crate::model::fields::ListStatusFields;
+
+    let list: AnimeList = serde_json::from_value(serde_json::json!({
+        "data": [
+            {"node": {"id": 1, "title": "Steins;Gate, \"the\" movie", "main_picture": {}, "mean": 8.5,
+                      "genres": [{"id": 1, "name": "Sci-Fi"}, {"id": 2, "name": "Drama"}], "start_season": {"year": 2013, "season": "spring"}},
+             "list_status": {"status": "completed", "score": 9, "tags": ["time travel", "rewatch"], "comments": "line one\nline two"}},
+            {"node": {"id": 2, "title": "Empty", "main_picture": {}}}
+        ],
+        "paging": {}
+    }))
+    .unwrap();
+    let columns = (AnimeFields::Mean | AnimeFields::Genres | AnimeFields::StartSeason | AnimeFields::MainPicture)
+        .with_list_status(ListStatusFields::Status | ListStatusFields::Score | ListStatusFields::Tags | ListStatusFields::Comments);
+    let mut csv = Vec::new();
+    list.to_csv(&mut csv, columns).unwrap();
+    let text = String::from_utf8(csv).unwrap();
+    assert!(text.starts_with("id,title,mean,genres,start_season,my_status,my_score,my_tags,my_comments\n"));
+    assert!(text.contains("\"Steins;Gate, \"\"the\"\" movie\",8.5,\"Sci-Fi, Drama\",2013 spring,completed,9,\"time travel,rewatch\""));
+
+    let back = AnimeList::from_csv(text.as_bytes()).unwrap();
+    assert_eq!(back.data.len(), 2);
+    assert_eq!(back.data[0].node.title, list.data[0].node.title);
+    assert_eq!(back.data[0].details.start_season, list.data[0].details.start_season);
+    assert_eq!(back.data[0].list_status, list.data[0].list_status);
+    assert_eq!(back.data[1].list_status, None);
+    assert!(matches!(AnimeList::from_csv("title\nx\n".as_bytes()), Err(MALError::Export(_))));
+
+    let json = list.to_json_pretty().unwrap();
+    assert_eq!(AnimeList::from_json(&json).unwrap(), list);
 }
 
 #[tokio::test]
 async fn anime_ranking() {
-    let client = setup();
+    let (_server, client) = setup();
     let expected =
         serde_json::from_str::<AnimeList>(include_str!("test-data/anime_ranking.json")).unwrap();
-    let result = client.get_anime_ranking(RankingType::All, Some(4)).await
+    let result = client.get_anime_ranking(RankingType::All, Some(4), None).await
         .expect("Error performing request");
     let left = expected.data[0].node.id;
     let right = result.data[0].node.id;
@@ -42,17 +328,3026 @@ async fn anime_ranking() {
 
 #[tokio::test]
 async fn seasonal_anime() {
-    let client = setup();
+    let (_server, client) = setup();
     // let expected =
     //     serde_json::from_str::<AnimeList>(include_str!("test-data/seasonal_anime.json")).unwrap();
-    let result = client.get_seasonal_anime(Season::Summer, 2017, Some(4)).await;
+    let result = client.get_seasonal_anime((Season::Summer, 2017), Some(4), None, None).await;
     //.expect("Error performing request");
     // let left = expected.data[0].node.id;
     // let right = result.data[0].node.id;
     assert!(result.is_ok());
 }
 
-fn setup() -> MALClient {
-    let token = env::var("MAL_TOKEN").expect("Access token not in environment");
-    MALClient::with_access_token(&token)
+#[test]
+fn token_expiry() {
+    let mut tokens = Tokens {
+        access_token: String::from("access"),
+        refresh_token: String::from("refresh"),
+        expires_in: 3600,
+        today: 1,
+    };
+    assert!(tokens.is_expired());
+    assert_eq!(tokens.expires_at(), Some(3601));
+    tokens.today = u64::MAX;
+    assert!(!tokens.is_expired());
+    tokens.today = 1;
+    tokens.refresh_token.clear();
+    assert!(tokens.has_expired());
+    assert!(!tokens.is_expired(), "tokens without a refresh token can't be refreshed");
+    tokens.today = 0;
+    assert_eq!(tokens.expires_at(), None);
+}
+
+#[test]
+fn library_unwatched_episodes() {
+    struct Library;
+    impl LibraryProvider for Library {
+        fn episodes(&self, anime_id: AnimeId) -> Vec<LocalEpisode> {
+            match anime_id.0 {
+                21 => vec![
+                    LocalEpisode::new(2, PathBuf::from("op_002.mkv")),
+                    LocalEpisode::new(1, PathBuf::from("op_001.mkv")),
+                ],
+                _ => Vec::new(),
+            }
+        }
+    }
+    let list =
+        serde_json::from_str::<AnimeList>(include_str!("test-data/anime_list.json")).unwrap();
+    let unwatched = unwatched_episodes(&Library, &list);
+    assert_eq!(unwatched.len(), 1);
+    assert_eq!(unwatched[0].anime.id, 21);
+    let numbers: Vec<usize> = unwatched[0].episodes.iter().map(|e| e.number).collect();
+    assert_eq!(numbers, [1, 2]);
+}
+
+#[test]
+fn relation_query_media_types() {
+    let details =
+        serde_json::from_str::<AnimeDetails>(include_str!("test-data/anime_details.json")).unwrap();
+    assert_eq!(details.media_type, Some(MediaType::TV));
+    let query = RelationQuery::watch_order();
+    assert!(query.includes(details.media_type.as_ref()));
+    assert!(!query.includes(Some(&MediaType::Music)));
+    assert!(!query.includes(None));
+    assert!(RelationQuery::new().includes(None));
+}
+
+#[test]
+fn relation_types() {
+    let details =
+        serde_json::from_str::<AnimeDetails>(include_str!("test-data/anime_details.json")).unwrap();
+    let types: Vec<&RelationType> =
+        details.related_anime.iter().flatten().map(|r| &r.relation_type).collect();
+    assert_eq!(types, [&RelationType::Prequel, &RelationType::SideStory]);
+    let manga = serde_json::from_str::<RelatedManga>(
+        r#"{"node": {"id": 2, "title": "Manga 2", "main_picture": {}},
+            "relation_type": "spin_off", "relation_type_formatted": "Spin-off"}"#,
+    )
+    .unwrap();
+    assert_eq!(manga.node.id, MangaId(2));
+    assert_eq!(manga.relation_type, RelationType::SpinOff);
+    assert_eq!(RelationType::from("Spin-off"), RelationType::SpinOff);
+    assert_eq!(RelationType::from("other"), RelationType::Other(String::from("other")));
+    assert_eq!(serde_json::to_string(&RelationType::ParentStory).unwrap(), r#""parent_story""#);
+}
+
+#[test]
+fn list_inconsistencies() {
+    let list = serde_json::from_str::<AnimeList>(
+        r#"{
+            "data": [
+                {"node": {"id": 1, "title": "a", "main_picture": {}, "num_episodes": 12},
+                 "list_status": {"status": "watching", "num_episodes_watched": 13}},
+                {"node": {"id": 2, "title": "b", "main_picture": {}, "num_episodes": 0},
+                 "list_status": {"status": "completed", "num_episodes_watched": 0,
+                                 "start_date": "2020-05-01", "finish_date": "2020-04"}},
+                {"node": {"id": 1, "title": "a", "main_picture": {}}}
+            ],
+            "paging": {}
+        }"#,
+    )
+    .unwrap();
+    let kinds: Vec<InconsistencyKind> = list
+        .find_inconsistencies()
+        .into_iter()
+        .map(|i| i.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            InconsistencyKind::WatchedExceedsTotal {
+                watched: 13,
+                total: 12
+            },
+            InconsistencyKind::CompletedWithoutProgress,
+            InconsistencyKind::FinishedBeforeStarted {
+                start_date: String::from("2020-05-01"),
+                finish_date: String::from("2020-04")
+            },
+            InconsistencyKind::DuplicateEntry,
+        ]
+    );
+}
+
+#[test]
+fn list_percentile() {
+    let list = serde_json::from_str::<AnimeList>(
+        r#"{
+            "data": [
+                {"node": {"id": 1, "title": "a", "main_picture": {}, "rank": 10},
+                 "list_status": {"status": "completed", "score": 9}},
+                {"node": {"id": 2, "title": "b", "main_picture": {}, "rank": 500},
+                 "list_status": {"status": "completed", "score": 7}},
+                {"node": {"id": 3, "title": "c", "main_picture": {}, "rank": 900},
+                 "list_status": {"status": "completed", "score": 0}},
+                {"node": {"id": 4, "title": "d", "main_picture": {}, "rank": 1000},
+                 "list_status": {"status": "watching", "score": 1}}
+            ],
+            "paging": {}
+        }"#,
+    )
+    .unwrap();
+    let anime = serde_json::from_str::<AnimeDetails>(
+        r#"{"id": 5, "title": "e", "main_picture": {}, "mean": 8.2, "rank": 600}"#,
+    )
+    .unwrap();
+    let percentile = list.percentile_of(&anime).unwrap();
+    assert_eq!(percentile.mean, Some(50.0));
+    assert!((percentile.rank.unwrap() - 100.0 / 3.0).abs() < 0.001);
+}
+
+#[test]
+fn shared_plan_to_watch() {
+    let list = |json: &str| {
+        serde_json::from_str::<AnimeList>(&format!(r#"{{"data": [{json}], "paging": {{}}}}"#))
+            .unwrap()
+    };
+    let lists = [
+        list(
+            r#"{"node": {"id": 1, "title": "a", "main_picture": {}, "mean": 7.0}, "list_status": {"priority": 0}},
+               {"node": {"id": 2, "title": "b", "main_picture": {}, "mean": 8.0}, "list_status": {"priority": 0}},
+               {"node": {"id": 3, "title": "c", "main_picture": {}}, "list_status": {"priority": 2}}"#,
+        ),
+        list(
+            r#"{"node": {"id": 3, "title": "c", "main_picture": {}}, "list_status": {"priority": 1}},
+               {"node": {"id": 2, "title": "b", "main_picture": {}}, "list_status": {"priority": 0}},
+               {"node": {"id": 1, "title": "a", "main_picture": {}}, "list_status": {"priority": 0}}"#,
+        ),
+        list(
+            r#"{"node": {"id": 1, "title": "a", "main_picture": {}}},
+               {"node": {"id": 2, "title": "b", "main_picture": {}}},
+               {"node": {"id": 3, "title": "c", "main_picture": {}}}"#,
+        ),
+    ];
+    let shared: Vec<(usize, usize)> = AnimeList::shared_entries(&lists)
+        .iter()
+        .map(|e| (e.anime.id.0, e.interest))
+        .collect();
+    assert_eq!(shared, [(3, 6), (2, 3), (1, 3)]);
+    assert_eq!(AnimeList::shared_entries(&lists[..1]).len(), 3);
+    let disjoint = [
+        list(r#"{"node": {"id": 1, "title": "a", "main_picture": {}}}"#),
+        list(""),
+    ];
+    assert!(AnimeList::shared_entries(&disjoint).is_empty());
+}
+
+#[test]
+fn plan_to_watch_ranked() {
+    use crate::prioritize::{self, PriorityWeights};
+
+    let list = serde_json::from_str::<AnimeList>(
+        r#"{"data": [
+            {"node": {"id": 1, "title": "long", "main_picture": {}, "mean": 8.0, "num_list_users": 100000, "num_episodes": 500}},
+            {"node": {"id": 2, "title": "short", "main_picture": {}, "mean": 8.0, "num_list_users": 100000, "num_episodes": 1}},
+            {"node": {"id": 3, "title": "unaired", "main_picture": {}}, "list_status": {"priority": 2}}
+        ], "paging": {}}"#,
+    )
+    .unwrap();
+    let ids = |weights: &PriorityWeights| -> Vec<usize> {
+        prioritize::rank(list.data.clone(), weights).iter().map(|r| r.entry.node.id.0).collect()
+    };
+    assert_eq!(ids(&PriorityWeights::default()), [2, 1, 3]);
+    let by_priority = PriorityWeights { priority: 10.0, ..PriorityWeights::default() };
+    assert_eq!(ids(&by_priority), [3, 2, 1]);
+    let nothing = PriorityWeights { mean: 0.0, popularity: 0.0, length: 0.0, priority: 0.0 };
+    assert_eq!(ids(&nothing), [1, 2, 3]);
+    let weights = PriorityWeights { mean: 1.0, popularity: 0.0, length: 0.0, priority: 0.0 };
+    assert!((weights.score(&list.data[0]) - 0.8).abs() < 0.001);
+}
+
+#[test]
+fn preferred_title() {
+    let anime = serde_json::from_str::<AnimeDetails>(include_str!("test-data/anime_details.json")).unwrap();
+    assert_eq!(anime.title_english(), Some("Ace of Diamond: Second Season"));
+    assert_eq!(anime.preferred_title(TitleLanguage::Japanese), "ダイヤのA[エース]～Second Season～");
+    assert_eq!(anime.preferred_title(TitleLanguage::Romaji), "Diamond no Ace: Second Season");
+
+    let anime = serde_json::from_str::<AnimeDetails>(
+        r#"{"id": 1, "title": "Gekijouban", "main_picture": {}, "alternative_titles": {"synonyms": [], "en": "", "ja": "劇場版"}}"#,
+    )
+    .unwrap();
+    assert_eq!(anime.title_english(), None);
+    assert_eq!(anime.preferred_title(TitleLanguage::English), "Gekijouban");
+    let anime = serde_json::from_str::<AnimeDetails>(r#"{"id": 1, "title": "Gekijouban", "main_picture": {}}"#).unwrap();
+    assert_eq!(anime.preferred_title(TitleLanguage::Japanese), "Gekijouban");
+}
+
+#[test]
+fn share_card() {
+    let mut anime = serde_json::from_str::<AnimeDetails>(
+        r#"{"id": 80, "title": "Mobile Suit Gundam", "main_picture": {"medium": "m.jpg", "large": "l.jpg"},
+            "mean": 7.8, "start_season": {"year": 1979, "season": "spring"}}"#,
+    )
+    .unwrap();
+    anime.synopsis = Some("ガンダム ".repeat(60));
+    let card = anime.share_card();
+    assert_eq!(card.url, "https://myanimelist.net/anime/80");
+    assert_eq!(card.picture_url.as_deref(), Some("l.jpg"));
+    assert_eq!(card.season.as_deref(), Some("Spring 1979"));
+    let synopsis = card.synopsis.unwrap();
+    assert!(synopsis.chars().count() <= 200);
+    assert!(synopsis.ends_with("ガンダム…"));
+    anime.synopsis = Some(String::from("Short."));
+    assert_eq!(anime.share_card().synopsis.as_deref(), Some("Short."));
+}
+
+fn fresh(cache: &ResponseCache, url: &str) -> Option<String> {
+    match cache.lookup(url) {
+        Lookup::Fresh(body) => Some(body),
+        _ => None,
+    }
+}
+
+#[test]
+fn response_cache() {
+    let cache = ResponseCache::new(CacheConfig::new(std::time::Duration::from_secs(60)).capacity(2));
+    cache.insert("a", String::from("1"), Validators::default());
+    cache.insert("b", String::from("2"), Validators::default());
+    assert_eq!(fresh(&cache, "a").as_deref(), Some("1"));
+    cache.insert("c", String::from("3"), Validators::default());
+    assert!(fresh(&cache, "b").is_none());
+    assert_eq!(fresh(&cache, "a").as_deref(), Some("1"));
+
+    let dir = env::temp_dir().join("lib-mal-response-cache").join("created");
+    let _ = std::fs::remove_dir_all(&dir);
+    let codecs = [
+        CacheCodec::Json,
+        #[cfg(feature = "bincode")]
+        CacheCodec::Bincode,
+        #[cfg(feature = "postcard")]
+        CacheCodec::Postcard,
+    ];
+    for codec in codecs {
+        let config = CacheConfig::new(std::time::Duration::from_secs(60)).disk(dir.clone()).codec(codec);
+        ResponseCache::new(config.clone()).insert("https://example.com/", String::from("body"), Validators::default());
+        assert_eq!(fresh(&ResponseCache::new(config), "https://example.com/").as_deref(), Some("body"));
+    }
+
+    let expired = ResponseCache::new(CacheConfig::new(std::time::Duration::ZERO));
+    expired.insert("a", String::from("1"), Validators::default());
+    assert!(fresh(&expired, "a").is_none());
+    let etag = Validators {
+        etag: Some(String::from("\"v1\"")),
+        last_modified: None,
+    };
+    expired.insert("b", String::from("2"), etag);
+    assert!(matches!(expired.lookup("b"), Lookup::Stale(v) if v.etag.as_deref() == Some("\"v1\"")));
+    assert_eq!(expired.revalidate("b").as_deref(), Some("2"));
+    assert_eq!(
+        expired.stats(),
+        CacheStats { hits: 0, revalidated: 1, misses: 2 }
+    );
+    assert_eq!(cache.stats().hits, 2);
+
+    let dir = env::temp_dir().join("lib-mal-response-cache").join("invalidated");
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = ResponseCache::new(CacheConfig::new(std::time::Duration::from_secs(60)).capacity(1).disk(dir));
+    cache.insert("v2/anime/1?fields=id", String::from("1"), Validators::default());
+    cache.insert("v2/anime/10?fields=id", String::from("10"), Validators::default());
+    cache.invalidate("v2/anime/1?");
+    // the copy on disk is ignored too
+    assert!(fresh(&cache, "v2/anime/1?fields=id").is_none());
+    assert_eq!(fresh(&cache, "v2/anime/10?fields=id").as_deref(), Some("10"));
+    cache.insert("v2/anime/1?fields=id", String::from("new"), Validators::default());
+    assert_eq!(fresh(&cache, "v2/anime/1?fields=id").as_deref(), Some("new"));
+}
+
+#[tokio::test]
+async fn list_writes_invalidate_cached_details() {
+    struct Api(std::sync::Mutex<Vec<reqwest::Method>>);
+    impl crate::HttpTransport for Api {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            let body = match request.method {
+                reqwest::Method::GET => include_str!("test-data/anime_details.json"),
+                reqwest::Method::PUT => r#"{"status": "watching"}"#,
+                _ => "",
+            };
+            self.0.lock().unwrap().push(request.method);
+            Box::pin(async move { Ok(crate::HttpResponse::new(reqwest::StatusCode::OK, body)) })
+        }
+    }
+    let transport = std::sync::Arc::new(Api(std::sync::Mutex::default()));
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(transport.clone());
+    client.set_response_cache(CacheConfig::new(std::time::Duration::from_secs(60)));
+    let gets = || transport.0.lock().unwrap().iter().filter(|m| **m == reqwest::Method::GET).count();
+    client.get_anime_details(30230, None).await.unwrap();
+    client.get_anime_details(30230, None).await.unwrap();
+    assert_eq!(gets(), 1);
+    client.update_user_anime_status(30230, StatusUpdate::new().status(Status::Watching).build_unchecked()).await.unwrap();
+    client.get_anime_details(30230, None).await.unwrap();
+    assert_eq!(gets(), 2);
+    client.delete_anime_list_item(30230).await.unwrap();
+    client.get_anime_details(30230, None).await.unwrap();
+    assert_eq!(gets(), 3);
+}
+
+#[test]
+fn memory_token_store() {
+    let store = MemoryTokenStore::new();
+    assert!(store.load().unwrap().is_none());
+    let tokens = Tokens {
+        access_token: String::from("access"),
+        ..Tokens::default()
+    };
+    store.save(&tokens).unwrap();
+    assert_eq!(store.load().unwrap().unwrap().access_token, "access");
+    store.clear().unwrap();
+    assert!(store.load().unwrap().is_none());
+}
+
+#[cfg(feature = "oauth")]
+#[test]
+fn file_token_store_key() {
+    let dir = env::temp_dir().join("lib-mal-file-token-store-key");
+    std::fs::create_dir_all(&dir).unwrap();
+    let store = FileTokenStore::with_key(dir.clone(), [7; 32]);
+    let tokens = Tokens {
+        access_token: String::from("access"),
+        ..Tokens::default()
+    };
+    store.save(&tokens).unwrap();
+    assert_eq!(store.load().unwrap().unwrap().access_token, "access");
+    assert!(FileTokenStore::with_key(dir, [8; 32]).load().is_err());
+    store.clear().unwrap();
+    assert!(store.load().unwrap().is_none());
+}
+
+#[cfg(feature = "oauth")]
+#[test]
+fn file_token_store_migration() {
+    use aes_gcm::aead::Aead;
+    use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce};
+
+    let dir = env::temp_dir().join("lib-mal-file-token-store-migration");
+    std::fs::create_dir_all(&dir).unwrap();
+    let tokens = Tokens {
+        access_token: String::from("old"),
+        ..Tokens::default()
+    };
+    let cypher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(b"one two three four five six seve"));
+    let legacy = cypher
+        .encrypt(Nonce::from_slice(b"but the eart"), serde_json::to_vec(&tokens).unwrap().as_ref())
+        .unwrap();
+    std::fs::write(dir.join("tokens"), legacy).unwrap();
+
+    let store = FileTokenStore::with_key(dir.clone(), [7; 32]);
+    let legacy = store.cache().unwrap().unwrap();
+    assert_eq!(legacy.version(), 0);
+    assert!(legacy.is_supported() && legacy.needs_migration());
+    assert_eq!(store.load().unwrap().unwrap().access_token, "old");
+    let migrated = std::fs::read(dir.join("tokens")).unwrap();
+    assert_eq!(&migrated[..5], b"LMAL\x01");
+    assert_eq!(store.cache().unwrap().unwrap().version(), TokenCache::CURRENT_VERSION);
+    assert_eq!(store.load().unwrap().unwrap().access_token, "old");
+
+    let mut future = migrated;
+    future[4] = 2;
+    std::fs::write(dir.join("tokens"), future).unwrap();
+    assert!(!store.cache().unwrap().unwrap().is_supported());
+    assert!(store.load().is_err());
+    store.clear().unwrap();
+    assert_eq!(store.cache().unwrap(), None);
+
+    let cypher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&[7; 32]));
+    let mut partial = b"LMAL\x01".to_vec();
+    partial.extend_from_slice(b"nonce bytes!");
+    partial.extend(cypher.encrypt(Nonce::from_slice(b"nonce bytes!"), br#"{"access_token": "partial"}"#.as_ref()).unwrap());
+    std::fs::write(dir.join("tokens"), partial).unwrap();
+    assert_eq!(store.load().unwrap().unwrap().access_token, "partial");
+    store.clear().unwrap();
+
+    FileTokenStore::new(dir.clone()).save(&tokens).unwrap();
+    assert_eq!(store.load().unwrap().unwrap().access_token, "old");
+    let rekeyed = std::fs::read(dir.join("tokens")).unwrap();
+    assert_eq!(crate::file_store::decrypt_tokens(&rekeyed, &[7; 32]).unwrap().access_token, "old");
+    assert!(FileTokenStore::new(dir.clone()).load().is_err());
+    store.clear().unwrap();
+}
+
+#[cfg(feature = "oauth")]
+#[tokio::test]
+async fn malformed_inputs() {
+    use aes_gcm::aead::{Aead, AeadCore, OsRng};
+    use aes_gcm::{Aes256Gcm, Key, KeyInit};
+
+    let dir = env::temp_dir().join("lib-mal-malformed-token-cache");
+    std::fs::create_dir_all(&dir).unwrap();
+    let key = [7; 32];
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let mut cache = b"LMAL\x01".to_vec();
+    cache.extend_from_slice(&nonce);
+    cache.extend(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)).encrypt(&nonce, b"\xffnot json".as_ref()).unwrap());
+    std::fs::write(dir.join("tokens"), &cache).unwrap();
+    let store = FileTokenStore::with_key(dir.clone(), key);
+    assert!(matches!(store.load(), Err(MALError::TokenCache(_))));
+    assert!(matches!(crate::file_store::decrypt_tokens(&cache, &key), Err(MALError::TokenCache(_))));
+    assert!(matches!(crate::file_store::decrypt_tokens(b"LMAL\x01short", &key), Err(MALError::TokenCache(_))));
+    store.clear().unwrap();
+
+    let parts = AuthParts::new("not a url", "id", None, &[]);
+    assert!(parts.url.starts_with("not a url?response_type=code&client_id=id"));
+    assert!(parts.url.contains(&format!("state={}", parts.state)));
+
+    struct Garbage;
+    impl crate::HttpTransport for Garbage {
+        fn send(&self, _: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async { Ok(crate::HttpResponse::new(reqwest::StatusCode::OK, "<html>")) })
+        }
+    }
+    let mut client: MALClient = ClientBuilder::new().client_id(String::from("id")).build_no_refresh();
+    client.set_transport(std::sync::Arc::new(Garbage));
+    assert!(client.auth_with_code("code", "verifier").await.is_err());
+    assert!(crate::client::refresh_tokens(&Garbage, "https://myanimelist.net/", "id", None, "refresh").await.is_err());
+}
+
+#[cfg(feature = "oauth")]
+#[tokio::test]
+async fn corrupted_token_cache() {
+    let dir = env::temp_dir().join("lib-mal-corrupted-token-cache");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("tokens"), b"LMAL\x01trunc").unwrap();
+    let client: MALClient = ClientBuilder::new()
+        .client_id(String::from("id"))
+        .caching(true)
+        .cache_dir(dir.clone())
+        .build_with_refresh()
+        .await
+        .unwrap();
+    assert!(client.need_auth());
+    assert!(matches!(client.reauth_reason(), Some(ReauthReason::UnreadableCache(_))));
+    assert!(!dir.join("tokens").exists());
+
+    let client: MALClient = ClientBuilder::new().caching(true).cache_dir(dir).build_with_refresh().await.unwrap();
+    assert!(client.need_auth());
+    assert_eq!(client.reauth_reason(), Some(&ReauthReason::NeverAuthorized));
+}
+
+#[tokio::test]
+async fn reauth_reasons() {
+    struct Rejecting;
+    impl crate::HttpTransport for Rejecting {
+        fn send(&self, _: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            let body = r#"{"error": "invalid_request", "message": "The refresh token is invalid."}"#;
+            Box::pin(async move { Ok(crate::HttpResponse::new(reqwest::StatusCode::UNAUTHORIZED, body)) })
+        }
+    }
+    let client: MALClient = ClientBuilder::new().build_no_refresh();
+    assert_eq!(client.reauth_reason(), Some(&ReauthReason::NoCachedToken));
+    let mut client = MALClient::with_access_token("token");
+    assert_eq!(client.reauth_reason(), None);
+    client.set_transport(std::sync::Arc::new(Rejecting));
+    assert!(client.get_anime_details(1, None).await.is_err());
+    assert!(client.need_auth());
+    assert_eq!(client.reauth_reason(), Some(&ReauthReason::TokenExpired));
+    assert_eq!(ReauthReason::TokenExpired.to_string(), "the token expired");
+
+    let res = crate::client::refresh_tokens(&Rejecting, "https://myanimelist.net/", "id", None, "refresh").await;
+    assert!(res.is_err_and(|e| e.is_refresh_rejected()));
+}
+
+#[test]
+fn season_archive_roundtrip() {
+    let path = env::temp_dir().join("lib-mal-season-archive.json.gz");
+    let archive = SeasonArchive {
+        season: Season::Fall,
+        year: 2020,
+        fetched_at: 1,
+        data: serde_json::from_str::<AnimeList>(include_str!("test-data/anime_list.json"))
+            .unwrap()
+            .data,
+    };
+    archive.save(&path).unwrap();
+    let loaded = SeasonArchive::load(&path).unwrap();
+    assert_eq!(loaded.season, Season::Fall);
+    assert_eq!(loaded.data.len(), archive.data.len());
+    assert_eq!(loaded.data[0].node.id, archive.data[0].node.id);
+}
+
+#[test]
+fn title_normalization() {
+    let same = [
+        ("Shōnen Onmyouji", "Shounen Onmyoji"),
+        ("Yūki Yūna wa Yūsha de Aru", "Yuuki Yuuna wa Yuusha de Aru"),
+        ("Kimi wo Aishita Hitori no Boku e", "Kimi o Aishita Hitori no Boku e"),
+        ("Ｋ－ＯＮ！", "K-On!"),
+        ("Oniichan dakedo Ai Sae Areba", "Onichan Dakedo Ai sae Areba"),
+        ("Kyoukai no Kanata", "Kyôkai no Kanata"),
+    ];
+    for (a, b) in same {
+        assert_eq!(titles::normalize(a), titles::normalize(b), "{a} != {b}");
+    }
+    assert_eq!(titles::normalize_width("ＦＵＬＬ\u{3000}ＷＩＤＴＨ"), "FULL WIDTH");
+
+    let stripped = [
+        ("Shingeki no Kyojin: The Final Season", "Shingeki no Kyojin"),
+        ("Mob Psycho 100 II", "Mob Psycho 100"),
+        ("Boku no Hero Academia 2nd Season", "Boku no Hero Academia"),
+        ("Re:Zero kara Hajimeru Isekai Seikatsu 2nd Season Part 2", "Re:Zero kara Hajimeru Isekai Seikatsu"),
+        ("Mushoku Tensei: Isekai Ittara Honki Dasu Part 2", "Mushoku Tensei: Isekai Ittara Honki Dasu"),
+        ("86 Part 2", "86"),
+        ("Yuru Camp△ Season 2", "Yuru Camp△"),
+        ("Tokyo Revengers: Seiya Kessen-hen S2", "Tokyo Revengers: Seiya Kessen-hen"),
+        ("Steins;Gate 0", "Steins;Gate 0"),
+        ("Mob Psycho 100", "Mob Psycho 100"),
+        ("Hunter x Hunter (2011)", "Hunter x Hunter (2011)"),
+        ("Season 2", "Season 2"),
+    ];
+    for (title, base) in stripped {
+        assert_eq!(titles::strip_season_marker(title), base);
+    }
+}
+
+#[cfg(feature = "oauth")]
+#[test]
+fn auth_parts_url() {
+    let parts = AuthParts::new(
+        "https://myanimelist.net/v1/oauth2/authorize",
+        "id",
+        Some("http://localhost:2525/callback"),
+        &[("scope", "write:users")],
+    );
+    assert_eq!(parts.state.len(), 32);
+    assert_ne!(parts.state, AuthParts::new("https://example.com", "id", None, &[]).state);
+    assert!(parts.url.contains(&format!("code_challenge={}", parts.code_verifier)));
+    assert!(parts
+        .url
+        .contains("redirect_uri=http%3A%2F%2Flocalhost%3A2525%2Fcallback&scope=write%3Ausers"));
+}
+
+#[test]
+fn error_context() {
+    let status = reqwest::StatusCode::NOT_FOUND;
+    let error = MALError::from_status(status, String::new(), None).with_context(
+        "get_anime_details",
+        "https://api.myanimelist.net/v2/anime/0?fields=id",
+        status,
+    );
+    assert!(matches!(error, MALError::NotFound { .. }));
+    assert_eq!(
+        error.to_string(),
+        "lib_mal encountered an error: not found in get_anime_details (HTTP 404) at https://api.myanimelist.net/v2/anime/0"
+    );
+    let error = MALError::from_status(
+        reqwest::StatusCode::TOO_MANY_REQUESTS,
+        String::new(),
+        Some(std::time::Duration::from_secs(5)),
+    );
+    assert!(matches!(
+        error,
+        MALError::RateLimited { retry_after: Some(d), context: None } if d.as_secs() == 5
+    ));
+    let error = MALError::from_status(
+        reqwest::StatusCode::BAD_REQUEST,
+        String::from(r#"{"message": "invalid q", "error": "bad_request"}"#),
+        None,
+    );
+    assert_eq!(
+        error.to_string(),
+        "lib_mal encountered an error: unexpected response 400 Bad Request: bad_request (invalid q)"
+    );
+}
+
+#[tokio::test]
+async fn anime_details_batch() {
+    let client = MockMALClient::with_access_token("token");
+    let details = client.get_anime_details_batch(&[21, 1, 30230], None, 2).await;
+    assert_eq!(details.len(), 3);
+    assert_eq!(details[0].as_ref().unwrap().id, 21);
+    assert!(matches!(details[1], Err(MALError::Gone { .. })));
+    assert_eq!(details[2].as_ref().unwrap().id, 30230);
+}
+
+#[tokio::test]
+async fn enrich_list() {
+    let client = MockMALClient::with_access_token("token");
+    let list = serde_json::from_str::<AnimeList>(
+        r#"{
+            "data": [
+                {"node": {"id": 21, "title": "One Piece", "main_picture": {}}},
+                {"node": {"id": 30230, "title": "Diamond no Ace: Second Season", "main_picture": {}}},
+                {"node": {"id": 21, "title": "One Piece", "main_picture": {}}},
+                {"node": {"id": 1, "title": "Deleted", "main_picture": {}}}
+            ],
+            "paging": {}
+        }"#,
+    )
+    .unwrap();
+    let enriched = client.enrich_list(&list, None, 2).await.unwrap();
+    let ids: Vec<usize> = enriched.data.iter().map(|n| n.details.id.0).collect();
+    assert_eq!(ids, [21, 30230, 21]);
+    assert_eq!(enriched.skipped, [1]);
+    assert_eq!(enriched.data[1].details.num_episodes, Some(51));
+
+    let mut updates = Vec::new();
+    let enriched = client
+        .enrich_list_with_progress(&list, None, 2, |p| updates.push((p.done, p.total)))
+        .await
+        .unwrap();
+    assert_eq!(updates, [(1, 3), (2, 3), (3, 3)]);
+    let pairs = enriched.into_pairs();
+    assert_eq!(pairs[1].0.node.id, pairs[1].1.id);
+}
+
+#[tokio::test]
+async fn apply_updates() {
+    let client = MockMALClient::with_access_token("token");
+    client.on_update_user_anime_status(2).return_error(|| MALError::NotFound { context: None });
+    let updates = || {
+        (1..=5)
+            .map(|id| (AnimeId(id), StatusUpdate::new().status(Status::Watching).build_unchecked()))
+            .collect::<Vec<_>>()
+    };
+
+    let mut progress = Vec::new();
+    let report = client
+        .apply_updates(updates(), BatchOptions::new().concurrency(3), |p| progress.push((p.id.0, p.done, p.failed)))
+        .await;
+    assert_eq!(progress, [(1, 1, 0), (2, 2, 1), (3, 3, 1), (4, 4, 1), (5, 5, 1)]);
+    assert_eq!(report.applied().map(|(id, _)| id.0).collect::<Vec<_>>(), [1, 3, 4, 5]);
+    assert_eq!(report.applied().nth(3).unwrap().1.status.as_deref(), Some("watching"));
+    assert!(matches!(report.failed().next(), Some((AnimeId(2), MALError::NotFound { .. }))));
+    assert!(!report.is_complete());
+
+    let report = client
+        .apply_updates(updates(), BatchOptions::new().concurrency(1).abort_on_error(), |_| {})
+        .await;
+    assert!(matches!(report.results[0].1, BatchOutcome::Applied(_)));
+    assert_eq!(report.skipped().collect::<Vec<_>>(), [3, 4, 5]);
+    assert_eq!(client.calls_to("update_user_anime_status").len(), 7);
+}
+
+#[tokio::test]
+async fn dyn_client() {
+    use crate::DynMALClient;
+
+    let (_server, client) = setup();
+    let mock = MockMALClient::with_access_token("token");
+    mock.on_get_user_info("Nobody").return_error(|| MALError::NotFound { context: None });
+    let clients: [std::sync::Arc<dyn DynMALClient>; 2] = [std::sync::Arc::new(client), std::sync::Arc::new(mock)];
+    for client in &clients {
+        assert!(!client.need_auth());
+        let anime = client.get_anime_details(AnimeId(30230), None).await.unwrap();
+        assert_eq!(anime.show.title, "Diamond no Ace: Second Season");
+        let list = client.get_anime_list("one", 4.into(), None).await.unwrap();
+        assert!(!list.data.is_empty());
+    }
+    assert!(matches!(clients[1].get_user_info("Nobody", None).await, Err(MALError::NotFound { .. })));
+}
+
+#[test]
+fn parse_error_path() {
+    let parse = |body: &str| {
+        serde_json::from_str::<AnimeList>(body)
+            .map_err(|source| MALError::Parse { source, body: body.to_owned(), context: None })
+            .unwrap_err()
+    };
+    let body = r#"{"data": [{"node": {"id": 1, "title": "A", "main_picture": {}}}, {"node": {"id": 2, "title": "B", "main_picture": {}, "num_episodes": "two"}}], "paging": {}}"#;
+    let err = parse(body);
+    assert_eq!(err.json_path().as_deref(), Some("data[1].node.num_episodes"));
+    assert!(err.to_string().contains("unable to parse response at `data[1].node.num_episodes`: invalid type: string \"two\""));
+    assert!(err.snippet().unwrap().contains(r#""num_episodes": "two""#));
+    // the basic fields are flattened into `AnimeDetails`, so only the object is known
+    let err = parse(&body.replace(r#""num_episodes": "two""#, r#""num_episodes": 2"#).replace(r#""id": 2"#, r#""id": "2""#));
+    assert_eq!(err.json_path().as_deref(), Some("data[1].node"));
+    assert_eq!(parse(r#"{"data": [], "paging": [1]}"#).json_path().as_deref(), Some("paging"));
+
+    let pretty = "{\n  \"data\": [\n    {\"node\": {\"id\": 1, \"main_picture\": {}}}\n  ],\n  \"paging\": {}\n}";
+    let err = parse(pretty);
+    assert_eq!(err.json_path().as_deref(), Some("data[0].node"));
+    assert_eq!(err.snippet().unwrap(), r#"{"node": {"id": 1, "main_picture": {}}}"#);
+
+    let err = parse(r#"{"data": [}"#);
+    assert_eq!(err.json_path(), None);
+    assert!(MALError::NotConfirmed.snippet().is_none());
+
+    // escaped keys are decoded
+    let err = parse(r#"{"data": [{"node": {"id": 1, "title": "A", "main_picture": {}}, "ranking": {"a\"b\u00e9": "x"}}], "paging": {}}"#);
+    assert_eq!(err.json_path().as_deref(), Some("data[0].ranking.a\"bé"));
+    let path_at = |body: &str| crate::json_path::path_at(body, body.len());
+    assert_eq!(path_at(r#"{"a\\": {"b\"}": ["#), "a\\.b\"}[0]");
+    assert_eq!(path_at(r#"{"bad \q": 1, "x": {"y": "#), "x.y");
+    assert_eq!(path_at(r#"{"bad \q": "#), "bad \\q");
+    // malformed bodies give the containers that are still open
+    assert_eq!(path_at(r#"}]{"a": [1, 2, "#), "a[2]");
+    assert_eq!(path_at(r#"{"a": {"cut"#), "a");
+    assert_eq!(path_at(r#"{"a": "unterminated"#), "a");
+    assert_eq!(path_at(""), "");
+    let body = "{\"é\": [1,\n 2, x]}";
+    let offset = crate::json_path::offset_of(body, 2, 99);
+    assert_eq!(offset, body.len());
+    assert_eq!(crate::json_path::offset_of(body, 1, 3), 2);
+}
+
+#[tokio::test]
+async fn auto_paginate() {
+    let server = MockServer::start();
+    let client: MALClient = server.builder().auto_paginate(7).build_no_refresh();
+    // the fixture always links to another page of 3 entries
+    let list = client.get_anime_list("one", 4, None).await.unwrap();
+    assert_eq!(list.data.len(), 7);
+    assert!(list.next_page().is_none());
+    let requests = server.requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[1].ends_with("limit=4"), "{requests:?}");
+    assert!(requests[2].ends_with("limit=1"), "{requests:?}");
+    let ranking = client.get_anime_ranking(RankingType::All, 4, None).await.unwrap();
+    assert_eq!(ranking.data.len(), 7);
+
+    let client: MALClient = server.builder().auto_paginate(6).build_no_refresh();
+    let list = client.get_user_anime_list(None).await.unwrap();
+    assert_eq!(list.data.len(), 6);
+    assert!(list.next_page().is_some());
+    assert_eq!(server.client().get_anime_list("one", 4, None).await.unwrap().data.len(), 3);
+}
+
+#[test]
+fn builder_auth_state() {
+    let client: MALClient = ClientBuilder::new().build_no_refresh();
+    assert_eq!(client.auth_state(), AuthState::NoToken);
+    assert!(client.need_auth());
+    let client: MALClient = ClientBuilder::new()
+        .access_token(String::from("token"))
+        .build_no_refresh();
+    assert_eq!(client.auth_state(), AuthState::TokenCached);
+    assert!(!client.need_auth());
+}
+
+#[cfg(feature = "callback-server")]
+#[tokio::test]
+async fn auth_timeout() {
+    let mut client = MALClient::with_access_token("token");
+    let err = client
+        .auth_with_timeout("127.0.0.1:0", "verifier", "state", Some(std::time::Duration::from_millis(10)))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MALError::AuthTimeout));
+
+    // the listener doesn't block the executor, so a task on the same thread can cancel it
+    let cancel = crate::cancel::CancellationToken::new();
+    let on_close = cancel.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        on_close.cancel();
+    });
+    let err = client
+        .auth_with_cancellation("127.0.0.1:0", "verifier", "state", cancel)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MALError::Cancelled));
+}
+
+#[tokio::test]
+async fn cancellation() {
+    use crate::cancel::{with_cancellation, CancellationToken};
+    let token = CancellationToken::new();
+    let done = with_cancellation(&token, async { Ok(1) }).await;
+    assert_eq!(done.unwrap(), 1);
+    token.cancel();
+    let pending = with_cancellation(&token, futures_util::future::pending::<Result<(), MALError>>()).await;
+    assert!(matches!(pending, Err(MALError::Cancelled)));
+
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(std::sync::Arc::new(FakeTransport { requests: std::sync::Mutex::default() }));
+    let mut watcher = crate::watcher::ListWatcher::spawn(std::sync::Arc::new(client), std::time::Duration::from_secs(600));
+    assert!(matches!(watcher.next().await, Some(crate::watcher::ListEvent::PollFailed(_))));
+    watcher.cancellation_token().cancel();
+    assert!(watcher.next().await.is_none());
+}
+
+#[cfg(feature = "callback-server")]
+#[tokio::test]
+async fn callback_page_on_denied_auth() {
+    use std::io::{Read, Write};
+
+    let page = crate::CallbackPage::from_fn(|res| format!("denied: {}", res.is_err()));
+    let mut client: MALClient = ClientBuilder::new().callback_page(page).build_no_refresh();
+    let browser = std::thread::spawn(|| loop {
+        if let Ok(mut stream) = std::net::TcpStream::connect("127.0.0.1:25252") {
+            stream
+                .write_all(b"GET /?error=access_denied&state=abc HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                .unwrap();
+            let mut body = String::new();
+            stream.read_to_string(&mut body).unwrap();
+            return body;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    });
+    let err = client
+        .auth_with_timeout("127.0.0.1:25252", "verifier", "abc", Some(std::time::Duration::from_secs(5)))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MALError::Auth { error, .. } if error == "access_denied"));
+    assert!(browser.join().unwrap().ends_with("denied: true"));
+}
+
+#[tokio::test]
+async fn rate_limiter() {
+    let limiter = RateLimiter::new(100);
+    let start = std::time::Instant::now();
+    for _ in 0..3 {
+        limiter.acquire().await;
+    }
+    assert!(start.elapsed() >= std::time::Duration::from_millis(20));
+    for attempt in 0..10 {
+        let delay = rate_limit::backoff(attempt);
+        assert!(delay >= std::time::Duration::from_millis(250));
+        assert!(delay <= std::time::Duration::from_secs(30));
+    }
+}
+
+///Starts a local server answering with the fixtures, the client works as long as it's kept
+fn setup() -> (MockServer, MALClient) {
+    let server = MockServer::start();
+    let client = server.client();
+    (server, client)
+}
+
+#[test]
+fn user_list_query() {
+    let params = UserListQuery::new()
+        .status(Status::PlanToWatch)
+        .sort(ListSort::AnimeTitle)
+        .offset(20)
+        .fields(AnimeFields::Mean)
+        .nsfw(true)
+        .get_params();
+    assert_eq!(
+        params,
+        [
+            ("fields", String::from("list_status,mean")),
+            ("limit", String::from("100")),
+            ("status", String::from("plan_to_watch")),
+            ("sort", String::from("anime_title")),
+            ("offset", String::from("20")),
+            ("nsfw", String::from("true")),
+        ]
+    );
+    let params = UserListQuery::new().sort(ListSort::AnimeId).get_params();
+    assert!(params.contains(&("sort", String::from("anime_id"))));
+}
+
+#[test]
+fn page_cursor() {
+    let list = serde_json::from_str::<AnimeList>(
+        r#"{"data": [], "paging": {"next": "https://api.myanimelist.net/v2/users/@me/animelist?offset=200&limit=100"}}"#,
+    )
+    .unwrap();
+    let cursor = list.next_page().unwrap();
+    assert_eq!(cursor.offset(), 200);
+    let saved = cursor.to_string();
+    assert_eq!(saved.parse::<PageCursor>(), Ok(cursor));
+    assert!("offset=200".parse::<PageCursor>().is_err());
+}
+
+#[tokio::test]
+async fn cursors_follow_the_api_base_url() {
+    struct Mirror;
+    impl crate::HttpTransport for Mirror {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async move {
+                assert!(request.url.starts_with("http://localhost:8080/mal/v2/"), "{}", request.url);
+                let body = if request.url.contains("offset=1") {
+                    r#"{"data": [{"node": {"id": 2, "title": "B", "main_picture": {}}}], "paging": {}}"#
+                } else {
+                    r#"{"data": [{"node": {"id": 1, "title": "A", "main_picture": {}}}], "paging": {"next": "http://localhost:8080/mal/v2/users/@me/animelist?offset=1"}}"#
+                };
+                let mut res = crate::HttpResponse::new(reqwest::StatusCode::OK, body);
+                if !request.url.contains("offset") {
+                    res.headers.insert(reqwest::header::CONTENT_TYPE, "text/html".parse().unwrap());
+                }
+                Ok(res)
+            })
+        }
+    }
+    let client: MALClient = ClientBuilder::new()
+        .api_base_url("http://localhost:8080/mal/")
+        .access_token("token".to_owned())
+        .transport(Mirror)
+        .build_no_refresh();
+    // a wrong content type from the mirror is still taken for an error page
+    let raw = client.get_raw("users/@me/animelist").await;
+    assert!(matches!(raw, Err(MALError::ServerError { .. })), "{raw:?}");
+
+    let saved = "http://localhost:8080/mal/v2/users/@me/animelist?offset=1";
+    let mut cursor = Some(saved.parse::<PageCursor>().unwrap());
+    let mut data = Vec::new();
+    client.fetch_remaining(&mut cursor, &mut data).await.unwrap();
+    assert_eq!(data.len(), 1);
+    assert!(cursor.is_none());
+
+    let mut foreign = Some("https://example.com/v2/users/@me/animelist?offset=1".parse::<PageCursor>().unwrap());
+    let err = client.fetch_remaining(&mut foreign, &mut data).await.unwrap_err();
+    assert!(matches!(err, MALError::Invalid { field: "cursor", .. }));
+}
+
+#[cfg(feature = "jikan")]
+#[tokio::test]
+async fn scraping_disabled() {
+    let client: MALClient = ClientBuilder::new().allow_scraping(false).build_no_refresh();
+    let res = client.jikan().episodes(21, None, true).await;
+    assert!(matches!(res, Err(MALError::FeatureDisabled { feature: "scraping" })));
+}
+
+#[tokio::test]
+async fn anime_handle() {
+    let client = MockMALClient::with_access_token("token");
+    let anime = client.anime(30230);
+    let first = anime.details().await.unwrap();
+    assert!(std::ptr::eq(first, anime.details().await.unwrap()));
+    assert_eq!(anime.details().await.unwrap().num_episodes, Some(51));
+    assert!(client.anime(1).details().await.is_err());
+}
+
+#[tokio::test]
+async fn public_client_needs_client_id() {
+    let client: MALClient = ClientBuilder::new().public_client(true).build_no_refresh();
+    let res = client.get_anime_details(21, None).await;
+    assert!(matches!(res, Err(MALError::Auth { error, .. }) if error == "missing_client_id"));
+}
+
+#[test]
+fn builder_from_env() {
+    env::remove_var("MAL_CLIENT_ID");
+    env::remove_var("MAL_ACCESS_TOKEN");
+    env::remove_var("MAL_CACHE_DIR");
+    let res = ClientBuilder::from_env();
+    assert!(matches!(res, Err(MALError::MissingEnv { var: "MAL_CLIENT_ID" })));
+    env::set_var("MAL_ACCESS_TOKEN", "env-token");
+    let client: MALClient = ClientBuilder::from_env().unwrap().build_no_refresh();
+    assert_eq!(client.get_access_token(), "env-token");
+    env::remove_var("MAL_ACCESS_TOKEN");
+}
+
+#[test]
+fn season_year() {
+    let now = SeasonYear::now();
+    assert!(now.year >= 2024);
+    assert_eq!(now.next().prev(), now);
+    assert_eq!(SeasonYear::new(Season::Winter, 2024).prev(), SeasonYear::new(Season::Fall, 2023));
+    assert_eq!(Season::from_month(4), Season::Spring);
+    let back: Vec<SeasonYear> = SeasonYear::new(Season::Spring, 2024).iter_back(3).collect();
+    assert_eq!(
+        back,
+        [
+            SeasonYear::new(Season::Spring, 2024),
+            SeasonYear::new(Season::Winter, 2024),
+            SeasonYear::new(Season::Fall, 2023),
+        ]
+    );
+    assert_eq!(SeasonYear::FIRST.next().iter_back(10).count(), 2);
+    let archive: Vec<SeasonYear> = SeasonYear::archive().collect();
+    assert_eq!(archive.first(), Some(&now));
+    assert_eq!(archive.last(), Some(&SeasonYear::FIRST));
+    assert_eq!(archive.len(), (now.year - 1917) * 4 + now.season as usize + 1);
+}
+
+#[test]
+fn user_info() {
+    let user = serde_json::from_str::<User>(
+        r#"{
+            "id": 1, "name": "someone", "location": "", "joined_at": "2016-01-02T06:03:11+00:00",
+            "time_zone": "Europe/Warsaw", "is_supporter": false,
+            "anime_statistics": {"num_items_watching": 3, "num_items_completed": 120, "num_days": 50.5, "mean_score": 7.4}
+        }"#,
+    )
+    .unwrap();
+    let stats = user.anime_statistics.unwrap();
+    assert_eq!(stats.num_completed, 120);
+    assert_eq!(stats.num_dropped, 0);
+    assert!((stats.mean_score - 7.4).abs() < f32::EPSILON);
+    assert_eq!(UserFields::ALL.to_string(), "anime_statistics,time_zone,is_supporter");
+}
+
+#[cfg(feature = "jikan")]
+#[test]
+fn source_material() {
+    use crate::model::JikanRelations;
+
+    let relations = serde_json::from_str::<JikanRelations>(
+        r#"{"data": [
+            {"relation": "Adaptation", "entry": [
+                {"mal_id": 42451, "type": "manga", "name": "Dorohedoro", "url": "https://myanimelist.net/manga/42451/Dorohedoro"}
+            ]},
+            {"relation": "Sequel", "entry": [
+                {"mal_id": 41510, "type": "anime", "name": "Dorohedoro: Ma no Omake", "url": "https://myanimelist.net/anime/41510"}
+            ]}
+        ]}"#,
+    )
+    .unwrap();
+    let material = relations.into_source_material();
+    assert_eq!(material.len(), 1);
+    assert_eq!(material[0].mal_id, 42451);
+    assert_eq!(material[0].title, "Dorohedoro");
+}
+
+#[test]
+fn source_kind() {
+    let source: Source = serde_json::from_str(r#""4_koma_manga""#).unwrap();
+    assert_eq!(source, Source::YonKomaManga);
+    assert!(source.is_readable());
+    assert_eq!(serde_json::from_str::<Source>(r#""pachinko""#).unwrap(), Source::Other);
+}
+
+#[test]
+fn forum_models() {
+    let topics = serde_json::from_str::<ForumTopics>(
+        r#"{"data": [{
+            "id": 481, "title": "Your favorite opening", "created_at": "2008-02-01T11:55:34+00:00",
+            "created_by": {"id": 1, "name": "someone"}, "number_of_posts": 12,
+            "last_post_created_at": "2020-01-01T00:00:00+00:00",
+            "last_post_created_by": {"id": 2, "name": "someone else"}, "is_locked": false
+        }], "paging": {}}"#,
+    )
+    .unwrap();
+    assert_eq!(topics.data[0].number_of_posts, 12);
+    assert!(topics.data[0].created_by.forum_avatar.is_none());
+
+    let details = serde_json::from_str::<TopicDetails>(
+        r#"{"data": {"title": "Poll", "posts": [{
+            "id": 5, "number": 1, "created_at": "2008-02-01T11:55:34+00:00",
+            "created_by": {"id": 1, "name": "someone", "forum_avator": "https://cdn.myanimelist.net/a.png"},
+            "body": "[b]vote[/b]", "signature": ""
+        }], "poll": {"id": 3, "question": "Best?", "close": true, "options": [
+            {"id": 1, "text": "A", "votes": 4}, {"id": 2, "text": "B", "votes": 6}
+        ]}}, "paging": {}}"#,
+    )
+    .unwrap();
+    let poll = details.data.poll.unwrap();
+    assert!(poll.closed);
+    assert_eq!(poll.total_votes(), 10);
+    assert!(details.data.posts[0].created_by.forum_avatar.is_some());
+}
+
+#[test]
+fn forum_topic_query() {
+    let params = ForumTopicQuery::new()
+        .board_id(1)
+        .query("ep 1, discussion & more")
+        .sort(ForumSort::Recent)
+        .offset(20)
+        .get_params();
+    let mut url = reqwest::Url::parse("https://api.myanimelist.net/v2/forum/topics").unwrap();
+    url.query_pairs_mut().extend_pairs(params);
+    assert_eq!(
+        url.query(),
+        Some("limit=100&board_id=1&q=ep+1%2C+discussion+%26+more&sort=recent&offset=20")
+    );
+
+    let topics = serde_json::from_str::<ForumTopics>(
+        r#"{"data": [], "paging": {"next": "https://api.myanimelist.net/v2/forum/topics?offset=40&board_id=1"}}"#,
+    )
+    .unwrap();
+    assert_eq!(topics.paging.next.unwrap().offset(), 40);
+    assert!(topics.paging.previous.is_none());
+}
+
+#[test]
+fn stale_entries() {
+    let list = serde_json::from_str::<AnimeList>(
+        r#"{"data": [
+            {"node": {"id": 1, "title": "Fresh", "main_picture": {}},
+             "list_status": {"status": "watching", "updated_at": "2024-06-25T00:00:00+00:00"}},
+            {"node": {"id": 2, "title": "Forgotten", "main_picture": {}},
+             "list_status": {"status": "watching", "updated_at": "2024-05-01T02:00:00+02:00"}},
+            {"node": {"id": 3, "title": "Abandoned", "main_picture": {}},
+             "list_status": {"status": "watching", "updated_at": "2023-01-01T00:00:00Z"}},
+            {"node": {"id": 4, "title": "Done", "main_picture": {}},
+             "list_status": {"status": "completed", "updated_at": "2020-01-01T00:00:00+00:00"}}
+        ], "paging": {}}"#,
+    )
+    .unwrap();
+    let now = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_719_792_000); // 2024-07-01
+    let stale = list.stale_entries_at(std::time::Duration::from_secs(40 * 86400), now);
+    let found: Vec<(usize, Status)> = stale.iter().map(|e| (e.anime.id.0, e.suggestion)).collect();
+    assert_eq!(found, [(3, Status::Dropped), (2, Status::OnHold)]);
+    assert_eq!(stale[1].idle, std::time::Duration::from_secs(61 * 86400));
+}
+
+#[test]
+fn next_broadcast() {
+    let anime = |status: &str, day: &str| {
+        serde_json::from_value::<AnimeDetails>(serde_json::json!({
+            "id": 1, "title": "Airing", "main_picture": {}, "status": status,
+            "broadcast": {"day_of_the_week": day, "start_time": "01:00"}
+        }))
+        .unwrap()
+    };
+    // Saturday 01:00 JST is Friday 16:00 UTC
+    let monday = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_719_792_000);
+    let friday = monday + std::time::Duration::from_secs(4 * 86400 + 16 * 3600);
+    let airing = anime("currently_airing", "saturday");
+    assert_eq!(airing.next_broadcast(monday), Some(friday));
+    assert_eq!(
+        airing.next_broadcast(friday),
+        Some(friday + std::time::Duration::from_secs(7 * 86400))
+    );
+    assert_eq!(anime("currently_airing", "other").next_broadcast(monday), None);
+    assert_eq!(anime("finished_airing", "saturday").next_broadcast(monday), None);
+}
+
+#[test]
+fn episodes_pagination() {
+    let page = |has_next: bool| {
+        serde_json::from_value::<EpisodesList>(serde_json::json!({
+            "data": [], "pagination": {"last_visible_page": 12, "has_next_page": has_next}
+        }))
+        .unwrap()
+    };
+    assert!(page(true).has_next_page());
+    assert!(!page(false).has_next_page());
+}
+
+#[test]
+fn server_error_excerpt() {
+    let page = format!(
+        "<html><head><title>504 Gateway\n  Time-out</title></head><body>{}</body></html>",
+        "<p>markup</p>".repeat(10_000)
+    );
+    assert_eq!(crate::client::excerpt(&page), "504 Gateway Time-out");
+    let untitled = crate::client::excerpt(&format!("<body><h1>Maintenance</h1>\n{}</body>", "x".repeat(500)));
+    assert!(untitled.starts_with("Maintenance x"));
+    assert_eq!(untitled.chars().count(), 200);
+
+    let err = MALError::ServerError {
+        status: reqwest::StatusCode::GATEWAY_TIMEOUT,
+        excerpt: String::from("504 Gateway Time-out"),
+        context: None,
+    }
+    .with_context("get_anime_details", "https://api.myanimelist.net/v2/anime/21?fields=id", None);
+    assert_eq!(err.context().unwrap().url, "https://api.myanimelist.net/v2/anime/21");
+    assert!(err.to_string().contains("server error 504 Gateway Timeout: 504 Gateway Time-out"));
+}
+
+#[tokio::test]
+async fn list_session() {
+    let client = MockMALClient::with_access_token("token");
+    let session = client.list_session();
+    let update = |score: u8| StatusUpdate::new().score(score).build().unwrap();
+    let (a, b, c) = tokio::join!(
+        session.update(21, update(7)),
+        session.update(21, update(8)),
+        session.update(30230, update(9))
+    );
+    assert_eq!(a.unwrap().score, Score::new(7));
+    assert_eq!(b.unwrap().score, Score::new(8));
+    assert_eq!(c.unwrap().score, Score::new(9));
+    assert_eq!(session.pending(), 0);
+}
+
+#[cfg(feature = "scraping")]
+#[test]
+fn episode_scores() {
+    use crate::scrape::{parse_episode_scores, EpisodeScore};
+
+    let scores = parse_episode_scores(include_str!("test-data/episode_scores.html"));
+    assert_eq!(
+        scores,
+        [
+            EpisodeScore { mal_id: 1, score: Some(4.5152), votes: Some(12345) },
+            EpisodeScore { mal_id: 2, score: Some(4.39), votes: None },
+            EpisodeScore { mal_id: 3, score: None, votes: None },
+        ]
+    );
+    assert!(parse_episode_scores("<html><body>Maintenance</body></html>").is_empty());
+}
+
+#[cfg(feature = "scraping")]
+#[tokio::test]
+async fn watch_history() {
+    use crate::scrape::{parse_watch_history, HistoryEvent};
+
+    let history = parse_watch_history(include_str!("test-data/watch_history.html"));
+    let episodes: Vec<usize> = history.iter().map(|e| e.episode).collect();
+    assert_eq!(episodes, [1, 2, 3]);
+    assert_eq!(history[0], HistoryEvent { episode: 1, watched_at: String::from("2024-01-23T21:04:00") });
+    // 2024-01-23 12:04 UTC
+    let at = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_706_011_440);
+    assert_eq!(history[0].watched_at_in(UtcOffset::JST), Some(at));
+    assert!(parse_watch_history("<html><body>Please log in</body></html>").is_empty());
+
+    let res = MALClient::with_access_token("token").get_watch_history(5114).await;
+    assert!(matches!(res, Err(MALError::Auth { error, .. }) if error == "missing_site_session"));
+}
+
+#[test]
+fn cast_models() {
+    let characters = serde_json::from_value::<Vec<Character>>(serde_json::json!([{
+        "character": {"mal_id": 40, "url": "https://myanimelist.net/character/40", "name": "Monkey D., Luffy",
+            "images": {"jpg": {"image_url": "https://cdn.myanimelist.net/luffy.jpg"}}},
+        "role": "Main", "favorites": 120000,
+        "voice_actors": [{"person": {"mal_id": 5, "url": "https://myanimelist.net/people/5", "name": "Tanaka, Mayumi"},
+            "language": "Japanese"}]
+    }]))
+    .unwrap();
+    assert_eq!(characters[0].voice_actors[0].person.name, "Tanaka, Mayumi");
+    assert!(characters[0].character.images.contains_key("jpg"));
+
+    let staff = serde_json::from_value::<Vec<StaffMember>>(serde_json::json!([{
+        "person": {"mal_id": 6, "url": "https://myanimelist.net/people/6", "name": "Uda, Kounosuke"},
+        "positions": ["Director", "Storyboard"]
+    }]))
+    .unwrap();
+    assert_eq!(staff[0].positions, ["Director", "Storyboard"]);
+}
+
+#[tokio::test]
+async fn journal() {
+    let dir = env::temp_dir().join("lib-mal-journal");
+    let _ = std::fs::remove_dir_all(&dir);
+    // without a client ID public clients fail before sending anything, which is still journaled
+    let client: MALClient = ClientBuilder::new()
+        .cache_dir(dir.clone())
+        .journal(true)
+        .public_client(true)
+        .build_no_refresh();
+    assert!(client.read_journal().unwrap().is_empty());
+    let update = StatusUpdate::new().num_watched_episodes(3).build_unchecked();
+    assert!(client.update_user_anime_status(80, update).await.is_err());
+    assert!(client.get_anime_details(80, None).await.is_err());
+    assert!(client.delete_anime_list_item(80).await.is_err());
+
+    let entries = client.read_journal().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].method, "PUT");
+    assert_eq!(entries[0].endpoint, "update_user_anime_status");
+    assert_eq!(entries[0].params, [("num_watched_episodes".to_owned(), "3".to_owned())]);
+    assert!(entries[0].response.is_none() && entries[0].error.is_some());
+    assert_eq!(entries[1].method, "DELETE");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn watched_episode_helpers() {
+    let params = |update: StatusParams| update.get_params();
+    let entry = |json| serde_json::from_value::<ListStatus>(json).unwrap();
+
+    let planned = entry(serde_json::json!({"status": "plan_to_watch", "num_episodes_watched": 0}));
+    assert_eq!(
+        params(StatusUpdate::watched_episodes(Some(&planned), Some(12), 1, "2021-04-05")),
+        [
+            ("status", "watching".to_owned()),
+            ("num_watched_episodes", "1".to_owned()),
+            ("start_date", "2021-04-05".to_owned()),
+        ]
+    );
+    let watching = entry(serde_json::json!({"status": "watching", "num_episodes_watched": 11}));
+    assert_eq!(
+        params(StatusUpdate::watched_episodes(Some(&watching), Some(12), 3, "2021-04-05")),
+        [
+            ("status", "completed".to_owned()),
+            ("num_watched_episodes", "12".to_owned()),
+            ("finish_date", "2021-04-05".to_owned()),
+        ]
+    );
+    // unknown episode counts never complete the entry
+    assert_eq!(
+        params(StatusUpdate::watched_episodes(Some(&watching), Some(0), 3, "2021-04-05")),
+        [("num_watched_episodes", "14".to_owned())]
+    );
+    let rewatching = entry(serde_json::json!({"status": "completed", "is_rewatching": true,
+        "num_times_rewatched": 1, "finish_date": "2020-01-01"}));
+    assert_eq!(
+        params(StatusUpdate::completed(Some(&rewatching), Some(12), Some(9), "2021-04-05").unwrap()),
+        [
+            ("status", "completed".to_owned()),
+            ("is_rewatching", "false".to_owned()),
+            ("score", "9".to_owned()),
+            ("num_watched_episodes", "12".to_owned()),
+            ("num_times_rewatched", "2".to_owned()),
+        ]
+    );
+
+    let client = MockMALClient::with_access_token("");
+    let status = client.mark_completed(80, 7).await.unwrap();
+    assert_eq!(status.status.as_deref(), Some("completed"));
+    assert_eq!(status.score, Score::new(7));
+}
+
+#[tokio::test]
+async fn user_info_for_username() {
+    let client = MockMALClient::with_access_token("");
+    let user = client.get_user_info("Xinil", UserFields::TimeZone).await.unwrap();
+    assert_eq!(user.name, "Xinil");
+    assert_eq!(client.get_my_user_info(None).await.unwrap().name, "Mocked user");
+}
+
+#[test]
+fn list_export() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8" ?>
+<!-- Exported from MyAnimeList -->
+<myanimelist>
+  <myinfo>
+    <user_id>1</user_id>
+    <user_name>Xinil</user_name>
+    <user_export_type>1</user_export_type>
+    <user_total_anime>1</user_total_anime>
+  </myinfo>
+  <anime>
+    <series_animedb_id>1</series_animedb_id>
+    <series_title><![CDATA[Cowboy Bebop]]></series_title>
+    <series_type>TV</series_type>
+    <series_episodes>26</series_episodes>
+    <my_watched_episodes>26</my_watched_episodes>
+    <my_start_date>0000-00-00</my_start_date>
+    <my_finish_date>2020-05-01</my_finish_date>
+    <my_score>9</my_score>
+    <my_status>Completed</my_status>
+    <my_comments><![CDATA[]]></my_comments>
+    <my_times_watched>1</my_times_watched>
+    <my_priority>HIGH</my_priority>
+    <my_tags><![CDATA[space, jazz]]></my_tags>
+    <my_rewatching>0</my_rewatching>
+    <update_on_import>1</update_on_import>
+  </anime>
+</myanimelist>"#;
+    let export = MalExport::from_xml(xml).unwrap();
+    assert_eq!(export.myinfo.user_name, "Xinil");
+    let entry = &export.anime[0];
+    assert_eq!(entry.series_title, "Cowboy Bebop");
+    assert_eq!(entry.my_status, Status::Completed);
+    assert!(entry.update_on_import && !entry.my_rewatching);
+    assert_eq!(
+        entry.to_update().build().unwrap().get_params(),
+        [
+            ("status", "completed".to_owned()),
+            ("is_rewatching", "false".to_owned()),
+            ("score", "9".to_owned()),
+            ("num_watched_episodes", "26".to_owned()),
+            ("priority", "2".to_owned()),
+            ("num_times_rewatched", "1".to_owned()),
+            ("tags", "space,jazz".to_owned()),
+            ("finish_date", "2020-05-01".to_owned()),
+        ]
+    );
+    assert!(ConflictPolicy::UpdateOnImport.overwrites(entry));
+    assert_eq!(MalExport::from_xml(&export.to_xml().unwrap()).unwrap(), export);
+
+    let list = serde_json::from_value::<AnimeList>(serde_json::json!({
+        "data": [
+            {"node": {"id": 80, "title": "Mobile Suit Gundam", "main_picture": {}, "media_type": "tv"},
+             "list_status": {"status": "plan_to_watch", "tags": ["mecha"]}},
+            {"node": {"id": 81, "title": "No status", "main_picture": {}}}
+        ],
+        "paging": {}
+    }))
+    .unwrap();
+    let export = MalExport::from_list("me", &list);
+    assert_eq!(export.anime.len(), 1);
+    assert_eq!(export.myinfo.user_total_plantowatch, 1);
+    assert_eq!(export.anime[0].series_type, "TV");
+    assert_eq!(export.anime[0].my_start_date, "0000-00-00");
+    assert!(MalExport::from_xml("<myanimelist><anime></anime></myanimelist>").is_err());
+}
+
+#[test]
+fn wire_conversions() {
+    let json = serde_json::json!({
+        "id": 30230, "title": "Diamond no Ace: Second Season", "main_picture": {"medium": "m.jpg"},
+        "media_type": "web_series", "source": "brand_new_source", "popularity": 1234, "mean": 8.5
+    });
+    let raw: wire::Anime = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(raw.media_type.as_deref(), Some("web_series"));
+    assert_eq!(raw.extra.get("popularity"), Some(&serde_json::json!(1234)));
+    let details = AnimeDetails::try_from(raw.clone()).unwrap();
+    assert_eq!(details.media_type, Some(MediaType::Unknown));
+    assert_eq!(details.source, Some(Source::Other));
+    let back = wire::Anime::from(details);
+    assert_eq!(back.media_type.as_deref(), Some("unknown"));
+    #[cfg(not(feature = "minimal-models"))]
+    assert_eq!(back.extra.get("popularity"), Some(&serde_json::json!(1234)));
+
+    let user: wire::User = serde_json::from_value(serde_json::json!({
+        "id": 1, "name": "Xinil", "location": "", "joined_at": "2004-11-05T00:00:00+00:00",
+        "anime_statistics": {"num_items_watching": 3, "num_items": 10}
+    }))
+    .unwrap();
+    let typed = User::try_from(user.clone()).unwrap();
+    assert_eq!(typed.anime_statistics.as_ref().unwrap().num_watching, 3);
+    assert_eq!(wire::User::from(typed).anime_statistics, user.anime_statistics);
+
+    // `main_picture` is required by `AnimeDetails`
+    let no_picture: wire::Anime = serde_json::from_value(serde_json::json!({"id": 1, "title": ""})).unwrap();
+    assert!(AnimeDetails::try_from(no_picture).is_err());
+}
+
+#[test]
+fn list_sync_diff() {
+    let status = |json| serde_json::from_value::<ListStatus>(json).unwrap();
+    let remote: ListSnapshot = [
+        (AnimeId(1), status(serde_json::json!({"status": "watching", "num_episodes_watched": 3, "updated_at": "a"}))),
+        (AnimeId(2), status(serde_json::json!({"status": "completed", "score": 8}))),
+        (AnimeId(3), status(serde_json::json!({"status": "dropped"}))),
+    ]
+    .into();
+    let mut local: ListSnapshot = [
+        // the score isn't known locally, so it's not a change
+        (AnimeId(1), status(serde_json::json!({"status": "watching", "num_episodes_watched": 5, "updated_at": "b"}))),
+        (AnimeId(2), status(serde_json::json!({"status": "completed"}))),
+        (AnimeId(4), status(serde_json::json!({"status": "plan_to_watch", "tags": ["later"]}))),
+    ]
+    .into();
+
+    let push = sync::diff(&remote, &local);
+    assert_eq!(push.changes.iter().map(|c| c.id).collect::<Vec<_>>(), [1, 3, 4]);
+    let ChangeKind::Updated(fields) = &push.changes[0].kind else { panic!() };
+    assert_eq!(
+        fields,
+        &[FieldChange {
+            field: "num_episodes_watched".to_owned(),
+            from: serde_json::json!(3),
+            to: serde_json::json!(5),
+        }]
+    );
+    assert_eq!(push.changes[0].kind.to_update().unwrap().build_unchecked().get_params(), [("num_watched_episodes", "5".to_owned())]);
+    assert!(matches!(push.changes[1].kind, ChangeKind::Removed(_)));
+    assert!(push.changes[1].kind.to_update().is_none());
+    assert_eq!(
+        push.changes[2].kind.to_update().unwrap().build_unchecked().get_params(),
+        [("status", "plan_to_watch".to_owned()), ("tags", "later".to_owned())]
+    );
+
+    sync::diff(&local, &remote).apply(&mut local);
+    assert_eq!(local.keys().copied().collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(local[&AnimeId(1)].num_episodes_watched, Some(3));
+    assert_eq!(local[&AnimeId(2)].score, Score::new(8));
+    assert!(sync::diff(&local, &remote).is_empty());
+}
+
+#[test]
+fn watch_threshold() {
+    let minutes = |m: u64| std::time::Duration::from_secs(m * 60);
+    assert!(watch::reached_threshold(minutes(20), 24 * 60, 0.8));
+    assert!(!watch::reached_threshold(minutes(19), 24 * 60, 0.8));
+    assert!(watch::reached_threshold(minutes(0), 24 * 60, 0.0));
+    assert!(!watch::reached_threshold(minutes(60), 0, 0.8));
+    let client: MALClient = ClientBuilder::new().watch_threshold(0.5).build_no_refresh();
+    assert_eq!(client.watch_session(), None);
+    client.begin_watch_session(21, 4);
+    let replaced = client.begin_watch_session(21, 5).unwrap();
+    assert_eq!((replaced.id, replaced.episode), (AnimeId(21), 4));
+    assert_eq!(client.watch_session().map(|s| s.episode), Some(5));
+}
+
+#[test]
+fn ranking_digest() {
+    let list = |ids: &[usize]| {
+        let data: Vec<_> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| serde_json::json!({"node": {"id": id, "title": format!("Anime {id}"), "main_picture": {}}, "ranking": {"rank": i + 1}}))
+            .collect();
+        serde_json::from_value::<AnimeList>(serde_json::json!({"data": data, "paging": {}})).unwrap()
+    };
+    let old = RankingSnapshot::from_list("airing", 1, &list(&[10, 20, 30, 40]));
+    let new = RankingSnapshot::from_list("airing", 2, &list(&[30, 10, 50, 20]));
+    let digest = ranking::digest(Some(&old), &new);
+    assert_eq!(digest.since, Some(1));
+    assert_eq!(
+        digest.movers.iter().map(|m| (m.id.0, m.places())).collect::<Vec<_>>(),
+        [(30, 2), (10, -1), (20, -2)]
+    );
+    assert_eq!(digest.new_entries.iter().map(|e| (e.id.0, e.rank)).collect::<Vec<_>>(), [(50, 3)]);
+    assert_eq!(digest.dropped.iter().map(|e| e.id).collect::<Vec<_>>(), [40]);
+    assert!(ranking::digest(Some(&new), &new).is_empty());
+    assert_eq!(ranking::digest(None, &new).new_entries.len(), 4);
+
+    let path = env::temp_dir().join(format!("lib-mal-ranking-{}.json", std::process::id()));
+    assert_eq!(RankingSnapshot::load(&path).unwrap(), None);
+    new.save(&path).unwrap();
+    assert_eq!(RankingSnapshot::load(&path).unwrap(), Some(new));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn clear_list_needs_confirmation() {
+    let client = MALClient::with_access_token("token");
+    let options = ListClearOptions::new("delete my anime list").status(Status::Dropped);
+    let err = client.clear_list(options, |_| panic!("nothing should be deleted")).await.unwrap_err();
+    assert!(matches!(err, MALError::NotConfirmed));
+    assert!(ListClearOptions::new(CLEAR_LIST_CONFIRMATION).is_confirmed());
+}
+
+struct FakeTransport {
+    requests: std::sync::Mutex<Vec<crate::HttpRequest>>,
+}
+
+impl crate::HttpTransport for FakeTransport {
+    fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+        Box::pin(async move {
+            let found = request.url.contains("/anime/30230");
+            self.requests.lock().unwrap().push(request);
+            Ok(if found {
+                crate::HttpResponse::new(reqwest::StatusCode::OK, include_str!("test-data/anime_details.json"))
+            } else {
+                crate::HttpResponse::new(reqwest::StatusCode::NOT_FOUND, r#"{"error": "not_found"}"#)
+            })
+        })
+    }
+}
+
+#[tokio::test]
+async fn custom_transport() {
+    let transport = std::sync::Arc::new(FakeTransport { requests: std::sync::Mutex::default() });
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(transport.clone());
+    let details = client.get_anime_details(30230, None).await.unwrap();
+    assert_eq!(details.show.id, 30230);
+    let err = client.delete_anime_list_item(1).await.unwrap_err();
+    assert!(matches!(err, MALError::NotFound { .. }));
+
+    let requests = transport.requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].headers["authorization"], "Bearer token");
+    assert_eq!(requests[1].method, reqwest::Method::DELETE);
+}
+
+#[derive(Default)]
+struct CollectDumps(std::sync::Mutex<Vec<crate::debug::DebugDump>>);
+
+impl crate::debug::DebugSink for CollectDumps {
+    fn record(&self, dump: &crate::debug::DebugDump) {
+        self.0.lock().unwrap().push(dump.clone());
+    }
+}
+
+#[tokio::test]
+async fn debug_mode() {
+    use crate::debug::REDACTED;
+    use crate::endpoint::Endpoint;
+
+    let sink = std::sync::Arc::new(CollectDumps::default());
+    let mut client = MALClient::with_access_token("secret-token");
+    client.set_transport(std::sync::Arc::new(FakeTransport { requests: std::sync::Mutex::default() }));
+    client.set_debug_sink(sink.clone());
+    client.get_anime_details(30230, None).await.unwrap();
+    let endpoint = Endpoint::custom(reqwest::Method::POST, "oauth")
+        .form("refresh_token", "secret-refresh")
+        .form("grant_type", "refresh_token");
+    client.execute::<serde_json::Value>(endpoint).await.unwrap_err();
+    client.debug_mode(false);
+    client.get_anime_details(30230, None).await.unwrap();
+
+    let dumps = sink.0.lock().unwrap();
+    assert_eq!(dumps.len(), 2);
+    assert!(dumps[0].url.contains("/anime/30230"));
+    assert_eq!(dumps[0].status, Some(reqwest::StatusCode::OK));
+    let auth = dumps[0].request_headers.iter().find(|(name, _)| name == "authorization");
+    assert_eq!(auth.unwrap().1, REDACTED);
+    assert!(dumps[0].response_body.contains("Diamond no Ace"));
+    let form = dumps[1].request_body.as_ref().unwrap();
+    assert_eq!(form[0], (String::from("refresh_token"), String::from(REDACTED)));
+    assert_eq!(form[1], (String::from("grant_type"), String::from("refresh_token")));
+    let text = dumps.iter().map(ToString::to_string).collect::<String>();
+    assert!(!text.contains("secret-"));
+}
+
+struct TokenTransport;
+
+impl crate::HttpTransport for TokenTransport {
+    fn send(&self, _: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+        let body = r#"{"token_type":"Bearer","access_token":"a","nested":[{"refresh_token":"b"}]}"#;
+        Box::pin(async move { Ok(crate::HttpResponse::new(reqwest::StatusCode::OK, body)) })
+    }
+}
+
+#[tokio::test]
+async fn debug_redacts_json() {
+    use crate::HttpTransport;
+
+    let sink = CollectDumps::default();
+    let transport = crate::debug::Dumping { inner: &TokenTransport, sink: Some(&sink) };
+    let request = crate::HttpRequest::new(reqwest::Method::POST, "https://myanimelist.net/v1/oauth2/token");
+    let res = transport.send(request).await.unwrap();
+    assert!(res.body.contains(r#""access_token":"a""#));
+    let dump = &sink.0.lock().unwrap()[0];
+    assert_eq!(
+        dump.response_body,
+        r#"{"access_token":"[REDACTED]","nested":[{"refresh_token":"[REDACTED]"}],"token_type":"Bearer"}"#
+    );
+}
+
+#[tokio::test]
+async fn cloned_client() {
+    let transport = std::sync::Arc::new(FakeTransport { requests: std::sync::Mutex::default() });
+    let mut client: MALClient = ClientBuilder::new()
+        .access_token(String::from("token"))
+        .response_cache(CacheConfig::new(std::time::Duration::from_secs(60)))
+        .pool_max_idle_per_host(2)
+        .pool_idle_timeout(std::time::Duration::from_secs(30))
+        .tcp_keepalive(std::time::Duration::from_secs(60))
+        .http_version(crate::HttpVersion::Http1Only)
+        .http2_keep_alive(std::time::Duration::from_secs(30))
+        .build_no_refresh();
+    client.set_transport(transport.clone());
+    let clone = client.clone();
+    client.get_anime_details(30230, None).await.unwrap();
+    let handle = tokio::spawn(async move {
+        clone.get_anime_details(30230, None).await.unwrap();
+        clone.cache_stats()
+    });
+    let stats = handle.await.unwrap().unwrap();
+    assert_eq!((stats.hits, stats.misses), (1, 1));
+    assert_eq!(transport.requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn suggestions() {
+    #[derive(Default)]
+    struct Api {
+        urls: std::sync::Mutex<Vec<String>>,
+    }
+    impl crate::HttpTransport for Api {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async move {
+                self.urls.lock().unwrap().push(request.url.clone());
+                let body = if request.url.contains("/suggestions") {
+                    include_str!("test-data/anime_list.json").to_owned()
+                } else if request.url.contains("/animelist") {
+                    serde_json::json!({"data": [
+                        {"node": {"id": 21, "title": "One Piece", "main_picture": {}}},
+                        {"node": {"id": 459, "title": "One Piece Movie 1", "main_picture": {}}}
+                    ], "paging": {}})
+                    .to_string()
+                } else {
+                    String::from("{}")
+                };
+                Ok(crate::HttpResponse::new(reqwest::StatusCode::OK, body))
+            })
+        }
+    }
+    let api = std::sync::Arc::new(Api::default());
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(api.clone());
+    let ids = |list: AnimeList| list.data.iter().map(|e| e.node.id.0).collect::<Vec<_>>();
+
+    let all = ids(client.get_suggested_anime(5, None).await.unwrap());
+    assert!(all.contains(&21) && all.contains(&459));
+    let query = SuggestionQuery::new().limit(5).offset(10).exclude_on_list(true);
+    let unseen = ids(client.get_suggestions(query.clone()).await.unwrap());
+    assert_eq!(unseen.len(), all.len() - 2);
+    assert!(!unseen.contains(&21) && !unseen.contains(&459));
+    client.delete_anime_list_item(21).await.unwrap();
+    let unseen = ids(client.clone().get_suggestions(query).await.unwrap());
+    assert!(unseen.contains(&21) && !unseen.contains(&459));
+
+    let urls = api.urls.lock().unwrap();
+    assert!(urls[0].ends_with("/anime/suggestions?limit=5"));
+    assert!(urls[1].ends_with("/anime/suggestions?limit=5&offset=10"));
+    assert_eq!(urls.iter().filter(|url| url.contains("/animelist")).count(), 1);
+}
+
+#[tokio::test]
+async fn topic_posts() {
+    use futures_util::StreamExt;
+
+    struct Forum;
+    impl crate::HttpTransport for Forum {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async move {
+                let url = reqwest::Url::parse(&request.url).unwrap();
+                let offset: usize = url.query_pairs().find(|(k, _)| k == "offset").unwrap().1.parse().unwrap();
+                // 250 posts in pages of 100
+                let posts: Vec<_> = (offset + 1..=(offset + 100).min(250))
+                    .map(|n| serde_json::json!({
+                        "id": n * 10, "number": n, "created_at": "2024-01-01T00:00:00+00:00",
+                        "created_by": {"id": 1, "name": "poster"}, "body": "hi", "signature": ""
+                    }))
+                    .collect();
+                let paging = if offset + 100 < 250 { serde_json::json!({"next": "https://api.myanimelist.net/v2/forum/topic/5"}) } else { serde_json::json!({}) };
+                let body = serde_json::json!({"data": {"title": "Thread", "posts": posts, "poll": null}, "paging": paging});
+                Ok(crate::HttpResponse::new(reqwest::StatusCode::OK, body.to_string()))
+            })
+        }
+    }
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(std::sync::Arc::new(Forum));
+
+    let page = client.get_forum_topic_posts(5, 100, 200).await.unwrap();
+    assert_eq!(page.data.posts.first().map(|p| p.number), Some(201));
+    assert!(!page.has_next_page());
+    let posts: Vec<usize> = client.stream_topic_posts(5).map(|p| p.unwrap().number).collect().await;
+    assert_eq!(posts, (1..=250).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn metrics_sink() {
+    #[derive(Default)]
+    struct Recorder(std::sync::Mutex<Vec<crate::RequestMetrics>>);
+    impl crate::MetricsSink for Recorder {
+        fn record(&self, metrics: &crate::RequestMetrics) {
+            self.0.lock().unwrap().push(metrics.clone());
+        }
+    }
+    let recorder = std::sync::Arc::new(Recorder::default());
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(std::sync::Arc::new(FakeTransport { requests: std::sync::Mutex::default() }));
+    client.set_metrics_sink(recorder.clone());
+    client.get_anime_details(30230, None).await.unwrap();
+    client.delete_anime_list_item(1).await.unwrap_err();
+
+    let recorded = recorder.0.lock().unwrap();
+    let recorded: Vec<_> = recorded.iter().map(|m| (m.endpoint.as_str(), m.method.clone(), m.status.map(|s| s.as_u16()), m.is_success())).collect();
+    assert_eq!(
+        recorded,
+        [
+            ("get_anime_details", reqwest::Method::GET, Some(200), true),
+            ("delete_anime_list_item", reqwest::Method::DELETE, Some(404), false),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn get_franchise() {
+    struct Api {
+        requests: std::sync::Mutex<Vec<String>>,
+    }
+    impl crate::HttpTransport for Api {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async move {
+                let id: usize = request.url.split("/anime/").nth(1).unwrap().split('?').next().unwrap().parse().unwrap();
+                let (start, related): (Option<&str>, &[(usize, &str)]) = match id {
+                    1 => (Some("2020-01-10"), &[(2, "sequel"), (9, "character")]),
+                    2 => (Some("2022-04"), &[(1, "prequel"), (3, "side_story"), (4, "sequel")]),
+                    3 => (None, &[(2, "parent_story")]),
+                    4 => (Some("2023"), &[(2, "prequel"), (5, "sequel")]),
+                    _ => (None, &[]),
+                };
+                let related: Vec<_> = related
+                    .iter()
+                    .map(|(id, relation)| serde_json::json!({
+                        "node": {"id": id, "title": format!("Anime {id}"), "main_picture": {}},
+                        "relation_type": relation, "relation_type_formatted": relation
+                    }))
+                    .collect();
+                let media_type = if id == 3 { "special" } else { "tv" };
+                let body = serde_json::json!({
+                    "id": id, "title": format!("Anime {id}"), "main_picture": {}, "start_date": start,
+                    "media_type": media_type, "related_anime": related
+                });
+                self.requests.lock().unwrap().push(request.url);
+                Ok(crate::HttpResponse::new(reqwest::StatusCode::OK, body.to_string()))
+            })
+        }
+    }
+    let api = std::sync::Arc::new(Api { requests: std::sync::Mutex::default() });
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(api.clone());
+
+    let franchise = client.get_franchise(1, 2).await.unwrap();
+    let ids: Vec<usize> = franchise.anime.iter().map(|a| a.id.0).collect();
+    // the character relation isn't followed, and 5 is three relations away
+    assert_eq!(ids, [1, 2, 4, 3]);
+    assert_eq!(api.requests.lock().unwrap().len(), 4);
+    assert_eq!(franchise.root, AnimeId(1));
+    let from_2: Vec<(&RelationType, usize)> = franchise.relations_of(2).map(|(r, a)| (r, a.id.0)).collect();
+    assert_eq!(
+        from_2,
+        [(&RelationType::Prequel, 1), (&RelationType::SideStory, 3), (&RelationType::Sequel, 4)]
+    );
+    assert_eq!(franchise.edges.len(), 6);
+
+    // the query's depth and filters apply to both crawls
+    let series = client.get_franchise(1, RelationQuery::watch_order().max_depth(2)).await.unwrap();
+    let ids: Vec<usize> = series.anime.iter().map(|a| a.id.0).collect();
+    assert_eq!(ids, [1, 2, 4]);
+    let query = RelationQuery::new().relation_types(vec![RelationType::Sequel]).max_depth(3);
+    let sequels = client.get_related_anime(1, query).await.unwrap();
+    let ids: Vec<usize> = sequels.iter().map(|a| a.id.0).collect();
+    assert_eq!(ids, [2, 4, 5]);
+}
+
+#[tokio::test]
+async fn id_map() {
+    use crate::idmap::{Service, ID_MAP_FILE};
+
+    struct Api {
+        requests: std::sync::Mutex<Vec<String>>,
+    }
+    impl crate::HttpTransport for Api {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async move {
+                let body = if request.url.contains("anime-offline-database") {
+                    serde_json::json!({"data": [
+                        {"sources": ["https://anidb.net/anime/69", "https://anilist.co/anime/21", "https://kitsu.app/anime/12", "https://myanimelist.net/anime/21"]},
+                        {"sources": ["https://anilist.co/anime/999"]}
+                    ]})
+                    .to_string()
+                } else {
+                    include_str!("test-data/one_piece_details.json").to_owned()
+                };
+                self.requests.lock().unwrap().push(request.url);
+                Ok(crate::HttpResponse::new(reqwest::StatusCode::OK, body))
+            })
+        }
+    }
+    let api = std::sync::Arc::new(Api { requests: std::sync::Mutex::default() });
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(api.clone());
+    let dir = env::temp_dir().join("lib-mal-id-map");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let map = client.load_id_map(&dir, std::time::Duration::from_secs(60)).await.unwrap();
+    assert_eq!(map.len(), 1);
+    assert_eq!(map.mal_id(Service::Kitsu, 12), Some(AnimeId(21)));
+    assert_eq!(map.external_id(21, Service::AniDB), Some(69));
+    assert_eq!(map.mal_id(Service::AniList, 999), None);
+    assert!(dir.join(ID_MAP_FILE).is_file());
+    // read from the saved copy
+    let again = client.load_id_map(&dir, std::time::Duration::from_secs(60)).await.unwrap();
+    assert_eq!(again, map);
+    assert_eq!(api.requests.lock().unwrap().len(), 1);
+
+    let details = client.get_anime_details_by_anilist_id(&map, 21, None).await.unwrap();
+    assert_eq!(details.show.id, AnimeId(21));
+    assert!(matches!(client.get_anime_details_by_anilist_id(&map, 999, None).await, Err(MALError::NotFound { .. })));
+}
+
+#[tokio::test]
+async fn download_picture() {
+    struct Cdn {
+        requests: std::sync::Mutex<Vec<crate::HttpRequest>>,
+    }
+    impl crate::HttpTransport for Cdn {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async move {
+                let status = if request.url.contains("missing") { reqwest::StatusCode::NOT_FOUND } else { reqwest::StatusCode::OK };
+                let body = format!("picture at {}", request.url);
+                self.requests.lock().unwrap().push(request);
+                Ok(crate::HttpResponse::new(status, body))
+            })
+        }
+    }
+    let cdn = std::sync::Arc::new(Cdn { requests: std::sync::Mutex::default() });
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(cdn.clone());
+    let dir = env::temp_dir().join("lib-mal-pictures");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let details =
+        serde_json::from_str::<AnimeDetails>(include_str!("test-data/anime_details.json")).unwrap();
+    let path = client.download_picture(&details.main_picture, PictureSize::Large, &dir).await.unwrap();
+    let large = details.main_picture["large"].as_str().unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("picture at {large}"));
+    assert_eq!(path.extension().unwrap(), "jpg");
+    // already downloaded, and the CDN doesn't get the token
+    client.download_picture(&details.main_picture, PictureSize::Large, &dir).await.unwrap();
+    assert_eq!(cdn.requests.lock().unwrap().len(), 1);
+    assert!(cdn.requests.lock().unwrap()[0].headers.is_empty());
+
+    let only_medium = std::collections::HashMap::from([(String::from("medium"), String::from("https://cdn.test/a.png"))]);
+    let missing = std::collections::HashMap::from([(String::from("large"), String::from("https://cdn.test/missing.png"))]);
+    let res = client.download_pictures(&[only_medium, missing], PictureSize::Large, &dir).await;
+    assert_eq!(res[0].as_ref().unwrap().extension().unwrap(), "png");
+    assert!(matches!(res[1], Err(MALError::Http { status: reqwest::StatusCode::NOT_FOUND, .. })));
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+}
+
+#[cfg(feature = "oauth")]
+#[tokio::test]
+async fn confidential_client() {
+    struct Oauth {
+        forms: std::sync::Mutex<Vec<Vec<(String, String)>>>,
+    }
+    impl crate::HttpTransport for Oauth {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async move {
+                self.forms.lock().unwrap().push(request.form.unwrap_or_default());
+                Ok(crate::HttpResponse::new(
+                    reqwest::StatusCode::OK,
+                    r#"{"token_type": "Bearer", "expires_in": 3600, "access_token": "access", "refresh_token": "refresh"}"#,
+                ))
+            })
+        }
+    }
+    let oauth = std::sync::Arc::new(Oauth { forms: std::sync::Mutex::default() });
+    let secret_of = |form: &[(String, String)]| form.iter().find(|(k, _)| k == "client_secret").map(|(_, v)| v.clone());
+
+    let mut client: MALClient = ClientBuilder::new()
+        .client_id(String::from("id"))
+        .client_secret(String::from("shh"))
+        .build_no_refresh();
+    client.set_transport(oauth.clone());
+    client.auth_with_code("code", "verifier").await.unwrap();
+    assert_eq!(client.get_access_token(), "access");
+    let tokens = crate::client::refresh_tokens(oauth.as_ref(), "https://myanimelist.net/", "id", Some("shh"), "refresh").await.unwrap();
+    assert_eq!(tokens.refresh_token, "refresh");
+
+    let mut public: MALClient = ClientBuilder::new().client_id(String::from("id")).build_no_refresh();
+    public.set_transport(oauth.clone());
+    public.auth_with_code("code", "verifier").await.unwrap();
+
+    let forms = oauth.forms.lock().unwrap();
+    assert_eq!(secret_of(&forms[0]), Some(String::from("shh")));
+    assert!(forms[0].contains(&(String::from("client_id"), String::from("id"))));
+    assert_eq!(secret_of(&forms[1]), Some(String::from("shh")));
+    assert_eq!(secret_of(&forms[2]), None);
+}
+
+#[cfg(feature = "oauth")]
+#[tokio::test]
+async fn middleware_hooks() {
+    #[derive(Default)]
+    struct Recorder(std::sync::Mutex<Vec<String>>);
+    impl crate::RequestInterceptor for std::sync::Arc<Recorder> {
+        fn before_request(&self, method: &reqwest::Method, url: &str) {
+            self.0.lock().unwrap().push(format!("{method} {url}"));
+        }
+        fn after_response(&self, event: &crate::RequestEvent) {
+            self.0.lock().unwrap().push(format!("{:?} {}", event.status, event.url));
+        }
+    }
+    struct Oauth;
+    impl crate::HttpTransport for Oauth {
+        fn send(&self, _: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            let body = r#"{"token_type": "Bearer", "expires_in": 3600, "access_token": "access", "refresh_token": "refresh"}"#;
+            Box::pin(async move { Ok(crate::HttpResponse::new(reqwest::StatusCode::OK, body)) })
+        }
+    }
+    let recorder = std::sync::Arc::new(Recorder::default());
+    let mut client: MALClient = ClientBuilder::new()
+        .client_id(String::from("id"))
+        .transport(Oauth)
+        .with_middleware(recorder.clone())
+        .build_no_refresh();
+    client.auth_with_code("code", "verifier").await.unwrap();
+    client.refresh_token().await.unwrap();
+    let token_url = "https://myanimelist.net/v1/oauth2/token";
+    let before = format!("POST {token_url}");
+    let after = format!("Some(200) {token_url}");
+    assert_eq!(*recorder.0.lock().unwrap(), [before.clone(), after.clone(), before, after]);
+}
+
+#[tokio::test]
+async fn diagnose() {
+    use crate::diagnostics::{CheckKind, CheckStatus};
+
+    struct Diag {
+        down: std::sync::atomic::AtomicBool,
+        urls: std::sync::Mutex<Vec<String>>,
+    }
+    impl crate::HttpTransport for Diag {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async move {
+                self.urls.lock().unwrap().push(request.url.clone());
+                if self.down.load(std::sync::atomic::Ordering::Relaxed) {
+                    return Err(MALError::transport("connection refused"));
+                }
+                let authorized = request.headers.get(reqwest::header::AUTHORIZATION).is_some_and(|v| v == "Bearer good");
+                let mut res = if request.url.ends_with("/users/@me") && !authorized {
+                    crate::HttpResponse::new(reqwest::StatusCode::UNAUTHORIZED, r#"{"error": "invalid_token"}"#)
+                } else {
+                    crate::HttpResponse::new(reqwest::StatusCode::OK, "{}")
+                };
+                let date = httpdate::fmt_http_date(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000));
+                res.headers.insert(reqwest::header::DATE, date.parse().unwrap());
+                Ok(res)
+            })
+        }
+    }
+    let transport = std::sync::Arc::new(Diag { down: false.into(), urls: std::sync::Mutex::default() });
+    let client = |token: &str| -> MALClient {
+        let mut client: MALClient = ClientBuilder::new()
+            .access_token(token.to_owned())
+            .api_base_url("http://localhost:8080/mal/")
+            .clock(MockClock::from_unix(1_700_000_000 + 3600))
+            .rate_limit(1)
+            .build_no_refresh();
+        client.set_transport(transport.clone());
+        client
+    };
+    let report = client("good").diagnose().await;
+    let status = |kind| report.get(kind).unwrap().status;
+    assert_eq!(status(CheckKind::MalReachable), CheckStatus::Ok);
+    assert_eq!(status(CheckKind::Token), CheckStatus::Ok);
+    assert_eq!(status(CheckKind::ClockSkew), CheckStatus::Warning);
+    assert!(report.get(CheckKind::ClockSkew).unwrap().detail.contains("3600 seconds ahead of"));
+    assert_eq!(status(CheckKind::CacheDir), CheckStatus::Skipped);
+    assert_eq!(status(CheckKind::RateLimit), CheckStatus::Ok);
+    #[cfg(feature = "jikan")]
+    assert_eq!(status(CheckKind::JikanReachable), CheckStatus::Ok);
+    #[cfg(not(feature = "jikan"))]
+    assert_eq!(status(CheckKind::JikanReachable), CheckStatus::Skipped);
+    assert!(report.is_healthy());
+    {
+        let urls = transport.urls.lock().unwrap();
+        assert_eq!(urls[..2], ["http://localhost:8080/mal/v2/", "http://localhost:8080/mal/v2/users/@me"]);
+        #[cfg(feature = "jikan")]
+        assert_eq!(urls[2], "https://api.jikan.moe/v4/");
+    }
+
+    let report = client("expired").diagnose().await;
+    assert_eq!(report.get(CheckKind::Token).unwrap().status, CheckStatus::Failed);
+    assert!(!report.is_healthy());
+
+    transport.down.store(true, std::sync::atomic::Ordering::Relaxed);
+    let report = client("good").diagnose().await;
+    assert_eq!(report.get(CheckKind::MalReachable).unwrap().status, CheckStatus::Failed);
+    assert_eq!(report.get(CheckKind::Token).unwrap().status, CheckStatus::Skipped);
+}
+
+#[tokio::test]
+async fn search_pages_prefetch() {
+    use futures_util::StreamExt;
+
+    struct Pages(std::sync::Mutex<Vec<String>>);
+    impl crate::HttpTransport for Pages {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async move {
+                let offset: usize = request.url.split("offset=").nth(1).unwrap().split('&').next().unwrap().parse().unwrap();
+                self.0.lock().unwrap().push(request.url);
+                let next = if offset < 2 {
+                    format!(r#""next": "https://api.myanimelist.net/v2/anime?q=a&limit=1&offset={}""#, offset + 1)
+                } else {
+                    String::new()
+                };
+                let body = format!(r#"{{"data": [{{"node": {{"id": {offset}, "title": "t", "main_picture": {{}}}}}}], "paging": {{{next}}}}}"#);
+                Ok(crate::HttpResponse::new(reqwest::StatusCode::OK, body))
+            })
+        }
+    }
+    for prefetch in [false, true] {
+        let transport = std::sync::Arc::new(Pages(std::sync::Mutex::default()));
+        let mut client = MALClient::with_access_token("token");
+        client.set_transport(transport.clone());
+        let mut pages = std::pin::pin!(client.search_pages("a", 1, None, prefetch));
+        assert_eq!(pages.next().await.unwrap().unwrap().data[0].node.id, 0);
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(transport.0.lock().unwrap().len(), if prefetch { 2 } else { 1 });
+        let ids: Vec<usize> = pages.map(|p| p.unwrap().data[0].node.id.0).collect().await;
+        assert_eq!(ids, [1, 2]);
+        assert_eq!(transport.0.lock().unwrap().len(), 3);
+    }
+    let client = MALClient::with_access_token("token");
+    let pages: Vec<_> = client.search_pages("a", 101, None, false).collect().await;
+    assert!(matches!(pages[..], [Err(MALError::Invalid { field: "limit", .. })]));
+}
+
+#[cfg(feature = "oauth")]
+#[tokio::test]
+async fn base_urls() {
+    let transport = std::sync::Arc::new(FakeTransport { requests: std::sync::Mutex::default() });
+    let mut client: MALClient = ClientBuilder::new()
+        .client_id(String::from("id"))
+        .access_token(String::from("token"))
+        .api_base_url("http://localhost:8080/mal")
+        .auth_base_url("http://localhost:8080/auth/")
+        .build_no_refresh();
+    client.set_transport(transport.clone());
+    // FakeTransport only knows the paths, so the anime is found at the new base URL
+    client.get_anime_details(30230, None).await.unwrap();
+    assert!(client.get_auth_parts().url.starts_with("http://localhost:8080/auth/v1/oauth2/authorize?"));
+    let _ = client.auth_with_code("code", "verifier").await;
+    let requests = transport.requests.lock().unwrap();
+    assert!(requests[0].url.starts_with("http://localhost:8080/mal/v2/anime/30230?"));
+    assert!(requests[0].headers.contains_key(reqwest::header::AUTHORIZATION));
+    assert_eq!(requests[1].url, "http://localhost:8080/auth/v1/oauth2/token");
+}
+
+#[test]
+fn status_builder_validation() {
+    let update = StatusUpdate::new().score(10).priority(2).rewatch_value(5).start_date(String::from("2024-02-29")).build().unwrap();
+    assert!(update.get_params().contains(&("start_date", String::from("2024-02-29"))));
+    let invalid = [
+        (StatusUpdate::new().score(200), "score"),
+        (StatusUpdate::new().priority(3), "priority"),
+        (StatusUpdate::new().rewatch_value(6), "rewatch_value"),
+        (StatusUpdate::new().start_date(String::from("yesterday")), "start_date"),
+        (StatusUpdate::new().finish_date(String::from("2023-02-29")), "finish_date"),
+        (StatusUpdate::new().finish_date(String::from("2023-2-01")), "finish_date"),
+    ];
+    for (builder, field) in invalid {
+        let err = builder.build().unwrap_err();
+        assert_eq!(err.field, field);
+        assert!(matches!(MALError::from(err), MALError::Invalid { .. }));
+    }
+    let unchecked = StatusUpdate::new().score(200).build_unchecked();
+    assert_eq!(unchecked.get_params(), [("score", String::from("200"))]);
+    #[allow(deprecated)]
+    let old = crate::model::StatusBuilder::new().score(9).status(Status::Watching);
+    assert_eq!(old, StatusUpdate::new().status(Status::Watching).score(9));
+    let mut mutated = StatusUpdate::new();
+    #[allow(deprecated)]
+    {
+        mutated.set_status(Status::Watching);
+        mutated.set_score(9);
+        mutated.set_tags(vec![String::from("space")]);
+        mutated.set_comments("again");
+    }
+    assert_eq!(
+        mutated.build().unwrap(),
+        StatusUpdate::new().status(Status::Watching).score(9).add_tag("space").comments(String::from("again")).build().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn list_cache_refresh() {
+    struct Api {
+        requests: std::sync::Mutex<Vec<String>>,
+    }
+    impl crate::HttpTransport for Api {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async move {
+                let entry = |id: usize, day: u8| format!(r#"{{"node": {{"id": {id}, "title": "Anime {id}", "main_picture": {{}}}},
+                    "list_status": {{"status": "watching", "updated_at": "2024-07-0{day}T12:00:00+00:00"}}}}"#);
+                let body = if request.url.contains("offset=2") {
+                    format!(r#"{{"data": [{}], "paging": {{}}}}"#, entry(3, 1))
+                } else {
+                    format!(
+                        r#"{{"data": [{}, {}], "paging": {{"next": "https://api.myanimelist.net/v2/users/@me/animelist?offset=2"}}}}"#,
+                        entry(1, 3),
+                        entry(2, 2)
+                    )
+                };
+                self.requests.lock().unwrap().push(request.url);
+                Ok(crate::HttpResponse::new(reqwest::StatusCode::OK, body))
+            })
+        }
+    }
+    let api = std::sync::Arc::new(Api { requests: std::sync::Mutex::default() });
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(api.clone());
+
+    let path = env::temp_dir().join("lib-mal-list-cache.json.gz");
+    let _ = std::fs::remove_file(&path);
+    let mut cache = ListCache::open(&path).unwrap();
+    assert_eq!(cache, ListCache::default());
+    let changed = cache.refresh_incremental(&client).await.unwrap();
+    assert_eq!(changed, [AnimeId(1), AnimeId(2), AnimeId(3)]);
+    assert!(api.requests.lock().unwrap()[0].contains("sort=list_updated_at"));
+    // 2024-07-03T12:00:00Z
+    assert_eq!(cache.updated_until, Some(1_720_008_000));
+
+    // the first page already reaches older entries
+    assert!(cache.refresh_incremental(&client).await.unwrap().is_empty());
+    assert_eq!(api.requests.lock().unwrap().len(), 3);
+
+    cache.entries.insert(AnimeId(4), cache.entries[&AnimeId(1)].clone());
+    assert_eq!(cache.refresh_full(&client).await.unwrap(), [AnimeId(4)]);
+    cache.save(&path).unwrap();
+    assert_eq!(ListCache::load(&path).unwrap(), cache);
+    assert_eq!(cache.snapshot().len(), 3);
+}
+
+#[tokio::test]
+async fn offline_queue() {
+    struct Api {
+        online: std::sync::atomic::AtomicBool,
+        sent: std::sync::Mutex<Vec<(reqwest::Method, String)>>,
+    }
+    impl crate::HttpTransport for Api {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async move {
+                if !self.online.load(std::sync::atomic::Ordering::SeqCst) {
+                    return Err(MALError::transport("no connection"));
+                }
+                let body = if request.method != reqwest::Method::GET {
+                    self.sent.lock().unwrap().push((request.method, request.url));
+                    r#"{"status": "watching"}"#
+                } else if request.url.contains("/anime/81") {
+                    // changed on another device after the change was queued
+                    r#"{"id": 81, "title": "Anime", "main_picture": {}, "my_list_status": {"updated_at": "2100-01-01T00:00:00+00:00"}}"#
+                } else {
+                    r#"{"id": 80, "title": "Anime", "main_picture": {}, "my_list_status": {"updated_at": "2000-01-01T00:00:00+00:00"}}"#
+                };
+                Ok(crate::HttpResponse::new(reqwest::StatusCode::OK, body))
+            })
+        }
+    }
+    let dir = env::temp_dir().join("lib-mal-offline-queue");
+    let _ = std::fs::remove_dir_all(&dir);
+    let api = std::sync::Arc::new(Api { online: false.into(), sent: std::sync::Mutex::default() });
+    let mut client = MALClient::with_access_token("token");
+    client.set_cache_dir(dir);
+    client.set_transport(api.clone());
+    client.set_max_retries(0);
+    client.set_offline_queue(true);
+
+    let update = StatusUpdate::new().status(Status::Watching).build_unchecked();
+    assert!(matches!(client.update_user_anime_status(80, update).await, Err(MALError::Queued)));
+    assert!(matches!(client.delete_anime_list_item(81).await, Err(MALError::Queued)));
+    assert_eq!(client.pending_changes().unwrap().len(), 2);
+    // still offline
+    let report = client.flush_pending().await.unwrap();
+    assert_eq!(report.pending.len(), 2);
+    assert_eq!(client.pending_changes().unwrap().len(), 2);
+
+    api.online.store(true, std::sync::atomic::Ordering::SeqCst);
+    let report = client.flush_pending().await.unwrap();
+    assert_eq!(report.applied.iter().map(|c| c.id).collect::<Vec<_>>(), [AnimeId(80)]);
+    assert_eq!(report.conflicts.iter().map(|c| c.id).collect::<Vec<_>>(), [AnimeId(81)]);
+    assert!(report.failed.is_empty() && report.pending.is_empty());
+    assert!(client.pending_changes().unwrap().is_empty());
+    let sent = api.sent.lock().unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].0, reqwest::Method::PUT);
+}
+
+#[tokio::test]
+async fn flush_keeps_retryable_changes() {
+    struct Api {
+        online: std::sync::atomic::AtomicBool,
+        // the status MAL answers the change of anime 91 with
+        status: std::sync::Mutex<reqwest::StatusCode>,
+    }
+    impl crate::HttpTransport for Api {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async move {
+                if !self.online.load(std::sync::atomic::Ordering::SeqCst) {
+                    return Err(MALError::transport("no connection"));
+                }
+                Ok(if request.method == reqwest::Method::GET {
+                    crate::HttpResponse::new(
+                        reqwest::StatusCode::OK,
+                        r#"{"id": 90, "title": "Anime", "main_picture": {}, "my_list_status": {"updated_at": "2000-01-01T00:00:00+00:00"}}"#,
+                    )
+                } else if request.url.contains("/anime/90/") {
+                    crate::HttpResponse::new(reqwest::StatusCode::BAD_REQUEST, r#"{"error": "invalid_parameters"}"#)
+                } else if request.url.contains("/anime/91/") {
+                    let status = *self.status.lock().unwrap();
+                    crate::HttpResponse::new(status, r#"{"error": "try_later", "status": "watching"}"#)
+                } else {
+                    crate::HttpResponse::new(reqwest::StatusCode::OK, r#"{"status": "watching"}"#)
+                })
+            })
+        }
+    }
+    let dir = env::temp_dir().join(format!("lib-mal-flush-retryable-{}", std::process::id()));
+    let api = std::sync::Arc::new(Api {
+        online: false.into(),
+        status: std::sync::Mutex::new(reqwest::StatusCode::TOO_MANY_REQUESTS),
+    });
+    let mut client = MALClient::with_access_token("token");
+    client.set_cache_dir(dir.clone());
+    client.set_transport(api.clone());
+    client.set_max_retries(0);
+    client.set_offline_queue(true);
+    for id in [90, 91, 92] {
+        let update = StatusUpdate::new().status(Status::Watching).build_unchecked();
+        assert!(matches!(client.update_user_anime_status(id, update).await, Err(MALError::Queued)));
+    }
+    api.online.store(true, std::sync::atomic::Ordering::SeqCst);
+
+    let ids = |changes: &[crate::offline::PendingChange]| changes.iter().map(|c| c.id).collect::<Vec<_>>();
+    let report = client.flush_pending().await.unwrap();
+    let failed: Vec<_> = report.failed.iter().map(|(c, _)| c.id).collect();
+    assert_eq!(failed, [AnimeId(90)]);
+    assert_eq!(ids(&report.pending), [AnimeId(91), AnimeId(92)]);
+    for status in [reqwest::StatusCode::UNAUTHORIZED, reqwest::StatusCode::SERVICE_UNAVAILABLE] {
+        *api.status.lock().unwrap() = status;
+        let report = client.flush_pending().await.unwrap();
+        assert!(report.failed.is_empty(), "{status}");
+        assert_eq!(ids(&report.pending), [AnimeId(91), AnimeId(92)], "{status}");
+        assert_eq!(ids(&client.pending_changes().unwrap()), [AnimeId(91), AnimeId(92)], "{status}");
+    }
+
+    *api.status.lock().unwrap() = reqwest::StatusCode::OK;
+    let report = client.flush_pending().await.unwrap();
+    assert_eq!(ids(&report.applied), [AnimeId(91), AnimeId(92)]);
+    assert!(client.pending_changes().unwrap().is_empty());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn update_if_unchanged() {
+    struct Api {
+        updates: std::sync::atomic::AtomicUsize,
+    }
+    impl crate::HttpTransport for Api {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async move {
+                let body = if request.method == reqwest::Method::PUT {
+                    self.updates.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    r#"{"status": "watching", "score": 9}"#
+                } else {
+                    r#"{"id": 80, "title": "Anime", "main_picture": {}, "my_list_status": {"status": "watching", "updated_at": "2024-07-01T12:00:00+00:00"}}"#
+                };
+                Ok(crate::HttpResponse::new(reqwest::StatusCode::OK, body))
+            })
+        }
+    }
+    let api = std::sync::Arc::new(Api { updates: 0.into() });
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(api.clone());
+    let update = || StatusUpdate::new().score(9).build().unwrap();
+
+    // the same time written differently
+    let status = client.update_if_unchanged(80, update(), Some("2024-07-01T12:00:00Z")).await.unwrap();
+    assert_eq!(status.score, Score::new(9));
+    let err = client.update_if_unchanged(80, update(), Some("2024-06-30T08:00:00+00:00")).await.unwrap_err();
+    assert!(matches!(err, MALError::Conflict { current: Some(current) } if current.updated_at.as_deref() == Some("2024-07-01T12:00:00+00:00")));
+    // based on the anime not being on the list
+    let err = client.update_if_unchanged(80, update(), None).await.unwrap_err();
+    assert!(matches!(err, MALError::Conflict { .. }));
+    assert_eq!(api.updates.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn proxy_and_timeout() {
+    // a proxy that accepts connections but never answers
+    let proxy = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let client: MALClient = ClientBuilder::new()
+        .access_token("token".to_owned())
+        .proxy(&format!("http://{}", proxy.local_addr().unwrap()))
+        .timeout(std::time::Duration::from_millis(200))
+        .max_retries(0)
+        .user_agent("lib-mal-tests/1.0")
+        .build_no_refresh();
+    let err = client.get_anime_details(30230, None).await.unwrap_err();
+    assert!(matches!(err, MALError::Network { source, .. } if source.is_timeout()));
+}
+
+#[cfg(feature = "jikan")]
+#[tokio::test]
+async fn jikan_mocks() {
+    let client: MALClient = ClientBuilder::new().transport(crate::MockJikanTransport).build_no_refresh();
+    let jikan = client.jikan();
+    let episodes = jikan.all_episodes(30230, false).await.unwrap();
+    assert_eq!(episodes.data.iter().filter_map(|e| e.mal_id).collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(jikan.staff(30230).await.unwrap()[0].positions, ["Director", "Storyboard"]);
+    let characters = jikan.characters(30230).await.unwrap();
+    assert_eq!(characters.len(), 2);
+    assert_eq!(characters[0].character.name, "Sawamura, Eijun");
+    assert!(matches!(jikan.characters(21).await, Err(MALError::NotFound { .. })));
+
+    let news = jikan.news(30230, None).await.unwrap();
+    assert_eq!(news.len(), 2);
+    assert_eq!(news[0].url, "https://myanimelist.net/news/43017425");
+    assert!(news[0].image.as_deref().unwrap().starts_with("https://cdn.myanimelist.net/"));
+    assert_eq!(news[1].image, None);
+    assert!(news[0].published_at() > news[1].published_at());
+    let json = serde_json::to_string(&news[0]).unwrap();
+    assert_eq!(serde_json::from_str::<crate::model::NewsItem>(&json).unwrap(), news[0]);
+
+    let reviews = jikan.reviews(30230, None, None).await.unwrap();
+    assert!(reviews.has_next_page());
+    assert_eq!(reviews.data[0].author.username, "Kuramochi");
+    assert_eq!(reviews.data[1].reactions.overall, 47);
+    assert_eq!(reviews.data[1].episodes_watched, Some(25));
+    let sorted = jikan.reviews(30230, 1, crate::model::options::ReviewSort::MostReactions).await.unwrap();
+    assert_eq!(sorted.data[0].mal_id, 171_205);
+    let json = serde_json::to_string(&sorted.data[0]).unwrap();
+    assert_eq!(serde_json::from_str::<crate::model::Review>(&json).unwrap(), sorted.data[0]);
+    let lowest = jikan.reviews(30230, None, crate::model::options::ReviewSort::LowestScore).await.unwrap();
+    assert_eq!(lowest.data[0].score, 8);
+
+    let catalog = jikan.genres().await.unwrap();
+    assert_eq!(catalog.find(" sports").map(|g| g.mal_id), Some(30));
+    assert_eq!(catalog.find("time travel").unwrap().count, 187);
+    assert_eq!(catalog.all().count(), 13);
+    let page = jikan.browse_by_genre(30, crate::model::options::GenreSort::Popularity, None).await.unwrap();
+    assert!(page.has_next_page());
+    assert_eq!(page.data[1].mal_id, 30230);
+    assert_eq!(page.data[0].image.as_deref(), Some("https://cdn.myanimelist.net/images/anime/9/76662.jpg"));
+    assert_eq!(page.data[0].media_type.as_deref(), Some("TV"));
+    assert_eq!(jikan.browse_by_genre(30, None, None).await.unwrap(), page);
+    assert!(matches!(jikan.browse_by_genre(30, None, 2).await, Err(MALError::NotFound { .. })));
+
+    let themes = jikan.themes(30230).await.unwrap();
+    assert_eq!(themes.iter().filter(|s| s.kind == crate::model::ThemeKind::Opening).count(), 2);
+    assert_eq!(themes[1].title, "Perfect HERO");
+    assert_eq!(themes[3].artist.as_deref(), Some("Eijun Sawamura (CV: Ryota Osaka)"));
+    assert_eq!(themes[3].episodes.as_deref(), Some("51"));
+    assert_eq!(themes[3].number, None);
+    assert!(matches!(jikan.themes(21).await, Err(MALError::NotFound { .. })));
+}
+
+#[cfg(feature = "jikan")]
+#[test]
+fn theme_songs() {
+    use crate::model::{ThemeKind, ThemeSong};
+
+    let song = ThemeSong::parse(ThemeKind::Opening, r#"3: "Hikari e (光へ)" by The Babystars (eps 48-115)"#).unwrap();
+    assert_eq!(song.number, Some(3));
+    assert_eq!(song.title, "Hikari e (光へ)");
+    assert_eq!(song.artist.as_deref(), Some("The Babystars"));
+    assert_eq!(song.episodes.as_deref(), Some("48-115"));
+    let song = ThemeSong::parse(ThemeKind::Ending, r#"#2: "Say "Hello" by Me" by Someone"#).unwrap();
+    assert_eq!((song.number, song.title.as_str(), song.artist.as_deref()), (Some(2), r#"Say "Hello" by Me"#, Some("Someone")));
+    let song = ThemeSong::parse(ThemeKind::Opening, "Tank! by The Seatbelts").unwrap();
+    assert_eq!((song.title.as_str(), song.episodes), ("Tank!", None));
+    assert_eq!(ThemeSong::parse(ThemeKind::Opening, r#"1: """#), None);
+}
+
+#[test]
+fn heavy_fields() {
+    let fields = (AnimeFields::Title | AnimeFields::Pictures).to_string();
+    assert_eq!(fields, if cfg!(feature = "minimal-models") { "title" } else { "title,pictures" });
+    let details: AnimeDetails = serde_json::from_str(include_str!("test-data/anime_details.json")).unwrap();
+    assert_eq!(details.show.id, 30230);
+    #[cfg(not(feature = "minimal-models"))]
+    assert!(details.statistics.is_some());
+}
+
+#[test]
+fn nested_fields() {
+    use crate::model::fields::ListStatusFields;
+
+    let fields = AnimeFields::Title.with_list_status(ListStatusFields::Tags | ListStatusFields::Comments);
+    assert_eq!(fields.to_string(), "title,my_list_status{tags,comments}");
+    assert!(fields.my_list_status());
+    assert_eq!(fields.list_status_fields(), ListStatusFields::Tags | ListStatusFields::Comments);
+    let fields = (fields | AnimeFields::NumEpisodes).with_list_status(ListStatusFields::NumTimesRewatched);
+    assert_eq!(fields.to_string(), "title,my_list_status{num_times_rewatched},num_episodes");
+    assert_eq!((AnimeFields::Title | AnimeFields::MyListStatus).to_string(), "title,my_list_status");
+    assert_eq!(AnimeFields::ALL.list_status_fields(), ListStatusFields::empty());
+
+    let params = UserListQuery::new()
+        .list_status_fields(ListStatusFields::Score | ListStatusFields::UpdatedAt)
+        .fields(AnimeFields::Mean)
+        .get_params();
+    assert_eq!(params[0], ("fields", String::from("list_status{score,updated_at},mean")));
+}
+
+#[tokio::test]
+async fn record_and_replay() {
+    let dir = env::temp_dir().join(format!("lib-mal-fixtures-{}", std::process::id()));
+    let fake = FakeTransport { requests: std::sync::Mutex::default() };
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(std::sync::Arc::new(ReplayTransport::record(&dir, fake)));
+    let recorded = client.get_anime_details(30230, None).await.unwrap();
+    assert!(client.delete_anime_list_item(1).await.is_err());
+
+    let replay = ReplayTransport::replay(&dir);
+    assert!(!replay.is_recording());
+    client.set_transport(std::sync::Arc::new(replay));
+    let replayed = client.get_anime_details(30230, None).await.unwrap();
+    assert_eq!(replayed.show.title, recorded.show.title);
+    assert!(matches!(client.delete_anime_list_item(1).await, Err(MALError::NotFound { .. })));
+    let missing = client.get_anime_details(21, None).await.unwrap_err();
+    assert!(matches!(&missing, MALError::Transport { .. }));
+    assert!(missing.to_string().contains("MAL_RECORD=1"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+struct CookieTokenTransport;
+
+impl crate::HttpTransport for CookieTokenTransport {
+    fn send(&self, _: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+        Box::pin(async {
+            let mut res = crate::HttpResponse::new(
+                reqwest::StatusCode::OK,
+                r#"{"token_type": "Bearer", "expires_in": 2678400, "access_token": "secret-access", "refresh_token": "secret-refresh"}"#,
+            );
+            res.headers.insert(reqwest::header::SET_COOKIE, "MALSESSIONID=secret-cookie".parse().unwrap());
+            Ok(res)
+        })
+    }
+}
+
+#[tokio::test]
+async fn record_redacts_secrets() {
+    use crate::HttpTransport;
+
+    let dir = env::temp_dir().join(format!("lib-mal-oauth-fixtures-{}", std::process::id()));
+    let token_request = |refresh: &str| {
+        crate::HttpRequest::new(reqwest::Method::POST, "https://myanimelist.net/v1/oauth2/token")
+            .form(&[("grant_type", "refresh_token"), ("refresh_token", refresh)])
+    };
+    let recorder = ReplayTransport::record(&dir, CookieTokenTransport);
+    recorder.send(token_request("secret-refresh")).await.unwrap();
+
+    let path = recorder.fixture_path(&token_request("secret-refresh"));
+    assert_eq!(path, recorder.fixture_path(&token_request("another-refresh")));
+    let fixture = std::fs::read_to_string(&path).unwrap();
+    assert!(!fixture.contains("secret"), "{fixture}");
+    let replayed = ReplayTransport::replay(&dir).send(token_request("another-refresh")).await.unwrap();
+    assert!(replayed.body.contains("\"expires_in\":2678400"));
+    assert!(replayed.headers.get(reqwest::header::SET_COOKIE).is_none());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn programmable_mock() {
+    let mut mock = MockMALClient::with_access_token("token");
+    mock.on_get_anime_details(80)
+        .return_json(r#"{"id": 80, "title": "Mobile Suit Gundam", "main_picture": {}}"#);
+    mock.on_get_anime_details(81).return_error(|| MALError::AgeRestricted { context: None });
+    mock.on("get_forum_boards").return_json("{");
+    mock.on_delete_anime_list_item(5)
+        .delay(std::time::Duration::from_millis(20))
+        .return_json("null");
+
+    assert_eq!(mock.get_anime_details(80, AnimeFields::Title).await.unwrap().title, "Mobile Suit Gundam");
+    assert!(matches!(mock.get_anime_details(81, None).await, Err(MALError::AgeRestricted { .. })));
+    assert_eq!(mock.get_anime_details(30230, None).await.unwrap().id, 30230);
+    assert!(matches!(mock.get_forum_boards().await, Err(MALError::Parse { .. })));
+    let start = std::time::Instant::now();
+    mock.delete_anime_list_item(5).await.unwrap();
+    assert!(start.elapsed() >= std::time::Duration::from_millis(20));
+
+    assert_eq!(mock.calls().len(), 5);
+    assert_eq!(
+        mock.calls_to("get_anime_details"),
+        [
+            vec!["AnimeId(80)".to_owned(), "Some(AnimeFields(Title))".to_owned()],
+            vec!["AnimeId(81)".to_owned(), "None".to_owned()],
+            vec!["AnimeId(30230)".to_owned(), "None".to_owned()],
+        ]
+    );
+
+    mock.give_error = true;
+    assert!(matches!(mock.get_anime_details(80, None).await, Err(MALError::ServerError { .. })));
+    mock.give_error = false;
+    mock.reset();
+    mock.clear_calls();
+    assert!(mock.get_anime_details(80, None).await.is_err());
+    assert_eq!(mock.calls_to("get_anime_details").len(), 1);
+}
+
+#[tokio::test]
+async fn mock_clock() {
+    use futures_util::StreamExt;
+
+    let clock = MockClock::from_unix(1_700_000_000);
+    let tokens = Tokens {
+        access_token: String::from("token"),
+        refresh_token: String::from("refresh"),
+        expires_in: 3600,
+        today: 1_700_000_000,
+    };
+    assert!(!tokens.is_expired_at(clock.now()));
+    clock.advance(std::time::Duration::from_secs(3600));
+    assert!(tokens.is_expired_at(clock.now()));
+    assert_eq!(SeasonYear::at(clock.now()), SeasonYear::new(Season::Fall, 2023));
+
+    let transport = std::sync::Arc::new(FakeTransport { requests: std::sync::Mutex::default() });
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(transport.clone());
+    client.set_response_cache(CacheConfig::new(std::time::Duration::from_secs(60)));
+    client.set_clock(std::sync::Arc::new(clock.clone()));
+    client.get_anime_details(30230, None).await.unwrap();
+    client.get_anime_details(30230, None).await.unwrap();
+    assert_eq!(transport.requests.lock().unwrap().len(), 1);
+    clock.advance(std::time::Duration::from_secs(60));
+    client.get_anime_details(30230, None).await.unwrap();
+    assert_eq!(transport.requests.lock().unwrap().len(), 2);
+
+    struct Watching;
+    impl crate::HttpTransport for Watching {
+        fn send(&self, _: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            let body = r#"{"data": [{"node": {"id": 1, "title": "A", "main_picture": {}, "status": "currently_airing",
+                "broadcast": {"day_of_the_week": "monday", "start_time": "00:00"}}}], "paging": {}}"#;
+            Box::pin(async move { Ok(crate::HttpResponse::new(reqwest::StatusCode::OK, body)) })
+        }
+    }
+    client.set_transport(std::sync::Arc::new(Watching));
+    // a week of lead time means the first episode is due right away by the mock clock
+    let week = std::time::Duration::from_secs(7 * 24 * 3600);
+    let countdowns = client.countdowns_for_watchlist(week).await.unwrap();
+    let event = std::pin::pin!(countdowns).next().await.unwrap();
+    let airs_at = event.airs_at.duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+    assert!(airs_at > 1_700_000_060 && airs_at <= 1_700_000_060 + week.as_secs(), "{airs_at}");
+
+    let mut mock = MockMALClient::with_access_token("token");
+    mock.set_clock(std::sync::Arc::new(clock));
+    let status = mock.mark_completed(1, None).await.unwrap();
+    assert_eq!(status.finish_date.as_deref(), Some("2023-11-14"));
+}
+
+async fn watched_title<C: MALClientTrait + Sync>(client: &C, id: usize) -> Result<(String, bool), MALError> {
+    let details = client.get_anime_details(id, AnimeFields::Title).await?;
+    Ok((details.show.title.clone(), client.need_auth()))
+}
+
+///Only compiles if `T` can be cloned and shared between threads
+const fn assert_shareable<T: Send + Sync + Clone + 'static>() {}
+
+#[test]
+fn shareable_clients() {
+    assert_shareable::<MALClient>();
+}
+
+///Only compiles if `future` is `Send`
+fn assert_send<F: std::future::Future + Send>(future: F) -> F {
+    future
+}
+
+#[tokio::test]
+async fn generic_client() {
+    let mock = MockMALClient::with_access_token("token");
+    let (title, need_auth) = assert_send(watched_title(&mock, 30230)).await.unwrap();
+    assert_eq!(title, "Diamond no Ace: Second Season");
+    assert!(!need_auth);
+
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(std::sync::Arc::new(FakeTransport { requests: std::sync::Mutex::default() }));
+    assert_eq!(assert_send(watched_title(&client, 30230)).await.unwrap(), (title, need_auth));
+    assert!(matches!(watched_title(&client, 1).await, Err(MALError::Gone { .. })));
+}
+
+#[test]
+fn model_equality() {
+    let list = serde_json::from_str::<AnimeList>(include_str!("test-data/anime_list.json")).unwrap();
+    assert_eq!(list.clone(), list);
+    let mut other = list.clone();
+    other.data[0].node.title.push('!');
+    assert_ne!(other, list);
+
+    let mut seen = std::collections::HashSet::new();
+    assert!(seen.insert(list.data[0].node.clone()));
+    assert!(!seen.insert(list.data[0].node.clone()));
+    assert!(seen.insert(other.data[0].node.clone()));
+}
+
+#[cfg(not(feature = "minimal-models"))]
+#[test]
+fn serde_round_trip() {
+    fn strip_nulls(value: serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Object(map) => map
+                .into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, strip_nulls(v)))
+                .collect(),
+            serde_json::Value::Array(items) => items.into_iter().map(strip_nulls).collect(),
+            other => other,
+        }
+    }
+    fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug>(json: &str) -> T {
+        let mut original: serde_json::Value = serde_json::from_str(json).unwrap();
+        // the status counts are sent as strings but written as numbers
+        if let Some(serde_json::Value::Object(counts)) = original.pointer_mut("/statistics/status") {
+            for count in counts.values_mut() {
+                *count = count.as_str().unwrap().parse::<u64>().unwrap().into();
+            }
+        }
+        let model: T = serde_json::from_str(json).unwrap();
+        // through a string, `to_value` would widen the `f32` fields
+        let serialized = serde_json::to_string(&model).unwrap();
+        assert_eq!(strip_nulls(serde_json::from_str(&serialized).unwrap()), strip_nulls(original));
+        assert_eq!(serde_json::from_str::<T>(&serialized).unwrap(), model);
+        model
+    }
+
+    let details: AnimeDetails = round_trip(include_str!("test-data/anime_details.json"));
+    assert_eq!(details.extra["popularity"], 1381);
+    let counts = details.statistics.unwrap().status;
+    assert_eq!((counts.watching, counts.plan_to_watch, counts.total()), (7799, 9859, 57194));
+    let numeric: StatusCounts = serde_json::from_str(r#"{"watching": 3, "completed": "4"}"#).unwrap();
+    assert_eq!((numeric.watching, numeric.completed, numeric.dropped), (3, 4, 0));
+    assert!(serde_json::from_str::<StatusCounts>(r#"{"watching": "many"}"#).is_err());
+    round_trip::<AnimeDetails>(include_str!("test-data/one_piece_details.json"));
+    round_trip::<AnimeList>(include_str!("test-data/anime_list.json"));
+    round_trip::<AnimeList>(include_str!("test-data/anime_ranking.json"));
+    round_trip::<AnimeList>(include_str!("test-data/seasonal_anime.json"));
+}
+
+#[tokio::test]
+async fn find_anime() {
+    assert_eq!(titles::similarity("Fullmetal Alchemist: Brotherhood", "fullmetal alchemist brotherhood"), 1.0);
+    assert!(titles::similarity("Shingeki no Kyojin", "Shingeki no Kyoujin") == 1.0);
+    assert!(titles::similarity("fullmetal alchemist brotherhood", "Fullmetal Alchemist") > 0.5);
+    assert!(titles::similarity("fullmetal alchemist", "One Piece") < 0.2);
+    assert_eq!(titles::similarity("", ""), 0.0);
+
+    let mock = MockMALClient::with_access_token("token");
+    mock.on_get_anime_list("attack on titan").return_json(
+        r#"{"data": [
+            {"node": {"id": 1, "title": "Shingeki no Kyojin: Kuinaki Sentaku", "main_picture": {},
+                "alternative_titles": {"synonyms": [], "en": "Attack on Titan: No Regrets"}}},
+            {"node": {"id": 16498, "title": "Shingeki no Kyojin", "main_picture": {},
+                "alternative_titles": {"synonyms": ["AoT"], "en": "Attack on Titan", "ja": "進撃の巨人"}}},
+            {"node": {"id": 2, "title": "Attack on Tomato", "main_picture": {}}}
+        ], "paging": {}}"#,
+    );
+    let candidates = mock.find_anime_candidates("attack on titan").await.unwrap();
+    assert_eq!(candidates.iter().map(|c| c.anime.id.0).collect::<Vec<_>>(), [16498, 1, 2]);
+    assert_eq!((candidates[0].title.as_str(), candidates[0].confidence), ("Attack on Titan", 1.0));
+    let best = mock.find_anime("attack on titan").await.unwrap().unwrap();
+    assert_eq!(best.anime.id, 16498);
+    mock.on_get_anime_list("nothing").return_json(r#"{"data": [], "paging": {}}"#);
+    assert_eq!(mock.find_anime("nothing").await.unwrap(), None);
+}
+
+#[test]
+fn scrobble_parsing() {
+    let parsed = |name| {
+        let media = scrobble::parse(name).unwrap();
+        (media.title, media.season, media.episode)
+    };
+    let frieren = || (String::from("Frieren"), None, Some(12));
+    assert_eq!(parsed("[SubsPlease] Frieren - 12 (1080p) [ABCD1234].mkv"), frieren());
+    assert_eq!(parsed("/media/anime/Frieren/[Erai-raws] Frieren - 12v2 [1080p][HEVC].mkv"), frieren());
+    assert_eq!(parsed(r"C:\Anime\Frieren_-_12_[720p].mp4"), frieren());
+    assert_eq!(parsed("Frieren episode 12"), frieren());
+    assert_eq!(parsed("Frieren Ep.12"), frieren());
+    assert_eq!(parsed("Frieren 12"), frieren());
+    assert_eq!(parsed("One.Piece.E1071.1080p.WEB.x264.mkv"), ("One Piece".to_owned(), None, Some(1071)));
+    assert_eq!(parsed("Shingeki no Kyojin S03E05"), ("Shingeki no Kyojin".to_owned(), Some(3), Some(5)));
+    assert_eq!(parsed("Re:Zero - Starting Life in Another World - 05"), ("Re:Zero - Starting Life in Another World".to_owned(), None, Some(5)));
+    assert_eq!(parsed("Mob Psycho 100 - 03"), ("Mob Psycho 100".to_owned(), None, Some(3)));
+    assert_eq!(parsed("Fate/Zero - 03"), ("Fate/Zero".to_owned(), None, Some(3)));
+    assert_eq!(parsed("Kimi no Na wa. (2016) [BD 1080p].mkv"), ("Kimi no Na wa.".to_owned(), None, None));
+    assert_eq!(scrobble::parse("Shingeki no Kyojin S02E01").unwrap().search_query(), "Shingeki no Kyojin Season 2");
+    assert_eq!(scrobble::parse("[Group] (1080p).mkv"), None);
+}
+
+#[tokio::test]
+async fn scrobble_updates_progress() {
+    struct Api {
+        updates: std::sync::Mutex<Vec<crate::HttpRequest>>,
+    }
+    impl crate::HttpTransport for Api {
+        fn send(&self, request: crate::HttpRequest) -> futures_util::future::BoxFuture<'_, Result<crate::HttpResponse, MALError>> {
+            Box::pin(async move {
+                let body = if request.url.contains("/anime?q=") {
+                    r#"{"data": [{"node": {"id": 52991, "title": "Sousou no Frieren", "main_picture": {},
+                        "alternative_titles": {"synonyms": [], "en": "Frieren: Beyond Journey's End"}}}], "paging": {}}"#
+                } else if request.method == reqwest::Method::PUT {
+                    self.updates.lock().unwrap().push(request);
+                    r#"{"status": "watching", "num_episodes_watched": 12}"#
+                } else {
+                    r#"{"id": 52991, "title": "Sousou no Frieren", "main_picture": {}, "num_episodes": 28,
+                        "my_list_status": {"status": "watching", "num_episodes_watched": 10}}"#
+                };
+                Ok(crate::HttpResponse::new(reqwest::StatusCode::OK, body))
+            })
+        }
+    }
+    let api = std::sync::Arc::new(Api { updates: std::sync::Mutex::default() });
+    let mut client = MALClient::with_access_token("token");
+    client.set_transport(api.clone());
+
+    let report = client.scrobble("[SubsPlease] Sousou no Frieren - 12 (1080p).mkv").await.unwrap().unwrap();
+    assert_eq!(report.matched.unwrap().anime.id, 52991);
+    assert_eq!(report.counted.unwrap().num_episodes_watched, Some(12));
+    assert_eq!(api.updates.lock().unwrap()[0].form.as_deref(), Some(&[("num_watched_episodes".to_owned(), "12".to_owned())][..]));
+    // already watched
+    let report = client.scrobble("Sousou no Frieren - 09").await.unwrap().unwrap();
+    assert!(report.counted.is_none());
+    // not a confident match
+    let report = client.scrobble("Dungeon Meshi - 13").await.unwrap().unwrap();
+    assert!(report.matched.is_some() && report.counted.is_none());
+    assert_eq!(api.updates.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn airing_schedule() {
+    let anime = |id: usize, status: &str, day: &str, time: &str| {
+        serde_json::from_value::<AnimeDetails>(serde_json::json!({
+            "id": id, "title": format!("Anime {id}"), "main_picture": {}, "status": status,
+            "broadcast": {"day_of_the_week": day, "start_time": time}
+        }))
+        .unwrap()
+    };
+    let season = || {
+        vec![
+            anime(1, "currently_airing", "saturday", "23:30"),
+            anime(2, "currently_airing", "monday", "01:00"),
+            anime(3, "not_yet_aired", "sunday", "17:00"),
+            anime(4, "finished_airing", "sunday", "17:00"),
+        ]
+    };
+    // 2024-07-01, a Monday, at 00:00 UTC
+    let now = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_719_792_000);
+    let slots = |schedule: &AiringSchedule, day| {
+        schedule.day(day).iter().map(|s| (s.anime.id.0, s.hour, s.minute)).collect::<Vec<_>>()
+    };
+
+    let utc = AiringSchedule::from_anime(season(), UtcOffset::UTC, now);
+    assert_eq!(slots(&utc, Weekday::Saturday), [(1, 14, 30)]);
+    assert_eq!(slots(&utc, Weekday::Sunday), [(3, 8, 0), (2, 16, 0)]);
+    assert_eq!(utc.days.len(), 2);
+    let first = &utc.day(Weekday::Saturday)[0];
+    // the Saturday after `now`
+    assert_eq!(first.next_airing, Some(now + std::time::Duration::from_secs(5 * 86400 + 14 * 3600 + 30 * 60)));
+    assert_eq!(utc.day(Weekday::Sunday)[0].next_airing, None);
+
+    let pacific = AiringSchedule::from_anime(season(), UtcOffset::from_hours(-8), now);
+    assert_eq!(slots(&pacific, Weekday::Saturday), [(1, 6, 30)]);
+    assert_eq!(slots(&pacific, Weekday::Sunday), [(3, 0, 0), (2, 8, 0)]);
+    assert_eq!(UtcOffset::from_minutes(-330).to_string(), "-05:30");
+    assert_eq!(UtcOffset::JST.to_string(), "+09:00");
+}
+
+#[test]
+fn airing_calendar() {
+    let anime = |id: usize, title: &str, extra: serde_json::Value| {
+        let mut anime = serde_json::json!({
+            "id": id, "title": title, "main_picture": {}, "status": "currently_airing",
+            "start_date": "2024-06-29", "broadcast": {"day_of_the_week": "saturday", "start_time": "23:30"}
+        });
+        anime.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        serde_json::from_value::<AnimeDetails>(anime).unwrap()
+    };
+    let long = "Long, long title; ".repeat(5);
+    let season = vec![
+        anime(1, "Anime", serde_json::json!({"num_episodes": 12, "average_episode_duration": 1440})),
+        anime(2, &long, serde_json::json!({})),
+        anime(3, "No broadcast", serde_json::json!({"broadcast": null})),
+    ];
+    // 2024-07-01, a Monday, at 00:00 UTC
+    let now = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_719_792_000);
+    let schedule = AiringSchedule::from_anime(season, UtcOffset::UTC, now);
+    let ics = schedule::to_ics(schedule.days.values().flatten());
+
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+    assert!(ics.contains("UID:anime-1@lib-mal\r\nDTSTAMP:20240706T143000Z\r\nDTSTART:20240706T143000Z\r\nDTEND:20240706T145400Z\r\n"));
+    // the episode on the 29th already aired
+    assert!(ics.contains("RRULE:FREQ=WEEKLY;COUNT=11\r\n"));
+    assert!(ics.contains("RRULE:FREQ=WEEKLY\r\nSUMMARY:Long\\, long title\\;"));
+    assert!(ics.lines().all(|line| line.len() <= 75));
+    let unfolded = ics.replace("\r\n ", "");
+    assert!(unfolded.contains(&format!("SUMMARY:{}\r\n", long.replace(',', "\\,").replace(';', "\\;"))));
+}
+
+#[test]
+fn episodes_aired() {
+    let anime = |status: &str, start: &str, broadcast: Option<&str>, episodes: usize| {
+        serde_json::from_value::<AnimeDetails>(serde_json::json!({
+            "id": 1, "title": "Anime", "main_picture": {}, "status": status,
+            "start_date": start, "num_episodes": episodes,
+            "broadcast": broadcast.map(|time| serde_json::json!({"day_of_the_week": "saturday", "start_time": time}))
+        }))
+        .unwrap()
+    };
+    // 2024-07-22, a Monday, at 00:00 JST
+    let now = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_721_574_000);
+
+    // aired on the 6th, 13th, and 20th
+    assert_eq!(anime("currently_airing", "2024-07-06", Some("23:30"), 12).episodes_aired(now), Some(3));
+    // starts counting from the first Saturday
+    assert_eq!(anime("currently_airing", "2024-07-05", Some("23:30"), 0).episodes_aired(now), Some(3));
+    assert_eq!(anime("currently_airing", "2024-07-06", None, 0).episodes_aired(now), Some(3));
+    assert_eq!(anime("currently_airing", "2024-07-06", Some("23:30"), 2).episodes_aired(now), Some(2));
+    // the first episode hasn't aired yet
+    assert_eq!(anime("currently_airing", "2024-07-27", Some("23:30"), 12).episodes_aired(now), Some(0));
+    assert_eq!(anime("not_yet_aired", "2024-07-06", None, 12).episodes_aired(now), Some(0));
+    assert_eq!(anime("finished_airing", "2020-01-04", None, 12).episodes_aired(now), Some(12));
+    assert_eq!(anime("currently_airing", "2024-07", None, 12).episodes_aired(now), None);
+
+    let progress = AiringProgress {
+        anime: anime("currently_airing", "2024-07-06", Some("23:30"), 12),
+        list_status: serde_json::from_value(serde_json::json!({"num_episodes_watched": 1})).unwrap(),
+        episodes_aired: 3,
+    };
+    assert_eq!(progress.behind(), 2);
+}
+
+#[test]
+fn plan_to_watch_now_airing() {
+    let node = |id: usize, status: &str, start: &str| {
+        serde_json::from_value::<crate::model::ListNode>(serde_json::json!({"node": {
+            "id": id, "title": format!("Anime {id}"), "main_picture": {}, "status": status, "start_date": start,
+            "broadcast": {"day_of_the_week": "saturday", "start_time": "23:30"}
+        }}))
+        .unwrap()
+    };
+    let plan_to_watch = [node(1, "", ""), node(2, "", ""), node(3, "", ""), node(4, "", "")];
+    let season = vec![
+        node(1, "currently_airing", "2024-07-06"),
+        node(2, "not_yet_aired", "2024-09-28"),
+        node(3, "currently_airing", "2024-07-13"),
+        node(3, "currently_airing", "2024-07-13"),
+        node(5, "currently_airing", "2024-07-06"),
+    ];
+    // 2024-07-22, a Monday, at 00:00 JST
+    let now = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_721_574_000);
+    let airing = crate::model::now_airing(&plan_to_watch, season, now);
+
+    assert_eq!(airing.iter().map(|a| a.anime.id.0).collect::<Vec<_>>(), [3, 1]);
+    // 23:30 JST on the 13th
+    let first = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_720_881_000);
+    assert_eq!(airing[0].first_episode_at, Some(first));
+    assert_eq!(airing[0].episodes_aired, Some(2));
+    assert_eq!(airing[1].episodes_aired, Some(3));
+}
+
+#[cfg(not(feature = "minimal-models"))]
+#[test]
+fn recommend_from_list() {
+    let seed = |id: usize, recs: &[(usize, usize)]| {
+        let recs: Vec<_> = recs
+            .iter()
+            .map(|(id, n)| serde_json::json!({"node": {"id": id, "title": format!("Anime {id}"), "main_picture": {}}, "num_recommendations": n}))
+            .collect();
+        serde_json::from_value::<AnimeDetails>(serde_json::json!({
+            "id": id, "title": format!("Anime {id}"), "main_picture": {}, "recommendations": recs
+        }))
+        .unwrap()
+    };
+    let seeds = [
+        (10, seed(1, &[(10, 3), (11, 1), (2, 50)])),
+        (8, seed(2, &[(11, 4), (12, 2)])),
+        // unscored counts as 1
+        (0, seed(3, &[(12, 5)])),
+    ];
+    let on_list = std::collections::HashSet::from([AnimeId(1), AnimeId(2), AnimeId(3)]);
+    let ranked = crate::model::rank_recommendations(&seeds, &on_list);
+    let ranked: Vec<_> = ranked.iter().map(|r| (r.anime.id.0, r.weight, r.because.clone())).collect();
+    assert_eq!(
+        ranked,
+        [
+            (11, 42, vec![AnimeId(1), AnimeId(2)]),
+            (10, 30, vec![AnimeId(1)]),
+            (12, 21, vec![AnimeId(2), AnimeId(3)]),
+        ]
+    );
+}
+
+#[test]
+fn compare_with_user() {
+    let list = |entries: &[(usize, u8)]| -> Vec<crate::model::ListNode> {
+        entries
+            .iter()
+            .map(|(id, score)| {
+                serde_json::from_value(serde_json::json!({
+                    "node": {"id": id, "title": format!("Anime {id}"), "main_picture": {}},
+                    "list_status": {"status": "completed", "score": score}
+                }))
+                .unwrap()
+            })
+            .collect()
+    };
+    let mine = list(&[(1, 10), (2, 8), (3, 6), (4, 0), (5, 7)]);
+    let theirs = list(&[(1, 9), (2, 7), (3, 5), (4, 8), (6, 3)]);
+    let report = AffinityReport::from_lists(&mine, &theirs);
+    assert_eq!(report.shared.iter().map(|s| s.anime.id.0).collect::<Vec<_>>(), [1, 2, 3, 4]);
+    assert_eq!(report.only_mine.iter().map(|a| a.id.0).collect::<Vec<_>>(), [5]);
+    assert_eq!(report.only_theirs.iter().map(|a| a.id.0).collect::<Vec<_>>(), [6]);
+    // the unscored anime 4 is left out of the correlations
+    assert!((report.pearson.unwrap() - 1.0).abs() < 1e-9);
+    assert!((report.spearman.unwrap() - 1.0).abs() < 1e-9);
+    assert!((report.affinity().unwrap() - 100.0).abs() < 1e-6);
+    assert_eq!(report.mean_difference, Some(1.0));
+    assert_eq!(report.mean_score_mine, Some(7.75));
+    assert_eq!(report.mean_score_theirs, Some(6.4));
+
+    let opposite = AffinityReport::from_lists(&list(&[(1, 10), (2, 5), (3, 1)]), &list(&[(1, 2), (2, 6), (3, 9)]));
+    assert!((opposite.spearman.unwrap() + 1.0).abs() < 1e-9);
+    assert!(opposite.pearson.unwrap() < -0.9);
+    // one shared score isn't enough for a correlation
+    assert_eq!(AffinityReport::from_lists(&list(&[(1, 10)]), &list(&[(1, 2)])).pearson, None);
+}
+
+#[test]
+fn watcher_events() {
+    use crate::watcher::{detail_events, list_events, ListEvent};
+
+    let status = |status: &str, watched: usize| -> ListStatus {
+        serde_json::from_value(serde_json::json!({"status": status, "num_episodes_watched": watched})).unwrap()
+    };
+    let before = crate::sync::ListSnapshot::from([(AnimeId(1), status("watching", 3)), (AnimeId(2), status("watching", 11))]);
+    let after = crate::sync::ListSnapshot::from([(AnimeId(2), status("completed", 12)), (AnimeId(3), status("plan_to_watch", 0))]);
+    let events = list_events(&before, &after);
+    assert!(matches!(events[0], ListEvent::Removed { id: AnimeId(1) }));
+    assert!(matches!(&events[1], ListEvent::StatusChanged { id: AnimeId(2), to: Some(to), .. } if to == "completed"));
+    assert!(matches!(events[2], ListEvent::ProgressChanged { id: AnimeId(2), from: 11, to: 12 }));
+    assert!(matches!(events[3], ListEvent::Added { id: AnimeId(3), .. }));
+    assert_eq!(events.len(), 4);
+    assert!(list_events(&after, &after).is_empty());
+
+    let anime = |episodes: Option<usize>, related: &[(usize, &str)]| {
+        let related: Vec<_> = related
+            .iter()
+            .map(|(id, relation)| serde_json::json!({
+                "node": {"id": id, "title": format!("Anime {id}"), "main_picture": {}},
+                "relation_type": relation, "relation_type_formatted": relation
+            }))
+            .collect();
+        serde_json::from_value::<AnimeDetails>(serde_json::json!({
+            "id": 1, "title": "Anime 1", "main_picture": {}, "num_episodes": episodes, "related_anime": related
+        }))
+        .unwrap()
+    };
+    let events = detail_events(&anime(None, &[(2, "prequel")]), &anime(Some(12), &[(2, "prequel"), (3, "sequel"), (4, "side_story")]));
+    assert!(matches!(events[0], ListEvent::EpisodeCountChanged { id: AnimeId(1), from: None, to: Some(12) }));
+    assert!(matches!(&events[1], ListEvent::NewSeasonAnnounced { id: AnimeId(1), sequel } if sequel.id == 3));
+    assert_eq!(events.len(), 2);
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn blocking_client() {
+    let client = crate::blocking::MALClient::new(ClientBuilder::new().public_client(true).build_no_refresh()).unwrap();
+    let res = client.get_anime_details(21, None);
+    assert!(matches!(res, Err(MALError::Auth { error, .. }) if error == "missing_client_id"));
+    assert_eq!(client.auth_state(), AuthState::NoToken);
 }
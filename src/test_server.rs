@@ -0,0 +1,144 @@
+//! A local HTTP server answering like the MAL API with the fixtures in `test-data`, so tests can
+//! run the real `MALClient` and its HTTP stack without a token or network access
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+use crate::{ClientBuilder, MALClient};
+
+///The fixture served for each API path, the query string is ignored
+const ROUTES: &[(&str, &str)] = &[
+    ("/v2/anime", include_str!("test-data/anime_list.json")),
+    (
+        "/v2/anime/21",
+        include_str!("test-data/one_piece_details.json"),
+    ),
+    (
+        "/v2/anime/30230",
+        include_str!("test-data/anime_details.json"),
+    ),
+    (
+        "/v2/anime/ranking",
+        include_str!("test-data/anime_ranking.json"),
+    ),
+    (
+        "/v2/anime/season/2017/summer",
+        include_str!("test-data/seasonal_anime.json"),
+    ),
+    (
+        "/v2/users/@me/animelist",
+        include_str!("test-data/anime_list.json"),
+    ),
+];
+
+///Serves `ROUTES` on a random local port until it's dropped
+pub(crate) struct MockServer {
+    url: String,
+    ///The method and path with the query string of every request received
+    pub(crate) requests: Arc<Mutex<Vec<String>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl MockServer {
+    pub(crate) fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("binding a local port should work");
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let (log, stop, base) = (requests.clone(), stopped.clone(), url.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let (log, base) = (log.clone(), base.clone());
+                    thread::spawn(move || serve(stream, &log, &base));
+                }
+            }
+        });
+        Self {
+            url,
+            requests,
+            stopped,
+        }
+    }
+
+    ///Returns a client with an access token that sends its API requests to this server
+    pub(crate) fn client(&self) -> MALClient {
+        self.builder().build_no_refresh()
+    }
+
+    ///Returns a builder with an access token that sends its API requests to this server
+    pub(crate) fn builder(&self) -> ClientBuilder {
+        ClientBuilder::new()
+            .access_token(String::from("token"))
+            .api_base_url(self.url.clone())
+            .auth_base_url(self.url.clone())
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // wakes up the accept loop so it sees the flag
+        let _ = TcpStream::connect(self.url.trim_start_matches("http://").trim_end_matches('/'));
+    }
+}
+
+///Answers the requests on one connection until the client closes it, the page links in the
+///fixtures point at `base` like the ones of the API point at the API
+fn serve(stream: TcpStream, log: &Mutex<Vec<String>>, base: &str) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut writer = stream;
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+            return;
+        }
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).unwrap_or(0) == 0 {
+                return;
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = (
+            parts.next().unwrap_or_default(),
+            parts.next().unwrap_or_default(),
+        );
+        log.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(format!("{method} {target}"));
+        let path = target.split('?').next().unwrap_or_default();
+        let (status, body) = ROUTES.iter().find(|(route, _)| *route == path).map_or(
+            ("404 Not Found", r#"{"error": "not_found"}"#.to_owned()),
+            |(_, body)| ("200 OK", body.replace("https://api.myanimelist.net/", base)),
+        );
+        let response = format!(
+            "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        if writer.write_all(response.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
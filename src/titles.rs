@@ -0,0 +1,201 @@
+//! Helpers for comparing anime titles written in different ways
+//!
+//! The same anime is often written with macrons or without (`Shōnen`, `Shounen`, `Shonen`), in
+//! full width characters, or with a season marker (`2nd Season`, `Part 2`, `II`). The functions
+//! here turn titles into keys that can be compared with each other, they aren't meant for
+//! displaying.
+//!
+//! # Example
+//!
+//! ```
+//! use lib_mal::titles::{normalize, strip_season_marker};
+//!
+//! assert_eq!(normalize("Yūki Yūna wa Yūsha de Aru"), normalize("Yuuki Yuuna wa Yuusha de Aru"));
+//! assert_eq!(strip_season_marker("Boku no Hero Academia 2nd Season"), "Boku no Hero Academia");
+//! ```
+
+use std::collections::HashMap;
+
+///Returns a lowercase key for `title` with the width, romanization, and punctuation normalized
+///
+///Doesn't remove season markers, see [`strip_season_marker`] for that.
+pub fn normalize(title: &str) -> String {
+    let title: String = normalize_width(title)
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'ā' | 'â' => 'a',
+            'ī' | 'î' => 'i',
+            'ū' | 'û' => 'u',
+            'ē' | 'ê' => 'e',
+            'ō' | 'ô' => 'o',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect();
+    title
+        .split_whitespace()
+        .map(|word| match word {
+            // the particle を is romanized as both "wo" and "o"
+            "wo" => String::from("o"),
+            word => normalize_romanization(word),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+///Returns how similar two titles are, from 0 for nothing in common to 1 for the same normalized
+///title
+///
+///Compares the pairs of adjacent characters of the [`normalize`]d titles, so small differences
+///in spelling or word order still score high.
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let (a, b) = (normalize(a), normalize(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+    let (a, b) = (bigrams(&a), bigrams(&b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let mut unmatched: HashMap<(char, char), usize> = HashMap::new();
+    for pair in &b {
+        *unmatched.entry(*pair).or_default() += 1;
+    }
+    let mut shared = 0;
+    for pair in &a {
+        if let Some(n) = unmatched.get_mut(pair).filter(|n| **n > 0) {
+            *n -= 1;
+            shared += 1;
+        }
+    }
+    (2 * shared) as f32 / (a.len() + b.len()) as f32
+}
+
+fn bigrams(key: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = key.chars().collect();
+    chars.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+///Converts full width ASCII characters and the ideographic space to their regular counterparts
+pub fn normalize_width(title: &str) -> String {
+    title
+        .chars()
+        .map(|c| match c {
+            '\u{3000}' => ' ',
+            '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+            c => c,
+        })
+        .collect()
+}
+
+///Collapses long vowels written as `ou` or doubled vowels into a single vowel, so `shounen`,
+///`shoonen`, and `shonen` all become `shonen`. Expects a lowercase word without macrons.
+pub fn normalize_romanization(word: &str) -> String {
+    let mut res = String::with_capacity(word.len());
+    for c in word.chars() {
+        let long = match (res.chars().last(), c) {
+            (Some('o'), 'u') => true,
+            (Some(prev), c) => prev == c && matches!(c, 'a' | 'i' | 'u' | 'e' | 'o'),
+            _ => false,
+        };
+        if !long {
+            res.push(c);
+        }
+    }
+    res
+}
+
+///Removes season markers like `Season 2`, `2nd Season`, `The Final Season`, `Part 2`, `Cour 2`,
+///`S2`, or a roman numeral from the end of `title`
+///
+///Trailing numbers without a marker are kept since they're often part of the title, like in
+///`Mob Psycho 100` or `Steins;Gate 0`.
+pub fn strip_season_marker(title: &str) -> &str {
+    let mut title = title.trim();
+    loop {
+        let words: Vec<&str> = title.split_whitespace().collect();
+        let len = marker_len(&words);
+        if len == 0 || len == words.len() {
+            return title;
+        }
+        let mut rest = &words[..words.len() - len];
+        if rest.len() > 1 && rest[rest.len() - 1].eq_ignore_ascii_case("the") {
+            rest = &rest[..rest.len() - 1];
+        }
+        let last = rest[rest.len() - 1];
+        // the marker always starts at a word boundary, so the remaining title is a prefix
+        let end = last.as_ptr() as usize - title.as_ptr() as usize + last.len();
+        title = title[..end].trim_end_matches([':', '-', ',', ' ']);
+    }
+}
+
+///Returns how many words at the end of `words` form a season marker
+fn marker_len(words: &[&str]) -> usize {
+    let word = |i: usize| {
+        words
+            .len()
+            .checked_sub(i + 1)
+            .map(|i| {
+                words[i]
+                    .trim_matches(|c: char| !c.is_alphanumeric())
+                    .to_lowercase()
+            })
+            .unwrap_or_default()
+    };
+    let (last, second_last) = (word(0), word(1));
+    if matches!(second_last.as_str(), "season" | "part" | "cour") && is_number(&last) {
+        return 2;
+    }
+    if matches!(last.as_str(), "season" | "part" | "cour") && is_ordinal(&second_last) {
+        return 2;
+    }
+    let is_short_season = last
+        .strip_prefix('s')
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+    if is_short_season || (is_roman(&last) && !matches!(last.as_str(), "i" | "v" | "x")) {
+        return 1;
+    }
+    0
+}
+
+fn is_number(word: &str) -> bool {
+    (!word.is_empty() && word.chars().all(|c| c.is_ascii_digit()))
+        || is_roman(word)
+        || matches!(
+            word,
+            "one" | "two" | "three" | "four" | "five" | "six" | "seven" | "eight" | "nine" | "ten"
+        )
+}
+
+fn is_ordinal(word: &str) -> bool {
+    let numbered = ["st", "nd", "rd", "th"].iter().any(|suffix| {
+        word.strip_suffix(suffix)
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+    });
+    numbered
+        || matches!(
+            word,
+            "first"
+                | "second"
+                | "third"
+                | "fourth"
+                | "fifth"
+                | "sixth"
+                | "seventh"
+                | "eighth"
+                | "ninth"
+                | "tenth"
+                | "final"
+        )
+}
+
+fn is_roman(word: &str) -> bool {
+    matches!(
+        word,
+        "i" | "ii" | "iii" | "iv" | "v" | "vi" | "vii" | "viii" | "ix" | "x"
+    )
+}
@@ -0,0 +1,44 @@
+//! Spans for the `tracing` feature. Without the feature `instrument` just awaits the request, and
+//! the events in the client are compiled out, so they cost nothing.
+
+use std::future::Future;
+
+use reqwest::Method;
+
+///Runs `request` in a `mal_request` span with the endpoint, method, URL, and the anime id if the
+///URL has one
+#[cfg(feature = "tracing")]
+pub(crate) async fn instrument<F: Future>(
+    endpoint: &str,
+    method: &Method,
+    url: &str,
+    request: F,
+) -> F::Output {
+    use tracing::Instrument;
+
+    let span = tracing::debug_span!(
+        "mal_request",
+        endpoint,
+        %method,
+        url,
+        anime_id = anime_id(url),
+    );
+    request.instrument(span).await
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) async fn instrument<F: Future>(
+    _endpoint: &str,
+    _method: &Method,
+    _url: &str,
+    request: F,
+) -> F::Output {
+    request.await
+}
+
+///Returns the id in API URLs like `.../v2/anime/30230/my_list_status`
+#[cfg(feature = "tracing")]
+fn anime_id(url: &str) -> Option<usize> {
+    let (_, rest) = url.split_once("/v2/anime/")?;
+    rest.split(['/', '?']).next()?.parse().ok()
+}
@@ -0,0 +1,164 @@
+use futures_util::future::BoxFuture;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Method, StatusCode};
+
+use crate::rt;
+use crate::MALError;
+
+///Sends the HTTP requests of a `MALClient`
+///
+///The client uses a [`ReqwestTransport`] by default. Implement this trait to send the requests
+///with another HTTP client, through a middleware stack, or to answer them with canned responses
+///in tests. Rate limiting, retries, token refreshes, and caching all happen before a request
+///reaches the transport.
+///
+///# Example
+///
+///```
+/// use futures_util::future::BoxFuture;
+/// use lib_mal::{ClientBuilder, HttpRequest, HttpResponse, HttpTransport, MALClient, MALError};
+/// use reqwest::StatusCode;
+///
+/// struct Offline;
+///
+/// impl HttpTransport for Offline {
+///     fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, MALError>> {
+///         Box::pin(async move {
+///             Ok(HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE, format!("{} is offline", request.url)))
+///         })
+///     }
+/// }
+///
+/// let client: MALClient = ClientBuilder::new().transport(Offline).build_no_refresh();
+///```
+pub trait HttpTransport: Send + Sync {
+    ///Sends `request` and reads the whole response. Responses with an error status are still
+    ///`Ok`, failing to get a response at all should be reported as `MALError::Transport`.
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, MALError>>;
+
+    ///Sends `request` and returns the status and the body as raw bytes, used for downloading
+    ///pictures. The default goes through `send`, which only works for text bodies, so transports
+    ///that download pictures have to override it.
+    fn send_bytes(
+        &self,
+        request: HttpRequest,
+    ) -> BoxFuture<'_, Result<(StatusCode, Vec<u8>), MALError>> {
+        Box::pin(async move {
+            let res = self.send(request).await?;
+            Ok((res.status, res.body.into_bytes()))
+        })
+    }
+}
+
+///A request the client sends, with the auth headers already set
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    ///Parameters sent as an `application/x-www-form-urlencoded` body
+    pub form: Option<Vec<(String, String)>>,
+}
+
+impl HttpRequest {
+    pub fn new(method: Method, url: &str) -> Self {
+        Self {
+            method,
+            url: url.to_owned(),
+            headers: HeaderMap::new(),
+            form: None,
+        }
+    }
+
+    pub(crate) fn form(mut self, params: &[(&str, impl AsRef<str>)]) -> Self {
+        self.form = Some(
+            params
+                .iter()
+                .map(|(k, v)| ((*k).to_owned(), v.as_ref().to_owned()))
+                .collect(),
+        );
+        self
+    }
+}
+
+///A response with its whole body
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn new(status: StatusCode, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: body.into(),
+        }
+    }
+}
+
+///Sends the requests with `reqwest`, the default transport
+///
+///`ClientBuilder` settings that configure the HTTP client, like `default_header` and
+///`accept_language`, only apply to this transport.
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    pub const fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, MALError>> {
+        rt::boxed(async move {
+            let network = |source| MALError::Network {
+                source,
+                context: None,
+            };
+            let mut req = self
+                .client
+                .request(request.method, &request.url)
+                .headers(request.headers);
+            if let Some(form) = &request.form {
+                req = req.form(form);
+            }
+            let res = req.send().await.map_err(network)?;
+            let status = res.status();
+            let headers = res.headers().clone();
+            let body = res.text().await.map_err(network)?;
+            Ok(HttpResponse {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+
+    fn send_bytes(
+        &self,
+        request: HttpRequest,
+    ) -> BoxFuture<'_, Result<(StatusCode, Vec<u8>), MALError>> {
+        rt::boxed(async move {
+            let network = |source| MALError::Network {
+                source,
+                context: None,
+            };
+            let res = self
+                .client
+                .request(request.method, &request.url)
+                .headers(request.headers)
+                .send()
+                .await
+                .map_err(network)?;
+            let status = res.status();
+            let body = res.bytes().await.map_err(network)?;
+            Ok((status, body.to_vec()))
+        })
+    }
+}
@@ -0,0 +1,67 @@
+//! Timing episodes as they're watched, see
+//! [`MALClient::begin_watch_session`](crate::MALClient::begin_watch_session)
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! // the player started episode 4
+//! client.begin_watch_session(21, 4);
+//! // ...and stopped
+//! if let Some(report) = client.end_watch_session().await? {
+//!     if report.counted.is_some() {
+//!         println!("marked episode {} as watched", report.episode);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use crate::model::{AnimeId, ListStatus};
+use crate::rt::Instant;
+
+///Share of an episode that has to be watched for it to count, see
+///`ClientBuilder::watch_threshold`
+pub const DEFAULT_WATCH_THRESHOLD: f32 = 0.8;
+
+///An episode that's being watched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchSession {
+    ///Id of the anime
+    pub id: AnimeId,
+    pub episode: usize,
+    pub started: Instant,
+}
+
+impl WatchSession {
+    pub(crate) fn new(id: AnimeId, episode: usize) -> Self {
+        Self {
+            id,
+            episode,
+            started: Instant::now(),
+        }
+    }
+}
+
+///The outcome of `MALClient::end_watch_session`
+#[derive(Debug, Clone)]
+pub struct WatchReport {
+    ///Id of the anime
+    pub id: AnimeId,
+    pub episode: usize,
+    pub elapsed: Duration,
+    ///The updated list entry if the episode counted as watched and wasn't already
+    pub counted: Option<ListStatus>,
+}
+
+///Returns true if `elapsed` is at least `threshold` of an episode that's `episode_secs` long.
+///Episodes of unknown length never count.
+pub(crate) fn reached_threshold(elapsed: Duration, episode_secs: usize, threshold: f32) -> bool {
+    #[allow(clippy::cast_precision_loss)]
+    let needed = episode_secs as f32 * threshold;
+    episode_secs > 0 && elapsed.as_secs_f32() >= needed
+}
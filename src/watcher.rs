@@ -0,0 +1,246 @@
+//! Polling the user's list for changes, e.g. for bots that post when someone finishes a show
+//!
+//! A [`ListWatcher`] fetches the list every `interval`, compares it with the previous poll, and
+//! sends a [`ListEvent`] for every change. It can also watch the details of some anime for
+//! changes in their episode count and for newly announced sequels. The first poll only records
+//! the current state.
+//!
+//! The details go through the response cache, if it's enabled, so changes show up once the cached
+//! copy expires.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::watcher::{ListEvent, ListWatcher};
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! # async fn test() -> Result<(), MALError> {
+//! let client = Arc::new(MALClient::with_access_token("[YOUR_SECRET_HERE]"));
+//! let mut watcher = ListWatcher::spawn_with_anime(client, Duration::from_secs(600), &[52991]);
+//! while let Some(event) = watcher.next().await {
+//!     match event {
+//!         ListEvent::StatusChanged { id, to, .. } => println!("{id} is now {to:?}"),
+//!         ListEvent::NewSeasonAnnounced { sequel, .. } => println!("{} was announced", sequel.title),
+//!         _ => {}
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::{self, Either};
+use tokio::sync::mpsc;
+
+use crate::cancel::CancellationToken;
+use crate::model::fields::AnimeFields;
+use crate::model::options::RelationType;
+use crate::model::{Anime, AnimeDetails, AnimeId, ListStatus};
+use crate::rt;
+use crate::sync::ListSnapshot;
+use crate::{MALClient, MALClientTrait, MALError};
+
+///How many events can wait in the channel before the watcher stops polling until they're read
+const EVENT_BUFFER: usize = 64;
+
+///A change found by a `ListWatcher`
+#[derive(Debug)]
+pub enum ListEvent {
+    ///The anime was added to the list
+    Added {
+        id: AnimeId,
+        status: ListStatus,
+    },
+    Removed {
+        id: AnimeId,
+    },
+    ///The list status of the anime changed, like from `watching` to `completed`
+    StatusChanged {
+        id: AnimeId,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    ///The number of watched episodes on the list changed
+    ProgressChanged {
+        id: AnimeId,
+        from: usize,
+        to: usize,
+    },
+    ///A watched anime's episode count changed, e.g. once MAL knows how long it is
+    EpisodeCountChanged {
+        id: AnimeId,
+        from: Option<usize>,
+        to: Option<usize>,
+    },
+    ///A sequel of a watched anime was added to MAL
+    NewSeasonAnnounced {
+        id: AnimeId,
+        sequel: Anime,
+    },
+    ///A poll failed, the watcher tries again after the interval
+    PollFailed(MALError),
+}
+
+///Polls the user's list in a background task, see the module docs. The task stops when the
+///watcher is dropped or stopped with `stop`.
+#[derive(Debug)]
+pub struct ListWatcher {
+    events: mpsc::Receiver<ListEvent>,
+    cancel: CancellationToken,
+}
+
+impl ListWatcher {
+    ///Starts polling the user's list every `interval`. Has to be called from a tokio runtime.
+    pub fn spawn(client: Arc<MALClient>, interval: Duration) -> Self {
+        Self::spawn_with_anime(client, interval, &[] as &[AnimeId])
+    }
+
+    ///Same as `spawn`, but also watches the details of `anime` for `EpisodeCountChanged` and
+    ///`NewSeasonAnnounced` events
+    pub fn spawn_with_anime(
+        client: Arc<MALClient>,
+        interval: Duration,
+        anime: &[impl Into<AnimeId> + Copy],
+    ) -> Self {
+        let anime: Vec<AnimeId> = anime.iter().map(|&id| id.into()).collect();
+        let (sender, events) = mpsc::channel(EVENT_BUFFER);
+        let cancel = CancellationToken::new();
+        let stopped = cancel.clone();
+        drop(rt::spawn(async move {
+            let cancelled = std::pin::pin!(stopped.cancelled());
+            let poll = std::pin::pin!(poll(client, interval, anime, sender));
+            future::select(cancelled, poll).await;
+        }));
+        Self { events, cancel }
+    }
+
+    ///Stops the background task, also in the middle of a poll. Events that were already sent
+    ///can still be read, then `next` returns `None`.
+    pub fn stop(&self) {
+        self.cancel.cancel();
+    }
+
+    ///Returns a token that stops the watcher when it's cancelled, for stopping it from another
+    ///task while this one waits in `next`
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    ///Waits for the next change. Returns `None` once the background task stopped.
+    pub async fn next(&mut self) -> Option<ListEvent> {
+        self.events.recv().await
+    }
+}
+
+async fn poll(
+    client: Arc<MALClient>,
+    interval: Duration,
+    anime: Vec<AnimeId>,
+    sender: mpsc::Sender<ListEvent>,
+) {
+    let fields = AnimeFields::ID | AnimeFields::NumEpisodes | AnimeFields::RelatedAnime;
+    let mut list: Option<ListSnapshot> = None;
+    let mut details: HashMap<AnimeId, AnimeDetails> = HashMap::new();
+    loop {
+        let mut events = Vec::new();
+        match client.list_updated_since(None).await {
+            Ok(entries) => {
+                let current: ListSnapshot = entries
+                    .into_iter()
+                    .filter_map(|entry| Some((entry.node.id, entry.list_status?)))
+                    .collect();
+                if let Some(previous) = &list {
+                    events.extend(list_events(previous, &current));
+                }
+                list = Some(current);
+            }
+            Err(e) => events.push(ListEvent::PollFailed(e)),
+        }
+        for id in &anime {
+            match client.get_anime_details(*id, fields).await {
+                Ok(current) => {
+                    if let Some(previous) = details.get(id) {
+                        events.extend(detail_events(previous, &current));
+                    }
+                    details.insert(*id, current);
+                }
+                Err(e) => events.push(ListEvent::PollFailed(e)),
+            }
+        }
+        for event in events {
+            if sender.send(event).await.is_err() {
+                // the watcher was dropped
+                return;
+            }
+        }
+        let sleep = std::pin::pin!(rt::sleep(interval));
+        let closed = std::pin::pin!(sender.closed());
+        if let Either::Right(_) = future::select(sleep, closed).await {
+            return;
+        }
+    }
+}
+
+///Returns the events that turn the list `from` into `to`, ordered by anime id
+pub(crate) fn list_events(from: &ListSnapshot, to: &ListSnapshot) -> Vec<ListEvent> {
+    let mut events = Vec::new();
+    let ids: BTreeSet<AnimeId> = from.keys().chain(to.keys()).copied().collect();
+    for id in ids {
+        match (from.get(&id), to.get(&id)) {
+            (None, Some(status)) => events.push(ListEvent::Added {
+                id,
+                status: status.clone(),
+            }),
+            (Some(_), None) => events.push(ListEvent::Removed { id }),
+            (Some(old), Some(new)) => {
+                if old.status != new.status {
+                    events.push(ListEvent::StatusChanged {
+                        id,
+                        from: old.status.clone(),
+                        to: new.status.clone(),
+                    });
+                }
+                let (from, to) = (
+                    old.num_episodes_watched.unwrap_or_default(),
+                    new.num_episodes_watched.unwrap_or_default(),
+                );
+                if from != to {
+                    events.push(ListEvent::ProgressChanged { id, from, to });
+                }
+            }
+            (None, None) => {}
+        }
+    }
+    events
+}
+
+///Returns the events for the changes in the details of an anime
+pub(crate) fn detail_events(from: &AnimeDetails, to: &AnimeDetails) -> Vec<ListEvent> {
+    let mut events = Vec::new();
+    if from.num_episodes != to.num_episodes {
+        events.push(ListEvent::EpisodeCountChanged {
+            id: to.id,
+            from: from.num_episodes,
+            to: to.num_episodes,
+        });
+    }
+    let known: Vec<AnimeId> = from
+        .related_anime
+        .iter()
+        .flatten()
+        .map(|r| r.node.id)
+        .collect();
+    for related in to.related_anime.iter().flatten() {
+        if related.relation_type == RelationType::Sequel && !known.contains(&related.node.id) {
+            events.push(ListEvent::NewSeasonAnnounced {
+                id: to.id,
+                sequel: related.node.clone(),
+            });
+        }
+    }
+    events
+}
@@ -0,0 +1,172 @@
+//! The JSON exactly as MAL sends it, without the normalization of the `model` types
+//!
+//! The `model` types turn strings like `media_type` into enums and rename confusing fields. The
+//! types in this module keep every value as MAL sent it, including unknown fields in `extra`,
+//! and can be converted to and from the `model` types. Fetch them with [`MALClient::get_wire`](crate::MALClient::get_wire).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use lib_mal::{MALClient, MALClientTrait, MALError};
+//! use lib_mal::model::AnimeDetails;
+//! use lib_mal::wire;
+//! # async fn test() -> Result<(), MALError> {
+//! # let client = MALClient::with_access_token("[YOUR_SECRET_HERE]");
+//! let raw: wire::Anime = client.get_wire("anime/30230?fields=media_type,source,popularity").await?;
+//! println!("{:?} {:?} {:?}", raw.media_type, raw.source, raw.extra.get("popularity"));
+//! let details = AnimeDetails::try_from(raw)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{model, MALError};
+
+///The list status MAL sends, the `model` type already matches it exactly
+pub use crate::model::ListStatus;
+
+///An anime as MAL sends it, see `model::AnimeDetails`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Anime {
+    pub id: usize,
+    pub title: String,
+    pub main_picture: Option<Value>,
+    pub alternative_titles: Option<Value>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub synopsis: Option<String>,
+    pub mean: Option<f64>,
+    pub rank: Option<usize>,
+    pub num_list_users: Option<usize>,
+    pub num_scoring_users: Option<usize>,
+    pub nsfw: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub media_type: Option<String>,
+    pub status: Option<String>,
+    pub genres: Option<Value>,
+    pub my_list_status: Option<ListStatus>,
+    pub num_episodes: Option<usize>,
+    pub start_season: Option<Value>,
+    pub broadcast: Option<Value>,
+    pub source: Option<String>,
+    pub average_episode_duration: Option<usize>,
+    pub rating: Option<String>,
+    pub pictures: Option<Value>,
+    pub background: Option<String>,
+    pub related_anime: Option<Value>,
+    pub related_manga: Option<Value>,
+    pub recommendations: Option<Value>,
+    pub studios: Option<Value>,
+    pub statistics: Option<Value>,
+    ///Fields without a typed counterpart, like `popularity`
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+///An entry of a list as MAL sends it, see `model::ListNode`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ListNode {
+    pub node: Anime,
+    pub list_status: Option<ListStatus>,
+    pub ranking: Option<Value>,
+}
+
+///A page of a list as MAL sends it, see `model::AnimeList`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AnimeList {
+    pub data: Vec<ListNode>,
+    #[serde(default)]
+    pub paging: Paging,
+    pub season: Option<Value>,
+}
+
+///The URLs of the previous and next pages, see `model::Paging`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Paging {
+    pub previous: Option<String>,
+    pub next: Option<String>,
+}
+
+///A user as MAL sends it, see `model::User`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct User {
+    pub id: usize,
+    pub name: String,
+    pub picture: Option<String>,
+    pub gender: Option<String>,
+    pub birthday: Option<String>,
+    pub location: String,
+    pub joined_at: String,
+    pub anime_statistics: Option<AnimeStatistics>,
+    pub time_zone: Option<String>,
+    pub is_supporter: Option<bool>,
+    ///Fields without a typed counterpart
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+///A user's list statistics as MAL sends them, see `model::AnimeStatistics`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct AnimeStatistics {
+    pub num_items_watching: usize,
+    pub num_items_completed: usize,
+    pub num_items_on_hold: usize,
+    pub num_items_dropped: usize,
+    pub num_items_plan_to_watch: usize,
+    pub num_items: usize,
+    pub num_days_watched: f64,
+    pub num_days_watching: f64,
+    pub num_days_completed: f64,
+    pub num_days_on_hold: f64,
+    pub num_days_dropped: f64,
+    pub num_days: f64,
+    pub num_episodes: usize,
+    pub num_times_rewatched: usize,
+    pub mean_score: f64,
+}
+
+///Converts between the two representations through their shared JSON form
+fn convert<T: Serialize, U: DeserializeOwned>(from: &T) -> Result<U, MALError> {
+    serde_json::to_value(from)
+        .and_then(serde_json::from_value)
+        .map_err(|source| MALError::Parse {
+            source,
+            body: String::new(),
+            context: None,
+        })
+}
+
+///`model` types serialize to the wire format, so they always convert. Wire types can hold data
+///the `model` types can't, like a missing required field, so converting them can fail.
+macro_rules! conversions {
+    ($($wire:ty => $model:ty),* $(,)?) => {
+        $(
+            impl From<$model> for $wire {
+                fn from(model: $model) -> Self {
+                    convert(&model).expect("model types should serialize to the wire format")
+                }
+            }
+
+            impl TryFrom<$wire> for $model {
+                type Error = MALError;
+
+                fn try_from(wire: $wire) -> Result<Self, Self::Error> {
+                    convert(&wire)
+                }
+            }
+        )*
+    };
+}
+
+conversions!(
+    Anime => model::AnimeDetails,
+    ListNode => model::ListNode,
+    AnimeList => model::AnimeList,
+    User => model::User,
+    AnimeStatistics => model::AnimeStatistics,
+);
@@ -4,9 +4,13 @@ use std::env;
 
 #[tokio::main]
 async fn main() {
-    let mut c = ClientBuilder::new().secret(include_str!("secret").to_string()).build_no_refresh();
-    let parts = c.get_auth_parts();
-    println!("URL: {}", parts.0);
-    c.auth("localhost:2561", &parts.1, &parts.2).await.unwrap();
+    let mut c: MALClient = ClientBuilder::new().secret(include_str!("secret").to_string()).build_no_refresh();
+    c.auth_interactive("http://localhost:2561", None, |p| {
+        if let AuthProgress::BrowserUnavailable { url } = p {
+            println!("URL: {}", url);
+        }
+    })
+    .await
+    .unwrap();
     println!("{}", c.get_access_token());
 }